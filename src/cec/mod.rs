@@ -1,37 +1,194 @@
-use cec_rs::{CecConnection, CecConnectionCfgBuilder, CecDeviceType, CecDeviceTypeVec};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use cec_rs::{
+    CecConnection, CecConnectionCfgBuilder, CecDeviceType, CecDeviceTypeVec, CecLogLevel,
+    CecLogicalAddress, CecOpcode, CecUserControlCode,
+};
+use gilrs::Button;
+
+use crate::{
+    command::{Command, Event},
+    config::config,
+    diagnostics::Status,
+    input::{InputBackend, InputEvent},
+};
 
 pub struct Cec {
-    cec: CecConnection,
+    /// `None` if it's turned off or opening the adapter failed, see `status`.
+    cec: Option<CecConnection>,
+    status: Status,
+    /// Until it's handed to the input backends, see [`Cec::remote`].
+    received: Option<Receiver<InputEvent>>,
+    /// Keys and what the TV did, sent from libcec's thread.
+    received_tx: Sender<InputEvent>,
+}
+
+/// The TV remote's keys and what the TV did, as an input backend.
+pub struct CecRemote {
+    received: Receiver<InputEvent>,
+}
+
+impl InputBackend for CecRemote {
+    fn name(&self) -> &'static str {
+        "cec"
+    }
+
+    fn poll(&mut self, input: &mut Vec<InputEvent>) {
+        input.extend(self.received.try_iter());
+    }
+}
+
+/// Something the TV did that playback should stop for.
+#[derive(Debug, Clone, Copy)]
+pub enum TvEvent {
+    Standby,
+    /// Switched to another input.
+    SwitchedAway,
 }
 
 impl Cec {
     pub fn new() -> Self {
-        let cec = CecConnectionCfgBuilder::default()
-            .device_name("Sinon".to_string())
-            .device_types(CecDeviceTypeVec::new(CecDeviceType::PlaybackDevice))
-            .activate_source(false)
-            .log_message_callback(Box::new(|msg| {
-                println!("[CEC] {}", &msg.message);
-            }))
-            .command_received_callback(Box::new(|cmd| {
-                println!("[CEC] Command received: {:?}", cmd.opcode);
-            }))
-            .build()
-            .expect("Failed to build CEC config")
-            .open()
-            .expect("Failed to open CEC connection");
-
-        let a = cec.get_active_source();
-        dbg!(&a);
-
-        Self { cec }
+        let (received_tx, received) = mpsc::channel();
+        let mut this = Self {
+            cec: None,
+            status: Status::Ok,
+            received: Some(received),
+            received_tx,
+        };
+        this.retry();
+        this
+    }
+
+    /// The remote's keys, so it can drive the overlay when there's no gamepad around. Only
+    /// there the first time, since it's moved into the input backends.
+    pub fn remote(&mut self) -> Option<CecRemote> {
+        Some(CecRemote { received: self.received.take()? })
+    }
+
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    /// Opens the adapter again.
+    pub fn retry(&mut self) {
+        if !config().cec {
+            self.status = Status::Off;
+            return;
+        }
+
+        match open(self.received_tx.clone()) {
+            Ok(cec) => {
+                self.cec = Some(cec);
+                self.status = Status::Ok;
+            }
+            Err(reason) => {
+                eprintln!("[CEC] {reason}");
+                self.cec = None;
+                self.status = Status::Failed(reason);
+            }
+        }
+    }
+
+    /// Whether the adapter is open, so there's any point in sending the TV anything.
+    pub fn is_open(&self) -> bool {
+        self.cec.is_some()
     }
 
+    /// Switches the TV over to us.
     pub fn take_focus(&mut self) {
-        self.cec
-            .set_active_source(CecDeviceType::PlaybackDevice)
-            .expect("Failed to set active source");
+        let Some(cec) = &self.cec else {
+            return;
+        };
+
+        if let Err(e) = cec.set_active_source(CecDeviceType::PlaybackDevice) {
+            eprintln!("[CEC] Failed to set active source: {e:?}");
+        }
     }
+
+    pub fn tv_on(&mut self) {
+        let Some(cec) = &self.cec else {
+            return;
+        };
+
+        if let Err(e) = cec.send_power_on_devices(CecLogicalAddress::Tv) {
+            eprintln!("[CEC] Failed to turn the TV on: {e:?}");
+        }
+    }
+
+    pub fn tv_standby(&mut self) {
+        let Some(cec) = &self.cec else {
+            return;
+        };
+
+        if let Err(e) = cec.send_standby_devices(CecLogicalAddress::Tv) {
+            eprintln!("[CEC] Failed to put the TV in standby: {e:?}");
+        }
+    }
+}
+
+fn open(tx: Sender<InputEvent>) -> Result<CecConnection, String> {
+    let (keys, commands) = (tx.clone(), tx.clone());
+
+    CecConnectionCfgBuilder::default()
+        .device_name("Sinon".to_string())
+        .device_types(CecDeviceTypeVec::new(CecDeviceType::PlaybackDevice))
+        .activate_source(false)
+        .log_message_callback(Box::new(|msg| {
+            // libcec logs every message on the bus at the lower levels
+            if matches!(msg.level, CecLogLevel::Error | CecLogLevel::Warning) {
+                eprintln!("[CEC] {}", &msg.message);
+            }
+        }))
+        .command_received_callback(Box::new(move |cmd| {
+            println!("[CEC] Command received: {:?}", cmd.opcode);
+
+            if matches!(cmd.opcode, CecOpcode::Standby) {
+                commands
+                    .send(InputEvent::Event(Event::Tv(TvEvent::Standby)))
+                    .ok();
+            }
+        }))
+        .source_activated_callback(Box::new(move |_, activated| {
+            if !activated {
+                tx.send(InputEvent::Event(Event::Tv(TvEvent::SwitchedAway)))
+                    .ok();
+            }
+        }))
+        .key_press_callback(Box::new(move |key| {
+            // released keys are reported again with a non-zero duration
+            if key.duration.is_zero()
+                && let Some(key) = translate(key.keycode)
+            {
+                keys.send(key).ok();
+            }
+        }))
+        .build()
+        .map_err(|e| format!("Failed to build CEC config: {e:?}"))?
+        .open()
+        .map_err(|e| format!("Failed to open CEC connection: {e:?}"))
+}
+
+/// What a key on the TV remote does: the same as a button on a gamepad, or a command of its own
+/// for keys a gamepad doesn't have.
+fn translate(code: CecUserControlCode) -> Option<InputEvent> {
+    let key = match code {
+        CecUserControlCode::Up => InputEvent::Press(Button::DPadUp),
+        CecUserControlCode::Down => InputEvent::Press(Button::DPadDown),
+        CecUserControlCode::Left => InputEvent::Press(Button::DPadLeft),
+        CecUserControlCode::Right => InputEvent::Press(Button::DPadRight),
+        CecUserControlCode::Select => InputEvent::Press(Button::East),
+        CecUserControlCode::Exit => InputEvent::Press(Button::South),
+        CecUserControlCode::RootMenu => InputEvent::Press(Button::Mode),
+        CecUserControlCode::ContentsMenu => InputEvent::Press(Button::Start),
+        CecUserControlCode::Play => InputEvent::Command(Command::Play),
+        CecUserControlCode::Pause => InputEvent::Command(Command::Pause),
+        CecUserControlCode::Stop => InputEvent::Command(Command::Stop),
+        CecUserControlCode::Rewind => InputEvent::Command(Command::SeekBackwardStateless),
+        CecUserControlCode::FastForward => InputEvent::Command(Command::SeekForwardStateless),
+        _ => return None,
+    };
+
+    Some(key)
 }
 
 impl Default for Cec {
@@ -1,19 +1,28 @@
 use core::sync::atomic::Ordering;
+use std::time::{Duration, SystemTime};
 
-use egui::{FocusDirection, Id};
+use egui::FocusDirection;
 use gilrs::Button;
+use serde::Deserialize;
 
 use crate::{
     App, EXIT,
+    cec::TvEvent,
+    config::config,
     mpv::time::Time,
+    store::{history::HistoryEntry, settings::VolumeTarget},
     ui::{
+        focus, theme,
         toast::{SpawnedToast, Toast},
         views::{
-            hidden::HiddenView, home_menu::HomeMenuView, media_menu::MediaMenuView,
-            miniseek::MiniSeekView, seekbar::SeekBarView, seeking::SeekingView,
+            confirm::ConfirmView, hidden::HiddenView, home_menu::HomeMenuView,
+            media_menu::MediaMenuView, miniseek::MiniSeekView, resume::ResumeView,
+            seekbar::SeekBarView, seeking::SeekingView, stats::StatsView,
+            time_entry::TimeEntryView,
         },
+        volume_osd::VolumeSource,
     },
-    utils::Activated,
+    utils,
 };
 
 #[derive(Clone, Copy, Default, Debug)]
@@ -26,11 +35,35 @@ pub enum Command {
     HideUi,
     ShowMediaMenu,
     ShowHomeMenu,
+    ShowToneMapping,
+    /// Suggestions for when playback drops frames.
+    ShowPerformance,
+    /// Live playback statistics.
+    ShowStats,
+    /// Opens the drive that was just plugged in in the library.
+    BrowseDrive,
 
     MoveFocus(FocusDirection),
     Activate,
+    /// The button is claimed by the focused widget, see [`Gamepad::claim`]. Only there for its
+    /// prompt, since the widget handles the press itself.
+    ///
+    /// [`Gamepad::claim`]: crate::gamepad::Gamepad::claim
+    Claimed(&'static str),
 
     TogglePause,
+    Pause,
+    Play,
+    Stop,
+    UndoSkip,
+    /// Skips the SponsorBlock segment or intro chapter that's playing.
+    SkipSegment,
+    NextEntry,
+    PreviousEntry,
+    /// Asks first.
+    ClearPlaylist,
+    NextChapter,
+    PrevChapter,
 
     StartSeeking,
     SeekBackward,
@@ -42,10 +75,40 @@ pub enum Command {
     SeekFaster,
     SeekSlower,
     SeekExact,
+    /// Switches from seeking to typing in the time to go to.
+    EnterTime,
+    SeekToEntered,
+    SetLoopA,
+    SetLoopB,
+    ClearLoop,
 
     VolumeUp,
     VolumeDown,
+    ToggleMute,
+    /// Makes the volume buttons control the DLNA device that was just found.
+    UseFoundForVolume,
 
+    SpeedUp,
+    SpeedDown,
+
+    SubScaleUp,
+    SubScaleDown,
+    ResetSpeed,
+    /// Turns video off while the audio keeps playing, or back on.
+    ToggleAudioOnly,
+
+    TvOn,
+    TvStandby,
+    /// Makes the TV switch to our input.
+    TakeCecFocus,
+
+    ConfirmDialog,
+    CancelDialog,
+
+    Resume,
+    StartOver,
+
+    /// Asks first.
     Quit,
 }
 
@@ -53,6 +116,15 @@ pub enum Command {
 pub enum Event {
     Toast(Toast),
     LastGamepadDisconnected,
+    /// Another program's OSD appeared, and will be up for this long if it said so.
+    ExternalOsd(Option<Duration>),
+    Tv(TvEvent),
+    /// A file that was played before was picked, so ask whether to continue where it was left
+    /// off.
+    AskResume {
+        path: String,
+        position: Time,
+    },
 }
 
 #[derive(Default)]
@@ -74,6 +146,19 @@ pub struct Actions {
     pub home: Command,
 }
 
+/// What left and right do while a menu is open.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LeftRight {
+    /// Nudges playback, so it can be adjusted without closing the menu.
+    #[default]
+    Seek,
+    /// Moves focus between things side by side.
+    MoveFocus,
+    /// Nothing, besides whatever the focused widget claims them for.
+    Nothing,
+}
+
 impl Command {
     pub fn label(self, app: &App) -> &'static str {
         match self {
@@ -84,12 +169,27 @@ impl Command {
             Command::HideUi => "Hide UI",
             Command::ShowMediaMenu => "Media Menu",
             Command::ShowHomeMenu => "Home Menu",
+            Command::ShowToneMapping => "Tone Mapping",
+            Command::ShowPerformance => "Performance",
+            Command::ShowStats => "Statistics",
+            Command::BrowseDrive => "Browse",
 
             Command::MoveFocus(_) => "Move Focus",
             Command::Activate => "Activate",
+            Command::Claimed(label) => label,
 
             Command::TogglePause if app.mpv.get_property_cached("pause") == Some(true) => "Play",
             Command::TogglePause => "Pause",
+            Command::Pause => "Pause",
+            Command::Play => "Play",
+            Command::Stop => "Stop",
+            Command::UndoSkip => "Undo Skip",
+            Command::SkipSegment => "Skip",
+            Command::NextEntry => "Next",
+            Command::PreviousEntry => "Previous",
+            Command::ClearPlaylist => "Clear Playlist",
+            Command::NextChapter => "Next Chapter",
+            Command::PrevChapter => "Previous Chapter",
 
             Command::StartSeeking => "Seek",
             Command::SeekBackward => "Seek Backward",
@@ -102,15 +202,56 @@ impl Command {
             Command::SeekSlower => "Slower",
             Command::SeekExact if app.mpv.seek_exact() => "Keyframes",
             Command::SeekExact => "Exact",
+            Command::EnterTime => "Go to Time",
+            Command::SeekToEntered => "Go",
+            Command::SetLoopA => "Loop Start",
+            Command::SetLoopB => "Loop End",
+            Command::ClearLoop => "Clear Loop",
 
             Command::VolumeUp => "Volume Up",
             Command::VolumeDown => "Volume Down",
+            Command::ToggleMute => "Mute",
+            Command::UseFoundForVolume => "Use for Volume",
+
+            Command::SpeedUp => "Faster",
+            Command::SpeedDown => "Slower",
+            Command::SubScaleUp => "Bigger Subs",
+            Command::SubScaleDown => "Smaller Subs",
+            Command::ResetSpeed => "Normal Speed",
+            Command::ToggleAudioOnly if app.mpv.audio_only() => "Show Video",
+            Command::ToggleAudioOnly => "Audio Only",
+
+            Command::TvOn => "TV On",
+            Command::TvStandby => "TV Standby",
+            Command::TakeCecFocus => "Switch TV Input",
+
+            Command::ConfirmDialog => "Confirm",
+            Command::CancelDialog => "Cancel",
+
+            Command::Resume => "Resume",
+            Command::StartOver => "Start Over",
 
             Command::Quit => "Quit",
         }
     }
 
-    pub fn show_prompt(self) -> bool {
+    pub fn show_prompt(self, app: &App) -> bool {
+        if let Command::ShowToneMapping = self {
+            return app.hdr_warning_path.is_some();
+        }
+
+        if let Command::ResetSpeed = self {
+            return app.mpv.speed() != 1.;
+        }
+
+        if let Command::NextChapter | Command::PrevChapter = self {
+            return !app.mpv.chapters().is_empty();
+        }
+
+        if let Command::ClearLoop = self {
+            return app.mpv.ab_loop() != (None, None);
+        }
+
         !matches!(
             self,
             Command::None
@@ -124,9 +265,27 @@ impl Command {
         )
     }
 
+    /// Commands that change configuration or can't be undone, which are unavailable in guest mode.
+    pub fn is_restricted(self) -> bool {
+        matches!(
+            self,
+            Command::Stop
+                | Command::ClearPlaylist
+                | Command::UseFoundForVolume
+                | Command::TvStandby
+                | Command::Quit
+        )
+    }
+
     pub fn execute(self, app: &mut App, ctx: &egui::Context) {
+        if app.guest_mode && self.is_restricted() {
+            app.toasts
+                .push(SpawnedToast::new(Toast::DisabledInGuestMode));
+            return;
+        }
+
         match self {
-            Command::None => {}
+            Command::None | Command::Claimed(_) => {}
 
             Command::ShowMiniSeek => {
                 app.change_view(MiniSeekView);
@@ -143,33 +302,120 @@ impl Command {
             Command::ShowHomeMenu => {
                 app.change_view(HomeMenuView::main());
             }
+            // only HDR video has anything to tone map, see ToneMappingMenu::enabled
+            Command::ShowToneMapping if app.mpv.video_params().is_some_and(|p| p.is_hdr()) => {
+                app.change_view(MediaMenuView::tone_mapping());
+            }
+            Command::ShowToneMapping => {}
+            Command::ShowPerformance => {
+                app.change_view(MediaMenuView::performance());
+            }
+            Command::ShowStats => {
+                app.change_view(StatsView);
+            }
+            Command::BrowseDrive => {
+                if let Some(path) = app.removable.recent() {
+                    HomeMenuView::browse(ctx, path);
+                    app.change_view(HomeMenuView::library());
+                }
+            }
 
             Command::MoveFocus(dir) => {
-                ctx.memory_mut(|m| m.move_focus(dir));
+                if !focus::move_focus(ctx, dir) {
+                    ctx.memory_mut(|m| m.move_focus(dir));
+                }
             }
             Command::Activate => {
-                ctx.memory_mut(|m| m.data.insert_temp(Id::NULL, Activated(true)));
+                utils::queue_activation(ctx);
             }
 
             Command::TogglePause => {
-                app.mpv.cycle_property("pause").unwrap();
+                if app.mpv.get_property_cached::<bool>("pause") == Some(false) {
+                    app.resume.remember(&app.mpv);
+                }
+                app.mpv.toggle_pause().ok();
+            }
+            Command::Pause => {
+                app.resume.remember(&app.mpv);
+                app.mpv.pause().ok();
+            }
+            Command::Play => {
+                app.mpv.unpause().ok();
+            }
+            Command::UndoSkip => {
+                app.mpv.undo_skip().ok();
+            }
+            Command::SkipSegment => {
+                if app.mpv.skip_current_segment().is_ok_and(|skipped| !skipped) {
+                    app.toasts.push(SpawnedToast::new(Toast::NothingToSkip));
+                }
+            }
+            Command::NextEntry => {
+                app.mpv.playlist_next().ok();
+            }
+            Command::PreviousEntry => {
+                app.mpv.playlist_prev().ok();
+            }
+            Command::ClearPlaylist => {
+                app.confirm("Clear the playlist?", |app| {
+                    app.mpv.playlist_clear().ok();
+                });
+            }
+            Command::NextChapter | Command::PrevChapter => {
+                let delta = if let Command::NextChapter = self {
+                    1
+                } else {
+                    -1
+                };
+                if let Ok(Some(index)) = app.mpv.add_chapter(delta) {
+                    let chapters = app.mpv.chapters();
+                    app.toasts.push(SpawnedToast::new(Toast::Chapter {
+                        number: index + 1,
+                        count: chapters.len(),
+                        title: chapters[index].title.map(str::to_string),
+                    }));
+                }
+            }
+            Command::Stop => {
+                if let Some(path) = app.mpv.get_property_cached::<String>("path") {
+                    let title = app.mpv.get_property::<Option<String>>("media-title");
+                    app.history.record(HistoryEntry {
+                        path,
+                        title,
+                        position: app.mpv.time_pos_fallback(),
+                        duration: app.mpv.duration(),
+                        played_at: SystemTime::now(),
+                    });
+                }
+                app.resume.remember(&app.mpv);
+
+                app.mpv.stop().ok();
+                app.change_view(HomeMenuView::main());
             }
 
             Command::StartSeeking => {
                 app.mpv.start_seek();
                 app.change_view(SeekingView);
             }
-            Command::SeekForward => app.mpv.seek_forward().unwrap(),
-            Command::SeekBackward => app.mpv.seek_backward().unwrap(),
+            Command::SeekForward => {
+                app.mpv.seek_forward().ok();
+            }
+            Command::SeekBackward => {
+                app.mpv.seek_backward().ok();
+            }
             Command::SeekForwardStateless => {
-                app.mpv.seek_stateless(Time::seconds(5), false).unwrap();
+                app.mpv
+                    .seek_stateless(config().seek.stateless_step(), false)
+                    .ok();
             }
             Command::SeekBackwardStateless => {
-                app.mpv.seek_stateless(Time::seconds(-5), false).unwrap();
+                app.mpv
+                    .seek_stateless(-config().seek.stateless_step(), false)
+                    .ok();
             }
             Command::DoneSeeking => {
                 app.change_view(SeekBarView);
-                app.mpv.finish_seek().unwrap();
+                app.mpv.finish_seek().ok();
             }
             Command::CancelSeeking => {
                 app.change_view(SeekBarView);
@@ -184,20 +430,99 @@ impl Command {
             Command::SeekExact => {
                 app.mpv.toggle_seek_exact();
             }
+            Command::SetLoopA => {
+                app.mpv.set_ab_loop_a().ok();
+            }
+            Command::SetLoopB => {
+                app.mpv.set_ab_loop_b().ok();
+            }
+            Command::ClearLoop => {
+                app.mpv.clear_ab_loop().ok();
+            }
+            Command::EnterTime => {
+                app.change_view(TimeEntryView::new(app.mpv.time_pos_fallback()));
+            }
+            Command::SeekToEntered => {
+                if let Some(entry) = app.take_view_as::<TimeEntryView>() {
+                    entry.seek(app);
+                }
+            }
+
+            Command::VolumeUp => change_volume(app, 5.),
+            Command::VolumeDown => change_volume(app, -5.),
+            Command::ToggleMute => toggle_mute(app),
+            Command::UseFoundForVolume => {
+                if let Some(udn) = app.dlna.found() {
+                    let target = VolumeTarget::Renderer(udn.to_string());
+                    app.dlna.set_volume_target(target.clone());
+                    app.settings.volume_target = target;
+                    app.settings.save();
+                }
+            }
+
+            Command::SpeedUp => {
+                app.mpv.speed_up().ok();
+            }
+            Command::SpeedDown => {
+                app.mpv.speed_down().ok();
+            }
+            Command::ResetSpeed => {
+                app.mpv.set_speed(1.).ok();
+            }
+            Command::ToggleAudioOnly => {
+                let audio_only = !app.mpv.audio_only();
+                if app.mpv.toggle_audio_only().is_ok() {
+                    app.toasts
+                        .push(SpawnedToast::new(Toast::AudioOnly { enabled: audio_only }));
+                }
+            }
+
+            Command::SubScaleUp | Command::SubScaleDown => {
+                let steps = if let Command::SubScaleUp = self {
+                    1.
+                } else {
+                    -1.
+                };
+                let scale = app.settings.change_sub_scale(steps);
+                app.mpv
+                    .set_property("sub-scale", theme::sub_scale(&app.settings))
+                    .ok();
+
+                app.toasts.push(SpawnedToast::new(Toast::SubtitleSize {
+                    percent: (scale * 100.).round() as u32,
+                }));
+            }
 
-            Command::VolumeUp => {
-                if let Some(device) = app.dlna.devices().get_mut(0) {
-                    device.set_volume((device.volume() as f32 + 5.) as u8);
+            Command::TvOn => app.cec.tv_on(),
+            Command::TvStandby => app.cec.tv_standby(),
+            Command::TakeCecFocus => app.cec.take_focus(),
+
+            Command::ConfirmDialog => {
+                if let Some(confirm) = app.take_view_as::<ConfirmView>() {
+                    confirm.confirm(app);
+                }
+            }
+            Command::CancelDialog => {
+                if let Some(confirm) = app.take_view_as::<ConfirmView>() {
+                    confirm.cancel(app);
+                } else if let Some(resume) = app.take_view_as::<ResumeView>() {
+                    resume.cancel(app);
+                }
+            }
+
+            Command::Resume => {
+                if let Some(resume) = app.take_view_as::<ResumeView>() {
+                    resume.resume(app);
                 }
             }
-            Command::VolumeDown => {
-                if let Some(device) = app.dlna.devices().get_mut(0) {
-                    device.set_volume((device.volume() as f32 - 5.) as u8);
+            Command::StartOver => {
+                if let Some(resume) = app.take_view_as::<ResumeView>() {
+                    resume.start_over(app);
                 }
             }
 
             Command::Quit => {
-                EXIT.store(true, Ordering::Relaxed);
+                app.confirm("Quit the overlay?", |_| EXIT.store(true, Ordering::Relaxed));
             }
         }
     }
@@ -217,10 +542,44 @@ impl Event {
                     app.change_view(HiddenView);
                 }
             }
+            Event::Tv(event) => {
+                // nobody's watching anymore
+                if app.mpv.get_property_cached::<bool>("pause") == Some(false) {
+                    app.resume.remember(&app.mpv);
+                    app.mpv.pause().ok();
+                    app.toasts
+                        .push(SpawnedToast::new(Toast::PausedForTv { event }));
+                }
+            }
+            Event::ExternalOsd(duration) => {
+                app.external_osd
+                    .shown(duration.unwrap_or_else(|| config().external_osd.hide_for()));
+            }
+            Event::AskResume { path, position } => {
+                let previous = app.take_view();
+                app.view = Box::new(ResumeView::new(path, position, previous));
+                app.view_changed = true;
+                app.gamepad.clear_claims();
+            }
         }
     }
 }
 
+impl LeftRight {
+    pub fn actions(self) -> Actions {
+        let (left, right) = match self {
+            LeftRight::Seek => (Command::SeekBackwardStateless, Command::SeekForwardStateless),
+            LeftRight::MoveFocus => (
+                Command::MoveFocus(FocusDirection::Left),
+                Command::MoveFocus(FocusDirection::Right),
+            ),
+            LeftRight::Nothing => (Command::None, Command::None),
+        };
+
+        Actions { left, right, ..Actions::default() }
+    }
+}
+
 impl Actions {
     pub fn iter(&self) -> impl Iterator<Item = (Button, Command)> {
         [
@@ -243,6 +602,28 @@ impl Actions {
         .into_iter()
     }
 
+    pub fn set(&mut self, button: Button, cmd: Command) {
+        let slot = match button {
+            Button::East => &mut self.a,
+            Button::South => &mut self.b,
+            Button::North => &mut self.x,
+            Button::West => &mut self.y,
+            Button::LeftTrigger => &mut self.l1,
+            Button::LeftTrigger2 => &mut self.l2,
+            Button::RightTrigger => &mut self.r1,
+            Button::RightTrigger2 => &mut self.r2,
+            Button::DPadUp => &mut self.up,
+            Button::DPadDown => &mut self.down,
+            Button::DPadLeft => &mut self.left,
+            Button::DPadRight => &mut self.right,
+            Button::Select => &mut self.select,
+            Button::Start => &mut self.start,
+            Button::Mode => &mut self.home,
+            _ => return,
+        };
+        *slot = cmd;
+    }
+
     pub fn get(&self, button: Button) -> Command {
         self.iter()
             .find(|(b, _action)| *b == button)
@@ -250,3 +631,27 @@ impl Actions {
             .unwrap_or(Command::None)
     }
 }
+
+/// Mutes or unmutes the DLNA device the volume buttons are set to, or mpv.
+fn toggle_mute(app: &mut App) {
+    if let Some(idx) = app.dlna.volume_device() {
+        let device = &mut app.dlna.devices()[idx];
+        device.set_muted(!device.muted());
+        app.volume_osd.show(VolumeSource::Dlna(idx));
+    } else {
+        app.mpv.cycle_property("mute").ok();
+        app.volume_osd.show(VolumeSource::Mpv);
+    }
+}
+
+/// Changes the volume of the DLNA device the volume buttons are set to, or mpv's.
+fn change_volume(app: &mut App, delta: f32) {
+    if let Some(idx) = app.dlna.volume_device() {
+        let device = &mut app.dlna.devices()[idx];
+        device.set_volume((device.volume() as f32 + delta) as u8);
+        app.volume_osd.show(VolumeSource::Dlna(idx));
+    } else {
+        app.mpv.change_volume(delta).ok();
+        app.volume_osd.show(VolumeSource::Mpv);
+    }
+}
@@ -0,0 +1,459 @@
+//! User configuration, read once from `~/.config/htpc-overlay/config.toml`. Every field is
+//! optional and falls back to the defaults below.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::Duration,
+};
+
+use gilrs::Button;
+use serde::Deserialize;
+
+use crate::{
+    command::LeftRight,
+    mpv::{sponsorblock::Category, time::Time},
+    store,
+};
+
+static CONFIG: LazyLock<Config> = LazyLock::new(Config::load);
+
+pub fn config() -> &'static Config {
+    &CONFIG
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// Directory the Library menu starts in.
+    pub library_root: PathBuf,
+    /// mpv's `input-ipc-server` socket.
+    pub mpv_socket: PathBuf,
+    /// Socket the overlay listens on for `htpc-overlay <command>` and scripts.
+    pub control_socket: PathBuf,
+    pub zoom_factor: f32,
+    /// Megabytes of thumbnails, posters and artwork to keep loaded before the least recently
+    /// shown ones are dropped.
+    pub image_cache_mb: usize,
+    /// UDP port watch party members broadcast on. Has to be the same on every member.
+    pub watch_party_port: u16,
+    /// TCP port for a WebSocket streaming what's playing to remote UIs. Off unless set.
+    pub websocket_port: Option<u16>,
+    /// Seconds before the end of a file that the next playlist entry is announced, with a chance
+    /// to play it right away or stay on the current file. 0 turns this off.
+    pub up_next: f32,
+    /// The four digits that turn guest mode on and off.
+    pub guest_pin: [u8; 4],
+    /// Opens the HDMI-CEC adapter, for the TV remote and turning the TV on and off. Off unless
+    /// set.
+    pub cec: bool,
+    /// Physical button to the button it acts as, e.g. `{ South = "East", East = "South" }` to
+    /// swap confirm and back on a Nintendo layout controller.
+    pub button_map: HashMap<Button, Button>,
+    /// What left and right do in menus when the focused widget doesn't claim them: `seek`,
+    /// `move-focus` or `nothing`.
+    pub menu_left_right: LeftRight,
+    pub seek: SeekConfig,
+    pub timeouts: TimeoutConfig,
+    pub sponsorblock: SponsorBlockConfig,
+    pub toasts: ToastConfig,
+    pub scrapers: ScraperConfig,
+    pub voice: VoiceConfig,
+    pub syncplay: SyncplayConfig,
+    /// Sets of shaders the Rendering menu can switch between.
+    pub shader_presets: Vec<ShaderPreset>,
+    /// Settings for each profile, by the name it's chosen with through `HTPC_OVERLAY_PROFILE`.
+    pub profiles: HashMap<String, ProfileConfig>,
+    pub external_osd: ExternalOsdConfig,
+    pub inputs: InputsConfig,
+    pub dlna_renderer: DlnaRendererConfig,
+    pub mqtt: MqttConfig,
+    /// Per widget drawn over the views, by name: `volume`, `lock`, `voice`, `skip`, `up-next`,
+    /// `hint` and `fast-scroll`.
+    pub overlays: HashMap<String, OverlayConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SeekConfig {
+    /// Seconds skipped by left/right while not in seek mode.
+    pub stateless_step: f32,
+    /// Step sizes in seconds that seek mode can switch between, from short to long.
+    pub steps: Vec<f32>,
+    /// Step size in seconds that seek mode starts with.
+    pub default_step: f32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TimeoutConfig {
+    /// Seconds without input before the seek bar hides.
+    pub seekbar: f32,
+    /// Seconds without input before the mini seek bar hides.
+    pub miniseek: f32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SponsorBlockConfig {
+    /// Base URLs of SponsorBlock-compatible servers, all of which are queried.
+    pub servers: Vec<String>,
+    pub categories: Vec<Category>,
+    /// Categories that are skipped automatically, rather than only shown on the seek bar.
+    pub auto_skip: Vec<Category>,
+    /// Whether chapters named like an opening, ending or preview count as segments of that
+    /// category. Off by default, since with `auto-skip` they're skipped without asking.
+    pub chapter_names: bool,
+}
+
+/// Online metadata sources for the Library. Each one is only used when its API key is set.
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ScraperConfig {
+    pub tmdb_api_key: Option<String>,
+    pub tvdb_api_key: Option<String>,
+    /// Language for titles and synopses, as an ISO 639-1 code.
+    pub language: String,
+}
+
+/// Voice commands from an external speech-to-text daemon. Disabled unless `socket` is set.
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct VoiceConfig {
+    /// Unix socket the daemon listens on. We send it `start` and `stop` lines as push-to-talk is
+    /// pressed and released, and it sends back each phrase it recognized on a line of its own.
+    pub socket: Option<PathBuf>,
+    /// Program and arguments to start the daemon with, if it isn't started some other way.
+    pub command: Vec<String>,
+    /// Button to hold while speaking.
+    pub push_to_talk: Button,
+}
+
+/// Watching along with people elsewhere in a Syncplay room. Disabled unless `server` is set.
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SyncplayConfig {
+    /// `host:port` of the server, e.g. `syncplay.pl:8999`. Servers that require TLS or a
+    /// password aren't supported.
+    pub server: Option<String>,
+    /// What others in the room see us as.
+    pub username: String,
+    pub room: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ProfileConfig {
+    /// mpv properties to set while the profile is active, e.g. `{ volume-max = 80 }`. They're put
+    /// back the way they were when the overlay exits.
+    pub mpv: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ShaderPreset {
+    pub name: String,
+    /// Paths as mpv takes them for `glsl-shaders`, so `~~/shaders/...` works.
+    pub shaders: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct OverlayConfig {
+    pub enabled: bool,
+    /// Seconds to fade in over.
+    pub fade_in: f32,
+    /// Seconds to fade out over.
+    pub fade_out: f32,
+    /// Widgets with a higher `z` are drawn over ones with a lower one. By default they're
+    /// stacked the way they're listed in the source.
+    pub z: Option<i32>,
+}
+
+/// Other programs' OSDs, like the compositor's volume popup, that the widgets drawn over the views
+/// hide for while they show. Programs can also announce theirs with `htpc-overlay external-osd`.
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ExternalOsdConfig {
+    /// D-Bus signals sent whenever another OSD appears.
+    pub signals: Vec<DbusSignal>,
+    /// Seconds the widgets stay hidden after each one.
+    pub hide_for: f32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DbusSignal {
+    #[serde(default)]
+    pub bus: DbusBus,
+    pub interface: String,
+    /// Only this signal on the interface, rather than any of them.
+    pub member: Option<String>,
+    /// Only signals from this object.
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DbusBus {
+    #[default]
+    Session,
+    System,
+}
+
+/// Sources of input besides gamepads.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct InputsConfig {
+    /// Backends to leave off, by name. `cec` is the TV remote, and also what pauses playback
+    /// when the TV goes into standby.
+    pub disabled: Vec<String>,
+}
+
+/// Being cast to from phones, with apps like BubbleUPnP, as a DLNA renderer on the network.
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct DlnaRendererConfig {
+    pub enabled: bool,
+    /// What the apps list it as.
+    pub name: String,
+}
+
+/// Publishing what's playing to an MQTT broker and taking commands from it, for home automation.
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct MqttConfig {
+    /// `host:port` of the broker, e.g. `homeassistant.local:1883`. Brokers that require TLS
+    /// aren't supported.
+    pub broker: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub client_id: String,
+    /// Topics are this followed by `/state`, `/title`, `/position`, `/duration`, `/volume` and
+    /// `/available`, and commands are taken on `/command`.
+    pub topic_prefix: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ToastConfig {
+    pub position: ToastPosition,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToastPosition {
+    #[default]
+    TopRight,
+    TopLeft,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .unwrap_or_default()
+            .join("htpc-overlay/config.toml")
+    }
+
+    /// Settings for the active profile, if there are any.
+    pub fn profile(&self) -> Option<&ProfileConfig> {
+        self.profiles.get(&store::profile_name())
+    }
+
+    pub fn overlay(&self, name: &str) -> &OverlayConfig {
+        self.overlays.get(name).unwrap_or(&DEFAULT_OVERLAY)
+    }
+
+    fn load() -> Self {
+        let path = Self::path();
+
+        match std::fs::read_to_string(&path) {
+            Ok(s) => toml::from_str(&s).unwrap_or_else(|e| {
+                eprintln!("Failed to parse {}: {e}", path.display());
+                Config::default()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+            Err(e) => {
+                eprintln!("Failed to read {}: {e}", path.display());
+                Config::default()
+            }
+        }
+    }
+}
+
+impl Default for ScraperConfig {
+    fn default() -> Self {
+        Self {
+            tmdb_api_key: None,
+            tvdb_api_key: None,
+            language: "en".to_string(),
+        }
+    }
+}
+
+impl Default for VoiceConfig {
+    fn default() -> Self {
+        Self {
+            socket: None,
+            command: vec![],
+            push_to_talk: Button::RightThumb,
+        }
+    }
+}
+
+impl Default for SyncplayConfig {
+    fn default() -> Self {
+        Self {
+            server: None,
+            username: "htpc".to_string(),
+            room: "htpc".to_string(),
+        }
+    }
+}
+
+impl Default for DlnaRendererConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            name: "HTPC".to_string(),
+        }
+    }
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            broker: None,
+            username: None,
+            password: None,
+            client_id: "htpc-overlay".to_string(),
+            topic_prefix: "htpc-overlay".to_string(),
+        }
+    }
+}
+
+impl Default for ExternalOsdConfig {
+    fn default() -> Self {
+        Self { signals: vec![], hide_for: 2. }
+    }
+}
+
+impl Default for OverlayConfig {
+    fn default() -> Self {
+        DEFAULT_OVERLAY
+    }
+}
+
+const DEFAULT_OVERLAY: OverlayConfig = OverlayConfig {
+    enabled: true,
+    fade_in: 0.1,
+    fade_out: 0.3,
+    z: None,
+};
+
+impl SeekConfig {
+    pub fn stateless_step(&self) -> Time {
+        Time::seconds(self.stateless_step)
+    }
+}
+
+impl TimeoutConfig {
+    pub fn seekbar(&self) -> Duration {
+        Duration::from_secs_f32(self.seekbar.max(0.))
+    }
+
+    pub fn miniseek(&self) -> Duration {
+        Duration::from_secs_f32(self.miniseek.max(0.))
+    }
+}
+
+impl ExternalOsdConfig {
+    pub fn hide_for(&self) -> Duration {
+        Duration::from_secs_f32(self.hide_for.max(0.))
+    }
+}
+
+impl ToastPosition {
+    pub fn is_left(self) -> bool {
+        matches!(self, Self::TopLeft | Self::BottomLeft)
+    }
+
+    pub fn is_bottom(self) -> bool {
+        matches!(self, Self::BottomLeft | Self::BottomRight)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            library_root: PathBuf::from("/data/index"),
+            mpv_socket: PathBuf::from("/run/user/1000/mpv.sock"),
+            control_socket: PathBuf::from("/run/user/1000/htpc-overlay.sock"),
+            zoom_factor: 1.5,
+            image_cache_mb: 256,
+            watch_party_port: 47810,
+            websocket_port: None,
+            up_next: 15.,
+            guest_pin: [1, 2, 3, 4],
+            cec: false,
+            button_map: HashMap::new(),
+            menu_left_right: LeftRight::default(),
+            seek: SeekConfig::default(),
+            timeouts: TimeoutConfig::default(),
+            sponsorblock: SponsorBlockConfig::default(),
+            toasts: ToastConfig::default(),
+            scrapers: ScraperConfig::default(),
+            voice: VoiceConfig::default(),
+            syncplay: SyncplayConfig::default(),
+            shader_presets: vec![],
+            profiles: HashMap::new(),
+            external_osd: ExternalOsdConfig::default(),
+            inputs: InputsConfig::default(),
+            dlna_renderer: DlnaRendererConfig::default(),
+            mqtt: MqttConfig::default(),
+            overlays: HashMap::new(),
+        }
+    }
+}
+
+impl Default for SeekConfig {
+    fn default() -> Self {
+        Self {
+            stateless_step: 5.,
+            steps: vec![1., 5., 30., 60., 600.],
+            default_step: 5.,
+        }
+    }
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self { seekbar: 5., miniseek: 2. }
+    }
+}
+
+impl Default for SponsorBlockConfig {
+    fn default() -> Self {
+        Self {
+            servers: vec!["https://sponsor.ajay.app".to_string()],
+            categories: vec![
+                Category::Sponsor,
+                Category::Selfpromo,
+                Category::Intro,
+                Category::Outro,
+            ],
+            auto_skip: vec![
+                Category::Sponsor,
+                Category::Selfpromo,
+                Category::Intro,
+                Category::Outro,
+            ],
+            chapter_names: false,
+        }
+    }
+}
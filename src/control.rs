@@ -0,0 +1,288 @@
+//! A Unix socket for controlling the running overlay from scripts and udev rules, and the
+//! `htpc-overlay <command>` subcommands that talk to it. Requests and responses are JSON, one per
+//! line.
+
+use core::sync::atomic::Ordering;
+use std::{
+    io::{self, BufRead as _, BufReader, ErrorKind, Write as _},
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    EXIT,
+    command::{Command, Event},
+    config::config,
+    mpv::Mpv,
+    ui::toast::Toast,
+    utils::read_line_nonblocking,
+};
+
+/// How long a subcommand waits for the overlay to respond.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest another OSD can say it's showing for, so a typo doesn't hide the overlay for good.
+const MAX_EXTERNAL_OSD: Duration = Duration::from_secs(60 * 60);
+
+const USAGE: &str = "\
+Usage: htpc-overlay [--demo] [--take-over]
+       htpc-overlay play [<path>]
+       htpc-overlay pause
+       htpc-overlay toast <text>
+       htpc-overlay open-menu [home|media]
+       htpc-overlay external-osd [<seconds>]
+       htpc-overlay quit";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+enum Request {
+    /// Plays `path`, or unpauses if there isn't one.
+    Play {
+        path: Option<String>,
+    },
+    Pause,
+    Toast {
+        text: String,
+    },
+    OpenMenu {
+        menu: Menu,
+    },
+    /// Another OSD is showing, for `seconds` or the configured time.
+    ExternalOsd {
+        seconds: Option<f32>,
+    },
+    /// Exits, even in guest mode, so another instance can take over.
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Menu {
+    Home,
+    Media,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Response {
+    error: Option<String>,
+}
+
+pub struct Control {
+    listener: Option<UnixListener>,
+    clients: Vec<Client>,
+}
+
+struct Client {
+    stream: BufReader<UnixStream>,
+    line_buf: String,
+}
+
+impl Control {
+    pub fn new() -> Self {
+        let listener = listen(&config().control_socket)
+            .map_err(|e| eprintln!("Failed to open control socket: {e}"))
+            .ok();
+
+        Self { listener, clients: vec![] }
+    }
+
+    pub fn update(&mut self, mpv: &mut Mpv, commands: &mut Vec<Command>, events: &mut Vec<Event>) {
+        let Some(listener) = &self.listener else {
+            return;
+        };
+
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => match stream.set_nonblocking(true) {
+                    Ok(()) => self.clients.push(Client {
+                        stream: BufReader::new(stream),
+                        line_buf: String::new(),
+                    }),
+                    Err(e) => eprintln!("Failed to set up control connection: {e}"),
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    eprintln!("Failed to accept control connection: {e}");
+                    break;
+                }
+            }
+        }
+
+        self.clients.retain_mut(|client| {
+            loop {
+                match client.read_line() {
+                    Ok(Some(line)) => {
+                        let error = match serde_json::from_str::<Request>(&line) {
+                            Ok(request) => handle(request, mpv, commands, events).err(),
+                            Err(e) => Some(format!("Invalid request: {e}")),
+                        };
+
+                        let response = serde_json::to_string(&Response { error }).unwrap();
+                        if let Err(e) = writeln!(client.stream.get_mut(), "{response}") {
+                            eprintln!("Failed to respond on control socket: {e}");
+                            break false;
+                        }
+                    }
+                    Ok(None) => break true,
+                    Err(e) => {
+                        if e.kind() != ErrorKind::UnexpectedEof {
+                            eprintln!("Lost control connection: {e}");
+                        }
+                        break false;
+                    }
+                }
+            }
+        });
+    }
+}
+
+impl Default for Control {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Control {
+    fn drop(&mut self) {
+        if self.listener.is_some() {
+            std::fs::remove_file(&config().control_socket).ok();
+        }
+    }
+}
+
+impl Client {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        read_line_nonblocking(&mut self.stream, &mut self.line_buf)
+    }
+}
+
+fn listen(path: &Path) -> io::Result<UnixListener> {
+    // a socket left behind by an instance that crashed would keep us from binding
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                ErrorKind::AddrInUse,
+                format!("another instance is listening on {}", path.display()),
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+fn handle(
+    request: Request,
+    mpv: &mut Mpv,
+    commands: &mut Vec<Command>,
+    events: &mut Vec<Event>,
+) -> Result<(), String> {
+    match request {
+        Request::Play { path: Some(path) } => {
+            mpv.load_file(&path).ok();
+            mpv.unpause().ok();
+            commands.push(Command::HideUi);
+        }
+        Request::Play { path: None } => commands.push(Command::Play),
+        Request::Pause => commands.push(Command::Pause),
+        Request::Toast { text } => events.push(Event::Toast(Toast::Message { text })),
+        Request::OpenMenu { menu: Menu::Home } => commands.push(Command::ShowHomeMenu),
+        Request::OpenMenu { menu: Menu::Media } => commands.push(Command::ShowMediaMenu),
+        Request::ExternalOsd { seconds } => {
+            let duration = seconds
+                .map(|s| {
+                    Duration::try_from_secs_f32(s.max(0.))
+                        .map(|duration| duration.min(MAX_EXTERNAL_OSD))
+                        .map_err(|_| format!("Invalid number of seconds {s}"))
+                })
+                .transpose()?;
+            events.push(Event::ExternalOsd(duration));
+        }
+        Request::Quit => EXIT.store(true, Ordering::Relaxed),
+    }
+
+    Ok(())
+}
+
+/// Runs the subcommand given on the command line against the running overlay, or returns `None`
+/// if there isn't one and the overlay itself should start.
+pub fn run_subcommand() -> Option<Result<(), Box<dyn std::error::Error>>> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (subcommand, rest) = args.split_first()?;
+
+    if subcommand.starts_with('-') {
+        return None;
+    }
+
+    Some(parse(subcommand, rest).and_then(send))
+}
+
+fn parse(subcommand: &str, args: &[String]) -> Result<Request, Box<dyn std::error::Error>> {
+    let request = match (subcommand, args) {
+        ("play", []) => Request::Play { path: None },
+        ("play", [path]) if path.contains("://") => Request::Play { path: Some(path.clone()) },
+        ("play", [path]) => {
+            // relative paths would be relative to wherever the overlay was started otherwise
+            let path = std::path::absolute(path)?;
+            Request::Play {
+                path: Some(path.to_string_lossy().into_owned()),
+            }
+        }
+        ("pause", []) => Request::Pause,
+        ("toast", [text]) => Request::Toast { text: text.clone() },
+        ("open-menu", [] | [_]) => {
+            let menu = match args.first().map(String::as_str) {
+                None | Some("home") => Menu::Home,
+                Some("media") => Menu::Media,
+                Some(other) => return Err(format!("Unknown menu {other}\n\n{USAGE}").into()),
+            };
+            Request::OpenMenu { menu }
+        }
+        ("external-osd", []) => Request::ExternalOsd { seconds: None },
+        ("external-osd", [seconds]) => Request::ExternalOsd {
+            seconds: Some(
+                seconds
+                    .parse()
+                    .map_err(|_| format!("Invalid number of seconds {seconds}\n\n{USAGE}"))?,
+            ),
+        },
+        ("quit", []) => Request::Quit,
+        _ => return Err(USAGE.into()),
+    };
+
+    Ok(request)
+}
+
+/// Shows `text` as a toast on the overlay that's running.
+pub fn notify_running(text: &str) -> Result<(), Box<dyn std::error::Error>> {
+    send(Request::Toast { text: text.to_string() })
+}
+
+/// Asks the overlay that's running to exit.
+pub fn quit_running() -> Result<(), Box<dyn std::error::Error>> {
+    send(Request::Quit)
+}
+
+fn send(request: Request) -> Result<(), Box<dyn std::error::Error>> {
+    let path = &config().control_socket;
+    let stream = UnixStream::connect(path).map_err(|e| {
+        format!("Failed to connect to {}, is the overlay running? {e}", path.display())
+    })?;
+    stream.set_read_timeout(Some(RESPONSE_TIMEOUT))?;
+
+    writeln!(&stream, "{}", serde_json::to_string(&request)?)?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let response = serde_json::from_str::<Response>(&line)
+        .map_err(|e| format!("Unexpected response from the overlay: {e}"))?;
+
+    match response.error {
+        Some(error) => Err(error.into()),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,115 @@
+//! Cycles through the views with made-up playback, for taking screenshots and checking layouts on
+//! different TVs without mpv running. Started with `--demo`.
+
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::{
+    App,
+    mpv::Mpv,
+    ui::{
+        toast::{SpawnedToast, Toast},
+        views::{
+            hidden::HiddenView, home_menu::HomeMenuView, media_menu::MediaMenuView,
+            miniseek::MiniSeekView, seekbar::SeekBarView,
+        },
+    },
+};
+
+/// How long each view is shown for.
+const STEP: Duration = Duration::from_secs(5);
+const STEPS: usize = 7;
+
+const TITLE: &str = "Big Buck Bunny";
+const DURATION: f32 = 596.;
+/// Where the made-up playback starts, so the seek bar isn't empty.
+const START: f32 = 140.;
+
+pub fn requested() -> bool {
+    std::env::args().any(|arg| arg == "--demo")
+}
+
+pub struct Demo {
+    started: Instant,
+    step: Option<usize>,
+}
+
+impl Demo {
+    /// Fills the offline mpv, see [`Mpv::offline`], with made-up playback.
+    pub fn new(mpv: &mut Mpv) -> Self {
+        mpv.fake_property("path", json!(format!("/demo/{TITLE}.mkv")));
+        mpv.fake_property("media-title", json!(TITLE));
+        mpv.fake_property("duration", json!(DURATION));
+        mpv.fake_property("pause", json!(false));
+        mpv.fake_property("speed", json!(1.));
+        mpv.fake_property("volume", json!(80.));
+        mpv.fake_property("video-params", json!({ "gamma": "bt.1886", "h": 1080 }));
+        mpv.fake_property(
+            "chapter-list",
+            json!([
+                { "title": "Opening", "time": 0. },
+                { "title": "The Meadow", "time": 90. },
+                { "title": "Trouble", "time": 250. },
+                { "title": "Revenge", "time": 420. },
+                { "title": "Credits", "time": 540. },
+            ]),
+        );
+        mpv.fake_property(
+            "track-list",
+            json!([
+                { "type": "video", "id": 1, "codec": "h264", "selected": true },
+                { "type": "audio", "id": 1, "lang": "eng", "codec": "aac", "selected": true },
+                { "type": "audio", "id": 2, "lang": "jpn", "codec": "opus" },
+                { "type": "sub", "id": 1, "lang": "eng", "title": "Full", "selected": true },
+                { "type": "sub", "id": 2, "lang": "nld" },
+            ]),
+        );
+        mpv.fake_property(
+            "playlist",
+            json!([
+                {
+                    "filename": format!("/demo/{TITLE}.mkv"),
+                    "title": TITLE,
+                    "id": 1,
+                    "playing": true,
+                    "current": true,
+                },
+                { "filename": "/demo/Sintel.mkv", "id": 2 },
+                { "filename": "/demo/Tears of Steel.mkv", "id": 3 },
+            ]),
+        );
+
+        Self { started: Instant::now(), step: None }
+    }
+
+    /// Advances the made-up playback, and moves on to the next view when it's time.
+    pub fn update(&mut self, app: &mut App) {
+        let elapsed = self.started.elapsed();
+
+        let time = (START + elapsed.as_secs_f32()) % DURATION;
+        app.mpv.fake_property("time-pos", json!(time));
+        app.mpv
+            .fake_property("percent-pos", json!(time / DURATION * 100.));
+
+        let step = (elapsed.as_secs_f32() / STEP.as_secs_f32()) as usize % STEPS;
+        if self.step == Some(step) {
+            return;
+        }
+        self.step = Some(step);
+
+        match step {
+            0 => {
+                app.change_view(HiddenView);
+                app.toasts
+                    .push(SpawnedToast::new(Toast::Queued { name: "Tears of Steel".to_string() }));
+            }
+            1 => app.change_view(SeekBarView),
+            2 => app.change_view(MiniSeekView),
+            3 => app.change_view(HomeMenuView::main()),
+            4 => app.change_view(HomeMenuView::library()),
+            5 => app.change_view(MediaMenuView::main()),
+            _ => app.change_view(MediaMenuView::performance()),
+        }
+    }
+}
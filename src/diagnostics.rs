@@ -0,0 +1,84 @@
+//! How the parts that talk to hardware and the network came up, so that one failing shows up in
+//! the Diagnostics menu instead of taking the whole overlay down.
+
+use egui::Color32;
+
+use crate::{App, BLUE};
+
+#[derive(Clone, Debug)]
+pub enum Status {
+    Ok,
+    /// Turned off in the config.
+    Off,
+    /// Running, but missing something.
+    Degraded(String),
+    Failed(String),
+}
+
+impl Status {
+    /// Whether there's nothing to look into.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Status::Ok | Status::Off)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Status::Ok => "OK",
+            Status::Off => "Off",
+            Status::Degraded(_) => "Degraded",
+            Status::Failed(_) => "Failed",
+        }
+    }
+
+    pub fn color(&self) -> Color32 {
+        match self {
+            Status::Ok => BLUE,
+            Status::Off => Color32::GRAY,
+            Status::Degraded(_) => Color32::YELLOW,
+            Status::Failed(_) => Color32::LIGHT_RED,
+        }
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            Status::Ok | Status::Off => None,
+            Status::Degraded(reason) | Status::Failed(reason) => Some(reason),
+        }
+    }
+}
+
+/// The parts that are allowed to fail at startup without stopping the overlay.
+#[derive(Clone, Copy, Debug)]
+pub enum Subsystem {
+    Controllers,
+    Dlna,
+    Cec,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 3] = [Subsystem::Controllers, Subsystem::Dlna, Subsystem::Cec];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Subsystem::Controllers => "Controllers",
+            Subsystem::Dlna => "DLNA",
+            Subsystem::Cec => "HDMI-CEC",
+        }
+    }
+
+    pub fn status(self, app: &App) -> &Status {
+        match self {
+            Subsystem::Controllers => app.gamepad.status(),
+            Subsystem::Dlna => app.dlna.status(),
+            Subsystem::Cec => app.cec.status(),
+        }
+    }
+
+    pub fn retry(self, app: &mut App) {
+        match self {
+            Subsystem::Controllers => app.gamepad.retry(),
+            Subsystem::Dlna => app.dlna.retry(),
+            Subsystem::Cec => app.cec.retry(),
+        }
+    }
+}
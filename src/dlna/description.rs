@@ -4,6 +4,10 @@ use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
 pub struct Root {
+    /// What relative URLs in the description are relative to, if not the description's own
+    /// location. Only older devices still send it.
+    #[serde(rename = "URLBase")]
+    pub url_base: Option<String>,
     pub device: Device,
 }
 
@@ -1,96 +1,711 @@
-use core::net::{Ipv4Addr, SocketAddrV4};
-use std::{io::ErrorKind, net::UdpSocket};
+use core::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::{
+    collections::HashSet,
+    io::{self, ErrorKind},
+    net::UdpSocket,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
 
 use ehttp::Request;
 use http::Uri;
-
-use crate::{command::Event, ui::toast::Toast};
+use serde::Deserialize;
+use url::Url;
+
+use self::{
+    renderer::Renderer,
+    search::{Notify, NotifyKind},
+    serve::FileServer,
+    soap::Action,
+};
+use crate::{
+    command::Event,
+    config::config,
+    diagnostics::Status,
+    mpv::{Mpv, time::Time},
+    store::settings::{Settings, VolumeTarget},
+    ui::toast::Toast,
+};
 
 mod description;
+mod renderer;
 mod search;
+mod serve;
+mod soap;
 
 pub struct Dlna {
-    socket: UdpSocket,
+    /// `None` if setting up discovery failed, see `status`.
+    socket: Option<UdpSocket>,
+    /// Hears devices announcing themselves and leaving. `None` if something else on this machine
+    /// already has the SSDP port, then devices are only found and refreshed by searching.
+    announcements: Option<UdpSocket>,
+    status: Status,
+    searched_at: Instant,
     devices: Vec<DlnaDevice>,
+    /// UDNs of devices whose description is being fetched on a background thread, so their
+    /// repeated responses don't start more fetches.
+    fetching: HashSet<String>,
+    fetched: Receiver<(String, Result<DlnaDevice, String>)>,
+    fetched_tx: Sender<(String, Result<DlnaDevice, String>)>,
+    polled_at: Instant,
+    /// UDNs of devices whose volume is being asked for, so a device that hangs doesn't pile up
+    /// threads.
+    polling: HashSet<String>,
+    polled: Receiver<Polled>,
+    polled_tx: Sender<Polled>,
+    transport_polled_at: Instant,
+    /// Whether the remote's device is being asked how playback is going.
+    transport_polling: bool,
+    transport_polled: Receiver<TransportPolled>,
+    transport_polled_tx: Sender<TransportPolled>,
+    server: Option<FileServer>,
+    cast: Option<Cast>,
+    remote: Option<Remote>,
+    /// Which device the volume buttons control, kept in sync with the settings.
+    volume_target: VolumeTarget,
+    /// UDN of the device found last, for its toast's offer to use it for volume.
+    found: Option<String>,
+    /// UDNs of every device found this session. Only the first time one shows up does its toast
+    /// offer to use it for volume, so one that keeps coming and going doesn't keep taking A.
+    seen: HashSet<String>,
+    /// Us as a device for others to cast to, if that's turned on.
+    renderer: Option<Renderer>,
+}
+
+/// Audio of the playing file going to a speaker while mpv plays it muted.
+struct Cast {
+    /// `None` once the device is gone, for `update_cast` to stop casting.
+    device: Option<usize>,
+    path: String,
+    paused: bool,
+    /// Speaker offset the speaker was last seeked with.
+    delay: f32,
+    /// mpv's position as of the last frame, to notice seeks.
+    position: Time,
+    checked_at: Instant,
 }
 
+/// A file from the library playing on a renderer by itself, instead of in mpv.
+struct Remote {
+    device: usize,
+    path: PathBuf,
+    paused: bool,
+    /// When it was last paused, resumed or started from here, so a poll that was already on its
+    /// way doesn't undo it.
+    changed_at: Instant,
+    /// Whether the device has reported playing it yet. Until then it can still be stopped from
+    /// before it got the file.
+    started: bool,
+}
+
+/// A jump in position bigger than this is a seek that the speaker has to follow.
+const CAST_RESYNC_THRESHOLD: f32 = 2.;
+
 const SSDP_ADDR: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
 const SSDP_PORT: u16 = 1900;
 
+/// How often to search for devices again, to notice ones that came up quietly and keep the ones
+/// that don't announce themselves from expiring. Sooner if a device's max-age needs it, see
+/// [`Dlna::search_every`].
+const SEARCH_EVERY: Duration = Duration::from_secs(120);
+/// Searching more often than this for a device with a tiny max-age would flood the network.
+const MIN_SEARCH_EVERY: Duration = Duration::from_secs(5);
+
+/// How often to ask devices for their volume, which can also be changed with their own remote.
+const POLL_VOLUME_EVERY: Duration = Duration::from_secs(5);
+
+/// How often to ask the device playing a file by itself how it's going, since it can be paused
+/// from elsewhere or reach the end.
+const POLL_TRANSPORT_EVERY: Duration = Duration::from_secs(2);
+
 impl Dlna {
     pub fn new() -> Self {
-        let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0))
-            .expect("Failed to bind UDP socket");
+        let (fetched_tx, fetched) = mpsc::channel();
+        let (polled_tx, polled) = mpsc::channel();
+        let (transport_polled_tx, transport_polled) = mpsc::channel();
+        let mut this = Dlna {
+            socket: None,
+            announcements: None,
+            status: Status::Ok,
+            searched_at: Instant::now(),
+            devices: Vec::new(),
+            fetching: HashSet::new(),
+            fetched,
+            fetched_tx,
+            polled_at: Instant::now(),
+            polling: HashSet::new(),
+            polled,
+            polled_tx,
+            transport_polled_at: Instant::now(),
+            transport_polling: false,
+            transport_polled,
+            transport_polled_tx,
+            server: None,
+            cast: None,
+            remote: None,
+            volume_target: VolumeTarget::default(),
+            found: None,
+            seen: HashSet::new(),
+            renderer: config()
+                .dlna_renderer
+                .enabled
+                .then(|| {
+                    Renderer::start()
+                        .inspect_err(|e| eprintln!("[DLNA] Failed to start the renderer: {e}"))
+                        .ok()
+                })
+                .flatten(),
+        };
+        this.retry();
+        this
+    }
 
-        socket
-            .set_nonblocking(true)
-            .expect("Failed to set non-blocking");
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
 
-        socket.set_broadcast(true).expect("Failed to set broadcast");
+    /// Sets up discovery again and searches for devices.
+    pub fn retry(&mut self) {
+        let socket = match open_socket() {
+            Ok(socket) => socket,
+            Err(e) => {
+                eprintln!("[DLNA] Failed to set up discovery: {e}");
+                self.socket = None;
+                self.status = Status::Failed(e.to_string());
+                return;
+            }
+        };
+        self.socket = Some(socket);
 
-        socket.set_multicast_ttl_v4(2).expect("Failed to set TTL");
+        if self.announcements.is_none() {
+            self.announcements = open_announcements_socket()
+                .inspect_err(|e| eprintln!("[DLNA] Not listening for announcements: {e}"))
+                .ok();
+        }
 
-        socket
-            .join_multicast_v4(&SSDP_ADDR, &Ipv4Addr::UNSPECIFIED)
-            .expect("Failed to join multicast group");
+        self.search();
+    }
 
-        socket
-            .send_to(search::M_SEARCH, (SSDP_ADDR, SSDP_PORT))
-            .expect("Failed to send M-SEARCH message");
+    /// Twice per the shortest max-age of the devices, so none of them expire between searches
+    /// and then come back.
+    fn search_every(&self) -> Duration {
+        self.devices
+            .iter()
+            .map(|d| d.max_age / 2)
+            .min()
+            .unwrap_or(SEARCH_EVERY)
+            .clamp(MIN_SEARCH_EVERY, SEARCH_EVERY)
+    }
 
-        Dlna { socket, devices: Vec::new() }
+    fn search(&mut self) {
+        let Some(socket) = &self.socket else {
+            return;
+        };
+
+        self.searched_at = Instant::now();
+        self.status = match socket.send_to(search::M_SEARCH, (SSDP_ADDR, SSDP_PORT)) {
+            Ok(_) => Status::Ok,
+            Err(e) => {
+                eprintln!("[DLNA] Failed to send M-SEARCH message: {e}");
+                Status::Degraded(format!("Couldn't search for devices: {e}"))
+            }
+        };
     }
 
     pub fn update(&mut self, events: &mut Vec<Event>) {
-        let mut buf = [0; 2048];
+        if self.socket.is_none() {
+            return;
+        }
 
-        loop {
-            match self.socket.recv_from(&mut buf) {
-                Ok((size, address)) => {
-                    eprintln!("[DLNA] Received {} bytes from {}", size, address);
-                    let msg = &buf[..size];
-                    let Some(notify) = search::Notify::from_response(msg) else {
-                        eprintln!("[DLNA] Failed to parse NOTIFY message");
-                        continue;
-                    };
+        if self.searched_at.elapsed() > self.search_every() {
+            self.search();
+        }
 
-                    let res = ehttp::fetch_blocking(&Request::get(&notify.location))
-                        .expect("Failed to fetch device description");
+        let mut notifies = Vec::new();
+        let mut searches = Vec::new();
+        for socket in self.socket.iter().chain(&self.announcements) {
+            receive(socket, &mut notifies, &mut searches);
+        }
 
-                    assert_eq!(res.status, 200, "Failed to fetch device description");
+        if let Some(renderer) = &self.renderer {
+            for (target, from) in searches {
+                renderer.answer(&target, from);
+            }
+        }
 
-                    let root =
-                        quick_xml::de::from_reader::<_, description::Root>(res.bytes.as_slice())
-                            .expect("Failed to parse device description");
+        for notify in notifies {
+            self.handle(notify, events);
+        }
 
-                    let name = root.device.friendly_name.clone();
+        for (udn, result) in self.fetched.try_iter() {
+            self.fetching.remove(&udn);
 
-                    let mut device = DlnaDevice {
-                        description: root,
-                        location: notify.location,
-                        volume: 0,
-                    };
+            match result {
+                Ok(device) => {
+                    let name = device.friendly_name().to_string();
+                    let first_time = self.seen.insert(device.udn().to_string());
+                    self.found = Some(device.udn().to_string());
+                    self.devices.push(device);
 
-                    device.get_volume();
+                    let offer_volume =
+                        first_time && self.volume_device() != Some(self.devices.len() - 1);
+                    events.push(Event::Toast(Toast::DlnaDeviceDiscovered { name, offer_volume }));
+                }
+                // it'll be tried again when it answers the next search
+                Err(e) => eprintln!("[DLNA] Failed to set up {udn}: {e}"),
+            }
+        }
 
-                    self.devices.push(device);
+        self.poll_volume();
+        self.poll_transport();
+
+        let now = Instant::now();
+        while let Some(idx) = self.devices.iter().position(|d| d.expires_at < now) {
+            eprintln!("[DLNA] {} expired", self.devices[idx].friendly_name());
+            self.remove(idx, events);
+        }
+    }
+
+    fn handle(&mut self, notify: Notify, events: &mut Vec<Event>) {
+        // our own announcements and answers come back around
+        if self
+            .renderer
+            .as_ref()
+            .is_some_and(|r| r.udn() == notify.udn)
+        {
+            return;
+        }
+
+        let known = self.devices.iter().position(|d| d.udn == notify.udn);
 
-                    events.push(Event::Toast(Toast::DlnaDeviceDiscovered { name }));
+        match (notify.kind, known) {
+            (NotifyKind::Alive { location, max_age }, Some(idx)) => {
+                let device = &mut self.devices[idx];
+                device.expires_at = Instant::now() + max_age;
+                device.max_age = max_age;
+                device.location = location;
+            }
+            (NotifyKind::Alive { location, max_age }, None) => {
+                if !self.fetching.insert(notify.udn.clone()) {
+                    return;
                 }
-                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
-                    break;
+
+                let tx = self.fetched_tx.clone();
+                std::thread::spawn(move || {
+                    let result = DlnaDevice::fetch(notify.udn.clone(), location, max_age);
+                    tx.send((notify.udn, result)).ok();
+                });
+            }
+            (NotifyKind::ByeBye, Some(idx)) => {
+                eprintln!("[DLNA] {} left", self.devices[idx].friendly_name());
+                self.remove(idx, events);
+            }
+            (NotifyKind::ByeBye, None) => {}
+        }
+    }
+
+    fn poll_volume(&mut self) {
+        for polled in self.polled.try_iter() {
+            self.polling.remove(&polled.udn);
+
+            if let Some((volume, muted)) = polled.volume
+                && let Some(device) = self.devices.iter_mut().find(|d| d.udn == polled.udn)
+                && device.changed_at < polled.at
+            {
+                device.volume = volume;
+                device.muted = muted;
+            }
+        }
+
+        if self.polled_at.elapsed() < POLL_VOLUME_EVERY {
+            return;
+        }
+        self.polled_at = Instant::now();
+
+        for device in &self.devices {
+            if !self.polling.insert(device.udn.clone()) {
+                continue;
+            }
+
+            let udn = device.udn.clone();
+            let url = device.control_url("RenderingControl");
+            let tx = self.polled_tx.clone();
+
+            std::thread::spawn(move || {
+                let at = Instant::now();
+                let volume = get_volume(&url)
+                    .inspect_err(|e| eprintln!("[DLNA] Failed to poll volume of {udn}: {e}"))
+                    .ok();
+                tx.send(Polled { udn, at, volume }).ok();
+            });
+        }
+    }
+
+    /// Keeps the remote's pause state up to date, and forgets it once the device stops playing.
+    fn poll_transport(&mut self) {
+        for polled in self.transport_polled.try_iter() {
+            self.transport_polling = false;
+
+            let Some(remote) = &mut self.remote else {
+                continue;
+            };
+            if self.devices[remote.device].udn != polled.udn || remote.changed_at > polled.at {
+                continue;
+            }
+
+            match polled.state.as_deref() {
+                Some("PLAYING" | "TRANSITIONING") => {
+                    remote.paused = false;
+                    remote.started = true;
                 }
-                Err(e) => {
-                    eprintln!("Error receiving from socket: {}", e);
-                    break;
+                Some("PAUSED_PLAYBACK") => {
+                    remote.paused = true;
+                    remote.started = true;
+                }
+                Some("STOPPED" | "NO_MEDIA_PRESENT") if remote.started => {
+                    eprintln!(
+                        "[DLNA] {} stopped playing",
+                        self.devices[remote.device].friendly_name()
+                    );
+                    self.remote = None;
+                    if let Some(server) = &self.server {
+                        server.stop();
+                    }
                 }
+                _ => {}
+            }
+        }
+
+        let Some(remote) = &self.remote else {
+            return;
+        };
+
+        if self.transport_polling || self.transport_polled_at.elapsed() < POLL_TRANSPORT_EVERY {
+            return;
+        }
+        self.transport_polled_at = Instant::now();
+        self.transport_polling = true;
+
+        let device = &self.devices[remote.device];
+        let udn = device.udn.clone();
+        let url = device.control_url("AVTransport");
+        let tx = self.transport_polled_tx.clone();
+
+        std::thread::spawn(move || {
+            let at = Instant::now();
+            let state = get_transport_state(&url)
+                .inspect_err(|e| eprintln!("[DLNA] Failed to poll playback of {udn}: {e}"))
+                .ok();
+            tx.send(TransportPolled { udn, at, state }).ok();
+        });
+    }
+
+    /// Forgets a device, keeping the indices everything else holds on to pointing at the right
+    /// devices.
+    fn remove(&mut self, idx: usize, events: &mut Vec<Event>) {
+        let device = self.devices.remove(idx);
+
+        if let Some(cast) = &mut self.cast {
+            cast.device = match cast.device {
+                Some(d) if d == idx => None,
+                Some(d) if d > idx => Some(d - 1),
+                d => d,
+            };
+        }
+
+        if self.remote.as_ref().is_some_and(|r| r.device == idx) {
+            self.remote = None;
+            if let Some(server) = &self.server {
+                server.stop();
             }
+        } else if let Some(remote) = &mut self.remote
+            && remote.device > idx
+        {
+            remote.device -= 1;
         }
+
+        events.push(Event::Toast(Toast::DlnaDeviceLost {
+            name: device.friendly_name().to_string(),
+        }));
     }
 
     pub fn devices(&mut self) -> &mut [DlnaDevice] {
         &mut self.devices
     }
+
+    pub fn has_devices(&self) -> bool {
+        !self.devices.is_empty()
+    }
+
+    pub fn volume_target(&self) -> &VolumeTarget {
+        &self.volume_target
+    }
+
+    pub fn set_volume_target(&mut self, target: VolumeTarget) {
+        self.volume_target = target;
+    }
+
+    /// UDN of the device found last, if it's still around.
+    pub fn found(&self) -> Option<&str> {
+        self.found
+            .as_deref()
+            .filter(|udn| self.devices.iter().any(|d| d.udn() == *udn))
+    }
+
+    /// The device the volume buttons control, or `None` for mpv.
+    pub fn volume_device(&self) -> Option<usize> {
+        match &self.volume_target {
+            VolumeTarget::FirstRenderer => self.has_devices().then_some(0),
+            VolumeTarget::Mpv => None,
+            VolumeTarget::Renderer(udn) => self.devices.iter().position(|d| d.udn == *udn),
+        }
+    }
+
+    /// The device the audio is being cast to.
+    pub fn casting(&self) -> Option<usize> {
+        self.cast.as_ref().and_then(|c| c.device)
+    }
+
+    /// Sends the audio of the file mpv is playing to a device, and mutes mpv.
+    pub fn cast(
+        &mut self,
+        device: usize,
+        mpv: &mut Mpv,
+        settings: &Settings,
+    ) -> Result<(), String> {
+        self.stop_cast(mpv);
+        self.stop_remote();
+
+        let path = mpv
+            .get_property_cached::<String>("path")
+            .ok_or("Nothing is playing")?;
+
+        let uri = if Path::new(&path).is_file() {
+            self.serve(device, Path::new(&path))?
+        } else if path.starts_with("http://") || path.starts_with("https://") {
+            // the device has to be able to play the URL itself, which rules out YouTube pages
+            path.clone()
+        } else {
+            return Err("Can't cast this file".to_string());
+        };
+
+        let dev = self.devices.get(device).ok_or("Device is gone")?;
+        let delay = settings.audio_delay(&dev.delay_key());
+        let position = mpv.time_pos_fallback();
+        dev.play_uri(&uri, cast_target(position, delay));
+
+        let paused = mpv.get_property::<bool>("pause");
+        if paused {
+            dev.pause();
+        }
+
+        mpv.set_property("mute", true).ok();
+
+        self.cast = Some(Cast {
+            device: Some(device),
+            path,
+            paused,
+            delay,
+            position,
+            checked_at: Instant::now(),
+        });
+
+        Ok(())
+    }
+
+    pub fn stop_cast(&mut self, mpv: &mut Mpv) {
+        let Some(cast) = self.cast.take() else {
+            return;
+        };
+
+        if let Some(device) = cast.device.and_then(|d| self.devices.get(d)) {
+            device.stop();
+        }
+        if let Some(server) = &self.server {
+            server.stop();
+        }
+
+        mpv.set_property("mute", false).ok();
+    }
+
+    /// The URL a device can fetch a local file from, starting the file server if it isn't yet.
+    /// It only serves one file at a time, so this replaces whatever was being cast before.
+    fn serve(&mut self, device: usize, path: &Path) -> Result<String, String> {
+        let peer = self
+            .devices
+            .get(device)
+            .ok_or("Device is gone")?
+            .ip()
+            .ok_or("Device has no IP address")?;
+
+        if self.server.is_none() {
+            self.server = Some(FileServer::start().map_err(|e| e.to_string())?);
+        }
+
+        self.server
+            .as_ref()
+            .unwrap()
+            .serve(path, peer)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Plays a file on a device instead of in mpv, from the start. Stops casting the audio of
+    /// whatever mpv is playing, since the file server can only serve one of them.
+    pub fn play_on(&mut self, device: usize, path: &Path, mpv: &mut Mpv) -> Result<(), String> {
+        self.stop_cast(mpv);
+        self.stop_remote();
+
+        let uri = self.serve(device, path)?;
+        self.devices[device].play_uri(&uri, Time::ZERO);
+
+        self.remote = Some(Remote {
+            device,
+            path: path.to_path_buf(),
+            paused: false,
+            changed_at: Instant::now(),
+            started: false,
+        });
+
+        Ok(())
+    }
+
+    /// The device a file is playing on by itself, and which file.
+    pub fn playing_on(&self) -> Option<(usize, &Path)> {
+        let remote = self.remote.as_ref()?;
+        Some((remote.device, &remote.path))
+    }
+
+    pub fn remote_paused(&self) -> bool {
+        self.remote.as_ref().is_some_and(|r| r.paused)
+    }
+
+    pub fn toggle_remote_pause(&mut self) {
+        let Some(remote) = &mut self.remote else {
+            return;
+        };
+        let device = &self.devices[remote.device];
+
+        remote.paused = !remote.paused;
+        remote.changed_at = Instant::now();
+        if remote.paused {
+            device.pause();
+        } else {
+            device.play();
+        }
+    }
+
+    pub fn stop_remote(&mut self) {
+        let Some(remote) = self.remote.take() else {
+            return;
+        };
+
+        self.devices[remote.device].stop();
+        if let Some(server) = &self.server {
+            server.stop();
+        }
+    }
+
+    /// Passes on what controllers casting to us asked for.
+    pub fn update_renderer(&mut self, mpv: &mut Mpv, events: &mut Vec<Event>) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.update(mpv, events);
+        }
+    }
+
+    /// Keeps the speaker following mpv's pauses and seeks, and the offset set for it.
+    pub fn update_cast(&mut self, mpv: &mut Mpv, settings: &Settings) {
+        let Some(cast) = &mut self.cast else {
+            return;
+        };
+
+        if mpv.get_property_cached::<String>("path").as_ref() != Some(&cast.path) {
+            self.stop_cast(mpv);
+            return;
+        }
+
+        let Some(device) = cast.device.and_then(|d| self.devices.get(d)) else {
+            self.stop_cast(mpv);
+            return;
+        };
+
+        let paused = mpv.get_property::<bool>("pause");
+        if paused != cast.paused {
+            cast.paused = paused;
+            if paused {
+                device.pause();
+            } else {
+                device.play();
+            }
+        }
+
+        let position = mpv.time_pos_fallback();
+        let expected = if paused {
+            cast.position
+        } else {
+            cast.position + Time::seconds(cast.checked_at.elapsed().as_secs_f32())
+        };
+        let delay = settings.audio_delay(&device.delay_key());
+
+        if (position - expected).as_secs().abs() > CAST_RESYNC_THRESHOLD || delay != cast.delay {
+            device.seek(cast_target(position, delay));
+            cast.delay = delay;
+        }
+
+        cast.position = position;
+        cast.checked_at = Instant::now();
+    }
+}
+
+/// Where the speaker should be when mpv is at `position`. Positive delays make the speaker
+/// later, like mpv's `audio-delay`.
+fn cast_target(position: Time, delay: f32) -> Time {
+    Time::seconds((position.as_secs() - delay).max(0.))
+}
+
+/// A socket that hears SSDP announcements and can send searches.
+fn open_socket() -> io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_nonblocking(true)?;
+    socket.set_broadcast(true)?;
+    socket.set_multicast_ttl_v4(2)?;
+    socket.join_multicast_v4(&SSDP_ADDR, &Ipv4Addr::UNSPECIFIED)?;
+    Ok(socket)
+}
+
+/// A socket on the SSDP port itself, where devices send their `ssdp:alive` and `ssdp:byebye`
+/// announcements.
+fn open_announcements_socket() -> io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, SSDP_PORT))?;
+    socket.set_nonblocking(true)?;
+    socket.join_multicast_v4(&SSDP_ADDR, &Ipv4Addr::UNSPECIFIED)?;
+    Ok(socket)
+}
+
+/// Everything that came in on a socket since the last frame, and what others searched for.
+fn receive(
+    socket: &UdpSocket,
+    notifies: &mut Vec<Notify>,
+    searches: &mut Vec<(String, SocketAddr)>,
+) {
+    let mut buf = [0; 2048];
+
+    loop {
+        match socket.recv_from(&mut buf) {
+            Ok((size, address)) => match search::search_target(&buf[..size]) {
+                Some(target) => searches.push((target, address)),
+                // ignore whatever isn't about a renderer
+                None => notifies.extend(Notify::from_response(&buf[..size])),
+            },
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                break;
+            }
+            Err(e) => {
+                eprintln!("Error receiving from socket: {}", e);
+                break;
+            }
+        }
+    }
 }
 
 impl Default for Dlna {
@@ -102,14 +717,73 @@ impl Default for Dlna {
 pub struct DlnaDevice {
     description: description::Root,
     location: Uri,
+    /// Identifies the device across responses and announcements, one device can send several.
+    udn: String,
+    /// When the device should be considered gone, unless it's heard from again before then.
+    expires_at: Instant,
+    /// How long it said it's good for after it's heard from.
+    max_age: Duration,
     volume: u8,
+    muted: bool,
+    /// When the volume or mute was last changed from here, so a poll that was already on its
+    /// way doesn't undo it.
+    changed_at: Instant,
+}
+
+/// Volume and mute as a device reported them.
+struct Polled {
+    udn: String,
+    /// When the poll was sent.
+    at: Instant,
+    /// `None` if the device couldn't be asked.
+    volume: Option<(u8, bool)>,
+}
+
+/// AVTransport's state as the device playing a file by itself reported it.
+struct TransportPolled {
+    udn: String,
+    /// When the poll was sent.
+    at: Instant,
+    /// Like `PLAYING` or `PAUSED_PLAYBACK`, `None` if the device couldn't be asked.
+    state: Option<String>,
 }
 
 impl DlnaDevice {
+    /// Gets a newly found device's description and volume. Blocks, so it's called from a
+    /// background thread.
+    fn fetch(udn: String, location: Uri, max_age: Duration) -> Result<Self, String> {
+        let res = ehttp::fetch_blocking(&Request::get(&location))?;
+        if !res.ok {
+            return Err(format!("description: {} {}", res.status, res.status_text));
+        }
+
+        let description = quick_xml::de::from_reader(res.bytes.as_slice())
+            .map_err(|e| format!("description: {e}"))?;
+
+        let mut device = DlnaDevice {
+            description,
+            location,
+            udn,
+            expires_at: Instant::now() + max_age,
+            max_age,
+            volume: 0,
+            muted: false,
+            changed_at: Instant::now(),
+        };
+
+        (device.volume, device.muted) = get_volume(&device.control_url("RenderingControl"))?;
+
+        Ok(device)
+    }
+
     pub fn friendly_name(&self) -> &str {
         &self.description.device.friendly_name
     }
 
+    pub fn udn(&self) -> &str {
+        &self.udn
+    }
+
     #[expect(dead_code)]
     pub fn icons(&self) -> &[description::Icon] {
         &self.description.device.icon_list
@@ -124,78 +798,175 @@ impl DlnaDevice {
         self.volume
     }
 
+    /// Key for this device's offset in [`Settings::audio_delays`], next to mpv's audio devices.
+    pub fn delay_key(&self) -> String {
+        format!("dlna:{}", self.friendly_name())
+    }
+
+    fn ip(&self) -> Option<IpAddr> {
+        self.location.host()?.parse().ok()
+    }
+
+    /// Control URL of a service like `AVTransport`, as the device description lists it. It can
+    /// be relative to where the description is, or absolute, or on another port entirely.
+    fn control_url(&self, service: &str) -> String {
+        let service_type = format!("urn:schemas-upnp-org:service:{service}:");
+        let url = self
+            .description
+            .device
+            .service_list
+            .iter()
+            .find(|s| s.service_type.starts_with(&service_type))
+            .map_or_else(
+                // what most renderers that leave it out use
+                || format!("/upnp/control/{service}1"),
+                |s| s.control_url.trim().to_string(),
+            );
+
+        let base = self
+            .description
+            .url_base
+            .clone()
+            .unwrap_or_else(|| self.location.to_string());
+
+        Url::parse(&base)
+            .and_then(|base| base.join(&url))
+            .map_or(url, String::from)
+    }
+
+    /// Calls actions in order on a background thread, giving up at the first one that fails.
+    fn send(&self, actions: Vec<Action>) {
+        let calls = actions
+            .into_iter()
+            .map(|action| (self.control_url(action.service), action))
+            .collect::<Vec<_>>();
+
+        std::thread::spawn(move || {
+            for (url, action) in calls {
+                if let Err(e) = action.call(&url) {
+                    eprintln!("[DLNA] {} failed: {e}", action.name);
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Starts playing a URL from `position`.
+    pub fn play_uri(&self, uri: &str, position: Time) {
+        self.send(vec![
+            Action::new("AVTransport", "SetAVTransportURI")
+                .arg("CurrentURI", uri)
+                .arg("CurrentURIMetaData", ""),
+            play(),
+            seek(position),
+        ]);
+    }
+
+    pub fn play(&self) {
+        self.send(vec![play()]);
+    }
+
+    pub fn pause(&self) {
+        self.send(vec![Action::new("AVTransport", "Pause")]);
+    }
+
+    pub fn seek(&self, position: Time) {
+        self.send(vec![seek(position)]);
+    }
+
+    pub fn stop(&self) {
+        self.send(vec![Action::new("AVTransport", "Stop")]);
+    }
+
     pub fn set_volume(&mut self, volume: u8) {
         let volume = volume.clamp(0, 100);
 
-        let req = r#"<?xml version="1.0" encoding="utf-8"?>
-<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
-  <s:Body>
-    <u:SetVolume xmlns:u="urn:schemas-upnp-org:service:RenderingControl:1">
-      <InstanceID>0</InstanceID>
-      <Channel>Master</Channel>
-      <DesiredVolume>%%VOL%%</DesiredVolume>
-    </u:SetVolume>
-  </s:Body>
-</s:Envelope>"#;
-
-        let (before, after) = req.split_once("%%VOL%%").unwrap();
-        let body = format!("{before}{volume}{after}");
-
-        let url = Uri::builder()
-            .scheme(self.location.scheme().unwrap().clone())
-            .authority(self.location.authority().unwrap().as_str())
-            .path_and_query("/upnp/control/RenderingControl1")
-            .build()
-            .unwrap();
-
-        let mut req = Request::post(url, body.into());
-        req.headers
-            .insert("Content-Type", "text/xml; charset=\"utf-8\"");
-        req.headers
-            .insert("SOAPACTION", "\"urn:schemas-upnp-org:service:RenderingControl:1#SetVolume\"");
-
-        ehttp::fetch(req, |res| eprintln!("SetVolume result: {:?}", res));
+        self.send(vec![
+            Action::new("RenderingControl", "SetVolume")
+                .arg("Channel", "Master")
+                .arg("DesiredVolume", volume),
+        ]);
 
         self.volume = volume;
+        self.changed_at = Instant::now();
+    }
+
+    pub fn muted(&self) -> bool {
+        self.muted
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.send(vec![
+            Action::new("RenderingControl", "SetMute")
+                .arg("Channel", "Master")
+                .arg("DesiredMute", u8::from(muted)),
+        ]);
+
+        self.muted = muted;
+        self.changed_at = Instant::now();
+    }
+}
+
+/// Volume and mute of a device's `RenderingControl`.
+fn get_volume(url: &str) -> Result<(u8, bool), String> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct GetVolumeResponse {
+        current_volume: u8,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct GetMuteResponse {
+        current_mute: String,
     }
 
-    pub fn get_volume(&mut self) {
-        let req = r#"<?xml version="1.0" encoding="utf-8"?>
-<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
-  <s:Body>
-    <u:GetVolume xmlns:u="urn:schemas-upnp-org:service:RenderingControl:1">
-      <InstanceID>0</InstanceID>
-      <Channel>Master</Channel>
-    </u:GetVolume>
-  </s:Body>
-</s:Envelope>"#;
-
-        let url = Uri::builder()
-            .scheme(self.location.scheme().unwrap().clone())
-            .authority(self.location.authority().unwrap().as_str())
-            .path_and_query("/upnp/control/RenderingControl1")
-            .build()
-            .unwrap();
-
-        let mut req = Request::post(url, req.into());
-        req.headers
-            .insert("Content-Type", "text/xml; charset=\"utf-8\"");
-        req.headers
-            .insert("SOAPACTION", "\"urn:schemas-upnp-org:service:RenderingControl:1#GetVolume\"");
-
-        let res = ehttp::fetch_blocking(&req).expect("Failed to fetch GetVolume");
-
-        assert_eq!(res.status, 200, "Failed to fetch GetVolume");
-
-        self.volume = std::str::from_utf8(&res.bytes)
-            .expect("GetVolume response not valid UTF-8")
-            .split_once("<CurrentVolume>")
-            .expect("Failed to find <CurrentVolume> in GetVolume response")
-            .1
-            .split_once("</CurrentVolume>")
-            .expect("Failed to find </CurrentVolume> in GetVolume response")
-            .0
-            .parse()
-            .expect("Failed to parse CurrentVolume from GetVolume response")
+    let volume = Action::new("RenderingControl", "GetVolume")
+        .arg("Channel", "Master")
+        .call_for::<GetVolumeResponse>(url)
+        .map_err(|e| format!("GetVolume: {e}"))?
+        .current_volume;
+
+    // booleans are `1` or `true`, depending on who made the device
+    let muted = Action::new("RenderingControl", "GetMute")
+        .arg("Channel", "Master")
+        .call_for::<GetMuteResponse>(url)
+        .map(|res| matches!(res.current_mute.trim(), "1" | "true"))
+        .map_err(|e| format!("GetMute: {e}"))?;
+
+    Ok((volume, muted))
+}
+
+/// `CurrentTransportState` of a device's `AVTransport`.
+fn get_transport_state(url: &str) -> Result<String, String> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct GetTransportInfoResponse {
+        current_transport_state: String,
     }
+
+    Action::new("AVTransport", "GetTransportInfo")
+        .call_for::<GetTransportInfoResponse>(url)
+        .map(|res| res.current_transport_state.trim().to_string())
+        .map_err(|e| format!("GetTransportInfo: {e}"))
+}
+
+fn play() -> Action {
+    Action::new("AVTransport", "Play").arg("Speed", 1)
+}
+
+fn seek(position: Time) -> Action {
+    Action::new("AVTransport", "Seek")
+        .arg("Unit", "REL_TIME")
+        .arg("Target", upnp_time(position))
+}
+
+/// A position formatted as `H:MM:SS.mmm`, the way AVTransport takes and reports them.
+fn upnp_time(position: Time) -> String {
+    let secs = position.as_secs().max(0.);
+    let hours = (secs / 3600.).floor();
+    let minutes = ((secs % 3600.) / 60.).floor();
+    let seconds = secs % 60.;
+
+    format!("{hours}:{minutes:02}:{seconds:06.3}")
 }
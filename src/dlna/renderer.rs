@@ -0,0 +1,558 @@
+//! The overlay as a MediaRenderer itself, so phones can cast to it with apps like BubbleUPnP or
+//! VLC, like they would to a smart TV. Controllers find it over SSDP, read its description from a
+//! small HTTP server, and call AVTransport and RenderingControl actions on it, which are passed on
+//! to mpv.
+//!
+//! There's no eventing, so controllers learn about the position and pauses by polling
+//! `GetPositionInfo` and `GetTransportInfo`, which they fall back to for renderers like this.
+
+use core::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead as _, BufReader, Read as _, Write as _},
+    net::{TcpListener, TcpStream, UdpSocket},
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use quick_xml::escape::{escape, unescape};
+
+use super::{SSDP_ADDR, SSDP_PORT, serve::local_ip_for, soap, upnp_time};
+use crate::{
+    command::Event,
+    config::config,
+    mpv::{Mpv, time::Time},
+    ui::toast::Toast,
+};
+
+/// How long controllers should remember us without hearing from us again.
+const MAX_AGE: Duration = Duration::from_secs(1800);
+/// How often to announce ourselves, well within `MAX_AGE` so a lost announcement doesn't make
+/// controllers forget us.
+const ANNOUNCE_EVERY: Duration = Duration::from_secs(300);
+/// How long a controller's call waits to be handled on the main thread before it fails.
+const CALL_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long a client gets to send its whole request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Control requests are small SOAP envelopes, anything much bigger isn't one.
+const MAX_BODY: usize = 64 * 1024;
+
+const DEVICE_TYPE: &str = "urn:schemas-upnp-org:device:MediaRenderer:1";
+const SERVICES: [&str; 3] = ["AVTransport", "RenderingControl", "ConnectionManager"];
+const SERVER: &str = "Linux UPnP/1.0 htpc-overlay/1.0";
+
+/// What can be cast to us, which is anything mpv plays.
+const SINK_PROTOCOLS: &str = "http-get:*:video/*:*,http-get:*:audio/*:*,http-get:*:image/*:*";
+
+/// A UPnP error code and description, from the service's spec.
+type Fault = (u32, &'static str);
+
+const INVALID_ACTION: Fault = (401, "Invalid Action");
+const INVALID_ARGS: Fault = (402, "Invalid Args");
+const ACTION_FAILED: Fault = (501, "Action Failed");
+const TRANSITION_NOT_AVAILABLE: Fault = (701, "Transition not available");
+const SEEK_MODE_NOT_SUPPORTED: Fault = (710, "Seek mode not supported");
+const ILLEGAL_SEEK_TARGET: Fault = (711, "Illegal seek target");
+const RESOURCE_NOT_FOUND: Fault = (716, "Resource not found");
+
+pub struct Renderer {
+    udn: String,
+    /// Of the HTTP server with the description and control URLs.
+    port: u16,
+    /// Sends announcements and answers searches.
+    socket: UdpSocket,
+    announced_at: Instant,
+    calls: Receiver<Call>,
+    /// What the controller last asked to play, and its DIDL-Lite metadata to hand back when
+    /// asked.
+    uri: Option<String>,
+    metadata: String,
+}
+
+/// An action a controller called, waiting on the HTTP server's thread for an answer.
+struct Call {
+    service: String,
+    action: String,
+    args: HashMap<String, String>,
+    reply: Sender<Result<Vec<(&'static str, String)>, Fault>>,
+}
+
+impl Renderer {
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0))?;
+        let port = listener.local_addr()?.port();
+
+        let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0))?;
+        socket.set_multicast_ttl_v4(2)?;
+
+        let udn = udn();
+        let description = description(&udn, &config().dlna_renderer.name);
+        let (calls_tx, calls) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let description = description.clone();
+                let calls = calls_tx.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = handle(stream, &description, &calls) {
+                        eprintln!("[DLNA] Failed to answer a controller: {e}");
+                    }
+                });
+            }
+        });
+
+        let mut this = Self {
+            udn,
+            port,
+            socket,
+            announced_at: Instant::now(),
+            calls,
+            uri: None,
+            metadata: String::new(),
+        };
+        this.announce();
+        Ok(this)
+    }
+
+    pub fn udn(&self) -> &str {
+        &self.udn
+    }
+
+    fn location(&self, peer: IpAddr) -> io::Result<String> {
+        Ok(format!("http://{}:{}/description.xml", local_ip_for(peer)?, self.port))
+    }
+
+    /// Every notification type we announce, with the USN that goes with it.
+    fn targets(&self) -> Vec<(String, String)> {
+        let udn = &self.udn;
+
+        ["upnp:rootdevice".to_string(), udn.clone(), DEVICE_TYPE.to_string()]
+            .into_iter()
+            .chain(SERVICES.map(|s| format!("urn:schemas-upnp-org:service:{s}:1")))
+            .map(|nt| {
+                let usn = if nt == *udn {
+                    udn.clone()
+                } else {
+                    format!("{udn}::{nt}")
+                };
+                (nt, usn)
+            })
+            .collect()
+    }
+
+    fn announce(&mut self) {
+        self.announced_at = Instant::now();
+
+        let location = match self.location(IpAddr::V4(SSDP_ADDR)) {
+            Ok(location) => location,
+            Err(e) => {
+                eprintln!("[DLNA] Not announcing the renderer: {e}");
+                return;
+            }
+        };
+
+        for (nt, usn) in self.targets() {
+            let message = format!(
+                "NOTIFY * HTTP/1.1\r\nHOST: {SSDP_ADDR}:{SSDP_PORT}\r\nCACHE-CONTROL: max-age={}\r\nLOCATION: {location}\r\nNT: {nt}\r\nNTS: ssdp:alive\r\nSERVER: {SERVER}\r\nUSN: {usn}\r\n\r\n",
+                MAX_AGE.as_secs(),
+            );
+            self.socket
+                .send_to(message.as_bytes(), (SSDP_ADDR, SSDP_PORT))
+                .inspect_err(|e| eprintln!("[DLNA] Failed to announce the renderer: {e}"))
+                .ok();
+        }
+    }
+
+    /// Answers an M-SEARCH that's looking for us, or for anything at all.
+    pub fn answer(&self, target: &str, from: SocketAddr) {
+        let location = match self.location(from.ip()) {
+            Ok(location) => location,
+            Err(e) => {
+                eprintln!("[DLNA] Not answering search from {from}: {e}");
+                return;
+            }
+        };
+
+        for (st, usn) in self.targets() {
+            if target != "ssdp:all" && target != st {
+                continue;
+            }
+
+            let message = format!(
+                "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age={}\r\nEXT:\r\nLOCATION: {location}\r\nSERVER: {SERVER}\r\nST: {st}\r\nUSN: {usn}\r\n\r\n",
+                MAX_AGE.as_secs(),
+            );
+            self.socket.send_to(message.as_bytes(), from).ok();
+        }
+    }
+
+    /// Handles the actions controllers called since the last frame.
+    pub fn update(&mut self, mpv: &mut Mpv, events: &mut Vec<Event>) {
+        if self.announced_at.elapsed() > ANNOUNCE_EVERY {
+            self.announce();
+        }
+
+        let calls = self.calls.try_iter().collect::<Vec<_>>();
+        for call in calls {
+            let reply = self.call(&call.service, &call.action, &call.args, mpv, events);
+            if let Err((code, description)) = reply {
+                eprintln!("[DLNA] {} from a controller failed: {code} {description}", call.action);
+            }
+            call.reply.send(reply).ok();
+        }
+    }
+
+    /// Whether what the controller asked to play is what mpv is playing, and not something that
+    /// was started here since.
+    fn is_loaded(&self, mpv: &Mpv) -> bool {
+        self.uri.is_some() && mpv.get_property_cached::<String>("path") == self.uri
+    }
+
+    fn transport_state(&self, mpv: &Mpv) -> &'static str {
+        if self.is_loaded(mpv) {
+            if mpv.get_property_cached::<bool>("pause") == Some(true) {
+                "PAUSED_PLAYBACK"
+            } else {
+                "PLAYING"
+            }
+        } else if self.uri.is_some() {
+            "STOPPED"
+        } else {
+            "NO_MEDIA_PRESENT"
+        }
+    }
+
+    fn call(
+        &mut self,
+        service: &str,
+        action: &str,
+        args: &HashMap<String, String>,
+        mpv: &mut Mpv,
+        events: &mut Vec<Event>,
+    ) -> Result<Vec<(&'static str, String)>, Fault> {
+        let arg = |name: &str| args.get(name).map(String::as_str).ok_or(INVALID_ARGS);
+        let failed = |e: io::Error| {
+            eprintln!("[DLNA] Failed to pass {action} on to mpv: {e}");
+            ACTION_FAILED
+        };
+
+        match (service, action) {
+            ("AVTransport", "SetAVTransportURI") => {
+                let uri = arg("CurrentURI")?;
+                if !uri.starts_with("http://") && !uri.starts_with("https://") {
+                    return Err(RESOURCE_NOT_FOUND);
+                }
+
+                // controllers call Play next, which is when it should start
+                mpv.pause().map_err(failed)?;
+                mpv.load_file(uri).map_err(failed)?;
+
+                self.uri = Some(uri.to_string());
+                self.metadata = arg("CurrentURIMetaData").unwrap_or_default().to_string();
+
+                events.push(Event::Toast(Toast::CastReceived {
+                    title: title(&self.metadata).unwrap_or_else(|| uri.to_string()),
+                }));
+                Ok(vec![])
+            }
+            ("AVTransport", "Play") => {
+                let uri = self.uri.clone().ok_or(TRANSITION_NOT_AVAILABLE)?;
+                if !self.is_loaded(mpv) {
+                    mpv.load_file(&uri).map_err(failed)?;
+                }
+                mpv.unpause().map_err(failed)?;
+                Ok(vec![])
+            }
+            ("AVTransport", "Pause") => {
+                if !self.is_loaded(mpv) {
+                    return Err(TRANSITION_NOT_AVAILABLE);
+                }
+                mpv.pause().map_err(failed)?;
+                Ok(vec![])
+            }
+            ("AVTransport", "Stop") => {
+                if self.is_loaded(mpv) {
+                    mpv.stop().map_err(failed)?;
+                }
+                Ok(vec![])
+            }
+            ("AVTransport", "Seek") => {
+                if !matches!(arg("Unit")?, "REL_TIME" | "ABS_TIME") {
+                    return Err(SEEK_MODE_NOT_SUPPORTED);
+                }
+                let target = parse_upnp_time(arg("Target")?).ok_or(ILLEGAL_SEEK_TARGET)?;
+                if !self.is_loaded(mpv) {
+                    return Err(TRANSITION_NOT_AVAILABLE);
+                }
+                mpv.seek_to(target).map_err(failed)?;
+                Ok(vec![])
+            }
+            ("AVTransport", "GetTransportInfo") => Ok(vec![
+                ("CurrentTransportState", self.transport_state(mpv).to_string()),
+                ("CurrentTransportStatus", "OK".to_string()),
+                ("CurrentSpeed", "1".to_string()),
+            ]),
+            ("AVTransport", "GetPositionInfo") => {
+                let loaded = self.is_loaded(mpv);
+                let (duration, position) = if loaded {
+                    (mpv.duration().unwrap_or(Time::ZERO), mpv.time_pos_fallback())
+                } else {
+                    (Time::ZERO, Time::ZERO)
+                };
+                let (uri, metadata) = match &self.uri {
+                    Some(uri) if loaded => (uri.clone(), self.metadata.clone()),
+                    _ => (String::new(), String::new()),
+                };
+
+                Ok(vec![
+                    ("Track", u8::from(loaded).to_string()),
+                    ("TrackDuration", upnp_time(duration)),
+                    ("TrackMetaData", metadata),
+                    ("TrackURI", uri),
+                    ("RelTime", upnp_time(position)),
+                    ("AbsTime", upnp_time(position)),
+                    ("RelCount", i32::MAX.to_string()),
+                    ("AbsCount", i32::MAX.to_string()),
+                ])
+            }
+            ("AVTransport", "GetMediaInfo") => {
+                let duration = if self.is_loaded(mpv) {
+                    mpv.duration().unwrap_or(Time::ZERO)
+                } else {
+                    Time::ZERO
+                };
+
+                Ok(vec![
+                    ("NrTracks", u8::from(self.uri.is_some()).to_string()),
+                    ("MediaDuration", upnp_time(duration)),
+                    ("CurrentURI", self.uri.clone().unwrap_or_default()),
+                    ("CurrentURIMetaData", self.metadata.clone()),
+                    ("NextURI", String::new()),
+                    ("NextURIMetaData", String::new()),
+                    ("PlayMedium", "NETWORK".to_string()),
+                    ("RecordMedium", "NOT_IMPLEMENTED".to_string()),
+                    ("WriteStatus", "NOT_IMPLEMENTED".to_string()),
+                ])
+            }
+            ("RenderingControl", "GetVolume") => {
+                let volume = mpv.get_property::<f64>("volume").round().clamp(0., 100.);
+                Ok(vec![("CurrentVolume", volume.to_string())])
+            }
+            ("RenderingControl", "SetVolume") => {
+                let volume = arg("DesiredVolume")?
+                    .parse::<u8>()
+                    .map_err(|_| INVALID_ARGS)?;
+                mpv.set_property("volume", volume.min(100))
+                    .map_err(failed)?;
+                Ok(vec![])
+            }
+            ("RenderingControl", "GetMute") => {
+                let muted = mpv.get_property::<bool>("mute");
+                Ok(vec![("CurrentMute", u8::from(muted).to_string())])
+            }
+            ("RenderingControl", "SetMute") => {
+                let muted = matches!(arg("DesiredMute")?, "1" | "true");
+                mpv.set_property("mute", muted).map_err(failed)?;
+                Ok(vec![])
+            }
+            ("ConnectionManager", "GetProtocolInfo") => {
+                Ok(vec![("Source", String::new()), ("Sink", SINK_PROTOCOLS.to_string())])
+            }
+            ("ConnectionManager", "GetCurrentConnectionIDs") => {
+                Ok(vec![("ConnectionIDs", "0".to_string())])
+            }
+            _ => Err(INVALID_ACTION),
+        }
+    }
+}
+
+impl Drop for Renderer {
+    /// Lets controllers know we're gone right away, instead of when `MAX_AGE` runs out.
+    fn drop(&mut self) {
+        for (nt, usn) in self.targets() {
+            let message = format!(
+                "NOTIFY * HTTP/1.1\r\nHOST: {SSDP_ADDR}:{SSDP_PORT}\r\nNT: {nt}\r\nNTS: ssdp:byebye\r\nUSN: {usn}\r\n\r\n",
+            );
+            self.socket
+                .send_to(message.as_bytes(), (SSDP_ADDR, SSDP_PORT))
+                .ok();
+        }
+    }
+}
+
+/// Stays the same across restarts, so controllers recognize us as the renderer they cast to
+/// before. Made from the machine ID rather than being it, since that's meant to stay private.
+fn udn() -> String {
+    let machine_id = std::fs::read_to_string("/etc/machine-id").unwrap_or_default();
+
+    // FNV-1a, twice over for enough bits
+    let hash = |seed: u64| {
+        format!("htpc-overlay renderer {}", machine_id.trim())
+            .bytes()
+            .fold(seed, |hash, b| (hash ^ u64::from(b)).wrapping_mul(0x100000001b3))
+    };
+    let id = format!("{:016x}{:016x}", hash(0xcbf29ce484222325), hash(0x84222325cbf29ce4));
+
+    format!("uuid:{}-{}-{}-{}-{}", &id[..8], &id[8..12], &id[12..16], &id[16..20], &id[20..],)
+}
+
+fn description(udn: &str, name: &str) -> String {
+    let services = SERVICES
+        .map(|s| {
+            format!(
+                "<service><serviceType>urn:schemas-upnp-org:service:{s}:1</serviceType><serviceId>urn:upnp-org:serviceId:{s}</serviceId><SCPDURL>/{s}.xml</SCPDURL><controlURL>/control/{s}</controlURL><eventSubURL>/event/{s}</eventSubURL></service>"
+            )
+        })
+        .concat();
+
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+  <specVersion><major>1</major><minor>0</minor></specVersion>
+  <device>
+    <deviceType>{DEVICE_TYPE}</deviceType>
+    <friendlyName>{}</friendlyName>
+    <manufacturer>htpc-overlay</manufacturer>
+    <modelName>htpc-overlay</modelName>
+    <UDN>{udn}</UDN>
+    <serviceList>{services}</serviceList>
+  </device>
+</root>"#,
+        escape(name),
+    )
+}
+
+/// A service's description, listing its actions.
+fn scpd(service: &str) -> Option<&'static str> {
+    match service {
+        "AVTransport" => Some(include_str!("../../assets/upnp/AVTransport.xml")),
+        "RenderingControl" => Some(include_str!("../../assets/upnp/RenderingControl.xml")),
+        "ConnectionManager" => Some(include_str!("../../assets/upnp/ConnectionManager.xml")),
+        _ => None,
+    }
+}
+
+/// The title out of DIDL-Lite metadata, which comes escaped inside the SOAP argument.
+fn title(metadata: &str) -> Option<String> {
+    let (_, rest) = metadata.split_once("<dc:title>")?;
+    let (title, _) = rest.split_once("</dc:title>")?;
+    Some(unescape(title).map_or_else(|_| title.to_string(), |t| t.into_owned()))
+}
+
+/// A position like `1:02:03` or `0:00:05.250`, as AVTransport takes them.
+fn parse_upnp_time(s: &str) -> Option<Time> {
+    let mut parts = s.trim().split(':').rev();
+    let seconds = parts.next()?.parse::<f64>().ok()?;
+    let minutes = parts.next().map_or(Some(0), |m| m.parse::<u32>().ok())?;
+    let hours = parts.next().map_or(Some(0), |h| h.parse::<u32>().ok())?;
+
+    // it's from the network, so in f64 to not overflow, and inf or NaN is no position at all
+    let total = f64::from(hours) * 3600. + f64::from(minutes) * 60. + seconds;
+    if parts.next().is_some() || seconds < 0. || !total.is_finite() {
+        return None;
+    }
+
+    Some(Time::seconds(total))
+}
+
+fn handle(stream: TcpStream, description: &str, calls: &Sender<Call>) -> io::Result<()> {
+    // a client that goes quiet would otherwise keep its thread forever
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let mut content_length = 0;
+    let mut soap_action = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("soapaction") {
+                soap_action = Some(value.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+
+    if content_length > MAX_BODY {
+        let mut stream = reader.into_inner();
+        return respond(&mut stream, "413 Payload Too Large", "");
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    let mut stream = reader.into_inner();
+
+    let service = path
+        .strip_prefix("/control/")
+        .filter(|s| SERVICES.contains(s));
+
+    match (method, path, service) {
+        ("GET", "/description.xml", _) => respond(&mut stream, "200 OK", description),
+        ("GET", _, _) => match path
+            .strip_prefix('/')
+            .and_then(|p| p.strip_suffix(".xml"))
+            .and_then(scpd)
+        {
+            Some(scpd) => respond(&mut stream, "200 OK", scpd),
+            None => respond(&mut stream, "404 Not Found", ""),
+        },
+        ("POST", _, Some(service)) => {
+            let action = soap_action
+                .as_deref()
+                .and_then(|a| a.split_once('#'))
+                .map(|(_, action)| action.to_string())
+                .unwrap_or_default();
+
+            let reply = match soap::parse_call(&body) {
+                Ok(args) => {
+                    let (reply_tx, reply) = mpsc::channel();
+                    calls
+                        .send(Call {
+                            service: service.to_string(),
+                            action: action.clone(),
+                            args,
+                            reply: reply_tx,
+                        })
+                        .ok();
+                    reply
+                        .recv_timeout(CALL_TIMEOUT)
+                        .unwrap_or(Err(ACTION_FAILED))
+                }
+                Err(e) => {
+                    eprintln!("[DLNA] Invalid call from a controller: {e}");
+                    Err(INVALID_ACTION)
+                }
+            };
+
+            match reply {
+                Ok(args) => {
+                    respond(&mut stream, "200 OK", &soap::response(service, &action, &args))
+                }
+                Err((code, description)) => respond(
+                    &mut stream,
+                    "500 Internal Server Error",
+                    &soap::fault(code, description),
+                ),
+            }
+        }
+        // controllers fall back to polling when they can't subscribe to events
+        _ => respond(&mut stream, "501 Not Implemented", ""),
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/xml; charset=\"utf-8\"\r\nContent-Length: {}\r\nEXT:\r\nServer: {SERVER}\r\nConnection: close\r\n\r\n{body}",
+        body.len(),
+    )
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use http::Uri;
 
 pub const M_SEARCH: &[u8] = b"M-SEARCH * HTTP/1.1\r
@@ -8,9 +10,27 @@ St: urn:schemas-upnp-org:device:MediaRenderer:1\r
 \r
 ";
 
+/// How long a device is assumed to stay around when it doesn't say, which is what UPnP
+/// recommends devices announce anyway.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(1800);
+
+/// A response to our M-SEARCH, or a device announcing itself or saying goodbye on its own.
 #[derive(Debug)]
 pub struct Notify {
-    pub location: Uri,
+    /// The device's UDN, the `uuid:...` part of the USN that's the same for every service it
+    /// announces.
+    pub udn: String,
+    pub kind: NotifyKind,
+}
+
+#[derive(Debug)]
+pub enum NotifyKind {
+    Alive {
+        location: Uri,
+        /// How long until the device should be considered gone if it's not heard from again.
+        max_age: Duration,
+    },
+    ByeBye,
 }
 
 impl Notify {
@@ -19,19 +39,78 @@ impl Notify {
             .split(|&b| b == b'\n')
             .map(|line| line.trim_ascii());
 
-        let mut first_line = lines.next()?.split(|&b| b == b' ');
-        let _version = first_line.next()?;
-        let _status_code = first_line.next()?;
+        // either `HTTP/1.1 200 OK` or `NOTIFY * HTTP/1.1`, the headers tell them apart well
+        // enough
+        let _first_line = lines.next()?;
 
-        let mut headers = lines.map_while(|line| line.split_once(|&b| b == b':'));
-        for (name, value) in &mut headers {
-            if name.eq_ignore_ascii_case(b"Location") {
-                let location = std::str::from_utf8(value).ok()?.trim();
-                let location = location.parse().ok()?;
-                return Some(Notify { location });
+        let mut usn = None;
+        let mut location = None;
+        let mut max_age = None;
+        let mut byebye = false;
+        let mut renderer = false;
+
+        for (name, value) in lines.map_while(|line| line.split_once(|&b| b == b':')) {
+            let Ok(value) = std::str::from_utf8(value) else {
+                continue;
+            };
+            let value = value.trim();
+
+            if name.eq_ignore_ascii_case(b"USN") {
+                usn = Some(value.split("::").next().unwrap_or(value).to_string());
+            } else if name.eq_ignore_ascii_case(b"Location") {
+                location = value.parse().ok();
+            } else if name.eq_ignore_ascii_case(b"Cache-Control") {
+                max_age = parse_max_age(value);
+            } else if name.eq_ignore_ascii_case(b"ST") || name.eq_ignore_ascii_case(b"NT") {
+                // other devices on the network announce themselves too
+                renderer = value.contains(":device:MediaRenderer:");
+            } else if name.eq_ignore_ascii_case(b"NTS") {
+                byebye = value.eq_ignore_ascii_case("ssdp:byebye");
             }
         }
 
-        None
+        let kind = if byebye {
+            NotifyKind::ByeBye
+        } else if renderer {
+            NotifyKind::Alive {
+                location: location?,
+                max_age: max_age.unwrap_or(DEFAULT_MAX_AGE),
+            }
+        } else {
+            return None;
+        };
+
+        Some(Notify { udn: usn?, kind })
     }
 }
+
+/// What another device is searching for, like `ssdp:all` or a device or service type, if the
+/// message is a search at all.
+pub fn search_target(message: &[u8]) -> Option<String> {
+    let mut lines = message.split(|&b| b == b'\n').map(|line| line.trim_ascii());
+
+    if !lines.next()?.starts_with(b"M-SEARCH ") {
+        return None;
+    }
+
+    lines
+        .map_while(|line| line.split_once(|&b| b == b':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case(b"ST"))
+        .and_then(|(_, value)| std::str::from_utf8(value).ok())
+        .map(|value| value.trim().to_string())
+}
+
+/// The `max-age` out of a `Cache-Control` header like `max-age=1800`.
+fn parse_max_age(value: &str) -> Option<Duration> {
+    value.split(',').find_map(|directive| {
+        let (name, secs) = directive.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("max-age") {
+            return None;
+        }
+        secs.trim()
+            .trim_matches('"')
+            .parse()
+            .ok()
+            .map(Duration::from_secs)
+    })
+}
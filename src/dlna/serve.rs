@@ -0,0 +1,132 @@
+//! A small HTTP server for the file that's being cast, since renderers fetch media themselves.
+
+use core::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::{
+    fs::File,
+    io::{self, BufRead as _, BufReader, Read as _, Seek as _, SeekFrom, Write as _},
+    net::{TcpListener, TcpStream, UdpSocket},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+pub struct FileServer {
+    port: u16,
+    /// The only file that's served, whatever path is asked for.
+    current: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl FileServer {
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0))?;
+        let port = listener.local_addr()?.port();
+        let current = Arc::new(Mutex::new(None));
+
+        let shared = current.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let current = shared.lock().unwrap().clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = handle(stream, current.as_deref()) {
+                        eprintln!("[DLNA] Failed to serve cast file: {e}");
+                    }
+                });
+            }
+        });
+
+        Ok(Self { port, current })
+    }
+
+    /// Starts serving `path`, returning the URL a device at `peer` can fetch it from.
+    pub fn serve(&self, path: &Path, peer: IpAddr) -> io::Result<String> {
+        let local_ip = local_ip_for(peer)?;
+
+        *self.current.lock().unwrap() = Some(path.to_path_buf());
+
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("bin");
+        Ok(format!("http://{local_ip}:{}/cast.{ext}", self.port))
+    }
+
+    pub fn stop(&self) {
+        *self.current.lock().unwrap() = None;
+    }
+}
+
+/// Our address as seen from `peer`. The address we'd use to reach it is one it can reach us on
+/// too.
+pub fn local_ip_for(peer: IpAddr) -> io::Result<IpAddr> {
+    let probe = UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0))?;
+    probe.connect(SocketAddr::new(peer, 9))?;
+    Ok(probe.local_addr()?.ip())
+}
+
+fn handle(stream: TcpStream, path: Option<&Path>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let head_only = request_line.starts_with("HEAD ");
+
+    let mut range_start = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("range")
+        {
+            // renderers seek with open ranges like `bytes=1234-`
+            range_start = value
+                .trim()
+                .strip_prefix("bytes=")
+                .and_then(|r| r.split('-').next())
+                .and_then(|start| start.parse::<u64>().ok());
+        }
+    }
+
+    let mut stream = reader.into_inner();
+
+    let Some(mut file) = path.and_then(|path| File::open(path).ok()) else {
+        stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")?;
+        return Ok(());
+    };
+
+    let len = file.metadata()?.len();
+    let start = range_start.unwrap_or(0).min(len);
+
+    let mut header = match range_start {
+        Some(_) => format!(
+            "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {start}-{}/{len}\r\n",
+            len.saturating_sub(1)
+        ),
+        None => "HTTP/1.1 200 OK\r\n".to_string(),
+    };
+    header.push_str(&format!(
+        "Content-Type: {}\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\nConnection: close\r\n\r\n",
+        content_type(path.unwrap_or(Path::new(""))),
+        len - start,
+    ));
+    stream.write_all(header.as_bytes())?;
+
+    if !head_only {
+        file.seek(SeekFrom::Start(start))?;
+        io::copy(&mut file.take(len - start), &mut stream)?;
+    }
+
+    Ok(())
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("mp3") => "audio/mpeg",
+        Some("flac") => "audio/flac",
+        Some("m4a" | "aac") => "audio/mp4",
+        Some("ogg" | "opus") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        Some("mp4" | "m4v") => "video/mp4",
+        Some("mkv") => "video/x-matroska",
+        Some("webm") => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
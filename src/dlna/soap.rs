@@ -0,0 +1,189 @@
+//! Just enough SOAP to call actions on UPnP services and read back what they answer, and to
+//! answer calls to the overlay's own services when it's a renderer.
+
+use core::fmt;
+use std::collections::HashMap;
+
+use ehttp::Request;
+use quick_xml::escape::escape;
+use serde::{Deserialize, de::DeserializeOwned};
+
+/// A call to an action on one of a device's services, like `SetVolume` on `RenderingControl`.
+#[derive(Debug, Clone)]
+pub struct Action {
+    pub service: &'static str,
+    pub name: &'static str,
+    args: Vec<(&'static str, String)>,
+}
+
+impl Action {
+    /// Every action on `AVTransport` and `RenderingControl` needs an `InstanceID`, which is
+    /// always 0 for devices that don't juggle several connections.
+    pub fn new(service: &'static str, name: &'static str) -> Self {
+        Self { service, name, args: Vec::new() }.arg("InstanceID", 0)
+    }
+
+    /// Adds an argument, in the order the action's description lists them.
+    pub fn arg(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+
+    fn request(&self, url: &str) -> Request {
+        let body = envelope(&element(self.service, self.name, &self.args));
+
+        let mut req = Request::post(url, body.into());
+        req.headers
+            .insert("Content-Type", "text/xml; charset=\"utf-8\"");
+        req.headers
+            .insert("SOAPACTION", format!("\"{}#{}\"", service_type(self.service), self.name));
+        req
+    }
+
+    /// Calls the action, blocking until the device answers.
+    pub fn call(&self, url: &str) -> Result<(), SoapError> {
+        self.fetch(url).map(drop)
+    }
+
+    /// Calls the action and reads its out arguments into `T`, whose fields are named like them.
+    pub fn call_for<T: DeserializeOwned>(&self, url: &str) -> Result<T, SoapError> {
+        let bytes = self.fetch(url)?;
+
+        quick_xml::de::from_reader::<_, Envelope<T>>(bytes.as_slice())
+            .map(|envelope| envelope.body.response)
+            .map_err(|e| SoapError::Parse(e.to_string()))
+    }
+
+    fn fetch(&self, url: &str) -> Result<Vec<u8>, SoapError> {
+        let res = ehttp::fetch_blocking(&self.request(url)).map_err(SoapError::Http)?;
+
+        // faults come with a 500, but say a lot more about what went wrong than that
+        if let Ok(Envelope {
+            body: Body { response: Fault { detail } },
+        }) = quick_xml::de::from_reader::<_, Envelope<Fault>>(res.bytes.as_slice())
+        {
+            return Err(SoapError::Fault {
+                code: detail.error.error_code,
+                description: detail.error.error_description,
+            });
+        }
+
+        if !res.ok {
+            return Err(SoapError::Http(format!("{} {}", res.status, res.status_text)));
+        }
+
+        Ok(res.bytes)
+    }
+}
+
+fn service_type(service: &str) -> String {
+    format!("urn:schemas-upnp-org:service:{service}:1")
+}
+
+/// An action call or response, like `<u:Play xmlns:u="...">` with its arguments.
+fn element(service: &str, name: &str, args: &[(&'static str, String)]) -> String {
+    let args = args
+        .iter()
+        .map(|(name, value)| format!("<{name}>{}</{name}>", escape(value.as_str())))
+        .collect::<String>();
+
+    format!(r#"<u:{name} xmlns:u="{}">{args}</u:{name}>"#, service_type(service))
+}
+
+fn envelope(body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+  <s:Body>
+    {body}
+  </s:Body>
+</s:Envelope>"#
+    )
+}
+
+/// The arguments of an action a controller called, by name.
+pub fn parse_call(body: &[u8]) -> Result<HashMap<String, String>, String> {
+    quick_xml::de::from_reader::<_, Envelope<HashMap<String, String>>>(body)
+        .map(|envelope| envelope.body.response)
+        .map_err(|e| e.to_string())
+}
+
+/// What to answer a call that went through, with the action's out arguments in order.
+pub fn response(service: &str, action: &str, args: &[(&'static str, String)]) -> String {
+    envelope(&element(service, &format!("{action}Response"), args))
+}
+
+/// What to answer a call that didn't, with an error code from the service's spec.
+pub fn fault(code: u32, description: &str) -> String {
+    envelope(&format!(
+        r#"<s:Fault>
+      <faultcode>s:Client</faultcode>
+      <faultstring>UPnPError</faultstring>
+      <detail>
+        <UPnPError xmlns="urn:schemas-upnp-org:control-1-0">
+          <errorCode>{code}</errorCode>
+          <errorDescription>{}</errorDescription>
+        </UPnPError>
+      </detail>
+    </s:Fault>"#,
+        escape(description),
+    ))
+}
+
+#[derive(Debug)]
+pub enum SoapError {
+    Http(String),
+    /// The device understood the call and refused it. Codes are listed in the service's spec,
+    /// like 701 for a transition AVTransport can't make right now.
+    Fault {
+        code: u32,
+        description: Option<String>,
+    },
+    Parse(String),
+}
+
+impl fmt::Display for SoapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SoapError::Http(e) => write!(f, "{e}"),
+            SoapError::Fault { code, description: Some(description) } => {
+                write!(f, "UPnP error {code}: {description}")
+            }
+            SoapError::Fault { code, description: None } => write!(f, "UPnP error {code}"),
+            SoapError::Parse(e) => write!(f, "Invalid response: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SoapError {}
+
+#[derive(Debug, Deserialize)]
+struct Envelope<T> {
+    #[serde(rename = "Body")]
+    body: Body<T>,
+}
+
+/// The response element, like `u:GetVolumeResponse`, whatever it's called.
+#[derive(Debug, Deserialize)]
+struct Body<T> {
+    #[serde(rename = "$value")]
+    response: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct Fault {
+    detail: FaultDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct FaultDetail {
+    #[serde(rename = "UPnPError")]
+    error: UpnpError,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpnpError {
+    error_code: u32,
+    error_description: Option<String>,
+}
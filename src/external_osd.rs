@@ -0,0 +1,89 @@
+//! Noticing when another program's OSD, like the compositor's volume popup or a notification,
+//! shows up over the video, so the overlay's own widgets can get out of its way for a moment.
+//! Programs can say so over D-Bus signals from the config, or with `htpc-overlay external-osd`.
+
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use zbus::{
+    MatchRule,
+    blocking::{Connection, MessageIterator},
+    message,
+};
+
+use crate::config::{DbusBus, DbusSignal, config};
+
+pub struct ExternalOsd {
+    /// Pinged by the watcher threads for every signal that matches.
+    seen: Receiver<()>,
+    hidden_until: Option<Instant>,
+}
+
+impl ExternalOsd {
+    pub fn new() -> Self {
+        let (tx, seen) = mpsc::channel();
+
+        for signal in &config().external_osd.signals {
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = watch(signal, tx) {
+                    eprintln!("Failed to watch for {}: {e}", signal.interface);
+                }
+            });
+        }
+
+        Self { seen, hidden_until: None }
+    }
+
+    pub fn update(&mut self) {
+        if self.seen.try_iter().count() > 0 {
+            self.shown(config().external_osd.hide_for());
+        }
+    }
+
+    /// Another OSD just appeared and is going to stay up for about `duration`.
+    pub fn shown(&mut self, duration: Duration) {
+        let until = Instant::now() + duration;
+        self.hidden_until = Some(self.hidden_until.map_or(until, |u| u.max(until)));
+    }
+
+    /// Whether the overlay's widgets should stay out of the way.
+    pub fn is_showing(&self) -> bool {
+        self.hidden_until
+            .is_some_and(|until| Instant::now() < until)
+    }
+}
+
+impl Default for ExternalOsd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn watch(signal: &DbusSignal, tx: Sender<()>) -> zbus::Result<()> {
+    let conn = match signal.bus {
+        DbusBus::Session => Connection::session()?,
+        DbusBus::System => Connection::system()?,
+    };
+
+    let mut rule = MatchRule::builder()
+        .msg_type(message::Type::Signal)
+        .interface(signal.interface.as_str())?;
+    if let Some(member) = &signal.member {
+        rule = rule.member(member.as_str())?;
+    }
+    if let Some(path) = &signal.path {
+        rule = rule.path(path.as_str())?;
+    }
+
+    for msg in MessageIterator::for_match_rule(rule.build(), &conn, None)? {
+        msg?;
+        if tx.send(()).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -13,48 +13,117 @@ use gilrs::{
     ev::filter::{FilterFn, Repeat, axis_dpad_to_button},
 };
 
-use crate::{command::Event, ui::toast::Toast};
+use crate::{
+    command::{Actions, Command, Event},
+    config::config,
+    diagnostics::Status,
+    ui::toast::Toast,
+};
+
+/// How long Select (or Select + Start, to unlock) has to be held to lock or unlock the controls.
+const LOCK_HOLD: Duration = Duration::from_secs(3);
+/// How long a button has to be held before it starts repeating, and how often it does then.
+const REPEAT_AFTER: Duration = Duration::from_millis(300);
+const REPEAT_EVERY: Duration = Duration::from_millis(1000 / 30);
+/// How often battery levels are checked.
+const POWER_POLL_INTERVAL: Duration = Duration::from_secs(60);
 
 pub struct Gamepad {
-    gilrs: Gilrs,
+    /// `None` if gilrs couldn't be initialized, see `status`.
+    gilrs: Option<Gilrs>,
+    status: Status,
     just_pressed: Vec<Button>,
     last_input: Instant,
     used_gamepads: Vec<GamepadId>,
     power_states: HashMap<GamepadId, (PowerInfo, Instant)>,
+    /// Whether all input is ignored, so pets and toddlers can't mess with playback.
+    locked: bool,
+    /// When the lock or unlock chord started being held, if it is.
+    chord_since: Option<Instant>,
+    /// The chord toggled the lock and hasn't been let go of yet.
+    chord_used: bool,
+    /// When a button was last pressed and ignored because of the lock.
+    blocked_at: Option<Instant>,
+    /// Buttons that are held down, with when they were pressed and whether that already counted
+    /// as a long press.
+    held: HashMap<Button, (Instant, bool)>,
+    /// Buttons that were let go of this frame before they counted as a long press.
+    short_presses: Vec<Button>,
+    /// Buttons claimed by focused widgets while drawing this frame, with what they do there.
+    claims: Vec<(Button, &'static str)>,
+    /// Claims made while drawing the last frame, which this frame's actions are resolved with.
+    last_claims: Vec<(Button, &'static str)>,
 }
 
 impl Gamepad {
     pub fn new() -> Self {
-        Self {
-            gilrs: GilrsBuilder::new()
-                .with_default_filters(false)
-                .build()
-                .expect("Failed to initialize Gilrs"),
+        let mut this = Self {
+            gilrs: None,
+            status: Status::Ok,
             just_pressed: Vec::new(),
             last_input: Instant::now(),
             used_gamepads: Vec::new(),
             power_states: HashMap::new(),
+            locked: false,
+            chord_since: None,
+            chord_used: false,
+            blocked_at: None,
+            held: HashMap::new(),
+            short_presses: Vec::new(),
+            claims: Vec::new(),
+            last_claims: Vec::new(),
+        };
+        this.retry();
+        this
+    }
+
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    /// Initializes gilrs again, if it failed before.
+    pub fn retry(&mut self) {
+        if self.gilrs.is_some() {
+            return;
+        }
+
+        match GilrsBuilder::new().with_default_filters(false).build() {
+            Ok(gilrs) => {
+                self.gilrs = Some(gilrs);
+                self.status = Status::Ok;
+            }
+            Err(e) => {
+                eprintln!("Failed to initialize gilrs: {e}");
+                self.status = Status::Failed(e.to_string());
+            }
         }
     }
 
-    pub fn update(&mut self, events: &mut Vec<Event>) {
-        self.just_pressed.clear();
+    fn next_event(&mut self) -> Option<gilrs::Event> {
+        let gilrs = self.gilrs.as_mut()?;
 
-        while let Some(ev @ gilrs::Event { id, event, .. }) = self
-            .gilrs
+        let ev = gilrs
             .next_event()
-            .filter_ev(&LeftStickToDPad { threshold: 0.3 }, &mut self.gilrs)
-            .filter_ev(&axis_dpad_to_button, &mut self.gilrs)
+            .filter_ev(&LeftStickToDPad { threshold: 0.3 }, gilrs)
+            .filter_ev(&axis_dpad_to_button, gilrs)
             .filter_ev(
                 &Repeat {
-                    after: Duration::from_millis(300),
-                    every: Duration::from_secs(1) / 30,
+                    after: REPEAT_AFTER,
+                    every: REPEAT_EVERY,
                 },
-                &mut self.gilrs,
-            )
-        {
-            self.gilrs.update(&ev);
+                gilrs,
+            )?;
+
+        gilrs.update(&ev);
+        Some(ev)
+    }
 
+    pub fn update(&mut self, events: &mut Vec<Event>) {
+        self.just_pressed.clear();
+        self.short_presses.clear();
+        self.last_claims = std::mem::take(&mut self.claims);
+
+        while let Some(gilrs::Event { id, event, .. }) = self.next_event() {
             if !self.used_gamepads.contains(&id) {
                 self.used_gamepads.push(id);
             }
@@ -63,19 +132,29 @@ impl Gamepad {
 
             self.update_power_state(id, events);
 
+            match event {
+                EventType::ButtonPressed(button, _) => {
+                    self.held.insert(remap(button), (Instant::now(), false));
+                }
+                EventType::ButtonReleased(button, _) => {
+                    if let Some((_, false)) = self.held.remove(&remap(button)) {
+                        self.short_presses.push(remap(button));
+                    }
+                }
+                _ => {}
+            }
+
             match event {
                 EventType::ButtonPressed(button, _) | EventType::ButtonRepeated(button, _)
-                    if button != Button::Mode =>
+                    if remap(button) != Button::Mode =>
                 {
-                    self.just_pressed.push(button)
+                    self.just_pressed.push(remap(button))
                 }
-                EventType::ButtonReleased(button, _) if button == Button::Mode => {
-                    self.just_pressed.push(button)
+                EventType::ButtonReleased(button, _) if remap(button) == Button::Mode => {
+                    self.just_pressed.push(remap(button))
                 }
                 EventType::Connected => {
-                    events.push(Event::Toast(Toast::GamepadConnected {
-                        name: self.gilrs.gamepad(id).name().to_string(),
-                    }));
+                    events.push(Event::Toast(Toast::GamepadConnected { name: self.name(id) }));
                 }
                 EventType::Disconnected => {
                     if self.used_gamepads.is_empty() {
@@ -87,24 +166,87 @@ impl Gamepad {
                     if self.used_gamepads.is_empty() {
                         events.push(Event::LastGamepadDisconnected);
                     } else {
-                        events.push(Event::Toast(Toast::GamepadDisconnected {
-                            name: self.gilrs.gamepad(id).name().to_string(),
-                        }));
+                        events
+                            .push(Event::Toast(Toast::GamepadDisconnected { name: self.name(id) }));
                     }
                 }
                 _ => {}
             }
         }
+
+        self.update_lock(events);
+    }
+
+    fn update_lock(&mut self, events: &mut Vec<Event>) {
+        let chord_down = if self.locked {
+            self.is_down(Button::Select) && self.is_down(Button::Start)
+        } else {
+            self.is_down(Button::Select)
+        };
+
+        if chord_down && !self.locked {
+            // Select only counts as a press of its own once it's let go without locking, see below
+            self.claims.push((Button::Select, "Hold to lock"));
+            self.just_pressed.retain(|&b| b != Button::Select);
+        }
+
+        if !chord_down {
+            if !self.locked && self.chord_since.is_some() && !self.chord_used {
+                self.just_pressed.push(Button::Select);
+            }
+
+            self.chord_since = None;
+            self.chord_used = false;
+        } else if !self.chord_used {
+            let since = *self.chord_since.get_or_insert_with(Instant::now);
+
+            if since.elapsed() >= LOCK_HOLD {
+                self.locked = !self.locked;
+                self.chord_used = true;
+                self.just_pressed.clear();
+                events.push(Event::Toast(Toast::ControlsLocked { locked: self.locked }));
+            }
+        }
+
+        if self.locked && !self.just_pressed.is_empty() {
+            self.just_pressed.clear();
+            self.blocked_at = Some(Instant::now());
+        }
+
+        if self.locked {
+            self.short_presses.clear();
+            self.held.clear();
+        }
+    }
+
+    /// When a button press was last ignored because the controls are locked.
+    pub fn blocked_at(&self) -> Option<Instant> {
+        self.blocked_at.filter(|_| self.locked)
+    }
+
+    /// Makes the next update check battery levels again, since they've likely changed while the
+    /// system was asleep.
+    pub fn recheck_power(&mut self) {
+        let Some(due) = Instant::now().checked_sub(POWER_POLL_INTERVAL) else {
+            return;
+        };
+
+        for (_, checked_at) in self.power_states.values_mut() {
+            *checked_at = due;
+        }
     }
 
     fn update_power_state(&mut self, id: GamepadId, events: &mut Vec<Event>) {
         match self.power_states.entry(id) {
             Entry::Occupied(mut entry) => {
-                if entry.get().1.elapsed() < Duration::from_secs(60) {
+                if entry.get().1.elapsed() < POWER_POLL_INTERVAL {
                     return;
                 }
 
-                let info = self.gilrs.gamepad(id).power_info();
+                let info = self
+                    .gilrs
+                    .as_ref()
+                    .map_or(PowerInfo::Unknown, |g| g.gamepad(id).power_info());
                 if entry.get().0 != info {
                     let prev = entry.insert((info, Instant::now()));
                     self.on_power_info_changed(id, Some(prev.0), info, events);
@@ -113,7 +255,10 @@ impl Gamepad {
                 }
             }
             Entry::Vacant(entry) => {
-                let info = self.gilrs.gamepad(id).power_info();
+                let info = self
+                    .gilrs
+                    .as_ref()
+                    .map_or(PowerInfo::Unknown, |g| g.gamepad(id).power_info());
                 entry.insert((info, Instant::now()));
                 self.on_power_info_changed(id, None, info, events);
             }
@@ -131,16 +276,10 @@ impl Gamepad {
             (
                 Some(PowerInfo::Discharging(prev) | PowerInfo::Charging(prev)),
                 PowerInfo::Discharging(lvl),
-            ) if lvl <= 15 && lvl != prev => events.push(Event::Toast(Toast::GamepadLowBattery {
-                name: self.gilrs.gamepad(id).name().to_string(),
-                level: lvl,
-            })),
-            (None, PowerInfo::Discharging(lvl)) if lvl <= 15 => {
-                events.push(Event::Toast(Toast::GamepadLowBattery {
-                    name: self.gilrs.gamepad(id).name().to_string(),
-                    level: lvl,
-                }))
-            }
+            ) if lvl <= 15 && lvl != prev => events
+                .push(Event::Toast(Toast::GamepadLowBattery { name: self.name(id), level: lvl })),
+            (None, PowerInfo::Discharging(lvl)) if lvl <= 15 => events
+                .push(Event::Toast(Toast::GamepadLowBattery { name: self.name(id), level: lvl })),
             _ => {}
         }
     }
@@ -152,8 +291,35 @@ impl Gamepad {
             .unwrap_or(PowerInfo::Unknown)
     }
 
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
     pub fn is_down(&self, button: Button) -> bool {
-        self.gilrs.gamepads().any(|(_, g)| g.is_pressed(button))
+        self.gilrs.as_ref().is_some_and(|gilrs| {
+            gilrs
+                .gamepads()
+                .any(|(_, g)| g.is_pressed(physical(button)))
+        })
+    }
+
+    /// Whether the button has been held long enough that it's repeating.
+    pub fn is_repeating(&self, button: Button) -> bool {
+        self.held
+            .get(&button)
+            .is_some_and(|(since, _)| since.elapsed() >= REPEAT_AFTER)
+    }
+
+    /// Counts as `button` being pressed this frame, for input from somewhere other than a gamepad
+    /// like the TV remote. Ignored while the controls are locked, like the real thing.
+    pub fn press(&mut self, button: Button) {
+        self.last_input = Instant::now();
+
+        if self.locked {
+            self.blocked_at = Some(Instant::now());
+        } else {
+            self.just_pressed.push(button);
+        }
     }
 
     pub fn get_just_pressed(&self) -> Vec<Button> {
@@ -169,12 +335,59 @@ impl Gamepad {
         }
     }
 
+    /// Claims `button` for a focused widget, so it does `label` instead of whatever the view binds
+    /// it to, and returns whether it was just pressed. Claims only last until the next frame, so
+    /// widgets claim every frame they have focus.
+    pub fn claim(&mut self, button: Button, label: &'static str) -> bool {
+        self.claims.push((button, label));
+        self.take_just_pressed(button)
+    }
+
+    /// Replaces the view's actions for buttons that were claimed last frame, so pressing them
+    /// doesn't also seek or move focus, and their prompts say what they'll do.
+    pub fn apply_claims(&self, actions: &mut Actions) {
+        for &(button, label) in &self.last_claims {
+            actions.set(button, Command::Claimed(label));
+        }
+    }
+
+    /// Forgets this frame's claims, since they belong to a view that's going away.
+    pub fn clear_claims(&mut self) {
+        self.claims.clear();
+    }
+
+    /// Whether the button was let go of before being held for long enough to count as a long
+    /// press. Consumes it, like [`Gamepad::take_just_pressed`].
+    pub fn take_short_press(&mut self, button: Button) -> bool {
+        if let Some(idx) = self.short_presses.iter().position(|&b| b == button) {
+            self.short_presses.remove(idx);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether the button has been held for `duration`. Only true once per press, after which
+    /// letting go of it doesn't count as a short press either.
+    pub fn take_long_press(&mut self, button: Button, duration: Duration) -> bool {
+        match self.held.get_mut(&button) {
+            Some((at, fired)) if !*fired && at.elapsed() >= duration => {
+                *fired = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
     pub fn inactive_for(&self, duration: Duration) -> bool {
         self.last_input.elapsed() > duration
     }
 
-    pub fn get(&self, id: GamepadId) -> gilrs::Gamepad<'_> {
-        self.gilrs.gamepad(id)
+    pub fn name(&self, id: GamepadId) -> String {
+        self.gilrs
+            .as_ref()
+            .map(|gilrs| gilrs.gamepad(id).name().to_string())
+            .unwrap_or_default()
     }
 
     pub fn gamepads(&self) -> &[GamepadId] {
@@ -188,8 +401,22 @@ impl Default for Gamepad {
     }
 }
 
+/// The button a physical button acts as, according to the config's `button-map`.
+fn remap(button: Button) -> Button {
+    config().button_map.get(&button).copied().unwrap_or(button)
+}
+
+/// The physical button that acts as `button`, so prompts show what to actually press.
+fn physical(button: Button) -> Button {
+    config()
+        .button_map
+        .iter()
+        .find(|&(_, &to)| to == button)
+        .map_or(button, |(&from, _)| from)
+}
+
 pub fn button_label(button: Button) -> &'static str {
-    match button {
+    match physical(button) {
         Button::East => "\u{e005}",
         Button::South => "\u{e007}",
         Button::North => "\u{e019}",
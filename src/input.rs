@@ -0,0 +1,75 @@
+//! Input from anything other than gamepads, like the TV remote over CEC, merged into what the
+//! gamepads press. The gamepad stays where every button ends up, since holds, repeats, claims
+//! and the lock all live there, so a backend only has to say what was pressed.
+//!
+//! Gamepads themselves aren't a backend. [`Gamepad`] reads gilrs directly, since holding buttons,
+//! the lock chord, battery levels and controller names all come from gilrs' own state, which
+//! taps wouldn't carry.
+
+use gilrs::Button;
+
+use crate::{
+    command::{Command, Event},
+    config::config,
+    gamepad::Gamepad,
+};
+
+/// What an input backend produced since the last frame.
+#[derive(Debug)]
+pub enum InputEvent {
+    /// Counts as a gamepad button being tapped.
+    Press(Button),
+    /// For keys a gamepad doesn't have, like a remote's play key.
+    Command(Command),
+    /// Anything else the device has to say, like the TV going into standby.
+    Event(Event),
+}
+
+/// A source of button taps and commands besides the gamepads. Only the CEC remote is one so far.
+pub trait InputBackend {
+    /// What it's called in `inputs.disabled` in the config.
+    fn name(&self) -> &'static str;
+    fn poll(&mut self, input: &mut Vec<InputEvent>);
+}
+
+#[derive(Default)]
+pub struct Inputs {
+    backends: Vec<Box<dyn InputBackend>>,
+    buf: Vec<InputEvent>,
+}
+
+impl Inputs {
+    /// Adds a backend to read every frame, unless the config turns it off.
+    pub fn register(&mut self, backend: impl InputBackend + 'static) {
+        if config()
+            .inputs
+            .disabled
+            .iter()
+            .any(|name| name == backend.name())
+        {
+            return;
+        }
+
+        self.backends.push(Box::new(backend));
+    }
+
+    pub fn update(
+        &mut self,
+        gamepad: &mut Gamepad,
+        commands: &mut Vec<Command>,
+        events: &mut Vec<Event>,
+    ) {
+        for backend in &mut self.backends {
+            backend.poll(&mut self.buf);
+        }
+
+        for input in self.buf.drain(..) {
+            match input {
+                InputEvent::Press(button) => gamepad.press(button),
+                InputEvent::Command(command) if !gamepad.is_locked() => commands.push(command),
+                InputEvent::Command(_) => {}
+                InputEvent::Event(event) => events.push(event),
+            }
+        }
+    }
+}
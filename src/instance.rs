@@ -0,0 +1,117 @@
+//! Making sure only one overlay runs at a time, since a second one would fight the first over
+//! mpv's socket and the gamepads, like when systemd restarts it while the old one is still
+//! shutting down.
+
+use std::{
+    io::{self, ErrorKind},
+    os::{
+        linux::net::SocketAddrExt as _,
+        unix::{
+            fs::MetadataExt as _,
+            net::{SocketAddr, UnixListener, UnixStream},
+        },
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rustix::{
+    net::sockopt::socket_peercred,
+    process::{Pid, Signal, kill_process},
+};
+
+use crate::control;
+
+/// How long to wait for the running instance to exit when taking over from it, after asking it
+/// to and after each signal.
+const TAKE_OVER_TIMEOUT: Duration = Duration::from_secs(10);
+const TERM_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Held for as long as the overlay runs. It's an abstract socket, so the kernel lets go of it
+/// when the process exits however that happens, and a crash can't leave it behind.
+pub struct InstanceLock {
+    _listener: UnixListener,
+}
+
+/// Whether the overlay was started with `--take-over`, to replace one that's already running.
+fn take_over_requested() -> bool {
+    std::env::args().any(|arg| arg == "--take-over")
+}
+
+pub fn acquire() -> Result<InstanceLock, Box<dyn std::error::Error>> {
+    match bind() {
+        Ok(lock) => return Ok(lock),
+        Err(e) if e.kind() == ErrorKind::AddrInUse => {}
+        Err(e) => return Err(format!("Failed to check for another instance: {e}").into()),
+    }
+
+    if !take_over_requested() {
+        control::notify_running("Another overlay tried to start").ok();
+        return Err("htpc-overlay is already running. Start with --take-over to replace it.".into());
+    }
+
+    // found out first, since a hung overlay is the main reason to take over and it won't answer
+    let pid = running_pid()
+        .inspect_err(|e| eprintln!("Failed to find the running overlay's PID: {e}"))
+        .ok();
+
+    eprintln!("Asking the running overlay to quit");
+    match control::quit_running() {
+        Ok(()) => {
+            if let Some(lock) = wait_for_exit(TAKE_OVER_TIMEOUT)? {
+                return Ok(lock);
+            }
+        }
+        Err(e) => eprintln!("Failed to ask the running overlay to quit: {e}"),
+    }
+
+    let Some(pid) = pid else {
+        return Err("The running overlay didn't quit, it may have to be killed".into());
+    };
+
+    for (signal, name) in [(Signal::TERM, "SIGTERM"), (Signal::KILL, "SIGKILL")] {
+        eprintln!("Sending {name} to the running overlay ({})", pid.as_raw_nonzero());
+        kill_process(pid, signal)
+            .map_err(|e| format!("Failed to signal the running overlay: {e}"))?;
+
+        if let Some(lock) = wait_for_exit(TERM_TIMEOUT)? {
+            return Ok(lock);
+        }
+    }
+
+    Err("The running overlay didn't quit, even after being killed".into())
+}
+
+/// Tries to take the lock until `timeout` runs out, returning `None` if it's still held then.
+fn wait_for_exit(timeout: Duration) -> Result<Option<InstanceLock>, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    loop {
+        match bind() {
+            Ok(lock) => return Ok(Some(lock)),
+            Err(e) if e.kind() == ErrorKind::AddrInUse && start.elapsed() < timeout => {
+                thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) if e.kind() == ErrorKind::AddrInUse => return Ok(None),
+            Err(e) => return Err(format!("Failed to check for another instance: {e}").into()),
+        }
+    }
+}
+
+/// The PID of the overlay holding the lock. The kernel remembers who's listening on a socket,
+/// so this works even if it never accepts.
+fn running_pid() -> io::Result<Pid> {
+    let stream = UnixStream::connect_addr(&addr()?)?;
+    Ok(socket_peercred(&stream)?.pid)
+}
+
+fn bind() -> io::Result<InstanceLock> {
+    Ok(InstanceLock {
+        _listener: UnixListener::bind_addr(&addr()?)?,
+    })
+}
+
+fn addr() -> io::Result<SocketAddr> {
+    // abstract sockets are shared by every user, who should each be able to run their own
+    let uid = std::fs::metadata("/proc/self")?.uid();
+    SocketAddr::from_abstract_name(format!("htpc-overlay-{uid}"))
+}
@@ -1,42 +1,110 @@
 #![feature(slice_split_once)]
 
 use core::{
+    any::Any,
     mem::take,
     sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
 };
+use std::{path::Path, time::Instant};
 
 use egui::{
-    Color32, FontData, FontFamily, Id, Stroke,
+    Color32, FontData, FontFamily, LayerId,
     epaint::text::{FontInsert, FontPriority, InsertFontFamily},
-    style::Selection,
 };
 use egui_wlr_layer::{
     Anchor, InputRegions, KeyboardInteractivity, Layer, LayerAppOpts, LayerSurface,
 };
+use gilrs::Button;
 
 use self::{
+    cec::Cec,
     command::{Command, Event},
+    control::Control,
+    demo::Demo,
+    diagnostics::Subsystem,
     dlna::Dlna,
+    external_osd::ExternalOsd,
     gamepad::Gamepad,
+    input::Inputs,
+    mpris::Mpris,
     mpv::Mpv,
-    ui::{View, toast::SpawnedToast},
-    utils::Activated,
+    mqtt::Mqtt,
+    scraper::Scrapers,
+    store::{
+        backup::Backups,
+        favorites::Favorites,
+        hints::{Hint, Hints},
+        history::History,
+        resume::ResumePositions,
+        settings::Settings,
+        watched::Watched,
+    },
+    suspend::Suspend,
+    syncplay::Syncplay,
+    udisks::Removable,
+    ui::{
+        View,
+        toast::{SpawnedToast, Toast, ToastHistory},
+        views::{
+            confirm::ConfirmView, hidden::HiddenView, idle::IdleView, miniseek::MiniSeekView,
+            seekbar::SeekBarView,
+        },
+        volume_osd::VolumeOsd,
+    },
+    voice::Voice,
+    watch_party::WatchParty,
+    websocket::WebSocket,
 };
 
 mod cec;
 mod command;
+mod config;
+mod control;
+mod demo;
+mod diagnostics;
 mod dlna;
+mod external_osd;
 mod gamepad;
+mod input;
+mod instance;
+mod mpris;
 mod mpv;
+mod mqtt;
+mod scraper;
+mod shuffle;
+mod store;
+mod suspend;
+mod syncplay;
+mod udisks;
 mod ui;
 mod utils;
+mod voice;
+mod watch_party;
+mod websocket;
 
 const BLUE: Color32 = Color32::from_rgb(137, 220, 235);
 
+/// How far into a file playback has to get for it to count as watched.
+const WATCHED_PERCENT: f32 = 90.;
+/// How long B has to be held to keep a SponsorBlock segment that's about to be skipped.
+const KEEP_SEGMENT_HOLD: Duration = Duration::from_millis(500);
+
 pub fn main() -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(result) = control::run_subcommand() {
+        return result;
+    }
+
+    let _instance = instance::acquire()?;
+
     let mut context = egui_wlr_layer::Context::new();
 
-    let handle = context.new_layer_app(Box::new(App::default()), LayerAppOpts {
+    let mut app = App::default();
+    if demo::requested() {
+        app.demo = Some(Demo::new(&mut app.mpv));
+    }
+
+    let handle = context.new_layer_app(Box::new(app), LayerAppOpts {
         layer: Layer::Overlay,
         namespace: Some("htpc-overlay"),
         output: None,
@@ -66,8 +134,52 @@ pub struct App {
     view: Box<dyn ui::View>,
     mpv: Mpv,
     dlna: Dlna,
-    // cec: Cec,
+    removable: Removable,
+    backups: Backups,
+    external_osd: ExternalOsd,
+    suspend: Suspend,
+    scrapers: Scrapers,
+    voice: Voice,
+    watch_party: WatchParty,
+    syncplay: Syncplay,
+    control: Control,
+    mpris: Mpris,
+    mqtt: Mqtt,
+    websocket: WebSocket,
+    cec: Cec,
+    /// Input from anything but gamepads, fed into `gamepad`.
+    inputs: Inputs,
+    demo: Option<Demo>,
     toasts: Vec<SpawnedToast>,
+    /// Toasts that have gone, for the Notifications menu.
+    toast_history: ToastHistory,
+    volume_osd: VolumeOsd,
+    favorites: Favorites,
+    history: History,
+    resume: ResumePositions,
+    settings: Settings,
+    watched: Watched,
+    hints: Hints,
+    /// The hint for the current view, with when it was shown.
+    hint: Option<(Hint, Instant)>,
+    /// The audio device whose latency offset was last applied to mpv.
+    audio_delay_device: Option<String>,
+    /// The file for which the HDR tone mapping warning was shown, if it's still playing.
+    hdr_warning_path: Option<String>,
+    /// Whether mpv had nothing loaded last frame.
+    idle: bool,
+    /// Whether this frame's view only shows playback, like the seek bar, and leaves the buttons a
+    /// menu would use free for overlays.
+    passive: bool,
+    /// The file that was last marked as watched for getting far enough into it.
+    watched_path: Option<String>,
+    /// How tall the panels along the bottom of the screen were last frame, not counting the
+    /// letterbox margin.
+    bottom_panels_height: f32,
+    /// Whether the view changed since the last frame, so the images it showed can be dropped.
+    view_changed: bool,
+    /// Disables destructive and configuration actions, see [`Command::is_restricted`].
+    guest_mode: bool,
     queued_commands: Vec<Command>,
     queued_events: Vec<Event>,
 }
@@ -84,12 +196,76 @@ impl App {
     }
 
     fn change_view(&mut self, new_view: impl View) {
-        self.view = Box::new(new_view);
+        self.mpv.end_view_scope();
+
+        if self.view.is_dirty() {
+            let previous = self.take_view();
+            self.view = Box::new(ConfirmView::discard_changes(previous, new_view));
+        } else {
+            self.view = Box::new(new_view);
+        }
+
+        self.view_changed = true;
+        self.gamepad.clear_claims();
+    }
+
+    /// Asks before doing something that can't be undone, going back to the current view either
+    /// way. Only works outside of drawing, while the view isn't taken, like from commands.
+    fn confirm(&mut self, message: impl Into<String>, on_confirm: impl FnOnce(&mut App) + 'static) {
+        let previous = self.take_view();
+        self.view = Box::new(ConfirmView::new(message, previous, on_confirm));
+        self.view_changed = true;
+        self.gamepad.clear_claims();
+    }
+
+    fn take_view_as<T: View>(&mut self) -> Option<Box<T>> {
+        if self.view.is::<T>() {
+            let view: Box<dyn Any> = self.take_view();
+            view.downcast().ok()
+        } else {
+            None
+        }
+    }
+
+    fn view_is_passive(&self) -> bool {
+        self.view.is::<HiddenView>()
+            || self.view.is::<SeekBarView>()
+            || self.view.is::<MiniSeekView>()
     }
 
     fn queue_command(&mut self, cmd: Command) {
         self.queued_commands.push(cmd);
     }
+
+    /// Loads everything from the store and applies the settings, at startup and after restoring
+    /// a backup.
+    fn load_state(&mut self, ctx: &egui::Context) {
+        self.favorites = Favorites::load();
+        self.history = History::load();
+        self.resume = ResumePositions::load();
+        self.settings = Settings::load();
+        self.watched = Watched::load();
+        self.hints = Hints::load();
+        self.scrapers.load();
+        self.mpv.set_audio_fade(self.settings.audio_fade());
+        self.mpv.set_cache_preset(self.settings.cache_preset);
+        self.dlna
+            .set_volume_target(self.settings.volume_target.clone());
+        self.mpv.set_overrides(
+            config::config()
+                .profile()
+                .map(|profile| profile.mpv.clone().into_iter().collect())
+                .unwrap_or_default(),
+        );
+
+        ui::theme::apply(ctx, &self.settings);
+        self.mpv
+            .set_property("sub-scale", ui::theme::sub_scale(&self.settings))
+            .ok();
+        self.mpv
+            .set_property("sub-border-size", self.settings.subtitles.border_size)
+            .ok();
+    }
 }
 
 impl egui_wlr_layer::App for App {
@@ -97,17 +273,11 @@ impl egui_wlr_layer::App for App {
         if !self.initialized {
             self.initialized = true;
 
-            ctx.set_visuals(egui::Visuals {
-                dark_mode: true,
-                override_text_color: Some(Color32::WHITE),
-                selection: Selection {
-                    bg_fill: Color32::WHITE,
-                    stroke: Stroke::new(1.0, Color32::RED),
-                },
-                extreme_bg_color: Color32::from_black_alpha(128),
-                panel_fill: Color32::from_black_alpha(192),
-                ..Default::default()
-            });
+            self.load_state(ctx);
+
+            if let Some(remote) = self.cec.remote() {
+                self.inputs.register(remote);
+            }
 
             ctx.add_font(FontInsert::new(
                 "kenney_input_nintendo_switch",
@@ -118,25 +288,159 @@ impl egui_wlr_layer::App for App {
                 }],
             ));
 
-            ctx.set_zoom_factor(1.5);
+            ctx.set_zoom_factor(config::config().zoom_factor);
+            egui_extras::install_image_loaders(ctx);
 
             ctx.options_mut(|o| o.max_passes = 3.try_into().unwrap());
 
+            if Subsystem::ALL.iter().any(|s| !s.status(self).is_ok()) {
+                self.queued_events
+                    .push(Event::Toast(Toast::StartupProblems));
+            }
+
             ctx.request_discard("init");
             return;
         }
 
+        if self.suspend.take_resumed() {
+            self.toasts.iter_mut().for_each(SpawnedToast::restart);
+            self.mpv.resumed();
+            self.gamepad.recheck_power();
+        }
+
         self.gamepad.update(&mut self.queued_events);
+        self.inputs
+            .update(&mut self.gamepad, &mut self.queued_commands, &mut self.queued_events);
+        self.voice
+            .update(&mut self.gamepad, &mut self.queued_commands, &mut self.queued_events);
         self.dlna.update(&mut self.queued_events);
-        self.mpv.update().expect("mpv connection broke");
+        self.removable.update(&mut self.queued_events);
+        if self
+            .backups
+            .update(self.removable.mounts(), &mut self.queued_events)
+        {
+            self.load_state(ctx);
+        }
+        self.external_osd.update();
+        self.mpv.update(&mut self.queued_events);
+        self.dlna.update_cast(&mut self.mpv, &self.settings);
+        self.dlna
+            .update_renderer(&mut self.mpv, &mut self.queued_events);
+        self.watch_party
+            .update(&mut self.mpv, &mut self.queued_events);
+        self.syncplay.update(&mut self.mpv, &mut self.queued_events);
+        self.control
+            .update(&mut self.mpv, &mut self.queued_commands, &mut self.queued_events);
+        self.mpris.update(&mut self.mpv, &mut self.queued_commands);
+        self.mqtt.update(&mut self.mpv, &mut self.queued_commands);
+        self.websocket.update(&self.mpv);
+        self.scrapers.update(&mut self.toasts);
+
+        if let Some(mut demo) = self.demo.take() {
+            demo.update(self);
+            self.demo = Some(demo);
+        }
 
+        // not until mpv has reported it, or the delay for no device would be set first
+        if let Some(audio_device) = self.mpv.get_property::<Option<String>>("audio-device")
+            && self.audio_delay_device.as_ref() != Some(&audio_device)
+        {
+            let delay = self.settings.audio_delay(&audio_device);
+            self.mpv.set_property("audio-delay", delay).ok();
+            self.audio_delay_device = Some(audio_device);
+        }
+
+        // only once per file, so unmarking it by hand while it's still playing sticks
+        if self.mpv.get_property::<f32>("percent-pos") >= WATCHED_PERCENT
+            && let Some(path) = self.mpv.get_property_cached::<String>("path")
+            && self.watched_path.as_ref() != Some(&path)
+        {
+            self.watched.mark(Path::new(&path));
+            self.watched_path = Some(path);
+        }
+
+        if self.mpv.is_tone_mapping_hdr() {
+            let path = self.mpv.get_property::<Option<String>>("path");
+            if path.is_some() && self.hdr_warning_path != path {
+                self.queued_events.push(Event::Toast(Toast::HdrToneMapped));
+                self.hdr_warning_path = path;
+            }
+        } else {
+            self.hdr_warning_path = None;
+        }
+
+        // idle-active rather than an empty playlist, since entries that have been played can still
+        // be in it
+        let idle = self.mpv.get_property_cached::<bool>("idle-active") == Some(true);
+        if idle != self.idle {
+            self.idle = idle;
+
+            if idle && self.view_is_passive() {
+                self.change_view(IdleView);
+            } else if !idle && self.view.is::<IdleView>() {
+                self.change_view(HiddenView);
+            }
+        }
+
+        let view_changed = take(&mut self.view_changed);
+        self.passive = self.view_is_passive();
         let view = self.take_view();
 
-        let actions = view.button_actions();
+        if view_changed {
+            self.hint = view
+                .hint()
+                .filter(|&hint| self.hints.take(hint))
+                .map(|hint| (hint, Instant::now()));
+        }
+
+        let mut actions = view.button_actions();
+        self.gamepad.apply_claims(&mut actions);
+
+        // toasts offering something take their button while they're up, the newest first
+        if let Some(toast) = self.toasts.iter_mut().rev().find(|toast| {
+            toast
+                .action()
+                .is_some_and(|action| self.gamepad.take_just_pressed(action.button))
+        }) && let Some(action) = toast.action()
+        {
+            toast.dismiss();
+            self.queue_command(action.command);
+        }
+
+        // holding B while a skip is coming up keeps the segment, and pressing it doesn't also hide
+        // the UI. Menus keep B for going back.
+        if self.passive && self.mpv.upcoming_skip().is_some() {
+            self.gamepad.take_just_pressed(Button::South);
+
+            if self
+                .gamepad
+                .take_long_press(Button::South, KEEP_SEGMENT_HOLD)
+            {
+                self.mpv.keep_upcoming_segment();
+            }
+        }
+
+        // while the up next card is showing, A moves on right away and B stays on this file
+        if self.passive && self.mpv.up_next().is_some() {
+            if self.gamepad.take_just_pressed(Button::East) {
+                self.mpv.playlist_next().ok();
+            } else if self.gamepad.take_just_pressed(Button::South) {
+                self.mpv.cancel_up_next().ok();
+            }
+        }
 
         let just_pressed = self.gamepad.get_just_pressed();
         for button in just_pressed {
-            self.queued_commands.push(actions.get(button));
+            let cmd = match button {
+                // clicking a stick confirms too, wherever A does
+                Button::LeftThumb | Button::RightThumb
+                    if matches!(actions.a, Command::Activate) =>
+                {
+                    Command::Activate
+                }
+                _ => actions.get(button),
+            };
+            self.queued_commands.push(cmd);
         }
 
         if let Some(limit) = view.hide_on_inactive()
@@ -145,25 +449,47 @@ impl egui_wlr_layer::App for App {
             self.queue_command(Command::HideUi);
         }
 
+        // panels are stacked from the bottom up in the order they're drawn
+        let margin = ui::letterbox::draw_margin(ctx, &self.mpv, self.bottom_panels_height);
+
         if view.show_prompts() {
             ui::button_prompts(ctx, self, &actions);
         }
 
+        // with no video, mpv might not be showing anything at all, and black is the next best thing
+        // to the TV being off
+        if self.mpv.audio_only() {
+            ctx.layer_painter(LayerId::background()).rect_filled(
+                ctx.screen_rect(),
+                0.,
+                Color32::BLACK,
+            );
+        }
+
         view.draw(ctx, self);
 
-        let sub_pos = self.mpv.get_property::<f32>("sub-pos");
-        let new_sub_pos =
-            (ctx.available_rect().bottom() / ctx.screen_rect().bottom() * 100.).round();
-        if sub_pos != new_sub_pos {
+        self.bottom_panels_height =
+            ctx.screen_rect().bottom() - ctx.available_rect().bottom() - margin;
+
+        let sub_pos = self.mpv.get_property::<Option<f32>>("sub-pos");
+        let new_sub_pos = ((ctx.available_rect().bottom() / ctx.screen_rect().bottom() * 100.)
+            .round()
+            + self.settings.subtitles.pos_offset)
+            .clamp(0., 150.);
+        if let Some(sub_pos) = sub_pos
+            && sub_pos != new_sub_pos
+        {
             eprintln!("Changing sub-pos from {} to {}", sub_pos, new_sub_pos);
             self.mpv.set_property("sub-pos", new_sub_pos).ok();
         }
 
-        ui::toast::draw(&mut self.toasts, ctx);
+        ui::overlay::draw(ctx, self);
+        ui::image_cache::evict(ctx, view_changed);
+        ui::toast::draw(&mut self.toasts, &mut self.toast_history, ctx);
 
         self.restore_view(view);
 
-        ctx.memory_mut(|m| m.data.insert_temp(Id::NULL, Activated(false)));
+        utils::clear_activation(ctx);
 
         for cmd in take(&mut self.queued_commands) {
             cmd.execute(self, ctx);
@@ -183,7 +509,9 @@ impl egui_wlr_layer::App for App {
     }
 
     fn on_exit(&mut self) {
+        self.resume.remember(&self.mpv);
         self.mpv.set_property("sub-pos", 100).ok();
+        self.mpv.restore_overrides();
         EXITED.store(true, Ordering::Relaxed);
     }
 }
@@ -0,0 +1,511 @@
+//! What's playing as an MPRIS player on the session bus, so KDE Connect, playerctl and desktop
+//! widgets can show it and control it. Their calls come in as commands like the gamepad's, or go
+//! straight to mpv for the ones that take a value, like seeking to a position.
+//!
+//! The bus is served from a thread of its own, reading the state the main thread publishes every
+//! frame, so a slow client can't hold up drawing.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+    time::Instant,
+};
+
+use zbus::{
+    blocking::{Connection, connection},
+    interface,
+    zvariant::{ObjectPath, OwnedValue, Value},
+};
+
+use crate::{
+    command::Command,
+    mpv::{Mpv, SPEED_PRESETS, time::Time},
+};
+
+const NAME: &str = "org.mpris.MediaPlayer2.htpc_overlay";
+const PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// The range the speed menu offers.
+const MIN_RATE: f64 = SPEED_PRESETS[0];
+const MAX_RATE: f64 = SPEED_PRESETS[SPEED_PRESETS.len() - 1];
+
+/// A jump in position bigger than this, in seconds, is a seek that clients are told about.
+const SEEK_THRESHOLD: f32 = 1.5;
+
+pub struct Mpris {
+    requests: Receiver<Request>,
+    /// Read by the bus thread whenever a client asks for a property.
+    shared: Arc<Mutex<State>>,
+    /// Properties that changed, for the bus thread to signal.
+    signals: Sender<Signal>,
+    published: State,
+    published_at: Instant,
+}
+
+/// A call from a client for the main thread to carry out.
+#[derive(Debug)]
+enum Request {
+    Command(Command),
+    /// Relative, in microseconds.
+    Seek(i64),
+    /// Absolute, in microseconds, if the track is still the one the client saw.
+    SetPosition {
+        track_id: String,
+        position: i64,
+    },
+    /// From 0 to 1.
+    SetVolume(f64),
+    SetRate(f64),
+    OpenUri(String),
+}
+
+enum Signal {
+    PropertiesChanged(Vec<&'static str>),
+    /// Position in microseconds.
+    Seeked(i64),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct State {
+    status: &'static str,
+    track: Option<Track>,
+    /// From 0 to 1, where mpv's 100 is 1.
+    volume: f64,
+    rate: f64,
+    /// Microseconds. Clients ask for it when they need it, it's not signaled.
+    position: i64,
+    can_go_next: bool,
+    can_go_previous: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Track {
+    /// Object path made from mpv's playlist entry ID, which is unique for as long as mpv runs.
+    id: String,
+    title: String,
+    artist: Option<String>,
+    url: String,
+    /// Microseconds.
+    length: Option<i64>,
+    art_url: Option<String>,
+}
+
+impl Mpris {
+    pub fn new() -> Self {
+        let (requests_tx, requests) = mpsc::channel();
+        let (signals, signals_rx) = mpsc::channel();
+        let shared = Arc::new(Mutex::new(State::default()));
+
+        let state = shared.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = serve(requests_tx, state, signals_rx) {
+                eprintln!("Failed to serve MPRIS: {e}");
+            }
+        });
+
+        Self {
+            requests,
+            shared,
+            signals,
+            published: State::default(),
+            published_at: Instant::now(),
+        }
+    }
+
+    pub fn update(&mut self, mpv: &mut Mpv, commands: &mut Vec<Command>) {
+        for request in self.requests.try_iter() {
+            handle(request, &self.published, mpv, commands);
+        }
+
+        let state = State::from_mpv(mpv);
+
+        let changed = [
+            ("PlaybackStatus", state.status != self.published.status),
+            ("Metadata", state.track != self.published.track),
+            ("Volume", state.volume != self.published.volume),
+            ("Rate", state.rate != self.published.rate),
+            ("CanGoNext", state.can_go_next != self.published.can_go_next),
+            ("CanGoPrevious", state.can_go_previous != self.published.can_go_previous),
+        ]
+        .into_iter()
+        .filter_map(|(name, changed)| changed.then_some(name))
+        .collect::<Vec<_>>();
+
+        let elapsed = if self.published.status == "Playing" {
+            self.published_at.elapsed().as_secs_f64() * self.published.rate
+        } else {
+            0.
+        };
+        let expected = self.published.position + (elapsed * 1e6) as i64;
+        let seeked = state.track.is_some()
+            && state.track == self.published.track
+            && (state.position - expected).abs() as f32 > SEEK_THRESHOLD * 1e6;
+
+        *self.shared.lock().unwrap() = state.clone();
+
+        if !changed.is_empty() {
+            self.signals.send(Signal::PropertiesChanged(changed)).ok();
+        }
+        if seeked {
+            self.signals.send(Signal::Seeked(state.position)).ok();
+        }
+
+        self.published = state;
+        self.published_at = Instant::now();
+    }
+}
+
+impl Default for Mpris {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn handle(request: Request, state: &State, mpv: &mut Mpv, commands: &mut Vec<Command>) {
+    match request {
+        Request::Command(command) => commands.push(command),
+        Request::Seek(offset) => {
+            let target = micros_to_time(state.position + offset);
+            mpv.seek_to(Time::seconds(target.as_secs().max(0.))).ok();
+        }
+        Request::SetPosition { track_id, position } => {
+            // ignored if the track changed since, as the spec says
+            if state.track.as_ref().is_some_and(|t| t.id == track_id) && position >= 0 {
+                mpv.seek_to(micros_to_time(position)).ok();
+            }
+        }
+        Request::SetVolume(volume) => {
+            mpv.set_property("volume", (volume.max(0.) * 100.).round())
+                .ok();
+        }
+        Request::SetRate(rate) => {
+            mpv.set_speed(rate.clamp(MIN_RATE, MAX_RATE)).ok();
+        }
+        Request::OpenUri(uri) => {
+            let path = uri.strip_prefix("file://").unwrap_or(&uri);
+            mpv.load_file(path).ok();
+            mpv.unpause().ok();
+        }
+    }
+}
+
+impl State {
+    fn from_mpv(mpv: &mut Mpv) -> Self {
+        let track = mpv
+            .playlist()
+            .iter()
+            .find(|e| e.playing)
+            .map(|entry| entry.id)
+            .zip(mpv.get_property_cached::<String>("path"))
+            .map(|(id, path)| {
+                let metadata = mpv.metadata();
+                Track {
+                    id: format!("/org/htpc_overlay/track/{id}"),
+                    title: mpv
+                        .get_property_cached::<String>("media-title")
+                        .unwrap_or_else(|| path.clone()),
+                    artist: metadata.artist.clone(),
+                    url: if path.contains("://") {
+                        path
+                    } else {
+                        format!("file://{path}")
+                    },
+                    length: mpv.duration().map(time_to_micros),
+                    art_url: mpv
+                        .youtube_id()
+                        .map(|id| format!("https://i.ytimg.com/vi/{id}/hqdefault.jpg")),
+                }
+            });
+
+        let playing_idx = mpv.playlist().iter().position(|e| e.playing);
+
+        Self {
+            status: match &track {
+                None => "Stopped",
+                Some(_) if mpv.get_property_cached::<bool>("pause") == Some(true) => "Paused",
+                Some(_) => "Playing",
+            },
+            track,
+            volume: mpv.get_property::<f64>("volume") / 100.,
+            rate: mpv.speed(),
+            position: time_to_micros(mpv.time_pos_fallback()),
+            can_go_next: playing_idx.is_some_and(|i| i + 1 < mpv.playlist().len()),
+            can_go_previous: playing_idx.is_some_and(|i| i > 0),
+        }
+    }
+
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let mut metadata = HashMap::new();
+        let Some(track) = &self.track else {
+            return metadata;
+        };
+
+        let mut insert = |key: &str, value: Value<'_>| {
+            if let Ok(value) = value.try_to_owned() {
+                metadata.insert(key.to_string(), value);
+            }
+        };
+
+        if let Ok(path) = ObjectPath::try_from(track.id.as_str()) {
+            insert("mpris:trackid", Value::from(path));
+        }
+        insert("xesam:title", Value::from(track.title.as_str()));
+        insert("xesam:url", Value::from(track.url.as_str()));
+        if let Some(artist) = &track.artist {
+            insert("xesam:artist", Value::from(vec![artist.as_str()]));
+        }
+        if let Some(length) = track.length {
+            insert("mpris:length", Value::from(length));
+        }
+        if let Some(art_url) = &track.art_url {
+            insert("mpris:artUrl", Value::from(art_url.as_str()));
+        }
+
+        metadata
+    }
+
+    /// A property that changed, for `PropertiesChanged`.
+    fn property(&self, name: &str) -> Option<Value<'static>> {
+        Some(match name {
+            "PlaybackStatus" => Value::from(self.status),
+            "Metadata" => Value::from(self.metadata()),
+            "Volume" => Value::from(self.volume),
+            "Rate" => Value::from(self.rate),
+            "CanGoNext" => Value::from(self.can_go_next),
+            "CanGoPrevious" => Value::from(self.can_go_previous),
+            _ => return None,
+        })
+    }
+}
+
+fn time_to_micros(time: Time) -> i64 {
+    (f64::from(time.as_secs()) * 1e6) as i64
+}
+
+fn micros_to_time(micros: i64) -> Time {
+    Time::seconds(micros as f64 / 1e6)
+}
+
+fn serve(
+    requests: Sender<Request>,
+    state: Arc<Mutex<State>>,
+    signals: Receiver<Signal>,
+) -> zbus::Result<()> {
+    let conn = connection::Builder::session()?
+        .name(NAME)?
+        .serve_at(PATH, Root)?
+        .serve_at(PATH, Player { requests, state: state.clone() })?
+        .build()?;
+
+    for signal in signals {
+        match signal {
+            Signal::PropertiesChanged(names) => {
+                let changed = {
+                    let state = state.lock().unwrap();
+                    names
+                        .into_iter()
+                        .filter_map(|name| Some((name, state.property(name)?)))
+                        .collect::<HashMap<_, _>>()
+                };
+
+                emit(
+                    &conn,
+                    "org.freedesktop.DBus.Properties",
+                    "PropertiesChanged",
+                    &(PLAYER_INTERFACE, changed, Vec::<String>::new()),
+                );
+            }
+            Signal::Seeked(position) => emit(&conn, PLAYER_INTERFACE, "Seeked", &(position,)),
+        }
+    }
+
+    Ok(())
+}
+
+fn emit<B>(conn: &Connection, interface: &str, member: &str, body: &B)
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    if let Err(e) = conn.emit_signal(None::<&str>, PATH, interface, member, body) {
+        eprintln!("Failed to send MPRIS {member}: {e}");
+    }
+}
+
+/// `org.mpris.MediaPlayer2`, about the player itself rather than playback.
+struct Root;
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> &str {
+        "htpc-overlay"
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<&str> {
+        vec!["file", "http", "https"]
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<&str> {
+        vec![]
+    }
+}
+
+struct Player {
+    requests: Sender<Request>,
+    state: Arc<Mutex<State>>,
+}
+
+impl Player {
+    fn send(&self, request: Request) {
+        self.requests.send(request).ok();
+    }
+
+    fn state(&self) -> State {
+        self.state.lock().unwrap().clone()
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn next(&self) {
+        self.send(Request::Command(Command::NextEntry));
+    }
+
+    fn previous(&self) {
+        self.send(Request::Command(Command::PreviousEntry));
+    }
+
+    fn pause(&self) {
+        self.send(Request::Command(Command::Pause));
+    }
+
+    fn play_pause(&self) {
+        self.send(Request::Command(Command::TogglePause));
+    }
+
+    fn stop(&self) {
+        self.send(Request::Command(Command::Stop));
+    }
+
+    fn play(&self) {
+        self.send(Request::Command(Command::Play));
+    }
+
+    fn seek(&self, offset: i64) {
+        self.send(Request::Seek(offset));
+    }
+
+    fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+        self.send(Request::SetPosition {
+            track_id: track_id.to_string(),
+            position,
+        });
+    }
+
+    fn open_uri(&self, uri: String) {
+        self.send(Request::OpenUri(uri));
+    }
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        self.state().status.to_string()
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        self.state().rate
+    }
+
+    #[zbus(property)]
+    fn set_rate(&self, rate: f64) {
+        // a rate of 0 is meant to pause, but clients call Pause for that anyway
+        if rate > 0. {
+            self.send(Request::SetRate(rate));
+        }
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        self.state().metadata()
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.state().volume
+    }
+
+    #[zbus(property)]
+    fn set_volume(&self, volume: f64) {
+        self.send(Request::SetVolume(volume));
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        self.state().position
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        MIN_RATE
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        MAX_RATE
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.state().can_go_next
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.state().can_go_previous
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        self.state().track.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        self.state().track.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        self.state().track.is_some()
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
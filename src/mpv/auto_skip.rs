@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+
+use super::{sponsorblock::SkipSegment, time::Time};
+use crate::config::config;
+
+/// Seconds ahead of a segment it's announced, so the skip can be called off.
+const LOOKAHEAD: f32 = 10.;
+/// How long after a skip it can still be undone.
+const UNDO_WINDOW: Duration = Duration::from_secs(5);
+/// mpv keeps reporting the old position for a moment after we seek, which shouldn't trigger the
+/// same skip again.
+const RESKIP_DELAY: Duration = Duration::from_secs(1);
+
+/// Keeps track of SponsorBlock segments skipped in the current file.
+#[derive(Default)]
+pub struct AutoSkip {
+    last: Option<Skip>,
+    /// Segments that were skipped and then undone, or kept before they came up, which are left
+    /// alone from then on.
+    kept: Vec<(Time, Time)>,
+}
+
+struct Skip {
+    /// Where playback was when the segment got skipped.
+    from: Time,
+    segment: (Time, Time),
+    at: Instant,
+}
+
+impl AutoSkip {
+    /// The segment playback should skip past right now, if any.
+    pub fn segment_to_skip<'a>(
+        &self,
+        segments: &'a [SkipSegment],
+        time: Time,
+    ) -> Option<&'a SkipSegment> {
+        let auto_skip = &config().sponsorblock.auto_skip;
+
+        segments.iter().find(|s| {
+            s.contains(time)
+                && auto_skip.contains(&s.category)
+                && !self.kept.contains(&s.segment)
+                && !self
+                    .last
+                    .as_ref()
+                    .is_some_and(|l| l.segment == s.segment && l.at.elapsed() < RESKIP_DELAY)
+        })
+    }
+
+    /// The next segment that's going to be skipped, if it starts soon.
+    pub fn upcoming<'a>(&self, segments: &'a [SkipSegment], time: Time) -> Option<&'a SkipSegment> {
+        let auto_skip = &config().sponsorblock.auto_skip;
+
+        segments
+            .iter()
+            .filter(|s| {
+                s.start() > time
+                    && (s.start() - time).as_secs() <= LOOKAHEAD
+                    && auto_skip.contains(&s.category)
+                    && !self.kept.contains(&s.segment)
+            })
+            .min_by(|a, b| a.start().as_secs().total_cmp(&b.start().as_secs()))
+    }
+
+    /// Leaves the segment alone when it comes up.
+    pub fn keep(&mut self, segment: &SkipSegment) {
+        self.kept.push(segment.segment);
+    }
+
+    pub fn skipped(&mut self, from: Time, segment: &SkipSegment) {
+        self.last = Some(Skip {
+            from,
+            segment: segment.segment,
+            at: Instant::now(),
+        });
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.last
+            .as_ref()
+            .is_some_and(|l| l.at.elapsed() < UNDO_WINDOW)
+    }
+
+    /// Forgets the last skip and returns where to go back to, if it can still be undone.
+    pub fn undo(&mut self) -> Option<Time> {
+        if !self.can_undo() {
+            return None;
+        }
+
+        let last = self.last.take()?;
+        self.kept.push(last.segment);
+        Some(last.from)
+    }
+}
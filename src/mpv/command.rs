@@ -1,17 +1,44 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 
-use super::time::Time;
+use super::{sponsorblock::SkipSegment, time::Time};
 
 #[derive(Serialize)]
 pub struct Command {
     command: Value,
+    /// Echoed back by mpv in the response, assigned when the command is sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<i64>,
 }
 
 impl Command {
+    pub fn with_request_id(self, request_id: i64) -> Self {
+        Self { request_id: Some(request_id), ..self }
+    }
+
+    /// The command's name, for logging.
+    pub fn name(&self) -> &str {
+        self.command[0].as_str().unwrap_or("?")
+    }
+
     pub fn observe_property(id: i32, property: &str) -> Self {
         Command {
             command: json!(["observe_property", id, property]),
+            request_id: None,
+        }
+    }
+
+    pub fn unobserve_property(id: i32) -> Self {
+        Command {
+            command: json!(["unobserve_property", id]),
+            request_id: None,
+        }
+    }
+
+    pub fn get_property(name: &str) -> Self {
+        Command {
+            command: json!(["get_property", name]),
+            request_id: None,
         }
     }
 
@@ -20,35 +47,133 @@ impl Command {
 
         Command {
             command: serde_json::json!(["set_property", name, value]),
+            request_id: None,
         }
     }
 
     pub fn cycle_property(name: &str) -> Self {
         Command {
             command: serde_json::json!(["cycle", name]),
+            request_id: None,
         }
     }
 
     pub fn add_property(name: &str, value: f32) -> Self {
         Command {
             command: serde_json::json!(["add", name, value]),
+            request_id: None,
+        }
+    }
+
+    pub fn add_chapter(delta: i64) -> Self {
+        Command {
+            // mpv parses the value as the property's type, so it has to be an integer
+            command: json!(["add", "chapter", delta]),
+            request_id: None,
         }
     }
 
     pub fn seek(seconds: Time, exact: bool) -> Command {
         Command {
             command: json!(["seek", seconds, if exact { "exact" } else { "keyframes" }]),
+            request_id: None,
+        }
+    }
+
+    pub fn stop() -> Command {
+        Command {
+            command: json!(["stop"]),
+            request_id: None,
+        }
+    }
+
+    pub fn playlist_remove(index: usize) -> Command {
+        Command {
+            command: json!(["playlist-remove", index]),
+            request_id: None,
+        }
+    }
+
+    pub fn playlist_clear() -> Command {
+        Command {
+            command: json!(["playlist-clear"]),
+            request_id: None,
+        }
+    }
+
+    pub fn playlist_next() -> Command {
+        Command {
+            command: json!(["playlist-next"]),
+            request_id: None,
+        }
+    }
+
+    pub fn playlist_prev() -> Command {
+        Command {
+            command: json!(["playlist-prev"]),
+            request_id: None,
         }
     }
 
     pub fn loadfile(path: &str) -> Command {
-        Command { command: json!(["loadfile", path]) }
+        Command {
+            command: json!(["loadfile", path]),
+            request_id: None,
+        }
+    }
+
+    /// Like [`Command::loadfile`], adding to the end of the playlist instead of replacing it, and
+    /// starting playback if nothing's playing.
+    pub fn loadfile_append(path: &str) -> Command {
+        Command {
+            command: json!(["loadfile", path, "append-play"]),
+            request_id: None,
+        }
+    }
+
+    /// Like [`Command::loadfile`], starting playback at `start` instead of the beginning.
+    pub fn loadfile_at(path: &str, start: Time) -> Command {
+        Command {
+            // the -1 is the playlist index, which mpv 0.38 put before the per-file options
+            command: json!(["loadfile", path, "replace", -1, format!("start={}", start.as_secs())]),
+            request_id: None,
+        }
+    }
+
+    pub fn af_add(filter: &str) -> Command {
+        Command {
+            command: json!(["af", "add", filter]),
+            request_id: None,
+        }
+    }
+
+    pub fn af_command(label: &str, command: &str, argument: &str) -> Command {
+        Command {
+            command: json!(["af-command", label, command, argument]),
+            request_id: None,
+        }
+    }
+
+    pub fn apply_profile(name: &str) -> Command {
+        Command {
+            command: json!(["apply-profile", name]),
+            request_id: None,
+        }
+    }
+
+    pub fn playlist_move(index1: usize, index2: usize) -> Command {
+        Command {
+            command: json!(["playlist-move", index1, index2]),
+            request_id: None,
+        }
     }
 }
 
 #[derive(Deserialize)]
 pub struct Response<T> {
     pub error: String,
+    #[serde(default)]
+    pub request_id: i64,
     pub data: Option<T>,
 }
 
@@ -62,6 +187,13 @@ pub enum Event {
         name: String,
     },
     Seek,
+    PlaybackRestart,
+    /// Skip segments for a file, from the thread that looked them up rather than from mpv.
+    #[serde(skip)]
+    SkipSegments {
+        lookup: u64,
+        segments: Vec<SkipSegment>,
+    },
     #[serde(other)]
     Unknown,
 }
@@ -0,0 +1,48 @@
+use std::time::{Duration, Instant};
+
+use super::RequestId;
+
+/// Ramps the gain of a dedicated volume filter back up after a jump in playback, so audio
+/// doesn't resume at full volume mid-scene.
+#[derive(Default)]
+pub struct AudioFade {
+    pub duration: Duration,
+    /// Whether the `@fade` filter has been inserted into mpv's filter chain.
+    pub filter_added: bool,
+    /// The `af add` command for the filter, until mpv has confirmed it.
+    pub filter_request: Option<RequestId>,
+    started: Option<Instant>,
+}
+
+impl AudioFade {
+    pub const FILTER: &str = "@fade:lavfi=[volume=volume=1]";
+    pub const LABEL: &str = "fade";
+
+    pub fn enabled(&self) -> bool {
+        !self.duration.is_zero()
+    }
+
+    pub fn start(&mut self) {
+        if self.enabled() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.started = None;
+    }
+
+    /// The gain to apply this frame, or `None` if no fade is in progress.
+    pub fn gain(&mut self) -> Option<f32> {
+        let started = self.started?;
+
+        let progress = started.elapsed().as_secs_f32() / self.duration.as_secs_f32();
+        if progress >= 1. {
+            self.started = None;
+            return Some(1.);
+        }
+
+        // quadratic, since loudness is perceived logarithmically
+        Some(progress * progress)
+    }
+}
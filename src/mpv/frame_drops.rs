@@ -0,0 +1,41 @@
+use std::time::{Duration, Instant};
+
+/// How many frames have to be dropped or delayed within [`WINDOW`] to count as playback not
+/// keeping up, rather than a hiccup.
+const THRESHOLD: u64 = 30;
+const WINDOW: Duration = Duration::from_secs(10);
+
+/// Watches mpv's dropped and delayed frame counters, to suggest fixes when playback can't keep
+/// up. Warns once per file.
+#[derive(Default)]
+pub struct FrameDrops {
+    window: Option<(Instant, u64)>,
+    warned_path: Option<String>,
+}
+
+impl FrameDrops {
+    /// Whether to warn about the file now, given its combined count of dropped and delayed
+    /// frames.
+    pub fn check(&mut self, path: Option<&str>, count: u64) -> bool {
+        let Some(path) = path else {
+            self.window = None;
+            return false;
+        };
+
+        match self.window {
+            // mpv resets the counters for every file
+            Some((started, start_count)) if started.elapsed() < WINDOW && count >= start_count => {
+                if count - start_count < THRESHOLD || self.warned_path.as_deref() == Some(path) {
+                    return false;
+                }
+
+                self.warned_path = Some(path.to_string());
+                true
+            }
+            _ => {
+                self.window = Some((Instant::now(), count));
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use super::time::Time;
+
+/// Keyframes smaller than this apart are the same one, give or take how precisely mpv reports
+/// where it landed.
+const SAME_KEYFRAME: f32 = 0.05;
+
+/// Where the keyframes in the current file are, as far as seeks in keyframe mode have shown. The
+/// demuxer's index isn't used, since mpv has no property for it, but every keyframe seek lands on
+/// one, and encoders mostly space them evenly.
+#[derive(Default)]
+pub struct Keyframes {
+    /// In seconds, sorted.
+    known: Vec<f32>,
+    landing: Landing,
+}
+
+#[derive(Default, PartialEq, Eq)]
+enum Landing {
+    #[default]
+    None,
+    /// A keyframe seek was sent.
+    Seeking,
+    /// mpv restarted playback after it, so the next position it reports is a keyframe.
+    Restarted,
+}
+
+impl Keyframes {
+    pub fn seeked(&mut self) {
+        self.landing = Landing::Seeking;
+    }
+
+    pub fn playback_restarted(&mut self) {
+        if self.landing == Landing::Seeking {
+            self.landing = Landing::Restarted;
+        }
+    }
+
+    /// mpv reported a new position.
+    pub fn position(&mut self, pos: f32) {
+        if self.landing != Landing::Restarted {
+            return;
+        }
+        self.landing = Landing::None;
+
+        let idx = self.known.partition_point(|&k| k < pos);
+        let near = |k: &f32| (k - pos).abs() < SAME_KEYFRAME;
+        if !self.known.get(idx).is_some_and(near)
+            && !idx
+                .checked_sub(1)
+                .and_then(|i| self.known.get(i))
+                .is_some_and(near)
+        {
+            self.known.insert(idx, pos);
+        }
+    }
+
+    /// Smallest gap between keyframes seen so far. Others are likely multiples of it, where seeks
+    /// skipped over some.
+    fn interval(&self) -> Option<f32> {
+        self.known
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .min_by(f32::total_cmp)
+    }
+
+    /// Where a keyframe seek to `target` is likely to end up. mpv goes to the keyframe after the
+    /// target when seeking forward and the one before it when seeking back. `None` until two
+    /// keyframes are known.
+    pub fn snap(&self, target: Time, forward: bool) -> Option<Time> {
+        let interval = self.interval()?;
+        let target = target.as_secs();
+
+        let nearest = *self
+            .known
+            .iter()
+            .min_by(|a, b| (*a - target).abs().total_cmp(&(*b - target).abs()))?;
+
+        let steps = (target - nearest) / interval;
+        let steps = if forward { steps.ceil() } else { steps.floor() };
+
+        Some(Time::seconds(nearest + steps * interval))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn land(keyframes: &mut Keyframes, pos: f32) {
+        keyframes.seeked();
+        keyframes.playback_restarted();
+        keyframes.position(pos);
+    }
+
+    #[test]
+    fn only_keyframe_seeks_count() {
+        let mut keyframes = Keyframes::default();
+        keyframes.position(3.);
+        keyframes.seeked();
+        keyframes.position(4.);
+        assert!(keyframes.known.is_empty());
+
+        keyframes.playback_restarted();
+        keyframes.position(5.);
+        keyframes.position(6.);
+        assert_eq!(keyframes.known, [5.]);
+    }
+
+    #[test]
+    fn position_dedup() {
+        let mut keyframes = Keyframes::default();
+        land(&mut keyframes, 12.);
+        land(&mut keyframes, 10.);
+        land(&mut keyframes, 10.02);
+        land(&mut keyframes, 9.97);
+        land(&mut keyframes, 11.99);
+        land(&mut keyframes, 14.);
+        assert_eq!(keyframes.known, [10., 12., 14.]);
+    }
+
+    #[test]
+    fn snap() {
+        let mut keyframes = Keyframes::default();
+        land(&mut keyframes, 10.);
+        assert_eq!(keyframes.snap(Time::seconds(15.), true), None);
+
+        land(&mut keyframes, 12.);
+        let snap = |target: f32, forward| keyframes.snap(Time::seconds(target), forward);
+        assert_eq!(snap(15., true), Some(Time::seconds(16.)));
+        assert_eq!(snap(15., false), Some(Time::seconds(14.)));
+        assert_eq!(snap(7., true), Some(Time::seconds(8.)));
+        assert_eq!(snap(7., false), Some(Time::seconds(6.)));
+        // already on one
+        assert_eq!(snap(14., true), Some(Time::seconds(14.)));
+        assert_eq!(snap(14., false), Some(Time::seconds(14.)));
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+use super::Mpv;
+
+/// A jump in position bigger than this that playback doesn't explain is a seek.
+const SEEK_THRESHOLD: f32 = 2.;
+/// How long after following someone else's change our own state isn't checked for changes, so
+/// mpv catching up isn't mistaken for the user pausing or seeking.
+const SETTLE: Duration = Duration::from_secs(1);
+
+/// A pause, play or seek done here, rather than playback moving along.
+#[derive(Clone, Copy, Debug)]
+pub enum LocalChange {
+    PauseToggled,
+    Seeked,
+}
+
+/// Notices pauses, plays and seeks, for sharing them with others watching along.
+#[derive(Default)]
+pub struct LocalChanges {
+    /// Pause state and position as of the last check.
+    last: Option<(bool, f32, Instant)>,
+    settle_until: Option<Instant>,
+}
+
+impl LocalChanges {
+    /// What changed since the last call, if anything. Call it every frame.
+    pub fn check(&mut self, mpv: &Mpv) -> Option<LocalChange> {
+        let paused = mpv.get_property_cached::<bool>("pause").unwrap_or(true);
+        let position = mpv.time_pos_fallback().as_secs();
+
+        let last = self.last.replace((paused, position, Instant::now()));
+
+        if self
+            .settle_until
+            .is_some_and(|until| Instant::now() < until)
+        {
+            return None;
+        }
+
+        let (last_paused, last_position, at) = last?;
+        let predicted = if last_paused {
+            last_position
+        } else {
+            last_position + at.elapsed().as_secs_f32() * mpv.speed() as f32
+        };
+
+        if paused != last_paused {
+            Some(LocalChange::PauseToggled)
+        } else if (position - predicted).abs() > SEEK_THRESHOLD {
+            Some(LocalChange::Seeked)
+        } else {
+            None
+        }
+    }
+
+    /// Ignores changes for a moment, after making mpv follow someone else's.
+    pub fn settle(&mut self) {
+        self.settle_until = Some(Instant::now() + SETTLE);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
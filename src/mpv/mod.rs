@@ -1,6 +1,8 @@
 use std::{
-    io::{self, BufRead, BufReader, ErrorKind, Write as _},
+    io::{self, BufReader, ErrorKind, Write as _},
     os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
     time::{Duration, Instant},
 };
 
@@ -9,20 +11,100 @@ use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::Value;
 
 use self::{
+    auto_skip::AutoSkip,
     command::{Command, Event, EventOrResponse, Response},
+    fade::AudioFade,
+    frame_drops::FrameDrops,
+    keyframes::Keyframes,
+    observe::{Observation, PropertyObserver},
+    overrides::Overrides,
     seek_speed::SeekSpeed,
+    skip::VideoRef,
     time::Time,
+    up_next::UpNext,
+    written::Written,
+};
+use crate::{
+    command::Event as AppEvent,
+    config::config,
+    demo,
+    store::settings::CachePreset,
+    ui::toast::Toast,
+    utils::{read_line_nonblocking, youtube_id_from_title, youtube_id_from_url},
 };
-use crate::utils::youtube_id_from_url;
 
+mod auto_skip;
 mod command;
+mod fade;
+mod frame_drops;
+mod keyframes;
+pub mod local_changes;
+mod observe;
+mod overrides;
 pub mod seek_speed;
-mod sponsorblock;
+pub mod skip;
+pub mod sponsorblock;
+#[cfg(test)]
+mod tests;
 pub mod time;
+mod up_next;
+mod written;
+
+/// How long to wait between attempts to reconnect to mpv.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+/// Playback speeds offered by the speed menu and stepped through by `speed_up`/`speed_down`.
+pub const SPEED_PRESETS: [f64; 7] = [0.5, 0.75, 1., 1.25, 1.5, 1.75, 2.];
+
+/// How long to wait for mpv to respond to a command before giving up on it.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long mpv gets to report a property `get_property` started observing before it's deemed
+/// stuck. mpv reports a property as soon as it's observed, so this is shorter than
+/// `COMMAND_TIMEOUT`.
+const REPORT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Seconds into a chapter after which going back restarts it, mpv's `chapter-seek-threshold`.
+const CHAPTER_SEEK_THRESHOLD: f32 = 5.;
+
+/// Identifies a command sent with [`Mpv::command_with_response`], whose result can be picked up
+/// later with [`Mpv::take_response`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestId(i64);
+
+struct PendingCommand {
+    name: String,
+    sent: Instant,
+    /// Whether to keep the response around for `take_response`, rather than only logging errors.
+    keep_response: bool,
+}
 
 pub struct Mpv {
-    socket: BufReader<UnixStream>,
+    socket_path: PathBuf,
+    /// `None` while mpv isn't reachable, in which case we periodically try to reconnect.
+    socket: Option<BufReader<UnixStream>>,
+    /// Never connects, so made-up state from [`Mpv::fake_property`] isn't overwritten.
+    offline: bool,
+    /// A partially received line, kept until the rest of it arrives.
+    line_buf: String,
+    next_request_id: i64,
+    /// Commands mpv hasn't responded to yet, by request ID.
+    pending: HashMap<i64, PendingCommand>,
+    responses: HashMap<i64, Response<Value>>,
+    last_connect_attempt: Instant,
+    /// Connection state as last reported through a toast.
+    reported_connected: bool,
+    /// Set when the connection was dropped because mpv stopped responding, until that's been
+    /// reported.
+    stalled: bool,
+    /// Properties `get_property` started observing that mpv hasn't reported yet, and since when.
+    unreported: HashMap<String, Instant>,
+    /// Every property we've asked mpv to observe, so they can be observed again after
+    /// reconnecting.
+    observed: Vec<Observation>,
+    released_tx: Sender<String>,
+    released_rx: Receiver<String>,
+    /// Observations made implicitly by `get_property` while the current view is shown.
+    view_scope: Vec<PropertyObserver>,
     observed_properties: HashMap<String, Value>,
+    written: Written,
     next_observe_id: i32,
     event_buffer: Vec<Event>,
     seek_state: Option<SeekState>,
@@ -30,7 +112,22 @@ pub struct Mpv {
     chapters: Vec<ChapterRaw>,
     playlist: Vec<PlaylistEntry>,
     metadata: Metadata,
-    sponsorblock_segments: Vec<sponsorblock::SkipSegment>,
+    /// Of the current file, from wherever it could be found, see [`Mpv::youtube_id`].
+    youtube_id: Option<String>,
+    skip_segments: Vec<sponsorblock::SkipSegment>,
+    /// Counts skip segment lookups, so the results for a file that's no longer playing are
+    /// dropped.
+    skip_lookup: u64,
+    skip_segments_tx: Sender<Event>,
+    skip_segments_rx: Receiver<Event>,
+    auto_skip: AutoSkip,
+    keyframes: Keyframes,
+    up_next: UpNext,
+    audio_fade: AudioFade,
+    frame_drops: FrameDrops,
+    /// The video track that was selected before switching to audio only, to switch back to.
+    video_before_audio_only: Option<Value>,
+    overrides: Overrides,
 }
 
 struct SeekState {
@@ -45,15 +142,80 @@ struct SeekState {
 
 impl Mpv {
     pub fn new() -> Self {
-        let stream = UnixStream::connect("/run/user/1000/mpv.sock")
-            .expect("Failed to connect to mpv socket");
-        stream
-            .set_nonblocking(true)
-            .expect("Failed to set non-blocking mode");
-
-        let mut this = Self {
-            socket: BufReader::new(stream),
+        Self::with_socket(config().mpv_socket.clone())
+    }
+
+    /// Connects to mpv's `input-ipc-server` at `socket_path` rather than the configured one.
+    pub fn with_socket(socket_path: PathBuf) -> Self {
+        let mut this = Self::disconnected(socket_path);
+
+        match this.connect() {
+            Ok(()) => this.reported_connected = true,
+            Err(e) => eprintln!("Failed to connect to mpv socket: {e}"),
+        }
+
+        this
+    }
+
+    /// An instance that never connects to mpv, for demo mode.
+    pub fn offline() -> Self {
+        let mut this = Self::disconnected(PathBuf::new());
+        this.offline = true;
+        this
+    }
+
+    fn disconnected(socket_path: PathBuf) -> Self {
+        let (released_tx, released_rx) = mpsc::channel();
+        let (skip_segments_tx, skip_segments_rx) = mpsc::channel();
+
+        Self {
+            socket_path,
+            socket: None,
+            offline: false,
+            line_buf: String::new(),
+            next_request_id: 0,
+            pending: HashMap::new(),
+            responses: HashMap::new(),
+            last_connect_attempt: Instant::now(),
+            reported_connected: false,
+            stalled: false,
+            unreported: HashMap::new(),
+            observed: [
+                "time-pos",
+                "duration",
+                "percent-pos",
+                "pause",
+                "speed",
+                "playlist",
+                "track-list",
+                "chapter-list",
+                "path",
+                "media-title",
+                "metadata",
+                "audio-device",
+                "video-params",
+                "target-trc",
+                "sub-pos",
+                "frame-drop-count",
+                "vo-delayed-frame-count",
+                "ab-loop-a",
+                "ab-loop-b",
+                "idle-active",
+                "vid",
+            ]
+            .into_iter()
+            .map(|name| Observation {
+                name: name.to_string(),
+                id: 0,
+                permanent: true,
+                handles: 0,
+            })
+            .collect(),
+            released_tx,
+            released_rx,
+            view_scope: Vec::new(),
             observed_properties: HashMap::new(),
+            written: Written::default(),
             next_observe_id: 0,
             event_buffer: Vec::new(),
             seek_state: None,
@@ -61,100 +223,423 @@ impl Mpv {
             chapters: Vec::new(),
             playlist: Vec::new(),
             metadata: Metadata::default(),
-            sponsorblock_segments: Vec::new(),
-        };
+            youtube_id: None,
+            skip_segments: Vec::new(),
+            skip_lookup: 0,
+            skip_segments_tx,
+            skip_segments_rx,
+            auto_skip: AutoSkip::default(),
+            keyframes: Keyframes::default(),
+            up_next: UpNext::default(),
+            audio_fade: AudioFade::default(),
+            frame_drops: FrameDrops::default(),
+            video_before_audio_only: None,
+            overrides: Overrides::default(),
+        }
+    }
 
-        this.observe_property("time-pos").unwrap();
-        this.observe_property("duration").unwrap();
-        this.observe_property("playlist").unwrap();
-        this.observe_property("track-list").unwrap();
-        this.observe_property("chapter-list").unwrap();
-        this.observe_property("chapter-list").unwrap();
-        this.observe_property("metadata").unwrap();
+    fn connect(&mut self) -> io::Result<()> {
+        self.last_connect_attempt = Instant::now();
 
-        this
+        let stream = UnixStream::connect(&self.socket_path)?;
+        stream.set_nonblocking(true)?;
+        self.socket = Some(BufReader::new(stream));
+
+        self.next_observe_id = 0;
+        for idx in 0..self.observed.len() {
+            let id = self.next_observe_id;
+            self.next_observe_id += 1;
+            self.observed[idx].id = id;
+
+            let cmd = Command::observe_property(id, &self.observed[idx].name);
+            self.command(cmd)?;
+        }
+
+        // overrides are applied once we know whether this is the same mpv, see update
+        let id = self.command_with_response(Command::get_property("pid"))?;
+        self.overrides.pid_request = Some(id);
+
+        Ok(())
     }
 
-    fn blocking<T>(&mut self, f: impl FnOnce(&mut Self) -> io::Result<T>) -> io::Result<T> {
-        self.socket.get_mut().set_nonblocking(false)?;
-        let result = f(self);
-        self.socket.get_mut().set_nonblocking(true)?;
-        result
+    /// Drops the connection and everything we knew about mpv's state, which will be rebuilt from
+    /// scratch once we reconnect.
+    fn disconnect(&mut self) {
+        if self.socket.take().is_none() {
+            return;
+        }
+
+        eprintln!("Lost connection to mpv");
+
+        self.line_buf.clear();
+        self.pending.clear();
+        self.responses.clear();
+        self.unreported.clear();
+
+        self.observed_properties.clear();
+        self.written.clear();
+        self.event_buffer.clear();
+        self.seek_state = None;
+        self.tracks.clear();
+        self.chapters.clear();
+        self.playlist.clear();
+        self.metadata = Metadata::default();
+        self.youtube_id = None;
+        self.skip_segments.clear();
+        self.auto_skip = AutoSkip::default();
+        self.keyframes = Keyframes::default();
+        self.up_next = UpNext::default();
+        self.audio_fade.filter_added = false;
+        self.audio_fade.filter_request = None;
+        // the requests are gone with the connection, but the originals are kept in case this is
+        // the same mpv, like after a stall
+        self.overrides.pending.clear();
+        self.overrides.pid_request = None;
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.socket.is_some()
+    }
+
+    fn socket(&mut self) -> io::Result<&mut BufReader<UnixStream>> {
+        self.socket
+            .as_mut()
+            .ok_or_else(|| io::Error::new(ErrorKind::NotConnected, "Not connected to mpv"))
     }
 
     fn read_line<T: DeserializeOwned>(&mut self) -> io::Result<Option<T>> {
-        let mut buf = String::new();
-        match self.socket.read_line(&mut buf) {
-            Ok(0) => Err(io::Error::new(
-                ErrorKind::UnexpectedEof,
-                "EOF reached while reading from mpv socket",
-            )),
-            Ok(_) => {
-                // eprintln!("< {}", buf.trim());
-
-                let event: T = serde_json::from_str(&buf).map_err(|e| {
-                    io::Error::other(format!("Failed to deserialize mpv event: {}", e))
-                })?;
-
-                Ok(Some(event))
+        let mut buf = std::mem::take(&mut self.line_buf);
+        let line = read_line_nonblocking(self.socket()?, &mut buf);
+        self.line_buf = buf;
+
+        let Some(line) = line? else {
+            return Ok(None);
+        };
+        // eprintln!("< {}", line.trim());
+
+        let event: T = serde_json::from_str(&line)
+            .map_err(|e| io::Error::other(format!("Failed to deserialize mpv event: {}", e)))?;
+
+        Ok(Some(event))
+    }
+
+    fn read_events(&mut self) -> io::Result<()> {
+        while let Some(msg) = self.read_line::<EventOrResponse<Value>>()? {
+            match msg {
+                EventOrResponse::Event(event) => self.event_buffer.push(event),
+                EventOrResponse::Response(response) => self.handle_response(response),
             }
-            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
-            Err(err) => Err(err),
         }
+        Ok(())
     }
 
-    fn read_response<T: DeserializeOwned>(&mut self) -> io::Result<Response<T>> {
-        self.blocking(|this| {
-            loop {
-                let v = this.read_line::<EventOrResponse<T>>()?.unwrap();
+    fn handle_response(&mut self, response: Response<Value>) {
+        let Some(pending) = self.pending.remove(&response.request_id) else {
+            return;
+        };
 
-                match v {
-                    EventOrResponse::Event(event) => {
-                        this.event_buffer.push(event);
-                    }
-                    EventOrResponse::Response(response) => {
-                        return Ok(response);
-                    }
-                }
+        if pending.keep_response {
+            self.responses.insert(response.request_id, response);
+        } else if response.error != "success" {
+            eprintln!("mpv command {} failed: {}", pending.name, response.error);
+        }
+    }
+
+    /// Sends a command without waiting for mpv to respond. Only fails if the command couldn't be
+    /// sent; errors reported by mpv are logged once the response comes in.
+    pub fn command(&mut self, cmd: Command) -> io::Result<()> {
+        self.send(cmd, false).map(|_| ())
+    }
+
+    /// Like [`Mpv::command`], but keeps mpv's response to be picked up with
+    /// [`Mpv::take_response`].
+    pub fn command_with_response(&mut self, cmd: Command) -> io::Result<RequestId> {
+        self.send(cmd, true)
+    }
+
+    /// The result of a command sent with [`Mpv::command_with_response`], or `None` while mpv
+    /// hasn't responded yet.
+    pub fn take_response<T: DeserializeOwned>(
+        &mut self,
+        id: RequestId,
+    ) -> Option<io::Result<Option<T>>> {
+        let Some(response) = self.responses.remove(&id.0) else {
+            if self.pending.contains_key(&id.0) {
+                return None;
             }
+
+            // timed out, or the connection dropped before mpv got to it
+            return Some(Err(io::Error::new(ErrorKind::TimedOut, "No response from mpv")));
+        };
+
+        Some(if response.error == "success" {
+            Ok(response
+                .data
+                .and_then(|data| serde_json::from_value(data).ok()))
+        } else {
+            Err(io::Error::other(format!("mpv command error: {}", response.error)))
         })
     }
 
-    fn read_events(&mut self) -> io::Result<()> {
-        while let Some(event) = self.read_line::<Event>()? {
-            self.event_buffer.push(event);
+    fn send(&mut self, cmd: Command, keep_response: bool) -> io::Result<RequestId> {
+        let result = self.send_inner(cmd, keep_response);
+        if let Err(ref e) = result
+            && is_connection_error(e)
+        {
+            self.disconnect();
         }
-        Ok(())
+        result
     }
 
-    pub fn command<T: DeserializeOwned>(&mut self, cmd: Command) -> io::Result<Option<T>> {
+    fn send_inner(&mut self, cmd: Command, keep_response: bool) -> io::Result<RequestId> {
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+
+        let cmd = cmd.with_request_id(id);
         let cmd_str = serde_json::to_string(&cmd).expect("Failed to serialize command");
         // eprintln!("> {}", cmd_str);
-        writeln!(self.socket.get_mut(), "{}", cmd_str)?;
-        self.socket.get_mut().flush()?;
+        let socket = self.socket()?.get_mut();
+        writeln!(socket, "{}", cmd_str)?;
+        socket.flush()?;
 
-        let response = self.blocking(|mpv| mpv.read_response::<T>())?;
+        self.pending.insert(id, PendingCommand {
+            name: cmd.name().to_string(),
+            sent: Instant::now(),
+            keep_response,
+        });
 
-        if response.error == "success" {
-            Ok(response.data)
-        } else {
-            Err(io::Error::other(format!("mpv command error: {}", response.error)))
-        }
+        Ok(RequestId(id))
     }
 
-    pub fn update(&mut self) -> io::Result<()> {
-        self.read_events()?;
+    pub fn update(&mut self, events: &mut Vec<AppEvent>) {
+        if self.is_connected() {
+            if let Err(e) = self.read_events() {
+                eprintln!("Failed to read mpv events: {e}");
+                if is_connection_error(&e) {
+                    self.disconnect();
+                }
+            }
+        } else if !self.offline
+            && self.last_connect_attempt.elapsed() > RECONNECT_INTERVAL
+            && let Err(e) = self.connect()
+        {
+            self.socket = None;
+            if e.kind() != ErrorKind::NotFound && e.kind() != ErrorKind::ConnectionRefused {
+                eprintln!("Failed to reconnect to mpv: {e}");
+            }
+        }
+
+        if std::mem::take(&mut self.stalled) {
+            self.reported_connected = false;
+            events.push(AppEvent::Toast(Toast::MpvNotResponding));
+        }
+
+        if self.reported_connected != self.is_connected() {
+            self.reported_connected = self.is_connected();
+            events.push(AppEvent::Toast(if self.reported_connected {
+                Toast::MpvReconnected
+            } else {
+                Toast::MpvDisconnected
+            }));
+        }
+
+        self.event_buffer.extend(self.skip_segments_rx.try_iter());
         for ev in std::mem::take(&mut self.event_buffer) {
+            if let Event::PropertyChange { name, .. } = &ev {
+                self.unreported.remove(name);
+            }
             self.handle_event(ev);
         }
-        Ok(())
+
+        if let Some(name) = self
+            .unreported
+            .iter()
+            .find(|(_, since)| since.elapsed() > REPORT_TIMEOUT)
+            .map(|(name, _)| name.clone())
+        {
+            eprintln!("mpv didn't report {name} in time, reconnecting");
+            self.stalled = true;
+            self.disconnect();
+            // no sense in retrying right away if it's still stuck
+            self.last_connect_attempt = Instant::now();
+        }
+
+        for (name, actual) in self.written.expired() {
+            match actual {
+                Some(value) => self.observed_properties.insert(name, value),
+                None => self.observed_properties.remove(&name),
+            };
+        }
+
+        self.release_observers();
+
+        let time = self.time_pos_fallback();
+        if let Some(segment) = self.auto_skip.segment_to_skip(&self.skip_segments, time) {
+            let (end, category) = (segment.end(), segment.category);
+            self.auto_skip.skipped(time, segment);
+            self.seek_to(end).ok();
+            events.push(AppEvent::Toast(Toast::SegmentSkipped { category }));
+        }
+
+        let slow_frames = self
+            .get_property_cached::<u64>("frame-drop-count")
+            .unwrap_or(0)
+            + self
+                .get_property_cached::<u64>("vo-delayed-frame-count")
+                .unwrap_or(0);
+        let path = self.get_property_cached::<String>("path");
+        if self.frame_drops.check(path.as_deref(), slow_frames) {
+            events.push(AppEvent::Toast(Toast::FramesDropping));
+        }
+
+        self.pending.retain(|_, pending| {
+            let waiting = pending.sent.elapsed() < COMMAND_TIMEOUT;
+            if !waiting {
+                eprintln!("mpv didn't respond to {}", pending.name);
+            }
+            waiting
+        });
+
+        if let Some(id) = self.audio_fade.filter_request
+            && let Some(result) = self.take_response::<Value>(id)
+        {
+            self.audio_fade.filter_request = None;
+
+            if let Err(e) = result {
+                eprintln!("Failed to add audio fade filter: {e}");
+                self.audio_fade.filter_added = false;
+                self.audio_fade.stop();
+            }
+        }
+
+        if let Some(id) = self.overrides.pid_request
+            && let Some(result) = self.take_response::<i64>(id)
+        {
+            self.overrides.pid_request = None;
+
+            let pid = result.ok().flatten();
+            if pid.is_none() || pid != self.overrides.pid {
+                // mpv restarted, so what it had before doesn't matter anymore
+                self.overrides.original.clear();
+                self.overrides.pid = pid;
+            }
+
+            self.apply_overrides();
+        }
+
+        for (name, id) in std::mem::take(&mut self.overrides.pending) {
+            let Some(result) = self.take_response::<Value>(id) else {
+                self.overrides.pending.push((name, id));
+                continue;
+            };
+
+            match result {
+                Ok(original) => {
+                    let original = original.unwrap_or(Value::Null);
+                    self.overrides.original.push((name.clone(), original));
+                }
+                Err(e) => eprintln!("Failed to get {name} before overriding it: {e}"),
+            }
+
+            if let Some((_, value)) = self.overrides.wanted.iter().find(|(n, _)| *n == name) {
+                let value = value.clone();
+                self.set_property(&name, value).ok();
+            }
+        }
+
+        if let Some(gain) = self.audio_fade.gain() {
+            self.command(Command::af_command(AudioFade::LABEL, "volume", &gain.to_string()))
+                .ok();
+        }
+    }
+
+    /// Applies to whatever's playing right away, as well as everything after it.
+    pub fn set_cache_preset(&mut self, preset: CachePreset) {
+        for (name, value) in preset.properties().into_iter().flatten() {
+            if let Err(e) = self.set_property(name, value) {
+                eprintln!("Failed to set {name}: {e}");
+            }
+        }
+    }
+
+    /// Sets mpv properties for the active profile, putting back the ones the previous profile
+    /// set that this one doesn't.
+    pub fn set_overrides(&mut self, wanted: Vec<(String, Value)>) {
+        for (name, original) in std::mem::take(&mut self.overrides.original) {
+            if wanted.iter().any(|(n, _)| *n == name) {
+                self.overrides.original.push((name, original));
+            } else {
+                self.set_property(&name, original).ok();
+            }
+        }
+
+        self.overrides.wanted = wanted;
+        self.apply_overrides();
+    }
+
+    fn apply_overrides(&mut self) {
+        // applied on connecting instead
+        if !self.is_connected() || self.overrides.pid_request.is_some() {
+            return;
+        }
+
+        for (name, value) in self.overrides.wanted.clone() {
+            if self.overrides.original(&name).is_some() {
+                self.set_property(&name, value).ok();
+                continue;
+            }
+
+            if self.overrides.pending.iter().any(|(n, _)| *n == name) {
+                continue;
+            }
+
+            // set once we know what to put back, see update
+            match self.command_with_response(Command::get_property(&name)) {
+                Ok(id) => self.overrides.pending.push((name, id)),
+                Err(e) => eprintln!("Failed to get {name} before overriding it: {e}"),
+            }
+        }
+    }
+
+    /// Puts back everything the profile set, for when the overlay exits.
+    pub fn restore_overrides(&mut self) {
+        for (name, original) in std::mem::take(&mut self.overrides.original) {
+            self.set_property(&name, original).ok();
+        }
+    }
+
+    /// Sets how long audio takes to fade back in after seeks, skips and unpausing. Zero disables
+    /// fading.
+    pub fn set_audio_fade(&mut self, duration: Duration) {
+        self.audio_fade.duration = duration;
+    }
+
+    fn fade_in_audio(&mut self) {
+        if !self.audio_fade.enabled() {
+            return;
+        }
+
+        if !self.audio_fade.filter_added {
+            match self.command_with_response(Command::af_add(AudioFade::FILTER)) {
+                Ok(id) => self.audio_fade.filter_request = Some(id),
+                Err(e) => {
+                    eprintln!("Failed to add audio fade filter: {e}");
+                    return;
+                }
+            }
+            self.audio_fade.filter_added = true;
+        }
+
+        self.audio_fade.start();
     }
 
     pub fn handle_event(&mut self, event: Event) {
         match event {
             Event::PropertyChange { data, name } => match name.as_str() {
                 "playlist" => {
-                    Self::store_deserialized_property(&name, data, &mut self.playlist);
+                    Self::store_deserialized_property(&name, data.clone(), &mut self.playlist);
+                    // kept as mpv sent it too, for passing on as is
+                    self.observed_properties.insert(name, data);
                 }
                 "track-list" => {
                     Self::store_deserialized_property(&name, data, &mut self.tracks);
@@ -162,42 +647,92 @@ impl Mpv {
                 "chapter-list" => {
                     Self::store_deserialized_property(&name, data, &mut self.chapters);
                 }
+                "time-pos" => {
+                    if let Some(pos) = data.as_f64() {
+                        self.keyframes.position(pos as f32);
+                    }
+                    // seeks write it through, and a report from before one mustn't undo it
+                    if let Some(data) = self.written.reported(&name, data) {
+                        self.observed_properties.insert(name, data);
+                    }
+                }
                 "metadata" => {
+                    self.auto_skip = AutoSkip::default();
+                    self.keyframes = Keyframes::default();
+                    self.up_next = UpNext::default();
+                    self.skip_segments.clear();
+                    self.skip_lookup += 1;
+
                     if data.is_null() {
                         self.metadata = Metadata::default();
-                        self.sponsorblock_segments.clear();
+                        self.youtube_id = None;
                         return;
                     }
 
                     Self::store_deserialized_property(&name, data, &mut self.metadata);
 
-                    if let Some(youtube_id) = self.metadata.youtube_id() {
-                        let res = sponsorblock::fetch_skip_segments(youtube_id);
-                        self.sponsorblock_segments = res.unwrap_or_default();
-                    } else {
-                        self.sponsorblock_segments.clear();
-                    }
+                    // `path`, `media-title` and `chapter-list` are observed before `metadata`, so
+                    // they're already up to date here
+                    let path = self.get_property_cached::<String>("path");
+                    let title = self.get_property_cached::<String>("media-title");
+                    self.youtube_id = self
+                        .metadata
+                        .youtube_id()
+                        .or_else(|| youtube_id_from_url(path.as_deref()?))
+                        .or_else(|| youtube_id_from_title(title.as_deref()?))
+                        .map(str::to_string);
+
+                    // SponsorBlock servers can take a while to answer
+                    let lookup = self.skip_lookup;
+                    let youtube_id = self.youtube_id.clone();
+                    let chapters = self.chapters.clone();
+                    let duration = self.duration();
+                    let tx = self.skip_segments_tx.clone();
+                    std::thread::spawn(move || {
+                        let segments = skip::skip_segments(&VideoRef {
+                            youtube_id: youtube_id.as_deref(),
+                            path: path.as_deref(),
+                            chapters: &chapters,
+                            duration,
+                        });
+                        tx.send(Event::SkipSegments { lookup, segments }).ok();
+                    });
                 }
                 _ => {
-                    if name == "time-pos"
-                        && let Some(segment) = self
-                            .sponsorblock_segments()
-                            .iter()
-                            .find(|s| s.contains(self.time_pos_fallback()))
+                    // Late changes for properties we've stopped observing shouldn't linger in the
+                    // cache, or get_property would never observe them again
+                    if self.observed.iter().any(|o| o.name == name)
+                        && let Some(data) = self.written.reported(&name, data)
                     {
-                        self.seek_to(segment.end()).ok();
+                        self.observed_properties.insert(name, data);
                     }
-
-                    self.observed_properties.insert(name, data);
                 }
             },
             Event::Seek => {}
+            Event::PlaybackRestart => self.keyframes.playback_restarted(),
+            Event::SkipSegments { lookup, segments } => {
+                if lookup == self.skip_lookup {
+                    self.skip_segments = segments;
+                }
+            }
             Event::Unknown => {
                 eprintln!("Unknown event received");
             }
         }
     }
 
+    /// Pretends mpv reported `data` for a property, for showing made-up playback in demo mode.
+    pub fn fake_property(&mut self, name: &str, data: Value) {
+        match name {
+            "playlist" | "track-list" | "chapter-list" => {
+                self.handle_event(Event::PropertyChange { data, name: name.to_string() });
+            }
+            _ => {
+                self.observed_properties.insert(name.to_string(), data);
+            }
+        }
+    }
+
     fn store_deserialized_property<T: DeserializeOwned>(name: &str, data: Value, field: &mut T) {
         match serde_json::from_value::<T>(data.clone()) {
             Ok(value) => {
@@ -209,14 +744,69 @@ impl Mpv {
         }
     }
 
-    pub fn observe_property(&mut self, property: &str) -> io::Result<()> {
-        let cmd = Command::observe_property(self.next_observe_id, property);
+    /// Observes a property for as long as the returned handle is kept around.
+    pub fn observe(&mut self, name: &str) -> PropertyObserver {
+        if let Err(e) = self.start_observing(name)
+            && self.is_connected()
+        {
+            eprintln!("Failed to observe property {name}: {e}");
+        }
+
+        if let Some(obs) = self.observed.iter_mut().find(|o| o.name == name) {
+            obs.handles += 1;
+        }
+
+        PropertyObserver::new(name, self.released_tx.clone())
+    }
+
+    fn start_observing(&mut self, name: &str) -> io::Result<()> {
+        if self.observed.iter().any(|o| o.name == name) {
+            return Ok(());
+        }
+
+        let id = self.next_observe_id;
         self.next_observe_id += 1;
 
-        self.command::<()>(cmd)?;
+        // Remembered even if the command fails, so it's observed again after reconnecting
+        self.observed.push(Observation {
+            name: name.to_string(),
+            id,
+            permanent: false,
+            handles: 0,
+        });
+
+        self.command(Command::observe_property(id, name))?;
         Ok(())
     }
 
+    /// Stops observing properties whose last handle has been dropped.
+    fn release_observers(&mut self) {
+        while let Ok(name) = self.released_rx.try_recv() {
+            let Some(idx) = self.observed.iter().position(|o| o.name == name) else {
+                continue;
+            };
+
+            let obs = &mut self.observed[idx];
+            obs.handles = obs.handles.saturating_sub(1);
+
+            if obs.handles == 0 && !obs.permanent {
+                let obs = self.observed.remove(idx);
+                self.observed_properties.remove(&obs.name);
+                self.unreported.remove(&obs.name);
+
+                if self.is_connected() {
+                    self.command(Command::unobserve_property(obs.id)).ok();
+                }
+            }
+        }
+    }
+
+    /// Drops the observations `get_property` made for the view that's going away. Properties
+    /// the next view reads get observed again on first use.
+    pub fn end_view_scope(&mut self) {
+        self.view_scope.clear();
+    }
+
     pub fn get_property_cached<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
         if let Some(value) = self.observed_properties.get(name) {
             serde_json::from_value(value.clone()).ok()
@@ -225,40 +815,57 @@ impl Mpv {
         }
     }
 
-    pub fn get_property<T: DeserializeOwned>(&mut self, name: &str) -> T {
+    /// Returns the current value of a property, observing it first if it wasn't yet. Until mpv
+    /// reports it, usually by the next frame, this is the default value, as it is while mpv isn't
+    /// connected or when the value doesn't parse.
+    pub fn get_property<T: DeserializeOwned + Default>(&mut self, name: &str) -> T {
         if let Some(value) = self.get_property_cached(name) {
-            value
-        } else {
-            self.observe_property(name)
-                .expect("Failed to observe property");
-
-            loop {
-                self.read_events().expect("Failed to read events");
-
-                for ev in &self.event_buffer {
-                    if let Event::PropertyChange { data, name: prop_name } = ev
-                        && prop_name == name
-                    {
-                        return serde_json::from_value(data.clone())
-                            .unwrap_or_else(|_| panic!("Failed to parse property {}", name));
-                    }
-                }
+            return value;
+        }
 
-                std::thread::sleep(std::time::Duration::from_millis(1));
+        if !self.observed.iter().any(|o| o.name == name) {
+            let observer = self.observe(name);
+            self.view_scope.push(observer);
+            if self.is_connected() {
+                self.unreported.insert(name.to_string(), Instant::now());
             }
         }
+
+        T::default()
     }
 
     pub fn set_property(&mut self, name: &str, value: impl Serialize) -> io::Result<()> {
-        self.command::<()>(Command::set_property(name, value))?;
+        let value = serde_json::to_value(value).expect("value to be serializable");
+        self.command(Command::set_property(name, &value))?;
+
+        self.write_through(name, value);
         Ok(())
     }
 
     pub fn cycle_property(&mut self, name: &str) -> io::Result<()> {
-        self.command::<()>(Command::cycle_property(name))?;
+        self.command(Command::cycle_property(name))?;
+
+        // only flags are obvious to cycle, the rest mpv has to report
+        if let Some(Value::Bool(value)) = self.observed_properties.get(name) {
+            self.write_through(name, Value::Bool(!value));
+        }
         Ok(())
     }
 
+    /// Caches a value that was just set, so whatever reads it next frame doesn't show the old
+    /// one while mpv gets around to reporting the change.
+    fn write_through(&mut self, name: &str, value: Value) {
+        // caching a property that isn't observed would keep `get_property` from observing it
+        if !self.observed.iter().any(|o| o.name == name) {
+            return;
+        }
+
+        let previous = self
+            .observed_properties
+            .insert(name.to_string(), value.clone());
+        self.written.set(name, value, previous);
+    }
+
     pub fn time_pos(&self) -> Option<Time> {
         self.get_property_cached("time-pos")
     }
@@ -271,6 +878,12 @@ impl Mpv {
         self.get_property_cached("duration")
     }
 
+    /// Playback time left until the end of the file at the current speed.
+    pub fn remaining(&self) -> Option<Time> {
+        let left = self.duration()? - self.time_pos()?;
+        Some(left / self.speed() as f32)
+    }
+
     pub fn duration_fallback(&self) -> Time {
         self.duration().unwrap_or(self.time_pos_fallback())
     }
@@ -280,9 +893,17 @@ impl Mpv {
     }
 
     pub fn unpause(&mut self) -> io::Result<()> {
+        self.fade_in_audio();
         self.set_property("pause", false)
     }
 
+    pub fn toggle_pause(&mut self) -> io::Result<()> {
+        if self.get_property::<bool>("pause") {
+            self.fade_in_audio();
+        }
+        self.cycle_property("pause")
+    }
+
     fn seek_state(&mut self) -> &mut SeekState {
         match self.seek_state {
             Some(SeekState { ended: Some(ended), .. })
@@ -347,7 +968,10 @@ impl Mpv {
 
         let would_seek_past_end = forward && seconds_left.is_some_and(|left| left < seconds);
         let exact = state.exact || would_seek_past_end;
-        self.command::<()>(Command::seek(seconds, exact))?;
+        self.command(Command::seek(seconds, exact))?;
+        if !exact {
+            self.keyframes.seeked();
+        }
 
         Ok(())
     }
@@ -361,7 +985,11 @@ impl Mpv {
     }
 
     pub fn seek_stateless(&mut self, seconds: Time, exact: bool) -> io::Result<()> {
-        self.command::<()>(Command::seek(seconds, exact))?;
+        self.fade_in_audio();
+        self.command(Command::seek(seconds, exact))?;
+        if !exact {
+            self.keyframes.seeked();
+        }
         Ok(())
     }
 
@@ -391,6 +1019,22 @@ impl Mpv {
         }
     }
 
+    /// Where the next step back and forward are likely to land while seeking by keyframes,
+    /// once enough keyframes are known to guess.
+    pub fn keyframe_snaps(&self) -> Option<(Time, Time)> {
+        let state = self
+            .seek_state
+            .as_ref()
+            .filter(|s| !s.exact && s.ended.is_none())?;
+        let pos = self.time_pos()?;
+        let step = state.speed.time();
+
+        Some((
+            Time::seconds(self.keyframes.snap(pos - step, false)?.as_secs().max(0.)),
+            self.keyframes.snap(pos + step, true)?,
+        ))
+    }
+
     pub fn seek_speed(&self) -> Option<SeekSpeed> {
         self.seek_state.as_ref().map(|s| s.speed)
     }
@@ -406,9 +1050,16 @@ impl Mpv {
         Ok(())
     }
 
+    /// Forgets state that's only good for a while, after the system woke up from suspend.
+    pub fn resumed(&mut self) {
+        // the grace period for picking a seek back up doesn't include the time spent asleep
+        self.seek_state.take_if(|s| s.ended.is_some());
+        self.frame_drops = FrameDrops::default();
+    }
+
     pub fn cancel_seek(&mut self) -> io::Result<()> {
         if let Some(SeekState { pos, paused, .. }) = self.seek_state.take() {
-            self.command::<()>(Command::set_property("percent-pos", pos))?;
+            self.command(Command::set_property("percent-pos", pos))?;
             if !paused {
                 self.unpause()?;
             }
@@ -417,10 +1068,37 @@ impl Mpv {
     }
 
     pub fn seek_to(&mut self, time: Time) -> io::Result<()> {
-        self.command::<()>(Command::set_property("time-pos", time))?;
+        self.fade_in_audio();
+        self.command(Command::set_property("time-pos", time))?;
         Ok(())
     }
 
+    /// File name of what's playing, for comparing with what others are playing, since paths
+    /// differ between machines.
+    pub fn file_name(&self) -> Option<String> {
+        let path = self.get_property_cached::<String>("path")?;
+        let name = Path::new(&path).file_name()?.to_string_lossy().into_owned();
+        Some(name)
+    }
+
+    /// The A-B loop's start and end, whichever are set. mpv reports unset points as `no`.
+    pub fn ab_loop(&self) -> (Option<Time>, Option<Time>) {
+        (self.get_property_cached("ab-loop-a"), self.get_property_cached("ab-loop-b"))
+    }
+
+    pub fn set_ab_loop_a(&mut self) -> io::Result<()> {
+        self.set_property("ab-loop-a", self.time_pos_fallback())
+    }
+
+    pub fn set_ab_loop_b(&mut self) -> io::Result<()> {
+        self.set_property("ab-loop-b", self.time_pos_fallback())
+    }
+
+    pub fn clear_ab_loop(&mut self) -> io::Result<()> {
+        self.set_property("ab-loop-a", "no")?;
+        self.set_property("ab-loop-b", "no")
+    }
+
     pub fn tracks_of_type(&self, ty: TrackType) -> &[Track] {
         let first = self.tracks.iter().position(|t| t.ty == ty);
         let last = self.tracks.iter().rposition(|t| t.ty == ty);
@@ -464,12 +1142,98 @@ impl Mpv {
             .collect()
     }
 
+    pub fn video_params(&self) -> Option<VideoParams> {
+        self.get_property_cached::<Option<VideoParams>>("video-params")
+            .flatten()
+    }
+
+    /// Whether HDR video is being tone mapped because the output is configured for SDR.
+    pub fn is_tone_mapping_hdr(&mut self) -> bool {
+        let Some(params) = self.get_property::<Option<VideoParams>>("video-params") else {
+            return false;
+        };
+
+        params.is_hdr()
+            && !matches!(self.get_property::<String>("target-trc").as_str(), "pq" | "hlg")
+    }
+
     pub fn playlist(&self) -> &[PlaylistEntry] {
         &self.playlist
     }
 
+    /// Moves the playlist entry at `from` so that it ends up at `to`.
+    pub fn playlist_move(&mut self, from: usize, to: usize) -> io::Result<()> {
+        // mpv inserts the entry before the one at index2, so moving down needs one more
+        let index2 = if to > from { to + 1 } else { to };
+        self.command(Command::playlist_move(from, index2))?;
+        Ok(())
+    }
+
+    pub fn playlist_remove(&mut self, index: usize) -> io::Result<()> {
+        self.command(Command::playlist_remove(index))?;
+        Ok(())
+    }
+
+    /// Removes every entry except the one that's playing.
+    pub fn playlist_clear(&mut self) -> io::Result<()> {
+        self.command(Command::playlist_clear())?;
+        Ok(())
+    }
+
+    /// Whether no video is being decoded or shown, either from [`Mpv::toggle_audio_only`] or from
+    /// choosing no video track.
+    pub fn audio_only(&self) -> bool {
+        // mpv reports `no` as false
+        matches!(self.get_property_cached::<Value>("vid"), Some(Value::Bool(false)))
+    }
+
+    /// Turns video off to save power while listening, keeping the position, or back on with the
+    /// track that was playing before.
+    pub fn toggle_audio_only(&mut self) -> io::Result<()> {
+        if self.audio_only() {
+            let vid = self
+                .video_before_audio_only
+                .take()
+                .unwrap_or_else(|| Value::from("auto"));
+            self.set_property("vid", vid)
+        } else {
+            self.video_before_audio_only = self.get_property_cached("vid");
+            self.set_property("vid", "no")
+        }
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.get_property_cached("speed").unwrap_or(1.)
+    }
+
+    pub fn set_speed(&mut self, speed: f64) -> io::Result<()> {
+        self.set_property("speed", speed)
+    }
+
+    /// Steps up to the next speed preset.
+    pub fn speed_up(&mut self) -> io::Result<()> {
+        let current = self.speed();
+        match SPEED_PRESETS.into_iter().find(|&s| s > current + 0.01) {
+            Some(speed) => self.set_speed(speed),
+            None => Ok(()),
+        }
+    }
+
+    /// Steps down to the previous speed preset.
+    pub fn speed_down(&mut self) -> io::Result<()> {
+        let current = self.speed();
+        match SPEED_PRESETS
+            .into_iter()
+            .rev()
+            .find(|&s| s < current - 0.01)
+        {
+            Some(speed) => self.set_speed(speed),
+            None => Ok(()),
+        }
+    }
+
     pub fn change_volume(&mut self, delta: f32) -> io::Result<()> {
-        self.command::<()>(Command::add_property("volume", delta))?;
+        self.command(Command::add_property("volume", delta))?;
         Ok(())
     }
 
@@ -477,19 +1241,170 @@ impl Mpv {
         &self.metadata
     }
 
-    pub fn sponsorblock_segments(&self) -> &[sponsorblock::SkipSegment] {
-        &self.sponsorblock_segments
+    /// The YouTube video that's playing, going by the file's `purl` tag like yt-dlp writes,
+    /// or otherwise the URL it was opened with or a `[id]` at the end of its title.
+    pub fn youtube_id(&self) -> Option<&str> {
+        self.youtube_id.as_deref()
+    }
+
+    pub fn skip_segments(&self) -> &[sponsorblock::SkipSegment] {
+        &self.skip_segments
+    }
+
+    /// The SponsorBlock segment that's about to be skipped, with how long until it is.
+    pub fn upcoming_skip(&self) -> Option<(&sponsorblock::SkipSegment, Duration)> {
+        let time = self.time_pos()?;
+        let segment = self.auto_skip.upcoming(&self.skip_segments, time)?;
+        let left = (segment.start() - time).as_secs() as f64 / self.speed();
+
+        Some((segment, Duration::from_secs_f64(left.max(0.))))
+    }
+
+    /// The playlist entry mpv is about to move on to, with how long until it does, once the end of
+    /// the file is near.
+    pub fn up_next(&self) -> Option<(&PlaylistEntry, Duration)> {
+        // how long it'll take to get there, at the speed it's playing
+        let left = self.remaining()?.as_secs() as f64 / self.speed();
+        let entry = self
+            .up_next
+            .upcoming(&self.playlist, Time::seconds(left as f32))?;
+
+        Some((entry, Duration::from_secs_f64(left.max(0.))))
+    }
+
+    /// Stays on the current file when it ends, paused on its last frame, rather than moving on to
+    /// the next entry.
+    pub fn cancel_up_next(&mut self) -> io::Result<()> {
+        self.up_next.cancel();
+        // file-local options are put back when the next file loads
+        self.set_property("file-local-options/keep-open", "yes")
+    }
+
+    /// Calls off the upcoming skip, playing the segment after all.
+    pub fn keep_upcoming_segment(&mut self) {
+        let Some(time) = self.time_pos() else {
+            return;
+        };
+
+        if let Some(segment) = self.auto_skip.upcoming(&self.skip_segments, time) {
+            self.auto_skip.keep(segment);
+        }
+    }
+
+    /// Goes back to where playback was before the last skip, and stops skipping that segment.
+    pub fn undo_skip(&mut self) -> io::Result<()> {
+        if let Some(from) = self.auto_skip.undo() {
+            self.seek_to(from)?;
+        }
+        Ok(())
+    }
+
+    /// Seeks past the skip segment that's playing, returning whether there was one.
+    pub fn skip_current_segment(&mut self) -> io::Result<bool> {
+        let Some(time) = self.time_pos() else {
+            return Ok(false);
+        };
+
+        let end = self
+            .skip_segments
+            .iter()
+            .find(|segment| segment.contains(time))
+            .map(|segment| segment.end());
+
+        match end {
+            Some(end) => {
+                self.seek_to(end)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Jumps `delta` chapters forward or back, returning the index of the chapter it lands on,
+    /// unless that's past the last one.
+    pub fn add_chapter(&mut self, delta: i64) -> io::Result<Option<usize>> {
+        let chapters = self.chapters();
+        if chapters.is_empty() {
+            return Ok(None);
+        }
+
+        // before the first chapter counts as chapter -1, like mpv has it
+        let current = chapters.iter().position(|c| c.current);
+        let into_chapter =
+            current.map(|c| (self.time_pos_fallback() - chapters[c].start).as_secs());
+        let current = current.map_or(-1, |c| c as i64);
+
+        // like mpv, going back from well into a chapter goes to its start first
+        let target = if delta < 0 && into_chapter.is_some_and(|t| t > CHAPTER_SEEK_THRESHOLD) {
+            current + delta + 1
+        } else {
+            current + delta
+        };
+        let count = chapters.len();
+
+        self.command(Command::add_chapter(delta))?;
+
+        Ok(usize::try_from(target.max(0)).ok().filter(|&t| t < count))
+    }
+
+    pub fn playlist_next(&mut self) -> io::Result<()> {
+        self.command(Command::playlist_next())?;
+        Ok(())
+    }
+
+    pub fn playlist_prev(&mut self) -> io::Result<()> {
+        self.command(Command::playlist_prev())?;
+        Ok(())
+    }
+
+    pub fn stop(&mut self) -> io::Result<()> {
+        self.seek_state = None;
+        self.command(Command::stop())?;
+        Ok(())
+    }
+
+    pub fn apply_profile(&mut self, name: &str) -> io::Result<()> {
+        self.command(Command::apply_profile(name))?;
+        Ok(())
     }
 
     pub fn load_file(&mut self, path: &str) -> io::Result<()> {
-        self.command::<()>(Command::loadfile(path))?;
+        self.command(Command::loadfile(path))?;
+        Ok(())
+    }
+
+    pub fn append_file(&mut self, path: &str) -> io::Result<()> {
+        self.command(Command::loadfile_append(path))?;
+        Ok(())
+    }
+
+    /// Needs mpv 0.38 or newer, which added the playlist index argument to `loadfile`. Older
+    /// ones read the index as the file's options and refuse to load it.
+    pub fn load_file_at(&mut self, path: &str, start: Time) -> io::Result<()> {
+        self.command(Command::loadfile_at(path, start))?;
         Ok(())
     }
 }
 
+fn is_connection_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::UnexpectedEof
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::BrokenPipe
+            | ErrorKind::NotConnected
+    )
+}
+
 impl Default for Mpv {
     fn default() -> Self {
-        Self::new()
+        // demo mode makes up its own playback, and leaves a real mpv that's running alone
+        if demo::requested() {
+            Self::offline()
+        } else {
+            Self::new()
+        }
     }
 }
 
@@ -556,8 +1471,58 @@ impl PlaylistEntry {
     }
 }
 
+/// The `video-params` property, only the parts we care about.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "kebab-case")]
+pub struct VideoParams {
+    /// Gamma function in use, e.g. `pq` or `hlg` for HDR content.
+    pub gamma: Option<String>,
+    /// Width in pixels.
+    pub w: Option<u32>,
+    /// Height in pixels.
+    pub h: Option<u32>,
+    /// Display aspect ratio, after any anamorphic stretching.
+    pub aspect: Option<f32>,
+    /// e.g. `yuv420p10`.
+    pub pixelformat: Option<String>,
+    /// e.g. `bt.2020-ncl`.
+    pub colormatrix: Option<String>,
+    /// e.g. `bt.2020`.
+    pub primaries: Option<String>,
+    /// Mastering display luminance in cd/m², from the HDR metadata.
+    pub min_luma: Option<f32>,
+    pub max_luma: Option<f32>,
+    /// Brightest pixel and brightest frame average in cd/m², from the HDR metadata.
+    pub max_cll: Option<f32>,
+    pub max_fall: Option<f32>,
+}
+
+impl VideoParams {
+    pub fn is_hdr(&self) -> bool {
+        matches!(self.gamma.as_deref(), Some("pq" | "hlg"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AudioDevice {
+    /// The name as used by the `audio-device` property.
+    pub name: String,
+    /// Human readable free form text, might be empty.
+    pub description: String,
+}
+
+/// An entry in `profile-list`, only the parts we care about.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Profile {
+    pub name: String,
+    pub profile_desc: Option<String>,
+    /// Set for profiles mpv applies by itself whenever the condition holds.
+    pub profile_cond: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct ChapterRaw {
     pub title: Option<String>,
     pub time: Time,
@@ -575,7 +1540,6 @@ pub struct Chapter<'a> {
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub struct Metadata {
     pub artist: Option<String>,
-    #[expect(dead_code)]
     pub comment: Option<String>,
     #[serde(deserialize_with = "deserialize_metadata_date")]
     pub date: Option<chrono::NaiveDate>,
@@ -593,7 +1557,11 @@ impl Metadata {
     }
 
     pub fn youtube_id(&self) -> Option<&str> {
-        youtube_id_from_url(self.purl.as_deref()?)
+        // yt-dlp puts the URL in the comment too, and some remuxes only keep that one
+        self.purl
+            .as_deref()
+            .and_then(youtube_id_from_url)
+            .or_else(|| youtube_id_from_url(self.comment.as_deref()?))
     }
 }
 
@@ -0,0 +1,30 @@
+use std::sync::mpsc::Sender;
+
+/// A property we've asked mpv to observe.
+pub(super) struct Observation {
+    pub name: String,
+    pub id: i32,
+    /// Observed for the entire lifetime of the connection, regardless of handles.
+    pub permanent: bool,
+    /// Number of live [`PropertyObserver`]s for this property.
+    pub handles: usize,
+}
+
+/// Keeps a property observed for as long as it's alive. Once the last handle for a property is
+/// dropped, the next `Mpv::update` tells mpv to stop observing it.
+pub struct PropertyObserver {
+    name: String,
+    released: Sender<String>,
+}
+
+impl PropertyObserver {
+    pub(super) fn new(name: &str, released: Sender<String>) -> Self {
+        Self { name: name.to_string(), released }
+    }
+}
+
+impl Drop for PropertyObserver {
+    fn drop(&mut self) {
+        self.released.send(std::mem::take(&mut self.name)).ok();
+    }
+}
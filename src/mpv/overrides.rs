@@ -0,0 +1,30 @@
+use serde_json::Value;
+
+use super::RequestId;
+
+/// mpv properties set by the active profile, remembering what they were before so they can be
+/// put back when the profile changes or the overlay exits.
+#[derive(Default)]
+pub struct Overrides {
+    /// What the profile wants each property set to.
+    pub wanted: Vec<(String, Value)>,
+    /// Properties we've set, with the value mpv had before.
+    pub original: Vec<(String, Value)>,
+    /// Requests for the values of properties we haven't set yet.
+    pub pending: Vec<(String, RequestId)>,
+    /// mpv's PID when `original` was recorded. A different one after reconnecting means mpv
+    /// restarted, and what it had before doesn't matter anymore.
+    pub pid: Option<i64>,
+    /// Request for mpv's PID after connecting. Overrides wait for it, so they aren't set before
+    /// we know whether `original` still holds.
+    pub pid_request: Option<RequestId>,
+}
+
+impl Overrides {
+    pub fn original(&self, name: &str) -> Option<&Value> {
+        self.original
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value)
+    }
+}
@@ -1,53 +1,45 @@
 use super::time::Time;
+use crate::config::config;
 
-#[derive(Debug, Clone, Copy, Default)]
-pub enum SeekSpeed {
-    Second,
-    #[default]
-    FiveSeconds,
-    ThirtySeconds,
-    Minute,
-    TenMinutes,
+/// One of the seek step sizes from the config, by index.
+#[derive(Debug, Clone, Copy)]
+pub struct SeekSpeed(usize);
+
+impl Default for SeekSpeed {
+    fn default() -> Self {
+        let seek = &config().seek;
+
+        seek.steps
+            .iter()
+            .position(|&s| s >= seek.default_step)
+            .map_or(Self(0), Self)
+    }
 }
 
 impl SeekSpeed {
+    fn seconds(self) -> f32 {
+        config().seek.steps.get(self.0).copied().unwrap_or(5.)
+    }
+
     pub fn time(self) -> Time {
-        match self {
-            SeekSpeed::Second => Time::seconds(1),
-            SeekSpeed::FiveSeconds => Time::seconds(5),
-            SeekSpeed::ThirtySeconds => Time::seconds(30),
-            SeekSpeed::Minute => Time::minutes(1),
-            SeekSpeed::TenMinutes => Time::minutes(10),
-        }
+        Time::seconds(self.seconds())
     }
 
-    pub fn label(self) -> &'static str {
-        match self {
-            SeekSpeed::Second => "1s",
-            SeekSpeed::FiveSeconds => "5s",
-            SeekSpeed::ThirtySeconds => "30s",
-            SeekSpeed::Minute => "1m",
-            SeekSpeed::TenMinutes => "10m",
+    pub fn label(self) -> String {
+        let seconds = self.seconds();
+
+        if seconds >= 60. && seconds % 60. == 0. {
+            format!("{}m", seconds / 60.)
+        } else {
+            format!("{seconds}s")
         }
     }
 
     pub fn longer(self) -> Option<Self> {
-        match self {
-            SeekSpeed::Second => Some(SeekSpeed::FiveSeconds),
-            SeekSpeed::FiveSeconds => Some(SeekSpeed::ThirtySeconds),
-            SeekSpeed::ThirtySeconds => Some(SeekSpeed::Minute),
-            SeekSpeed::Minute => Some(SeekSpeed::TenMinutes),
-            SeekSpeed::TenMinutes => None,
-        }
+        (self.0 + 1 < config().seek.steps.len()).then_some(Self(self.0 + 1))
     }
 
     pub fn shorter(self) -> Option<Self> {
-        match self {
-            SeekSpeed::Second => None,
-            SeekSpeed::FiveSeconds => Some(SeekSpeed::Second),
-            SeekSpeed::ThirtySeconds => Some(SeekSpeed::FiveSeconds),
-            SeekSpeed::Minute => Some(SeekSpeed::ThirtySeconds),
-            SeekSpeed::TenMinutes => Some(SeekSpeed::Minute),
-        }
+        self.0.checked_sub(1).map(Self)
     }
 }
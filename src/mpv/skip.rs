@@ -0,0 +1,101 @@
+//! Where skip segments come from. Every provider is asked about each file that's loaded, and
+//! what they find is merged into the one list that the seek bar shows and auto-skip acts on.
+
+use core::cmp::Ordering;
+
+use super::{
+    ChapterRaw,
+    sponsorblock::{Category, Sidecar, SkipSegment, SponsorBlock},
+    time::Time,
+};
+use crate::config::config;
+
+/// What's known about the file that's playing when its segments are looked up.
+pub struct VideoRef<'a> {
+    pub youtube_id: Option<&'a str>,
+    pub path: Option<&'a str>,
+    pub chapters: &'a [ChapterRaw],
+    pub duration: Option<Time>,
+}
+
+pub trait SkipProvider {
+    fn fetch(&self, video: &VideoRef) -> Vec<SkipSegment>;
+}
+
+fn providers() -> Vec<Box<dyn SkipProvider>> {
+    let mut providers: Vec<Box<dyn SkipProvider>> = vec![Box::new(SponsorBlock), Box::new(Sidecar)];
+
+    if config().sponsorblock.chapter_names {
+        providers.push(Box::new(ChapterNames));
+    }
+
+    providers
+}
+
+/// Segments from every provider, limited to the configured categories.
+pub fn skip_segments(video: &VideoRef) -> Vec<SkipSegment> {
+    let mut segments = providers()
+        .iter()
+        .flat_map(|provider| provider.fetch(video))
+        .collect::<Vec<_>>();
+
+    let categories = &config().sponsorblock.categories;
+    segments.retain(|s| categories.contains(&s.category));
+
+    // Mirrors usually share the same database, and a sidecar file may well have been made from
+    // it, so the same segment may come back more than once
+    segments.sort_by(|a, b| a.start().partial_cmp(&b.start()).unwrap_or(Ordering::Equal));
+    segments.dedup_by(|a, b| {
+        (!a.uuid.is_empty() && a.uuid == b.uuid)
+            || (a.category == b.category && a.segment == b.segment)
+    });
+
+    segments
+}
+
+/// Chapters named like the openings, endings and previews that anime and TV releases mark.
+pub struct ChapterNames;
+
+impl SkipProvider for ChapterNames {
+    fn fetch(&self, video: &VideoRef) -> Vec<SkipSegment> {
+        let ends = video
+            .chapters
+            .iter()
+            .skip(1)
+            .map(|c| Some(c.time))
+            .chain([video.duration]);
+
+        video
+            .chapters
+            .iter()
+            .zip(ends)
+            .filter_map(|(chapter, end)| {
+                let category = chapter_category(chapter.title.as_deref()?)?;
+                Some(SkipSegment {
+                    segment: (chapter.time, end?),
+                    uuid: String::new(),
+                    category,
+                })
+            })
+            .collect()
+    }
+}
+
+fn chapter_category(title: &str) -> Option<Category> {
+    let title = title.to_lowercase();
+    let is = |names: &[&str]| {
+        names
+            .iter()
+            .any(|name| title == *name || title.starts_with(&format!("{name} ")))
+    };
+
+    if is(&["intro", "opening", "op"]) {
+        Some(Category::Intro)
+    } else if is(&["outro", "ending", "ed", "credits"]) {
+        Some(Category::Outro)
+    } else if is(&["preview", "next episode"]) {
+        Some(Category::Preview)
+    } else {
+        None
+    }
+}
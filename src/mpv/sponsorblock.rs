@@ -1,38 +1,88 @@
+use std::path::Path;
+
 use egui::Color32;
 use ehttp::Request;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use super::time::Time;
+use super::{
+    skip::{SkipProvider, VideoRef},
+    time::Time,
+};
+use crate::config::config;
+
+/// Segments from every configured SponsorBlock server, for YouTube videos.
+pub struct SponsorBlock;
+
+impl SkipProvider for SponsorBlock {
+    fn fetch(&self, video: &VideoRef) -> Vec<SkipSegment> {
+        let Some(video_id) = video.youtube_id else {
+            return vec![];
+        };
+
+        config()
+            .sponsorblock
+            .servers
+            .iter()
+            .flat_map(|server| fetch_skip_segments(server, video_id).unwrap_or_default())
+            .collect()
+    }
+}
+
+/// Segments from a local `.sbsegments.json` sidecar file, if there is one.
+pub struct Sidecar;
+
+impl SkipProvider for Sidecar {
+    fn fetch(&self, video: &VideoRef) -> Vec<SkipSegment> {
+        video
+            .path
+            .and_then(|path| load_local_segments(Path::new(path)))
+            .unwrap_or_default()
+    }
+}
 
-pub fn fetch_skip_segments(video_id: &str) -> Option<Vec<SkipSegment>> {
-    let mut url = Url::parse("https://sponsor.ajay.app/api/skipSegments").unwrap();
+fn fetch_skip_segments(server: &str, video_id: &str) -> Option<Vec<SkipSegment>> {
+    let mut url = Url::parse(&format!("{}/api/skipSegments", server.trim_end_matches('/')))
+        .map_err(|e| eprintln!("Invalid SponsorBlock server {server}: {e}"))
+        .ok()?;
 
     url.query_pairs_mut()
         .append_pair("videoID", video_id)
         .append_pair(
             "categories",
-            &serde_json::to_string(&[
-                Category::Sponsor,
-                Category::Selfpromo,
-                Category::Intro,
-                Category::Outro,
-            ])
-            .unwrap(),
+            &serde_json::to_string(&config().sponsorblock.categories).unwrap(),
         );
 
     let res = ehttp::fetch_blocking(&Request::get(url.as_str())).ok()?;
 
+    // 404 means the server has no segments for this video
+    if res.status == 404 {
+        return None;
+    }
+
     serde_json::from_slice(&res.bytes)
-        .map_err(|e| eprintln!("Failed to parse skip segments: {}", e))
+        .map_err(|e| eprintln!("Failed to parse skip segments from {server}: {}", e))
+        .ok()
+}
+
+/// Reads `foo.sbsegments.json` next to `foo.mkv`, in the same format the API returns.
+fn load_local_segments(media_path: &Path) -> Option<Vec<SkipSegment>> {
+    if media_path.to_str().is_some_and(|p| p.contains("://")) {
+        return None;
+    }
+
+    let sidecar = media_path.with_extension("sbsegments.json");
+    let contents = std::fs::read(&sidecar).ok()?;
+
+    serde_json::from_slice(&contents)
+        .map_err(|e| eprintln!("Failed to parse {}: {}", sidecar.display(), e))
         .ok()
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SkipSegment {
     pub segment: (Time, Time),
-    #[expect(dead_code)]
-    #[serde(rename = "UUID")]
+    #[serde(default, rename = "UUID")]
     pub uuid: String,
     pub category: Category,
 }
@@ -51,7 +101,7 @@ impl SkipSegment {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum Category {
     Sponsor,
@@ -0,0 +1,280 @@
+//! Runs [`Mpv`] against a real mpv, to catch changes in how we talk to it or in how mpv responds.
+//! These need `mpv` and `ffmpeg` on the `PATH`, so they're ignored by default:
+//!
+//! ```sh
+//! cargo test mpv::tests -- --ignored --test-threads 1
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::{
+        OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{Mpv, TrackType, time::Time};
+use crate::{command::Event as AppEvent, ui::toast::Toast};
+
+/// How long anything may take to show up before a test fails.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Length of the sample file in seconds.
+const SAMPLE_LENGTH: u32 = 30;
+
+/// Title and start in seconds of each chapter in the sample file.
+const CHAPTERS: [(&str, u32); 3] = [("Intro", 0), ("Middle", 10), ("Credits", 20)];
+
+/// An mpv with nothing loaded, listening on a socket of its own in a temporary directory. Killed
+/// when dropped.
+struct Instance {
+    process: Child,
+    dir: PathBuf,
+    mpv: Mpv,
+    events: Vec<AppEvent>,
+}
+
+impl Instance {
+    fn spawn() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        let dir = std::env::temp_dir().join(format!(
+            "htpc-overlay-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).expect("Failed to create temporary directory");
+        let socket = dir.join("mpv.sock");
+
+        let process = Command::new("mpv")
+            .args(["--no-config", "--idle=yes", "--vo=null", "--ao=null", "--really-quiet"])
+            .arg(format!("--input-ipc-server={}", socket.display()))
+            .stdin(Stdio::null())
+            .spawn()
+            .expect("Failed to start mpv");
+
+        let start = Instant::now();
+        while !socket.exists() {
+            assert!(start.elapsed() < TIMEOUT, "mpv didn't create its socket");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let mpv = Mpv::with_socket(socket);
+        assert!(mpv.is_connected(), "Failed to connect to mpv");
+
+        Self { process, dir, mpv, events: vec![] }
+    }
+
+    /// Loads the sample file, paused at the start.
+    fn load_sample(&mut self) {
+        self.mpv.set_property("pause", true).unwrap();
+        self.mpv.load_file(sample().to_str().unwrap()).unwrap();
+        self.wait_for("the sample to load", |mpv| {
+            mpv.duration().is_some() && !mpv.chapters().is_empty()
+        });
+    }
+
+    /// Keeps updating until `done` holds, like the overlay does every frame.
+    fn wait_for(&mut self, what: &str, mut done: impl FnMut(&mut Mpv) -> bool) {
+        let start = Instant::now();
+        loop {
+            self.mpv.update(&mut self.events);
+            if done(&mut self.mpv) {
+                return;
+            }
+
+            assert!(start.elapsed() < TIMEOUT, "Timed out waiting for {what}");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        self.process.kill().ok();
+        self.process.wait().ok();
+        fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+/// A small file with two audio tracks and a few chapters, made with ffmpeg the first time it's
+/// needed.
+fn sample() -> &'static Path {
+    static SAMPLE: OnceLock<PathBuf> = OnceLock::new();
+
+    SAMPLE.get_or_init(|| {
+        let dir = std::env::temp_dir().join(format!("htpc-overlay-sample-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("Failed to create temporary directory");
+
+        let mut chapters = ";FFMETADATA1\n".to_string();
+        let ends = CHAPTERS.iter().skip(1).map(|&(_, start)| start);
+        for (&(title, start), end) in CHAPTERS.iter().zip(ends.chain([SAMPLE_LENGTH])) {
+            chapters += &format!(
+                "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={title}\n",
+                start * 1000,
+                end * 1000
+            );
+        }
+        let chapters_path = dir.join("chapters.txt");
+        fs::write(&chapters_path, chapters).expect("Failed to write chapters");
+
+        let path = dir.join("sample.mkv");
+        let length = SAMPLE_LENGTH.to_string();
+        let status = Command::new("ffmpeg")
+            .args(["-loglevel", "error", "-y"])
+            .args(["-f", "lavfi", "-i"])
+            .arg(format!("testsrc=duration={length}:size=320x240:rate=25"))
+            .args(["-f", "lavfi", "-i"])
+            .arg(format!("sine=frequency=440:duration={length}"))
+            .args(["-f", "lavfi", "-i"])
+            .arg(format!("sine=frequency=880:duration={length}"))
+            .arg("-i")
+            .arg(&chapters_path)
+            .args(["-map", "0", "-map", "1", "-map", "2", "-map_chapters", "3"])
+            .args(["-c:v", "mpeg4", "-c:a", "flac"])
+            .args(["-metadata:s:a:0", "language=eng", "-metadata:s:a:1", "language=jpn"])
+            .arg(&path)
+            .status()
+            .expect("Failed to run ffmpeg");
+        assert!(status.success(), "ffmpeg failed to make the sample file");
+
+        path
+    })
+}
+
+#[test]
+#[ignore = "needs mpv and ffmpeg"]
+fn load() {
+    let mut instance = Instance::spawn();
+    instance.load_sample();
+
+    let mpv = &instance.mpv;
+    assert_eq!(mpv.file_name().as_deref(), Some("sample.mkv"));
+    assert_eq!(mpv.duration().map(|d| d.as_secs().round()), Some(SAMPLE_LENGTH as f32));
+    assert_eq!(mpv.time_pos(), Some(Time::ZERO));
+    assert_eq!(mpv.get_property_cached::<bool>("pause"), Some(true));
+    assert_eq!(mpv.playlist().len(), 1);
+}
+
+#[test]
+#[ignore = "needs mpv and ffmpeg"]
+fn seek() {
+    let mut instance = Instance::spawn();
+    instance.load_sample();
+
+    instance.mpv.seek_to(Time::seconds(12)).unwrap();
+    instance.wait_for("the seek to land", |mpv| {
+        mpv.time_pos()
+            .is_some_and(|t| (t.as_secs() - 12.).abs() < 0.5)
+    });
+
+    instance
+        .mpv
+        .seek_stateless(Time::seconds(-5), true)
+        .unwrap();
+    instance.wait_for("the relative seek to land", |mpv| {
+        mpv.time_pos()
+            .is_some_and(|t| (t.as_secs() - 7.).abs() < 0.5)
+    });
+}
+
+#[test]
+#[ignore = "needs mpv and ffmpeg"]
+fn tracks() {
+    let mut instance = Instance::spawn();
+    instance.load_sample();
+
+    let audio = instance.mpv.tracks_of_type(TrackType::Audio);
+    let langs = audio.iter().map(|t| t.lang.as_deref()).collect::<Vec<_>>();
+    assert_eq!(langs, [Some("eng"), Some("jpn")]);
+    assert_eq!(instance.mpv.tracks_of_type(TrackType::Video).len(), 1);
+    assert!(instance.mpv.tracks_of_type(TrackType::Sub).is_empty());
+
+    let second = audio[1].id;
+    instance.mpv.set_property("aid", second).unwrap();
+    instance.wait_for("the second audio track to be selected", |mpv| {
+        mpv.tracks_of_type(TrackType::Audio)
+            .iter()
+            .any(|t| t.id == second && t.selected)
+    });
+}
+
+#[test]
+#[ignore = "needs mpv and ffmpeg"]
+fn chapters() {
+    let mut instance = Instance::spawn();
+    instance.load_sample();
+
+    let chapters = instance.mpv.chapters();
+    let titles = chapters.iter().map(|c| c.title).collect::<Vec<_>>();
+    assert_eq!(titles, CHAPTERS.map(|(title, _)| Some(title)));
+    assert!(chapters[0].current);
+
+    let landed = instance.mpv.add_chapter(1).unwrap();
+    assert_eq!(landed, Some(1));
+    instance.wait_for("the second chapter", |mpv| {
+        mpv.get_property::<i64>("chapter") == 1 && mpv.chapters()[1].current
+    });
+
+    // past the last chapter
+    let landed = instance.mpv.add_chapter(2).unwrap();
+    assert_eq!(landed, None);
+}
+
+#[test]
+#[ignore = "needs mpv"]
+fn get_property() {
+    let mut instance = Instance::spawn();
+
+    // not observed until it's asked for
+    assert_eq!(instance.mpv.get_property_cached::<f32>("volume"), None);
+    // the default until mpv reports it
+    assert_eq!(instance.mpv.get_property::<f32>("volume"), 0.);
+    instance.wait_for("the volume to be reported", |mpv| mpv.get_property::<f32>("volume") == 100.);
+
+    instance.mpv.change_volume(-10.).unwrap();
+    instance.wait_for("the volume to change", |mpv| {
+        mpv.get_property_cached::<f32>("volume") == Some(90.)
+    });
+}
+
+#[test]
+#[ignore = "needs mpv and ffmpeg"]
+fn write_through() {
+    let mut instance = Instance::spawn();
+    instance.load_sample();
+
+    // shows up before mpv reports it
+    instance.mpv.toggle_pause().unwrap();
+    assert_eq!(instance.mpv.get_property_cached::<bool>("pause"), Some(false));
+
+    instance.mpv.pause().unwrap();
+    assert_eq!(instance.mpv.get_property_cached::<bool>("pause"), Some(true));
+
+    // the unpause mpv reports in between is from before the pause, and doesn't undo it
+    instance.mpv.update(&mut instance.events);
+    assert_eq!(instance.mpv.get_property_cached::<bool>("pause"), Some(true));
+}
+
+#[test]
+#[ignore = "needs mpv"]
+fn disconnect() {
+    let mut instance = Instance::spawn();
+    instance.mpv.update(&mut instance.events);
+    assert!(instance.events.is_empty());
+
+    instance.process.kill().unwrap();
+    instance.process.wait().unwrap();
+
+    instance.wait_for("the connection to drop", |mpv| !mpv.is_connected());
+    assert!(
+        instance
+            .events
+            .iter()
+            .any(|ev| matches!(ev, AppEvent::Toast(Toast::MpvDisconnected)))
+    );
+}
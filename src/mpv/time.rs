@@ -13,8 +13,8 @@ impl Time {
         Time(n.into() as f32)
     }
 
-    pub fn minutes(n: impl Into<f64>) -> Self {
-        Time(n.into() as f32 * 60.)
+    pub fn as_secs(self) -> f32 {
+        self.0
     }
 
     pub fn mmss(self) -> String {
@@ -0,0 +1,31 @@
+use super::{PlaylistEntry, time::Time};
+use crate::config::config;
+
+/// Keeps track of whether moving on to the next playlist entry was called off for the current
+/// file.
+#[derive(Default)]
+pub struct UpNext {
+    cancelled: bool,
+}
+
+impl UpNext {
+    /// The entry after the one that's playing, if the end of the file is close enough to
+    /// announce it.
+    pub fn upcoming<'a>(
+        &self,
+        playlist: &'a [PlaylistEntry],
+        remaining: Time,
+    ) -> Option<&'a PlaylistEntry> {
+        let countdown = config().up_next;
+        if self.cancelled || countdown <= 0. || remaining.as_secs() > countdown {
+            return None;
+        }
+
+        let playing = playlist.iter().position(|e| e.playing)?;
+        playlist.get(playing + 1)
+    }
+
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+}
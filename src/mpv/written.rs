@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+use egui::ahash::HashMap;
+use serde_json::Value;
+
+/// How long to wait for mpv to report a value the overlay set before believing what it
+/// reports instead. Changes it sent before the write can still be on their way until then.
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// Properties the overlay set itself, which go straight into the cache instead of a frame or two
+/// later when mpv reports them back, so labels like Play/Pause follow the button right away.
+#[derive(Default)]
+pub struct Written {
+    writes: HashMap<String, Write>,
+}
+
+struct Write {
+    value: Value,
+    at: Instant,
+    /// The last value mpv reported, to go back to if it never reports the written one, like when
+    /// the command failed.
+    actual: Option<Value>,
+}
+
+impl Written {
+    /// Remembers that `value` was just set, and what was cached before.
+    pub fn set(&mut self, name: &str, value: Value, previous: Option<Value>) {
+        let actual = match self.writes.remove(name) {
+            Some(write) => write.actual,
+            None => previous,
+        };
+
+        self.writes
+            .insert(name.to_string(), Write { value, at: Instant::now(), actual });
+    }
+
+    /// Filters a value mpv reported. `None` means a write is still settling and this is likely
+    /// from before it, so the written value should stay.
+    pub fn reported(&mut self, name: &str, data: Value) -> Option<Value> {
+        let Some(write) = self.writes.get_mut(name) else {
+            return Some(data);
+        };
+
+        if same(&write.value, &data) || write.at.elapsed() > SETTLE {
+            self.writes.remove(name);
+            return Some(data);
+        }
+
+        write.actual = Some(data);
+        None
+    }
+
+    /// Writes mpv didn't confirm in time, with what to put back in the cache for them.
+    pub fn expired(&mut self) -> Vec<(String, Option<Value>)> {
+        let expired = self
+            .writes
+            .iter()
+            .filter(|(_, write)| write.at.elapsed() > SETTLE)
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+
+        expired
+            .into_iter()
+            .filter_map(|name| {
+                let write = self.writes.remove(&name)?;
+                Some((name, write.actual))
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.writes.clear();
+    }
+}
+
+/// Whether mpv reported what was written. Numbers are compared by value, since mpv reports
+/// `50.0` for a `volume` written as `50`.
+fn same(written: &Value, reported: &Value) -> bool {
+    match (written.as_f64(), reported.as_f64()) {
+        (Some(written), Some(reported)) => written == reported,
+        _ => written == reported,
+    }
+}
@@ -0,0 +1,449 @@
+//! A client for MQTT brokers, for home automation like Home Assistant to follow along with
+//! playback and control it. What's playing goes out on retained topics under the configured
+//! prefix, and plain text commands are taken on `<prefix>/command`:
+//!
+//! - `play`, `pause`, `play-pause`, `stop`, `next`, `previous`
+//! - `seek <seconds>` to seek to a position, or `seek +<seconds>`/`seek -<seconds>` to skip
+//! - `volume <percent>`
+//!
+//! Only MQTT 3.1.1 at QoS 0 is spoken, which brokers all support.
+
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind, Read as _, Write as _},
+    net::TcpStream,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    command::Command,
+    config::config,
+    mpv::{Mpv, time::Time},
+};
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+/// The broker drops us after one and a half of these without hearing from us.
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+/// Position changes every second, so it's only sent this often, or when it jumps.
+const POSITION_EVERY: Duration = Duration::from_secs(5);
+
+// packet types, in the high nibble of the first byte
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const SUBSCRIBE: u8 = 0x80;
+const PINGREQ: u8 = 0xc0;
+const DISCONNECT: u8 = 0xe0;
+
+#[derive(Default)]
+pub struct Mqtt {
+    socket: Option<TcpStream>,
+    /// Connecting happens on a thread of its own, since resolving the broker can take a while.
+    connecting: Option<Receiver<io::Result<TcpStream>>>,
+    read_buf: Vec<u8>,
+    last_connect_attempt: Option<Instant>,
+    /// Whether the broker accepted the connection.
+    connected: bool,
+    last_sent: Option<Instant>,
+    /// Payloads last published, by topic name under the prefix.
+    published: HashMap<&'static str, String>,
+    /// Where playback was when the position was last published, and when.
+    position: Option<(f32, Instant)>,
+}
+
+impl Mqtt {
+    pub fn update(&mut self, mpv: &mut Mpv, commands: &mut Vec<Command>) {
+        let Some(broker) = &config().mqtt.broker else {
+            return;
+        };
+
+        if self.socket.is_none()
+            && self.connecting.is_none()
+            && self
+                .last_connect_attempt
+                .is_none_or(|at| at.elapsed() > RECONNECT_INTERVAL)
+        {
+            self.last_connect_attempt = Some(Instant::now());
+
+            let (tx, rx) = mpsc::channel();
+            let broker = broker.clone();
+            std::thread::spawn(move || {
+                tx.send(TcpStream::connect(broker)).ok();
+            });
+            self.connecting = Some(rx);
+        }
+
+        if let Some(connecting) = &self.connecting {
+            match connecting.try_recv() {
+                Ok(Ok(stream)) => {
+                    self.connecting = None;
+                    if let Err(e) = self.connected(stream) {
+                        eprintln!("[MQTT] Failed to connect: {e}");
+                        self.disconnect();
+                    }
+                }
+                Ok(Err(e)) => {
+                    eprintln!("[MQTT] Failed to connect: {e}");
+                    self.connecting = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.connecting = None,
+            }
+        }
+
+        if let Err(e) = self.receive(mpv, commands) {
+            eprintln!("[MQTT] Lost connection: {e}");
+            self.disconnect();
+        }
+
+        if !self.connected {
+            return;
+        }
+
+        self.publish_state(mpv);
+
+        if self
+            .last_sent
+            .is_some_and(|at| at.elapsed() > KEEP_ALIVE / 2)
+        {
+            self.send(PINGREQ, &[]);
+        }
+    }
+
+    fn connected(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_nonblocking(true)?;
+        self.socket = Some(stream);
+
+        let config = &config().mqtt;
+
+        // clean session, and a will so the broker marks us offline if we vanish
+        let mut flags = 0x02 | 0x04 | 0x20;
+        let mut packet = Vec::new();
+        put_str(&mut packet, "MQTT");
+        packet.push(4);
+        let flags_at = packet.len();
+        packet.push(0);
+        packet.extend((KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+
+        put_str(&mut packet, &config.client_id);
+        put_str(&mut packet, &topic("available"));
+        put_str(&mut packet, "offline");
+        if let Some(username) = &config.username {
+            flags |= 0x80;
+            put_str(&mut packet, username);
+        }
+        if let Some(password) = &config.password {
+            flags |= 0x40;
+            put_str(&mut packet, password);
+        }
+        packet[flags_at] = flags;
+
+        self.send(CONNECT, &packet);
+        Ok(())
+    }
+
+    fn disconnect(&mut self) {
+        self.socket = None;
+        self.read_buf.clear();
+        self.connected = false;
+        self.last_sent = None;
+        self.published.clear();
+        self.position = None;
+    }
+
+    /// Handles whatever the broker sent since the last frame.
+    fn receive(&mut self, mpv: &mut Mpv, commands: &mut Vec<Command>) -> io::Result<()> {
+        let Some(socket) = &mut self.socket else {
+            return Ok(());
+        };
+
+        let mut buf = [0; 4096];
+        loop {
+            match socket.read(&mut buf) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "broker closed the connection",
+                    ));
+                }
+                Ok(n) => self.read_buf.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        while let Some((header, body)) = take_packet(&mut self.read_buf) {
+            match header & 0xf0 {
+                CONNACK => {
+                    let code = body.get(1).copied().unwrap_or(0xff);
+                    if code != 0 {
+                        return Err(io::Error::other(format!("broker refused with code {code}")));
+                    }
+
+                    self.connected = true;
+                    self.publish("available", "online");
+
+                    let mut packet = 1u16.to_be_bytes().to_vec();
+                    put_str(&mut packet, &topic("command"));
+                    packet.push(0);
+                    // SUBSCRIBE is the one packet with fixed header flags
+                    self.send(SUBSCRIBE | 0x02, &packet);
+                }
+                PUBLISH => {
+                    let Some((topic, rest)) = take_str(&body) else {
+                        continue;
+                    };
+                    // the packet ID only comes with QoS 1 and up
+                    let payload = if header & 0x06 != 0 {
+                        rest.get(2..).unwrap_or(&[])
+                    } else {
+                        rest
+                    };
+
+                    if topic == self::topic("command") {
+                        handle(&String::from_utf8_lossy(payload), mpv, commands);
+                    }
+                }
+                // SUBACK and PINGRESP
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn publish_state(&mut self, mpv: &mut Mpv) {
+        let idle = mpv.get_property_cached::<bool>("idle-active") != Some(false);
+        let paused = mpv.get_property_cached::<bool>("pause").unwrap_or(true);
+        let state = match (idle, paused) {
+            (true, _) => "idle",
+            (false, true) => "paused",
+            (false, false) => "playing",
+        };
+        let title = mpv
+            .get_property_cached::<String>("media-title")
+            .unwrap_or_default();
+        let duration = mpv
+            .duration()
+            .map(|d| format!("{:.0}", d.as_secs()))
+            .unwrap_or_default();
+        let volume = format!("{:.0}", mpv.get_property::<f32>("volume"));
+
+        let state_changed = self.published.get("state").map(String::as_str) != Some(state);
+        self.publish("state", state);
+        self.publish("title", &title);
+        self.publish("duration", &duration);
+        self.publish("volume", &volume);
+
+        let position = mpv.time_pos_fallback().as_secs();
+        let due = match self.position {
+            None => true,
+            Some((last, at)) => {
+                let expected = if paused {
+                    last
+                } else {
+                    last + at.elapsed().as_secs_f32()
+                };
+                state_changed
+                    || (position - expected).abs() > 2.
+                    || (at.elapsed() > POSITION_EVERY && position != last)
+            }
+        };
+        if due {
+            self.position = Some((position, Instant::now()));
+            self.publish("position", &format!("{position:.0}"));
+        }
+    }
+
+    /// Publishes a retained message under the prefix, unless it's what was published last.
+    fn publish(&mut self, name: &'static str, payload: &str) {
+        if self.published.get(name).map(String::as_str) == Some(payload) {
+            return;
+        }
+        self.published.insert(name, payload.to_string());
+
+        let mut packet = Vec::new();
+        put_str(&mut packet, &topic(name));
+        packet.extend_from_slice(payload.as_bytes());
+        self.send(PUBLISH | 0x01, &packet);
+    }
+
+    fn send(&mut self, header: u8, body: &[u8]) {
+        let Some(socket) = &mut self.socket else {
+            return;
+        };
+
+        if let Err(e) = socket.write_all(&encode_packet(header, body)) {
+            eprintln!("[MQTT] Failed to send: {e}");
+            self.disconnect();
+            return;
+        }
+        self.last_sent = Some(Instant::now());
+    }
+}
+
+impl Drop for Mqtt {
+    fn drop(&mut self) {
+        if self.connected {
+            // a clean disconnect doesn't trigger the will
+            self.publish("available", "offline");
+            self.send(DISCONNECT, &[]);
+        }
+    }
+}
+
+fn handle(command: &str, mpv: &mut Mpv, commands: &mut Vec<Command>) {
+    let (name, arg) = command
+        .trim()
+        .split_once(' ')
+        .map_or((command.trim(), ""), |(name, arg)| (name, arg.trim()));
+
+    match name {
+        "play" => commands.push(Command::Play),
+        "pause" => commands.push(Command::Pause),
+        "play-pause" => commands.push(Command::TogglePause),
+        "stop" => commands.push(Command::Stop),
+        "next" => commands.push(Command::NextEntry),
+        "previous" => commands.push(Command::PreviousEntry),
+        "seek" => match arg.parse::<f32>() {
+            Ok(offset) if arg.starts_with(['+', '-']) => {
+                mpv.seek_stateless(Time::seconds(offset), true).ok();
+            }
+            Ok(position) => {
+                mpv.seek_to(Time::seconds(position.max(0.))).ok();
+            }
+            Err(_) => eprintln!("[MQTT] Ignoring seek to {arg:?}"),
+        },
+        "volume" => match arg.parse::<f32>() {
+            Ok(volume) => {
+                mpv.set_property("volume", volume.max(0.).round()).ok();
+            }
+            Err(_) => eprintln!("[MQTT] Ignoring volume {arg:?}"),
+        },
+        _ => eprintln!("[MQTT] Ignoring unknown command {command:?}"),
+    }
+}
+
+fn topic(name: &str) -> String {
+    format!("{}/{name}", config().mqtt.topic_prefix.trim_end_matches('/'))
+}
+
+/// A packet as it goes on the wire, with the body's length in between.
+fn encode_packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut len = body.len();
+    loop {
+        let byte = (len % 128) as u8;
+        len /= 128;
+        if len == 0 {
+            packet.push(byte);
+            break;
+        }
+        packet.push(byte | 0x80);
+    }
+    packet.extend_from_slice(body);
+    packet
+}
+
+/// Splits the first whole packet off the front of `buf`, as its first byte and what follows the
+/// length. A length that runs past the four bytes it's allowed is taken as the stream being
+/// garbage, and everything is dropped.
+fn take_packet(buf: &mut Vec<u8>) -> Option<(u8, Vec<u8>)> {
+    let mut len = 0;
+    let mut at = 1;
+    loop {
+        if at > 4 {
+            buf.clear();
+            return None;
+        }
+
+        let byte = *buf.get(at)?;
+        len |= usize::from(byte & 0x7f) << (7 * (at - 1));
+        at += 1;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+
+    if buf.len() < at + len {
+        return None;
+    }
+
+    let header = buf[0];
+    let body = buf[at..at + len].to_vec();
+    buf.drain(..at + len);
+    Some((header, body))
+}
+
+fn put_str(packet: &mut Vec<u8>, s: &str) {
+    packet.extend((s.len() as u16).to_be_bytes());
+    packet.extend_from_slice(s.as_bytes());
+}
+
+fn take_str(data: &[u8]) -> Option<(String, &[u8])> {
+    let len = usize::from(u16::from_be_bytes([*data.first()?, *data.get(1)?]));
+    let s = data.get(2..2 + len)?;
+    Some((String::from_utf8_lossy(s).into_owned(), &data[2 + len..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packet_round_trip() {
+        // the edges of one, two and three length bytes
+        for len in [0, 1, 127, 128, 16_383, 16_384, 70_000] {
+            let body = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+            let mut buf = encode_packet(PUBLISH, &body);
+            buf.extend(encode_packet(PINGREQ, &[]));
+
+            assert_eq!(take_packet(&mut buf), Some((PUBLISH, body)));
+            assert_eq!(take_packet(&mut buf), Some((PINGREQ, vec![])));
+            assert!(buf.is_empty());
+        }
+    }
+
+    #[test]
+    fn length_bytes() {
+        assert_eq!(encode_packet(PINGREQ, &[]), [PINGREQ, 0]);
+        assert_eq!(encode_packet(PUBLISH, &[0; 128])[..3], [PUBLISH, 0x80, 0x01]);
+        assert_eq!(encode_packet(PUBLISH, &[0; 16_384])[..4], [PUBLISH, 0x80, 0x80, 0x01]);
+    }
+
+    #[test]
+    fn partial_packet_waits() {
+        let whole = encode_packet(PUBLISH, &[1; 200]);
+        for end in 0..whole.len() {
+            let mut buf = whole[..end].to_vec();
+            assert_eq!(take_packet(&mut buf), None);
+            assert_eq!(buf, whole[..end]);
+        }
+    }
+
+    #[test]
+    fn overlong_length_is_dropped() {
+        let mut buf = vec![PUBLISH, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        assert_eq!(take_packet(&mut buf), None);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn str_round_trip() {
+        let mut packet = Vec::new();
+        put_str(&mut packet, "htpc/command");
+        put_str(&mut packet, "");
+        packet.push(7);
+
+        assert_eq!(packet[..2], [0, 12]);
+        let (first, rest) = take_str(&packet).unwrap();
+        assert_eq!(first, "htpc/command");
+        let (second, rest) = take_str(rest).unwrap();
+        assert_eq!(second, "");
+        assert_eq!(rest, [7]);
+
+        // a length running past the end
+        assert_eq!(take_str(&[0, 5, b'a']), None);
+    }
+}
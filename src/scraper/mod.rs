@@ -0,0 +1,292 @@
+//! Matching Library files and folders to entries in online databases like TMDB and TVDB, for
+//! posters, synopses, ratings and episode titles.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use ehttp::Request;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::config,
+    store,
+    ui::toast::{ProgressHandle, SpawnedToast},
+};
+
+mod query;
+mod tmdb;
+mod tvdb;
+
+pub use self::query::Query;
+
+/// A metadata source. Scrapers are tried in order until one of them knows the query.
+pub trait Scraper: Send {
+    fn name(&self) -> &'static str;
+
+    /// Looks up the query, returning `Ok(None)` if there's no match. Errors are for things that
+    /// might work on a later try, like network failures, and aren't cached.
+    fn scrape(&mut self, query: &Query) -> ehttp::Result<Option<Metadata>>;
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Metadata {
+    /// Movie or series title.
+    pub title: String,
+    pub year: Option<u32>,
+    pub overview: Option<String>,
+    /// Out of 10.
+    pub rating: Option<f32>,
+    /// Season, episode number and title, for episodes.
+    pub episode: Option<(u32, u32, String)>,
+    pub poster_url: Option<String>,
+    /// Where the poster was downloaded to.
+    pub poster: Option<PathBuf>,
+    /// Which scraper found this.
+    pub source: String,
+}
+
+impl Metadata {
+    /// What to call the file in lists.
+    pub fn label(&self) -> String {
+        match &self.episode {
+            Some((season, episode, title)) => format!("S{season:02}E{episode:02} {title}"),
+            None => match self.year {
+                Some(year) => format!("{} ({year})", self.title),
+                None => self.title.clone(),
+            },
+        }
+    }
+
+    pub fn poster_uri(&self) -> Option<String> {
+        self.poster
+            .as_ref()
+            .map(|path| format!("file://{}", path.display()))
+    }
+}
+
+/// Lookups queued at once before there's a toast showing how far along they are, so browsing a
+/// folder or two doesn't put one up.
+const PROGRESS_FROM: usize = 5;
+
+/// How long a failed lookup waits before it's tried again, so a scraper that's down isn't asked
+/// on every frame.
+const RETRY_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// Looks up metadata on a background thread and keeps what it found in the store.
+pub struct Scrapers {
+    queue: Option<Sender<PathBuf>>,
+    /// `None` for lookups that failed, which aren't cached.
+    results: Receiver<(PathBuf, Option<Option<Metadata>>)>,
+    cache: store::metadata::MetadataCache,
+    pending: HashSet<PathBuf>,
+    /// When failed lookups failed. They stay pending until [`RETRY_AFTER`] has passed.
+    failed: HashMap<PathBuf, Instant>,
+    /// Lookups queued and finished since the worker was last idle.
+    queued: usize,
+    finished: usize,
+    progress: Option<ProgressHandle>,
+}
+
+impl Scrapers {
+    pub fn new() -> Self {
+        let scrapers = configured_scrapers();
+        let (done, results) = mpsc::channel();
+
+        // without any API keys there's nothing to look things up with
+        let queue = (!scrapers.is_empty()).then(|| {
+            let (queue, jobs) = mpsc::channel();
+            std::thread::spawn(move || scrape_worker(scrapers, jobs, done));
+            queue
+        });
+
+        Self {
+            queue,
+            results,
+            cache: store::metadata::MetadataCache::default(),
+            pending: HashSet::new(),
+            failed: HashMap::new(),
+            queued: 0,
+            finished: 0,
+            progress: None,
+        }
+    }
+
+    pub fn load(&mut self) {
+        self.cache = store::metadata::MetadataCache::load();
+    }
+
+    pub fn update(&mut self, toasts: &mut Vec<SpawnedToast>) {
+        let mut changed = false;
+
+        for (path, metadata) in self.results.try_iter() {
+            self.finished += 1;
+
+            match metadata {
+                Some(metadata) => {
+                    self.pending.remove(&path);
+                    self.cache.insert(path, metadata);
+                    changed = true;
+                }
+                None => {
+                    self.failed.insert(path, Instant::now());
+                }
+            }
+        }
+
+        // the next get queues them again
+        self.failed.retain(|path, at| {
+            let waiting = at.elapsed() < RETRY_AFTER;
+            if !waiting {
+                self.pending.remove(path);
+            }
+            waiting
+        });
+
+        if changed {
+            self.cache.save();
+        }
+
+        if self.finished >= self.queued {
+            self.queued = 0;
+            self.finished = 0;
+            self.progress = None;
+            return;
+        }
+
+        if self.progress.is_none() && self.queued - self.finished >= PROGRESS_FROM {
+            let (toast, progress) = SpawnedToast::new_progress("Looking up metadata");
+            toasts.push(toast);
+            self.progress = Some(progress);
+        }
+
+        if let Some(progress) = &self.progress {
+            progress.set_fraction(self.finished as f32 / self.queued as f32);
+            progress.set_detail(format!("{} of {}", self.finished, self.queued));
+        }
+    }
+
+    /// Metadata for a file or folder, looking it up in the background if we haven't yet.
+    pub fn get(&mut self, path: &Path) -> Option<&Metadata> {
+        if !self.cache.contains(path)
+            && let Some(queue) = &self.queue
+            && self.pending.insert(path.to_path_buf())
+        {
+            queue.send(path.to_path_buf()).ok();
+            self.queued += 1;
+        }
+
+        self.cache.get(path)
+    }
+
+    /// Metadata that was already looked up, without starting a lookup.
+    pub fn cached(&self, path: &Path) -> Option<&Metadata> {
+        self.cache.get(path)
+    }
+}
+
+impl Default for Scrapers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn configured_scrapers() -> Vec<Box<dyn Scraper>> {
+    let config = &config().scrapers;
+    let mut scrapers: Vec<Box<dyn Scraper>> = vec![];
+
+    if let Some(key) = &config.tmdb_api_key {
+        scrapers.push(Box::new(tmdb::Tmdb::new(key, &config.language)));
+    }
+
+    if let Some(key) = &config.tvdb_api_key {
+        scrapers.push(Box::new(tvdb::Tvdb::new(key)));
+    }
+
+    scrapers
+}
+
+fn scrape_worker(
+    mut scrapers: Vec<Box<dyn Scraper>>,
+    jobs: Receiver<PathBuf>,
+    done: Sender<(PathBuf, Option<Option<Metadata>>)>,
+) {
+    for path in jobs {
+        let Some(query) = Query::from_path(&path) else {
+            done.send((path, Some(None))).ok();
+            continue;
+        };
+
+        let mut found = Some(None);
+
+        for scraper in &mut scrapers {
+            match scraper.scrape(&query) {
+                Ok(Some(metadata)) => {
+                    found = Some(Some(metadata));
+                    break;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("{} failed to look up {query:?}: {e}", scraper.name());
+                    // tried again after a while, maybe it's back up by then
+                    found = None;
+                }
+            }
+        }
+
+        if let Some(Some(metadata)) = &mut found {
+            metadata.poster = metadata.poster_url.as_deref().and_then(download_poster);
+        }
+
+        if done.send((path, found)).is_err() {
+            break;
+        }
+    }
+}
+
+/// Saves a poster under the state directory, named after the last part of its URL.
+fn download_poster(url: &str) -> Option<PathBuf> {
+    let dir = store::state_dir().join("posters");
+    let path = dir.join(url.rsplit('/').next()?);
+
+    if path.is_file() {
+        return Some(path);
+    }
+
+    let res = ehttp::fetch_blocking(&Request::get(url))
+        .map_err(|e| eprintln!("Failed to download poster {url}: {e}"))
+        .ok()?;
+
+    if !res.ok {
+        eprintln!("Failed to download poster {url}: {}", res.status);
+        return None;
+    }
+
+    std::fs::create_dir_all(&dir)
+        .and_then(|()| std::fs::write(&path, &res.bytes))
+        .map_err(|e| eprintln!("Failed to save poster {}: {e}", path.display()))
+        .ok()?;
+
+    Some(path)
+}
+
+/// Fetches and parses a JSON response, treating 404 as no match.
+fn fetch_json<T: for<'de> Deserialize<'de>>(request: Request) -> ehttp::Result<Option<T>> {
+    let url = request.url.clone();
+    let res = ehttp::fetch_blocking(&request)?;
+
+    if res.status == 404 {
+        return Ok(None);
+    }
+
+    if !res.ok {
+        return Err(format!("{url}: {} {}", res.status, res.status_text));
+    }
+
+    serde_json::from_slice(&res.bytes)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse {url}: {e}"))
+}
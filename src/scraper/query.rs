@@ -0,0 +1,94 @@
+use std::path::Path;
+
+/// What to search for, guessed from a file or folder name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub title: String,
+    pub year: Option<u32>,
+    /// Season and episode number, from an `S01E02` style name.
+    pub episode: Option<(u32, u32)>,
+}
+
+impl Query {
+    /// Parses names like `Some.Movie.2019.1080p.mkv`, `Some Show - S01E02 - Title.mkv` and
+    /// `Some Show/Season 1/S01E02.mkv`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = if path.is_dir() {
+            path.file_name()?.to_str()?
+        } else {
+            path.file_stem()?.to_str()?
+        };
+
+        let words = words(name);
+
+        if let Some((idx, episode)) = words
+            .iter()
+            .enumerate()
+            .find_map(|(idx, word)| Some((idx, parse_episode(word)?)))
+        {
+            let title = if idx > 0 {
+                words[..idx].join(" ")
+            } else {
+                series_from_folders(path)?
+            };
+
+            return Some(Self {
+                title,
+                year: None,
+                episode: Some(episode),
+            });
+        }
+
+        // everything after the year is usually release info, like resolution and source
+        let (title, year) = match words.iter().skip(1).position(|w| parse_year(w).is_some()) {
+            Some(idx) => (&words[..=idx], parse_year(words[idx + 1])),
+            None => (&words[..], None),
+        };
+
+        if title.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            title: title.join(" "),
+            year,
+            episode: None,
+        })
+    }
+}
+
+/// Splits a name on the separators release names tend to use instead of spaces.
+fn words(name: &str) -> Vec<&str> {
+    name.split(|c: char| c.is_whitespace() || matches!(c, '.' | '_' | '-' | '[' | ']'))
+        .map(|w| w.trim_matches(|c| c == '(' || c == ')'))
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+fn parse_year(word: &str) -> Option<u32> {
+    let year: u32 = word.parse().ok()?;
+    (word.len() == 4 && (1900..2100).contains(&year)).then_some(year)
+}
+
+/// Parses `S01E02` or `s1e2`.
+fn parse_episode(word: &str) -> Option<(u32, u32)> {
+    let word = word.to_ascii_lowercase();
+    let (season, episode) = word.strip_prefix('s')?.split_once('e')?;
+    Some((season.parse().ok()?, episode.parse().ok()?))
+}
+
+/// The series name for an episode that's only named `S01E02`, from the folder it's in, skipping
+/// over a `Season 1` folder.
+fn series_from_folders(path: &Path) -> Option<String> {
+    let mut folders = path
+        .ancestors()
+        .skip(1)
+        .filter_map(|p| p.file_name()?.to_str());
+    let mut folder = folders.next()?;
+
+    if folder.to_ascii_lowercase().starts_with("season") {
+        folder = folders.next()?;
+    }
+
+    Some(words(folder).join(" "))
+}
@@ -0,0 +1,148 @@
+use ehttp::Request;
+use serde::Deserialize;
+use url::Url;
+
+use super::{Metadata, Query, Scraper, fetch_json};
+
+const API: &str = "https://api.themoviedb.org/3";
+const IMAGES: &str = "https://image.tmdb.org/t/p/w342";
+
+/// [The Movie Database](https://www.themoviedb.org/), for both movies and series.
+pub struct Tmdb {
+    api_key: String,
+    language: String,
+}
+
+#[derive(Deserialize)]
+struct SearchResults<T> {
+    results: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct Movie {
+    title: String,
+    release_date: Option<String>,
+    overview: Option<String>,
+    vote_average: Option<f32>,
+    poster_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Series {
+    id: u64,
+    name: String,
+    first_air_date: Option<String>,
+    overview: Option<String>,
+    vote_average: Option<f32>,
+    poster_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Episode {
+    name: String,
+    overview: Option<String>,
+    vote_average: Option<f32>,
+}
+
+impl Tmdb {
+    pub fn new(api_key: &str, language: &str) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            language: language.to_string(),
+        }
+    }
+
+    fn get<T: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+    ) -> ehttp::Result<Option<T>> {
+        let mut url = Url::parse(&format!("{API}{path}")).map_err(|e| e.to_string())?;
+
+        url.query_pairs_mut()
+            .append_pair("api_key", &self.api_key)
+            .append_pair("language", &self.language)
+            .extend_pairs(params);
+
+        fetch_json(Request::get(url.as_str()))
+    }
+
+    fn search_movie(&self, query: &Query) -> ehttp::Result<Option<Metadata>> {
+        let year = query.year.map(|y| y.to_string());
+        let mut params = vec![("query", query.title.as_str())];
+        if let Some(year) = &year {
+            params.push(("year", year));
+        }
+
+        let Some(results) = self.get::<SearchResults<Movie>>("/search/movie", &params)? else {
+            return Ok(None);
+        };
+
+        Ok(results.results.into_iter().next().map(|movie| Metadata {
+            title: movie.title,
+            year: year_of(movie.release_date.as_deref()),
+            overview: movie.overview.filter(|o| !o.is_empty()),
+            rating: movie.vote_average,
+            poster_url: movie.poster_path.map(|p| format!("{IMAGES}{p}")),
+            source: self.name().to_string(),
+            ..Metadata::default()
+        }))
+    }
+
+    fn search_series(&self, query: &Query) -> ehttp::Result<Option<Series>> {
+        let year = query.year.map(|y| y.to_string());
+        let mut params = vec![("query", query.title.as_str())];
+        if let Some(year) = &year {
+            params.push(("first_air_date_year", year));
+        }
+
+        Ok(self
+            .get::<SearchResults<Series>>("/search/tv", &params)?
+            .and_then(|results| results.results.into_iter().next()))
+    }
+}
+
+impl Scraper for Tmdb {
+    fn name(&self) -> &'static str {
+        "TMDB"
+    }
+
+    fn scrape(&mut self, query: &Query) -> ehttp::Result<Option<Metadata>> {
+        if query.episode.is_none()
+            && let Some(movie) = self.search_movie(query)?
+        {
+            return Ok(Some(movie));
+        }
+
+        let Some(series) = self.search_series(query)? else {
+            return Ok(None);
+        };
+
+        let mut metadata = Metadata {
+            title: series.name,
+            year: year_of(series.first_air_date.as_deref()),
+            overview: series.overview.filter(|o| !o.is_empty()),
+            rating: series.vote_average,
+            poster_url: series.poster_path.map(|p| format!("{IMAGES}{p}")),
+            source: self.name().to_string(),
+            ..Metadata::default()
+        };
+
+        if let Some((season, episode)) = query.episode {
+            let path = format!("/tv/{}/season/{season}/episode/{episode}", series.id);
+
+            if let Some(ep) = self.get::<Episode>(&path, &[])? {
+                metadata.episode = Some((season, episode, ep.name));
+                metadata.overview = ep.overview.filter(|o| !o.is_empty()).or(metadata.overview);
+                metadata.rating = ep.vote_average.or(metadata.rating);
+            }
+        }
+
+        Ok(Some(metadata))
+    }
+}
+
+/// The year out of a `2019-05-31` date.
+pub(super) fn year_of(date: Option<&str>) -> Option<u32> {
+    date?.get(..4)?.parse().ok()
+}
@@ -0,0 +1,136 @@
+use ehttp::Request;
+use serde::Deserialize;
+use url::Url;
+
+use super::{Metadata, Query, Scraper, fetch_json, tmdb::year_of};
+
+const API: &str = "https://api4.thetvdb.com/v4";
+
+/// [TheTVDB](https://thetvdb.com/), for series only.
+pub struct Tvdb {
+    api_key: String,
+    /// Bearer token from logging in with the API key, valid for a month.
+    token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct Login {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct Series {
+    tvdb_id: String,
+    name: String,
+    year: Option<String>,
+    overview: Option<String>,
+    image_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Episodes {
+    episodes: Vec<Episode>,
+}
+
+#[derive(Deserialize)]
+struct Episode {
+    name: Option<String>,
+    overview: Option<String>,
+}
+
+impl Tvdb {
+    pub fn new(api_key: &str) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            token: None,
+        }
+    }
+
+    fn token(&mut self) -> ehttp::Result<&str> {
+        if self.token.is_none() {
+            let body = serde_json::json!({ "apikey": self.api_key }).to_string();
+            let mut request = Request::post(format!("{API}/login"), body.into_bytes());
+            request.headers.insert("Content-Type", "application/json");
+
+            let login = fetch_json::<Response<Login>>(request)?
+                .ok_or_else(|| "TVDB login not found".to_string())?;
+            self.token = Some(login.data.token);
+        }
+
+        Ok(self.token.as_deref().unwrap_or_default())
+    }
+
+    fn get<T: for<'de> Deserialize<'de>>(
+        &mut self,
+        path: &str,
+        params: &[(&str, &str)],
+    ) -> ehttp::Result<Option<T>> {
+        let mut url = Url::parse(&format!("{API}{path}")).map_err(|e| e.to_string())?;
+        url.query_pairs_mut().extend_pairs(params);
+
+        let mut request = Request::get(url.as_str());
+        request
+            .headers
+            .insert("Authorization", format!("Bearer {}", self.token()?));
+
+        let res = fetch_json::<Response<T>>(request);
+        if res.is_err() {
+            // might have expired, log in again next time
+            self.token = None;
+        }
+
+        Ok(res?.map(|r| r.data))
+    }
+}
+
+impl Scraper for Tvdb {
+    fn name(&self) -> &'static str {
+        "TVDB"
+    }
+
+    fn scrape(&mut self, query: &Query) -> ehttp::Result<Option<Metadata>> {
+        let year = query.year.map(|y| y.to_string());
+        let mut params = vec![("query", query.title.as_str()), ("type", "series")];
+        if let Some(year) = &year {
+            params.push(("year", year));
+        }
+
+        let Some(series) = self
+            .get::<Vec<Series>>("/search", &params)?
+            .and_then(|results| results.into_iter().next())
+        else {
+            return Ok(None);
+        };
+
+        let mut metadata = Metadata {
+            title: series.name,
+            year: year_of(series.year.as_deref()),
+            overview: series.overview.filter(|o| !o.is_empty()),
+            rating: None,
+            poster_url: series.image_url,
+            source: self.name().to_string(),
+            ..Metadata::default()
+        };
+
+        if let Some((season, episode)) = query.episode {
+            let path = format!("/series/{}/episodes/default", series.tvdb_id);
+            let (season_str, episode_str) = (season.to_string(), episode.to_string());
+            let params = [("season", season_str.as_str()), ("episodeNumber", &episode_str)];
+
+            if let Some(ep) = self
+                .get::<Episodes>(&path, &params)?
+                .and_then(|episodes| episodes.episodes.into_iter().next())
+            {
+                metadata.episode = Some((season, episode, ep.name.unwrap_or_default()));
+                metadata.overview = ep.overview.filter(|o| !o.is_empty()).or(metadata.overview);
+            }
+        }
+
+        Ok(Some(metadata))
+    }
+}
@@ -0,0 +1,76 @@
+//! "Shuffle unwatched": a random order of the files in a folder or playlist that haven't been
+//! watched yet, where files that were added recently tend to come up sooner.
+
+use std::{
+    hash::{BuildHasher as _, RandomState},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{mpv::Mpv, store::watched::Watched};
+
+/// A file's weight halves every time this much has passed since it was added.
+const HALF_LIFE: Duration = Duration::from_secs(60 * 60 * 24 * 30);
+
+/// Lowest weight a file can get, so old files still have a chance.
+const MIN_WEIGHT: f64 = 0.1;
+
+/// The paths that haven't been watched, shuffled. Paths that aren't local files, like URLs, are
+/// weighted as if they were old.
+pub fn unwatched(paths: impl IntoIterator<Item = PathBuf>, watched: &Watched) -> Vec<PathBuf> {
+    // seeded differently every time, without pulling in a crate for randomness
+    let random = RandomState::new();
+    let now = SystemTime::now();
+
+    let mut keyed = paths
+        .into_iter()
+        .filter(|path| !watched.contains(path))
+        .enumerate()
+        .map(|(idx, path)| {
+            // uniform in (0, 1]
+            let u = (random.hash_one(idx) >> 11) as f64 / (1u64 << 53) as f64;
+            let u = u.max(f64::MIN_POSITIVE);
+
+            // a weighted sample without replacement, by sorting on u^(1/weight)
+            let key = u.ln() / weight(&path, now);
+            (key, path)
+        })
+        .collect::<Vec<_>>();
+
+    keyed.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    keyed.into_iter().map(|(_, path)| path).collect()
+}
+
+fn weight(path: &Path, now: SystemTime) -> f64 {
+    let Some(age) = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|added| now.duration_since(added).ok())
+    else {
+        return MIN_WEIGHT;
+    };
+
+    0.5_f64
+        .powf(age.as_secs_f64() / HALF_LIFE.as_secs_f64())
+        .max(MIN_WEIGHT)
+}
+
+/// Replaces mpv's playlist with `paths` and starts playing the first one. Returns false if there
+/// was nothing to play.
+pub fn play(mpv: &mut Mpv, paths: &[PathBuf]) -> bool {
+    let Some((first, rest)) = paths.split_first() else {
+        return false;
+    };
+
+    if let Err(e) = mpv.load_file(&first.to_string_lossy()) {
+        eprintln!("Failed to play {}: {e}", first.display());
+        return false;
+    }
+
+    for path in rest {
+        mpv.append_file(&path.to_string_lossy()).ok();
+    }
+
+    mpv.unpause().ok();
+    true
+}
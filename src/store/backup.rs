@@ -0,0 +1,436 @@
+//! Backups of the config and everything in the state directory as a single tar file, for getting
+//! the overlay back the way it was after reinstalling. Only plain files are stored, which is all
+//! there is in those directories.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write as _},
+    path::{Component, Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    time::UNIX_EPOCH,
+};
+
+use crate::{command::Event, config::Config, udisks::RemovableMount, ui::toast::Toast};
+
+const PREFIX: &str = "htpc-overlay-backup-";
+const BLOCK: usize = 512;
+
+/// Where the config goes in the archive. Everything else is under `state/`.
+const CONFIG_ENTRY: &str = "config.toml";
+const STATE_ENTRY: &str = "state";
+
+/// Backups on the drives that are plugged in. Looking for them, writing them and restoring them
+/// happens on threads of their own, so the Settings menu doesn't wait on the drives while drawing.
+pub struct Backups {
+    /// The newest backup on each drive that's been looked at, by mount point.
+    latest: HashMap<PathBuf, Option<PathBuf>>,
+    /// Drives a backup is being written to.
+    writing: HashSet<PathBuf>,
+    restoring: bool,
+    done: Receiver<Done>,
+    done_tx: Sender<Done>,
+}
+
+enum Done {
+    Found(PathBuf, Option<PathBuf>),
+    Created(PathBuf, io::Result<PathBuf>),
+    Restored(io::Result<()>),
+}
+
+impl Backups {
+    pub fn new() -> Self {
+        let (done_tx, done) = mpsc::channel();
+        Self {
+            latest: HashMap::new(),
+            writing: HashSet::new(),
+            restoring: false,
+            done,
+            done_tx,
+        }
+    }
+
+    /// Returns whether a backup was just restored, so the state should be loaded again.
+    pub fn update(&mut self, mounts: &[RemovableMount], events: &mut Vec<Event>) -> bool {
+        let mut restored = false;
+
+        for done in self.done.try_iter() {
+            match done {
+                Done::Found(mount, latest) => {
+                    self.latest.insert(mount, latest);
+                }
+                Done::Created(mount, result) => {
+                    self.writing.remove(&mount);
+                    let toast = match result {
+                        Ok(path) => {
+                            let name = path
+                                .file_name()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                                .into_owned();
+                            self.latest.insert(mount, Some(path));
+                            Toast::BackedUp { name }
+                        }
+                        Err(e) => Toast::BackupFailed { reason: e.to_string() },
+                    };
+                    events.push(Event::Toast(toast));
+                }
+                Done::Restored(result) => {
+                    self.restoring = false;
+                    let toast = match result {
+                        Ok(()) => {
+                            restored = true;
+                            Toast::Restored
+                        }
+                        Err(e) => Toast::RestoreFailed { reason: e.to_string() },
+                    };
+                    events.push(Event::Toast(toast));
+                }
+            }
+        }
+
+        // a drive that's plugged back in may have been written to elsewhere
+        self.latest
+            .retain(|mount, _| mounts.iter().any(|m| m.mount_point == *mount));
+
+        restored
+    }
+
+    /// The newest backup on a drive. It's looked for in the background the first time, and is
+    /// `None` until it's found.
+    pub fn latest(&mut self, mount: &Path) -> Option<&Path> {
+        if !self.latest.contains_key(mount) {
+            self.latest.insert(mount.to_path_buf(), None);
+
+            let mount = mount.to_path_buf();
+            let tx = self.done_tx.clone();
+            std::thread::spawn(move || {
+                let latest = find(&mount).into_iter().next();
+                tx.send(Done::Found(mount, latest)).ok();
+            });
+        }
+
+        self.latest.get(mount)?.as_deref()
+    }
+
+    pub fn is_writing(&self, mount: &Path) -> bool {
+        self.writing.contains(mount)
+    }
+
+    /// Starts writing a backup to a drive. A toast says when it's done.
+    pub fn create(&mut self, mount: &Path) {
+        if !self.writing.insert(mount.to_path_buf()) {
+            return;
+        }
+
+        let mount = mount.to_path_buf();
+        let tx = self.done_tx.clone();
+        std::thread::spawn(move || {
+            let result = create(&mount, &Config::path(), &super::state_dir());
+            tx.send(Done::Created(mount, result)).ok();
+        });
+    }
+
+    pub fn is_restoring(&self) -> bool {
+        self.restoring
+    }
+
+    /// Starts putting a backup back. A toast says when it's done, and [`Backups::update`] says
+    /// when to load the state again.
+    pub fn restore(&mut self, archive: &Path) {
+        if self.restoring {
+            return;
+        }
+        self.restoring = true;
+
+        let archive = archive.to_path_buf();
+        let tx = self.done_tx.clone();
+        std::thread::spawn(move || {
+            let result = restore(&archive, &Config::path(), &super::state_dir());
+            tx.send(Done::Restored(result)).ok();
+        });
+    }
+}
+
+impl Default for Backups {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes a backup of the config file and the state directory into `dir`, returning its path.
+fn create(dir: &Path, config: &Path, state: &Path) -> io::Result<PathBuf> {
+    let name = chrono::Local::now()
+        .format(&format!("{PREFIX}%Y%m%d-%H%M%S.tar"))
+        .to_string();
+    let path = dir.join(name);
+
+    let mut out = BufWriter::new(File::create(&path)?);
+
+    if config.exists() {
+        append(&mut out, CONFIG_ENTRY, config)?;
+    }
+    append_dir(&mut out, Path::new(STATE_ENTRY), state)?;
+
+    // end of archive
+    out.write_all(&[0; BLOCK * 2])?;
+    out.flush()?;
+
+    Ok(path)
+}
+
+/// Backups in `dir`, newest first.
+fn find(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let mut backups = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(PREFIX) && name.ends_with(".tar"))
+        })
+        .collect::<Vec<_>>();
+
+    // the timestamp in the name sorts the same as the time itself
+    backups.sort_by(|a, b| b.cmp(a));
+    backups
+}
+
+/// Puts every file in the backup back where it came from, overwriting what's there. Files that
+/// aren't in the backup are left alone.
+fn restore(archive: &Path, config: &Path, state: &Path) -> io::Result<()> {
+    let mut input = BufReader::new(File::open(archive)?);
+    let mut header = [0; BLOCK];
+
+    loop {
+        input.read_exact(&mut header)?;
+        if header.iter().all(|&b| b == 0) {
+            return Ok(());
+        }
+
+        let name = match field_str(&header[345..500]) {
+            "" => field_str(&header[..100]).to_string(),
+            prefix => format!("{prefix}/{}", field_str(&header[..100])),
+        };
+        let size = u64::from_str_radix(field_str(&header[124..136]).trim(), 8)
+            .map_err(|_| io::Error::new(ErrorKind::InvalidData, "bad size in backup"))?;
+        let is_file = matches!(header[156], b'0' | 0);
+
+        // streamed rather than read in whole, since the size is whatever the header says
+        let mut contents = (&mut input).take(size);
+        let copied = match is_file.then(|| target(Path::new(&name), config, state)) {
+            Some(Some(target)) => {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                io::copy(&mut contents, &mut File::create(target)?)?
+            }
+            skipped => {
+                if skipped.is_some() {
+                    eprintln!("Skipping {name} in backup");
+                }
+                io::copy(&mut contents, &mut io::sink())?
+            }
+        };
+
+        let padding = size.next_multiple_of(BLOCK as u64) - size;
+        if copied != size || io::copy(&mut (&mut input).take(padding), &mut io::sink())? != padding
+        {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "backup is cut short"));
+        }
+    }
+}
+
+/// Where an entry in the archive is restored to, or `None` if it isn't one of ours.
+fn target(entry: &Path, config: &Path, state: &Path) -> Option<PathBuf> {
+    if entry == Path::new(CONFIG_ENTRY) {
+        return Some(config.to_path_buf());
+    }
+
+    let rel = entry.strip_prefix(STATE_ENTRY).ok()?;
+    // never write outside the state directory, whatever the archive says
+    if rel.as_os_str().is_empty() || !rel.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+
+    Some(state.join(rel))
+}
+
+fn append_dir(out: &mut impl io::Write, entry: &Path, dir: &Path) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    for dir_entry in entries {
+        let dir_entry = dir_entry?;
+        let file_type = dir_entry.file_type()?;
+        let entry = entry.join(dir_entry.file_name());
+
+        if file_type.is_dir() {
+            append_dir(out, &entry, &dir_entry.path())?;
+        } else if file_type.is_file() {
+            let name = entry
+                .to_str()
+                .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "non-UTF-8 file name"))?;
+            append(out, name, &dir_entry.path())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn append(out: &mut impl io::Write, name: &str, path: &Path) -> io::Result<()> {
+    let contents = fs::read(path)?;
+    let mtime = fs::metadata(path)?
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    out.write_all(&header(name, contents.len() as u64, mtime)?)?;
+    out.write_all(&contents)?;
+    out.write_all(&vec![0; contents.len().next_multiple_of(BLOCK) - contents.len()])?;
+
+    Ok(())
+}
+
+/// A ustar header for a plain file.
+fn header(name: &str, size: u64, mtime: u64) -> io::Result<[u8; BLOCK]> {
+    // names over 100 bytes are split into a prefix and the rest at a slash
+    let (prefix, name) = if name.len() <= 100 {
+        ("", name)
+    } else {
+        name.match_indices('/')
+            .map(|(idx, _)| (&name[..idx], &name[idx + 1..]))
+            .find(|(prefix, name)| prefix.len() <= 155 && name.len() <= 100)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "file name too long"))?
+    };
+
+    let mut header = [0; BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    octal(&mut header[100..108], 0o644);
+    octal(&mut header[108..116], 0);
+    octal(&mut header[116..124], 0);
+    octal(&mut header[124..136], size);
+    octal(&mut header[136..148], mtime);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    // summed with the checksum field itself as spaces
+    header[148..156].fill(b' ');
+    let checksum = header.iter().map(|&b| u64::from(b)).sum();
+    octal(&mut header[148..155], checksum);
+
+    Ok(header)
+}
+
+/// Zero-padded octal with a trailing NUL, filling `field`.
+fn octal(field: &mut [u8], value: u64) {
+    let digits = format!("{value:0width$o}", width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[digits.len()] = 0;
+}
+
+fn field_str(field: &[u8]) -> &str {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    str::from_utf8(&field[..end]).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of its own under the system's temp dir, emptied first.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("htpc-overlay-{name}-{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = scratch("backup-round-trip");
+        let (drive, config, state) =
+            (dir.join("drive"), dir.join("config.toml"), dir.join("state"));
+        fs::create_dir_all(&drive).unwrap();
+
+        // over 100 bytes, so it's split into a prefix and a name
+        let long = format!("posters/{}/{}.jpg", "a".repeat(60), "b".repeat(60));
+        let files = [
+            ("history.json", b"[]".to_vec()),
+            ("empty", vec![]),
+            ("exactly-one-block", vec![7; BLOCK]),
+            (long.as_str(), (0..=255).cycle().take(1500).collect()),
+        ];
+
+        fs::write(&config, "zoom-factor = 2.0\n").unwrap();
+        for (name, contents) in &files {
+            let path = state.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let archive = create(&drive, &config, &state).unwrap();
+        assert_eq!(find(&drive), [archive.clone()]);
+
+        fs::remove_file(&config).unwrap();
+        fs::remove_dir_all(&state).unwrap();
+        restore(&archive, &config, &state).unwrap();
+
+        assert_eq!(fs::read_to_string(&config).unwrap(), "zoom-factor = 2.0\n");
+        for (name, contents) in &files {
+            assert_eq!(fs::read(state.join(name)).unwrap(), *contents, "{name}");
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn header_fields() {
+        let header = header("state/history.json", 1234, 5).unwrap();
+
+        assert_eq!(field_str(&header[..100]), "state/history.json");
+        assert_eq!(field_str(&header[124..136]), "00000002322");
+        assert_eq!(field_str(&header[136..148]), "00000000005");
+        assert_eq!(&header[257..263], b"ustar\0");
+
+        let checksum = u64::from_str_radix(field_str(&header[148..155]), 8).unwrap();
+        let sum = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    u64::from(b' ')
+                } else {
+                    u64::from(b)
+                }
+            })
+            .sum::<u64>();
+        assert_eq!(checksum, sum);
+    }
+
+    #[test]
+    fn oversized_entry_fails() {
+        let dir = scratch("backup-oversized");
+        let archive = dir.join("backup.tar");
+
+        // claims far more than there is, which mustn't be allocated up front
+        let mut contents = header("state/huge", 0o77777777777, 0).unwrap().to_vec();
+        contents.extend([1; 100]);
+        fs::write(&archive, contents).unwrap();
+
+        let e = restore(&archive, &dir.join("config.toml"), &dir.join("state")).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Library folders and files pinned to the top of the Library root.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Favorites {
+    paths: Vec<PathBuf>,
+}
+
+impl Favorites {
+    const NAME: &str = "favorites";
+
+    pub fn load() -> Self {
+        super::load(Self::NAME)
+    }
+
+    fn save(&self) {
+        if let Err(e) = super::save(Self::NAME, self) {
+            eprintln!("Failed to save favorites: {e}");
+        }
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths.iter().any(|p| p == path)
+    }
+
+    pub fn toggle(&mut self, path: &Path) {
+        if self.contains(path) {
+            self.paths.retain(|p| p != path);
+        } else {
+            self.paths.push(path.to_path_buf());
+        }
+        self.save();
+    }
+
+    pub fn move_up(&mut self, path: &Path) {
+        if let Some(idx) = self.paths.iter().position(|p| p == path)
+            && idx > 0
+        {
+            self.paths.swap(idx, idx - 1);
+            self.save();
+        }
+    }
+
+    pub fn move_down(&mut self, path: &Path) {
+        if let Some(idx) = self.paths.iter().position(|p| p == path)
+            && idx + 1 < self.paths.len()
+        {
+            self.paths.swap(idx, idx + 1);
+            self.save();
+        }
+    }
+}
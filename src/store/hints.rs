@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// How many times each hint is shown before it's assumed to have sunk in.
+const TIMES: u32 = 3;
+
+/// Tips on what the buttons do, shown the first few times a view opens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Hint {
+    SeekBar,
+    Seeking,
+    HomeMenu,
+    MediaMenu,
+}
+
+/// How often each hint has been shown.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Hints {
+    shown: BTreeMap<Hint, u32>,
+}
+
+impl Hints {
+    const NAME: &str = "hints";
+
+    pub fn load() -> Self {
+        super::load(Self::NAME)
+    }
+
+    fn save(&self) {
+        if let Err(e) = super::save(Self::NAME, self) {
+            eprintln!("Failed to save hints: {e}");
+        }
+    }
+
+    /// Whether `hint` should be shown, counting it as shown if so.
+    pub fn take(&mut self, hint: Hint) -> bool {
+        let shown = self.shown.entry(hint).or_default();
+        if *shown >= TIMES {
+            return false;
+        }
+
+        *shown += 1;
+        self.save();
+        true
+    }
+
+    /// Shows every hint again, as if the overlay was new.
+    pub fn reset(&mut self) {
+        self.shown.clear();
+        self.save();
+    }
+}
@@ -0,0 +1,50 @@
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::mpv::time::Time;
+
+/// How many entries to keep before the oldest ones are forgotten.
+const MAX_ENTRIES: usize = 500;
+
+/// Files that have been played, most recent first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub path: String,
+    pub title: Option<String>,
+    /// Where playback was when the file was stopped.
+    pub position: Time,
+    pub duration: Option<Time>,
+    pub played_at: SystemTime,
+}
+
+impl History {
+    const NAME: &str = "history";
+
+    pub fn load() -> Self {
+        super::load(Self::NAME)
+    }
+
+    fn save(&self) {
+        if let Err(e) = super::save(Self::NAME, self) {
+            eprintln!("Failed to save history: {e}");
+        }
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Moves the entry for this path to the front, replacing what we knew about it before.
+    pub fn record(&mut self, entry: HistoryEntry) {
+        self.entries.retain(|e| e.path != entry.path);
+        self.entries.insert(0, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        self.save();
+    }
+}
@@ -0,0 +1,41 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::scraper::Metadata;
+
+/// What the scrapers found for Library files and folders, including misses so they aren't looked
+/// up again.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MetadataCache {
+    entries: HashMap<PathBuf, Option<Metadata>>,
+}
+
+impl MetadataCache {
+    const NAME: &str = "metadata";
+
+    pub fn load() -> Self {
+        super::load(Self::NAME)
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = super::save(Self::NAME, self) {
+            eprintln!("Failed to save metadata: {e}");
+        }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&Metadata> {
+        self.entries.get(path)?.as_ref()
+    }
+
+    pub fn insert(&mut self, path: PathBuf, metadata: Option<Metadata>) {
+        self.entries.insert(path, metadata);
+    }
+}
@@ -0,0 +1,65 @@
+//! Persistent state that the overlay keeps across restarts, stored as JSON files under
+//! `~/.local/state/htpc-overlay/`.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+pub mod backup;
+pub mod favorites;
+pub mod hints;
+pub mod history;
+pub mod metadata;
+pub mod resume;
+pub mod settings;
+pub mod watched;
+
+pub fn state_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/state")))
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+
+    base.join("htpc-overlay")
+}
+
+pub fn profile_name() -> String {
+    std::env::var("HTPC_OVERLAY_PROFILE").unwrap_or_else(|_| "default".to_string())
+}
+
+/// Directory for state that belongs to the active profile.
+pub fn profile_dir() -> PathBuf {
+    state_dir().join("profiles").join(profile_name())
+}
+
+/// Loads `name.json` from the profile directory, falling back to the default value if it doesn't
+/// exist or can't be parsed.
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    let path = profile_dir().join(format!("{name}.json"));
+
+    match fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            eprintln!("Failed to parse {}: {e}", path.display());
+            T::default()
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => T::default(),
+        Err(e) => {
+            eprintln!("Failed to read {}: {e}", path.display());
+            T::default()
+        }
+    }
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) -> io::Result<()> {
+    let dir = profile_dir();
+    fs::create_dir_all(&dir)?;
+
+    let path = dir.join(format!("{name}.json"));
+    let tmp = dir.join(format!(".{name}.json.tmp"));
+
+    fs::write(&tmp, serde_json::to_vec_pretty(value)?)?;
+    fs::rename(tmp, path)
+}
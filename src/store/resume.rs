@@ -0,0 +1,80 @@
+use std::{collections::HashMap, time::SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::mpv::{Mpv, time::Time};
+
+/// Positions closer than this many seconds to the start aren't worth offering to resume from.
+const MIN_POSITION: f32 = 30.;
+/// Files stopped this many seconds from the end count as finished, and start over next time.
+const END_MARGIN: f32 = 60.;
+/// How many positions to keep before the oldest ones are forgotten.
+const MAX_ENTRIES: usize = 500;
+
+/// Where playback was left off in files that weren't finished, by path.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ResumePositions {
+    positions: HashMap<String, ResumePosition>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct ResumePosition {
+    position: Time,
+    saved_at: SystemTime,
+}
+
+impl ResumePositions {
+    const NAME: &str = "resume";
+
+    pub fn load() -> Self {
+        super::load(Self::NAME)
+    }
+
+    fn save(&self) {
+        if let Err(e) = super::save(Self::NAME, self) {
+            eprintln!("Failed to save resume positions: {e}");
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<Time> {
+        self.positions.get(path).map(|p| p.position)
+    }
+
+    /// Saves the position of whatever mpv is playing, or forgets it if it's (nearly) done.
+    pub fn remember(&mut self, mpv: &Mpv) {
+        let Some(path) = mpv.get_property_cached::<String>("path") else {
+            return;
+        };
+        let Some(position) = mpv.time_pos() else {
+            return;
+        };
+
+        let finished = mpv
+            .duration()
+            .is_some_and(|duration| position > duration - Time::seconds(END_MARGIN));
+
+        if position < Time::seconds(MIN_POSITION) || finished {
+            if self.positions.remove(&path).is_some() {
+                self.save();
+            }
+            return;
+        }
+
+        self.positions.insert(path, ResumePosition {
+            position,
+            saved_at: SystemTime::now(),
+        });
+
+        if self.positions.len() > MAX_ENTRIES
+            && let Some(oldest) = self
+                .positions
+                .iter()
+                .min_by_key(|(_, p)| p.saved_at)
+                .map(|(path, _)| path.clone())
+        {
+            self.positions.remove(&oldest);
+        }
+
+        self.save();
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Larger subtitles, text and focus outlines for watching from across the room.
+    pub tv_distance: bool,
+    /// Latency compensation in seconds per mpv `audio-device`, applied to `audio-delay` whenever
+    /// that device becomes active. DLNA speakers that audio is cast to have theirs here too.
+    pub audio_delays: BTreeMap<String, f32>,
+    /// Seconds over which audio fades back in after seeks, skips and unpausing. Zero disables it.
+    pub audio_fade: f32,
+    /// How the Library shows a directory, also used for everything below it that doesn't have
+    /// its own.
+    pub library_layouts: BTreeMap<PathBuf, LibraryLayout>,
+    /// How much of network streams mpv reads ahead and keeps around.
+    pub cache_preset: CachePreset,
+    pub subtitles: SubtitleStyle,
+    /// What the volume buttons control.
+    pub volume_target: VolumeTarget,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VolumeTarget {
+    /// The first DLNA renderer that was found, or mpv if there is none.
+    #[default]
+    FirstRenderer,
+    Mpv,
+    /// A DLNA renderer by its UDN. mpv while it's not around.
+    Renderer(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleStyle {
+    /// Multiplies `sub-scale`, on top of what TV distance does.
+    pub scale: f32,
+    /// Percentage points added to `sub-pos`, which otherwise keeps subtitles just above the
+    /// overlay's panels.
+    pub pos_offset: f32,
+    /// mpv's `sub-border-size`.
+    pub border_size: f32,
+}
+
+impl SubtitleStyle {
+    pub const SCALE_STEP: f32 = 0.1;
+    pub const MIN_SCALE: f32 = 0.5;
+    pub const MAX_SCALE: f32 = 3.;
+}
+
+impl Default for SubtitleStyle {
+    fn default() -> Self {
+        Self {
+            scale: 1.,
+            pos_offset: 0.,
+            border_size: 3.,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CachePreset {
+    /// Leaves the cache to mpv.conf. Switching here from another preset keeps that preset's
+    /// values until mpv restarts.
+    #[default]
+    Unchanged,
+    /// For boxes that run out of memory buffering long streams.
+    LowRam,
+    /// mpv's own defaults.
+    Default,
+    /// Reads far ahead, for flaky connections that can't keep up in the moment.
+    Aggressive,
+}
+
+impl CachePreset {
+    pub const ALL: [CachePreset; 4] = [
+        CachePreset::Unchanged,
+        CachePreset::LowRam,
+        CachePreset::Default,
+        CachePreset::Aggressive,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CachePreset::Unchanged => "From mpv.conf",
+            CachePreset::LowRam => "Low RAM",
+            CachePreset::Default => "mpv defaults",
+            CachePreset::Aggressive => "Aggressive prefetch",
+        }
+    }
+
+    /// Values for mpv's `cache-secs`, `demuxer-max-bytes` and `stream-buffer-size`, or `None` to
+    /// leave them be.
+    pub fn properties(self) -> Option<[(&'static str, &'static str); 3]> {
+        let (secs, demuxer, stream) = match self {
+            CachePreset::Unchanged => return None,
+            CachePreset::LowRam => ("10", "32MiB", "64KiB"),
+            CachePreset::Default => ("3600000", "150MiB", "128KiB"),
+            CachePreset::Aggressive => ("600", "1GiB", "4MiB"),
+        };
+
+        Some([("cache-secs", secs), ("demuxer-max-bytes", demuxer), ("stream-buffer-size", stream)])
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LibraryLayout {
+    #[default]
+    List,
+    /// Grid of thumbnails from artwork next to the files or frame grabs.
+    Thumbnails,
+    /// Grid of scraped posters.
+    Posters,
+}
+
+impl LibraryLayout {
+    pub fn label(self) -> &'static str {
+        match self {
+            LibraryLayout::List => "List",
+            LibraryLayout::Thumbnails => "Thumbnails",
+            LibraryLayout::Posters => "Posters",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            LibraryLayout::List => LibraryLayout::Thumbnails,
+            LibraryLayout::Thumbnails => LibraryLayout::Posters,
+            LibraryLayout::Posters => LibraryLayout::List,
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            tv_distance: false,
+            audio_delays: BTreeMap::new(),
+            audio_fade: 0.3,
+            library_layouts: BTreeMap::new(),
+            cache_preset: CachePreset::default(),
+            subtitles: SubtitleStyle::default(),
+            volume_target: VolumeTarget::default(),
+        }
+    }
+}
+
+impl Settings {
+    const NAME: &str = "settings";
+
+    pub fn load() -> Self {
+        super::load(Self::NAME)
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = super::save(Self::NAME, self) {
+            eprintln!("Failed to save settings: {e}");
+        }
+    }
+
+    pub fn audio_fade(&self) -> Duration {
+        Duration::from_secs_f32(self.audio_fade.max(0.))
+    }
+
+    pub fn library_layout(&self, dir: &Path) -> LibraryLayout {
+        dir.ancestors()
+            .find_map(|dir| self.library_layouts.get(dir))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn cycle_library_layout(&mut self, dir: &Path) {
+        let layout = self.library_layout(dir).next();
+        let inherited = dir
+            .parent()
+            .map_or(LibraryLayout::default(), |parent| self.library_layout(parent));
+
+        if layout == inherited {
+            self.library_layouts.remove(dir);
+        } else {
+            self.library_layouts.insert(dir.to_path_buf(), layout);
+        }
+        self.save();
+    }
+
+    /// Makes subtitles bigger or smaller by `steps` of [`SubtitleStyle::SCALE_STEP`], returning the
+    /// new scale.
+    pub fn change_sub_scale(&mut self, steps: f32) -> f32 {
+        let scale = self.subtitles.scale + steps * SubtitleStyle::SCALE_STEP;
+        // keep it on the step grid instead of accumulating float error
+        self.subtitles.scale = ((scale / SubtitleStyle::SCALE_STEP).round()
+            * SubtitleStyle::SCALE_STEP)
+            .clamp(SubtitleStyle::MIN_SCALE, SubtitleStyle::MAX_SCALE);
+        self.save();
+
+        self.subtitles.scale
+    }
+
+    pub fn audio_delay(&self, device: &str) -> f32 {
+        self.audio_delays.get(device).copied().unwrap_or(0.)
+    }
+
+    pub fn set_audio_delay(&mut self, device: &str, delay: f32) {
+        // round to whole milliseconds so repeated adjustments don't accumulate float noise
+        let delay = (delay * 1000.).round() / 1000.;
+
+        if delay == 0. {
+            self.audio_delays.remove(device);
+        } else {
+            self.audio_delays.insert(device.to_string(), delay);
+        }
+        self.save();
+    }
+}
@@ -0,0 +1,43 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Library files that have been watched, either played almost to the end or marked by hand.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Watched {
+    paths: HashSet<PathBuf>,
+}
+
+impl Watched {
+    const NAME: &str = "watched";
+
+    pub fn load() -> Self {
+        super::load(Self::NAME)
+    }
+
+    fn save(&self) {
+        if let Err(e) = super::save(Self::NAME, self) {
+            eprintln!("Failed to save watched files: {e}");
+        }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths.contains(path)
+    }
+
+    pub fn mark(&mut self, path: &Path) {
+        if self.paths.insert(path.to_path_buf()) {
+            self.save();
+        }
+    }
+
+    pub fn toggle(&mut self, path: &Path) {
+        if !self.paths.remove(path) {
+            self.paths.insert(path.to_path_buf());
+        }
+        self.save();
+    }
+}
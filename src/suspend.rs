@@ -0,0 +1,62 @@
+//! Noticing when the system wakes up from suspend through logind, since `Instant` doesn't count
+//! the time spent asleep and timers that were running need a fresh start.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use zbus::{
+    MatchRule,
+    blocking::{Connection, MessageIterator},
+    message,
+};
+
+const LOGIND: &str = "org.freedesktop.login1";
+
+pub struct Suspend {
+    /// Pinged by the watcher thread whenever the system has woken up.
+    resumed: Receiver<()>,
+}
+
+impl Suspend {
+    pub fn new() -> Self {
+        let (tx, resumed) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            if let Err(e) = watch(tx) {
+                eprintln!("Failed to watch logind for suspend: {e}");
+            }
+        });
+
+        Self { resumed }
+    }
+
+    /// Whether the system woke up since the last call.
+    pub fn take_resumed(&self) -> bool {
+        self.resumed.try_iter().count() > 0
+    }
+}
+
+impl Default for Suspend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn watch(tx: Sender<()>) -> zbus::Result<()> {
+    let conn = Connection::system()?;
+    let rule = MatchRule::builder()
+        .msg_type(message::Type::Signal)
+        .sender(LOGIND)?
+        .interface("org.freedesktop.login1.Manager")?
+        .member("PrepareForSleep")?
+        .build();
+
+    for msg in MessageIterator::for_match_rule(rule, &conn, None)? {
+        // true right before going to sleep, false once awake again
+        let going_to_sleep: bool = msg?.body().deserialize()?;
+        if !going_to_sleep && tx.send(()).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,453 @@
+//! A client for Syncplay servers, for watching along with people using mpv, VLC or anything else
+//! Syncplay supports. The protocol is newline-delimited JSON over TCP. The server relays every
+//! pause, play and seek to the rest of the room, and sends everyone its idea of the room's
+//! playback about once a second.
+
+use std::{
+    io::{self, BufRead as _, BufReader, ErrorKind, Write as _},
+    net::TcpStream,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{Map, Value, json};
+
+use crate::{
+    command::Event,
+    config::config,
+    mpv::{
+        Mpv,
+        local_changes::{LocalChange, LocalChanges},
+        time::Time,
+    },
+    ui::toast::Toast,
+};
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+/// How far from the room's position we can get before seeking to catch up.
+const MAX_DRIFT: f32 = 1.;
+/// Protocol version we speak, and the client version we claim to be for features that depend on
+/// it.
+const VERSION: &str = "1.2.255";
+const REAL_VERSION: &str = "1.7.0";
+
+pub enum Connection {
+    Disabled,
+    Connecting,
+    /// Connected, but the server hasn't let us into the room yet.
+    Joining,
+    Joined,
+    Disconnected,
+}
+
+pub struct User {
+    pub name: String,
+    /// File name of what they're playing.
+    pub file: Option<String>,
+    pub position: Time,
+}
+
+#[derive(Default)]
+pub struct Syncplay {
+    socket: Option<BufReader<TcpStream>>,
+    /// Connecting happens on a thread of its own, since resolving the server can take a while.
+    connecting: Option<Receiver<io::Result<TcpStream>>>,
+    line_buf: String,
+    /// What's been sent but didn't fit in the socket's buffer yet.
+    write_buf: Vec<u8>,
+    last_connect_attempt: Option<Instant>,
+    joined: bool,
+    users: Vec<User>,
+    changes: LocalChanges,
+    /// The file we last told the server about.
+    announced_file: Option<String>,
+    /// Changes of ours the server hasn't acknowledged yet. Its idea of the room's playback is
+    /// stale until it has.
+    client_ignoring: u64,
+    /// A change of the server's we have to acknowledge in our next state.
+    server_ignoring: u64,
+    /// Timestamp of the server's last ping, which is echoed back.
+    latency_calculation: Option<f64>,
+    rtt: f64,
+}
+
+impl Syncplay {
+    pub fn connection(&self) -> Connection {
+        if config().syncplay.server.is_none() {
+            Connection::Disabled
+        } else if self.connecting.is_some() {
+            Connection::Connecting
+        } else if self.socket.is_none() {
+            Connection::Disconnected
+        } else if !self.joined {
+            Connection::Joining
+        } else {
+            Connection::Joined
+        }
+    }
+
+    /// Everyone in the room besides us, sorted by name.
+    pub fn users(&self) -> &[User] {
+        &self.users
+    }
+
+    pub fn update(&mut self, mpv: &mut Mpv, events: &mut Vec<Event>) {
+        let Some(server) = &config().syncplay.server else {
+            return;
+        };
+
+        if self.socket.is_none()
+            && self.connecting.is_none()
+            && self
+                .last_connect_attempt
+                .is_none_or(|at| at.elapsed() > RECONNECT_INTERVAL)
+        {
+            self.last_connect_attempt = Some(Instant::now());
+
+            let (tx, rx) = mpsc::channel();
+            let server = server.clone();
+            std::thread::spawn(move || {
+                tx.send(TcpStream::connect(server)).ok();
+            });
+            self.connecting = Some(rx);
+        }
+
+        if let Some(connecting) = &self.connecting {
+            match connecting.try_recv() {
+                Ok(Ok(stream)) => {
+                    self.connecting = None;
+                    if let Err(e) = self.connected(stream) {
+                        eprintln!("[Syncplay] Failed to join room: {e}");
+                        self.disconnect();
+                    }
+                }
+                Ok(Err(e)) => {
+                    eprintln!("[Syncplay] Failed to connect: {e}");
+                    self.connecting = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.connecting = None,
+            }
+        }
+
+        self.flush();
+
+        loop {
+            match self.read_line() {
+                Ok(Some(line)) => match serde_json::from_str::<Value>(&line) {
+                    Ok(msg) => self.handle(&msg, mpv, events),
+                    Err(e) => eprintln!("[Syncplay] Ignoring malformed message: {e}"),
+                },
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("[Syncplay] Lost connection: {e}");
+                    self.disconnect();
+                    break;
+                }
+            }
+        }
+
+        if !self.joined {
+            return;
+        }
+
+        let file = mpv.file_name();
+        if file != self.announced_file {
+            self.announced_file = file.clone();
+            if let Some(file) = file {
+                let size = mpv
+                    .get_property_cached::<String>("path")
+                    .and_then(|path| std::fs::metadata(path).ok())
+                    .map_or(0, |meta| meta.len());
+                let duration = mpv.duration().map_or(0., Time::as_secs);
+
+                self.send(&json!({
+                    "Set": { "file": { "name": file, "duration": duration, "size": size } }
+                }));
+                self.send(&json!({ "List": null }));
+            }
+        }
+
+        if let Some(change) = self.changes.check(mpv) {
+            self.client_ignoring += 1;
+            self.send_state(mpv, matches!(change, LocalChange::Seeked));
+        }
+    }
+
+    fn connected(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_nonblocking(true)?;
+        self.socket = Some(BufReader::new(stream));
+        self.changes.reset();
+
+        let config = &config().syncplay;
+        self.send(&json!({
+            "Hello": {
+                "username": config.username,
+                "room": { "name": config.room },
+                "version": VERSION,
+                "realversion": REAL_VERSION,
+                "features": { "chat": true, "featureList": true },
+            }
+        }));
+
+        Ok(())
+    }
+
+    fn disconnect(&mut self) {
+        self.socket = None;
+        self.line_buf.clear();
+        self.write_buf.clear();
+        self.joined = false;
+        self.users.clear();
+        self.announced_file = None;
+        self.client_ignoring = 0;
+        self.server_ignoring = 0;
+        self.latency_calculation = None;
+    }
+
+    fn handle(&mut self, msg: &Value, mpv: &mut Mpv, events: &mut Vec<Event>) {
+        if let Some(hello) = msg.get("Hello") {
+            self.joined = true;
+            if let Some(motd) = hello.get("motd").and_then(Value::as_str)
+                && !motd.trim().is_empty()
+            {
+                eprintln!("[Syncplay] {}", motd.trim());
+            }
+            self.send(&json!({ "List": null }));
+        }
+
+        if let Some(state) = msg.get("State") {
+            self.handle_state(state, mpv, events);
+        }
+
+        if let Some(user) = msg.pointer("/Set/user").and_then(Value::as_object) {
+            for (name, user) in user {
+                let event = user.get("event");
+                for (key, joined) in [("joined", true), ("left", false)] {
+                    if event.and_then(|e| e.get(key)).is_some() {
+                        events
+                            .push(Event::Toast(Toast::SyncplayUser { name: name.clone(), joined }));
+                    }
+                }
+            }
+
+            // simpler than keeping track of every kind of update
+            self.send(&json!({ "List": null }));
+        }
+
+        if let Some(list) = msg.get("List") {
+            self.handle_list(list);
+        }
+
+        if let Some(chat) = msg.get("Chat") {
+            let user = chat.get("username").and_then(Value::as_str);
+            let message = chat.get("message").and_then(Value::as_str);
+
+            if let (Some(user), Some(message)) = (user, message) {
+                events.push(Event::Toast(Toast::SyncplayChat {
+                    user: user.to_string(),
+                    message: message.to_string(),
+                }));
+            }
+        }
+
+        if let Some(error) = msg.get("Error") {
+            let message = error.get("message").and_then(Value::as_str).unwrap_or("");
+            eprintln!("[Syncplay] Server error: {message}");
+            self.disconnect();
+        }
+    }
+
+    fn handle_state(&mut self, state: &Value, mpv: &mut Mpv, events: &mut Vec<Event>) {
+        if let Some(ping) = state.get("ping") {
+            self.latency_calculation = ping.get("latencyCalculation").and_then(Value::as_f64);
+            if let Some(sent) = ping.get("clientLatencyCalculation").and_then(Value::as_f64) {
+                self.rtt = (now() - sent).max(0.);
+            }
+        }
+
+        if let Some(ignoring) = state.get("ignoringOnTheFly") {
+            if let Some(server) = ignoring.get("server").and_then(Value::as_u64) {
+                self.server_ignoring = server;
+                self.client_ignoring = 0;
+            } else if ignoring.get("client").and_then(Value::as_u64) == Some(self.client_ignoring) {
+                self.client_ignoring = 0;
+            }
+        }
+
+        if self.client_ignoring == 0
+            && let Some(playstate) = state.get("playstate")
+        {
+            self.follow(playstate, mpv, events);
+        }
+
+        self.send_state(mpv, false);
+    }
+
+    /// Matches mpv up with the room's playback.
+    fn follow(&mut self, playstate: &Value, mpv: &mut Mpv, events: &mut Vec<Event>) {
+        let (Some(mut position), Some(paused)) = (
+            playstate.get("position").and_then(Value::as_f64),
+            playstate.get("paused").and_then(Value::as_bool),
+        ) else {
+            return;
+        };
+        let do_seek = playstate.get("doSeek").and_then(Value::as_bool) == Some(true);
+        let set_by = playstate
+            .get("setBy")
+            .and_then(Value::as_str)
+            .filter(|&name| name != config().syncplay.username);
+
+        if mpv.file_name().is_none() {
+            return;
+        }
+
+        if !paused {
+            position += self.rtt / 2.;
+        }
+        let position = position as f32;
+
+        let mut changed = false;
+
+        let local_paused = mpv.get_property_cached::<bool>("pause").unwrap_or(true);
+        if paused != local_paused {
+            mpv.set_property("pause", paused).ok();
+            changed = true;
+
+            if let Some(user) = set_by {
+                events.push(Event::Toast(Toast::SyncplayAction {
+                    user: user.to_string(),
+                    action: if paused { "paused" } else { "resumed" }.to_string(),
+                }));
+            }
+        }
+
+        let drift = (mpv.time_pos_fallback().as_secs() - position).abs();
+        if do_seek || drift > MAX_DRIFT {
+            mpv.seek_to(Time::seconds(position)).ok();
+            changed = true;
+
+            if do_seek && let Some(user) = set_by {
+                events.push(Event::Toast(Toast::SyncplayAction {
+                    user: user.to_string(),
+                    action: format!("jumped to {}", Time::seconds(position).mmss()),
+                }));
+            }
+        }
+
+        if changed {
+            self.changes.settle();
+        }
+    }
+
+    fn handle_list(&mut self, list: &Value) {
+        let Some(room) = list.get(&config().syncplay.room).and_then(Value::as_object) else {
+            return;
+        };
+
+        self.users = room
+            .iter()
+            .filter(|&(name, _)| *name != config().syncplay.username)
+            .map(|(name, user)| User {
+                name: name.clone(),
+                file: user
+                    .pointer("/file/name")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                position: Time::seconds(
+                    user.get("position").and_then(Value::as_f64).unwrap_or(0.) as f32
+                ),
+            })
+            .collect();
+        self.users.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Tells the server where we are, acknowledging whatever needs acknowledging.
+    fn send_state(&mut self, mpv: &Mpv, do_seek: bool) {
+        let mut state = json!({
+            "playstate": {
+                "position": mpv.time_pos_fallback().as_secs(),
+                "paused": mpv.get_property_cached::<bool>("pause").unwrap_or(true),
+                "doSeek": do_seek,
+            },
+            "ping": {
+                "latencyCalculation": self.latency_calculation,
+                "clientLatencyCalculation": now(),
+                "clientRtt": self.rtt,
+            },
+        });
+
+        let mut ignoring = Map::new();
+        if self.server_ignoring > 0 {
+            ignoring.insert("server".to_string(), self.server_ignoring.into());
+            self.server_ignoring = 0;
+        }
+        if self.client_ignoring > 0 {
+            ignoring.insert("client".to_string(), self.client_ignoring.into());
+        }
+        if !ignoring.is_empty() {
+            state["ignoringOnTheFly"] = Value::Object(ignoring);
+        }
+
+        self.send(&json!({ "State": state }));
+    }
+
+    fn send(&mut self, msg: &Value) {
+        if self.socket.is_none() {
+            return;
+        }
+
+        self.write_buf.extend(msg.to_string().as_bytes());
+        self.write_buf.extend(b"\r\n");
+        self.flush();
+    }
+
+    /// Writes as much of what's been sent as the socket takes, keeping the rest for next time.
+    fn flush(&mut self) {
+        let Some(socket) = &mut self.socket else {
+            return;
+        };
+
+        while !self.write_buf.is_empty() {
+            match socket.get_mut().write(&self.write_buf) {
+                Ok(0) => {
+                    eprintln!("[Syncplay] Failed to send: server closed the connection");
+                    self.disconnect();
+                    return;
+                }
+                Ok(n) => {
+                    self.write_buf.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    eprintln!("[Syncplay] Failed to send: {e}");
+                    self.disconnect();
+                    return;
+                }
+            }
+        }
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let Some(socket) = &mut self.socket else {
+            return Ok(None);
+        };
+
+        match socket.read_line(&mut self.line_buf) {
+            Ok(0) => Err(io::Error::new(ErrorKind::UnexpectedEof, "server closed the connection")),
+            Ok(_) if self.line_buf.ends_with('\n') => Ok(Some(std::mem::take(&mut self.line_buf))),
+            Ok(_) => Ok(None),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Seconds since the epoch, which is what pings are timed with.
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
@@ -0,0 +1,302 @@
+//! Detecting and safely ejecting removable drives through udisks2 on the system bus.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use zbus::{
+    MatchRule,
+    blocking::{Connection, MessageIterator, Proxy},
+    message,
+    zvariant::{OwnedObjectPath, Value},
+};
+
+use crate::{command::Event, ui::toast::Toast};
+
+const UDISKS: &str = "org.freedesktop.UDisks2";
+
+/// A mounted filesystem on a drive that can be unplugged.
+#[derive(Clone, Debug)]
+pub struct RemovableMount {
+    pub mount_point: PathBuf,
+    /// Filesystem label, or the mount point's name if it has none.
+    pub label: String,
+    block: OwnedObjectPath,
+    drive: OwnedObjectPath,
+}
+
+/// Keeps track of removable drives as they're plugged in and removed.
+pub struct Removable {
+    /// The removable drives that are mounted, from the watcher thread whenever udisks reports a
+    /// change.
+    changed: Receiver<Vec<RemovableMount>>,
+    mounts: Vec<RemovableMount>,
+    /// Whether the drives that were there at startup have come in yet. They don't get a toast.
+    started: bool,
+    /// The last drive that was plugged in, and when.
+    recent: Option<(PathBuf, Instant)>,
+    /// Ejects that finished, with the drive's label and whether it worked.
+    ejected: Receiver<(String, bool)>,
+    ejected_tx: Sender<(String, bool)>,
+}
+
+/// How long after a drive is plugged in the toast's Browse prompt works.
+const BROWSE_PROMPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl Removable {
+    pub fn new() -> Self {
+        let (tx, changed) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            if let Err(e) = watch(tx) {
+                eprintln!("Failed to watch udisks for removable drives: {e}");
+            }
+        });
+
+        let (ejected_tx, ejected) = mpsc::channel();
+
+        Self {
+            changed,
+            mounts: vec![],
+            started: false,
+            recent: None,
+            ejected,
+            ejected_tx,
+        }
+    }
+
+    pub fn update(&mut self, events: &mut Vec<Event>) {
+        for (name, ok) in self.ejected.try_iter() {
+            events.push(Event::Toast(if ok {
+                Toast::SafeToUnplug { name }
+            } else {
+                Toast::EjectFailed { name }
+            }));
+        }
+
+        while let Ok(mounts) = self.changed.try_recv() {
+            // drives that were already there don't get a toast
+            if self.started {
+                for mount in &mounts {
+                    if !self
+                        .mounts
+                        .iter()
+                        .any(|m| m.mount_point == mount.mount_point)
+                    {
+                        events.push(Event::Toast(Toast::DriveConnected {
+                            name: mount.label.clone(),
+                        }));
+                        self.recent = Some((mount.mount_point.clone(), Instant::now()));
+                    }
+                }
+            }
+
+            self.started = true;
+            self.mounts = mounts;
+        }
+
+        if let Some((path, _)) = &self.recent
+            && !self.mounts.iter().any(|m| m.mount_point == *path)
+        {
+            self.recent = None;
+        }
+    }
+
+    /// Removable drives that are currently mounted, listed as extra library roots.
+    pub fn mounts(&self) -> &[RemovableMount] {
+        &self.mounts
+    }
+
+    /// The drive mounted at `path`, if `path` is the root of one.
+    pub fn mount_at(&self, path: &Path) -> Option<&RemovableMount> {
+        self.mounts.iter().find(|m| m.mount_point == path)
+    }
+
+    /// Ejects a drive on a thread of its own, since unmounting waits for writes to be flushed. A
+    /// toast says when it's done.
+    pub fn eject(&self, mount: RemovableMount) {
+        let tx = self.ejected_tx.clone();
+        std::thread::spawn(move || {
+            let result = eject(&mount);
+            if let Err(e) = &result {
+                eprintln!("Failed to eject {}: {e}", mount.mount_point.display());
+            }
+            tx.send((mount.label, result.is_ok())).ok();
+        });
+    }
+
+    /// The drive that was just plugged in, while the toast offering to browse it is showing.
+    pub fn recent(&self) -> Option<&Path> {
+        self.recent
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() < BROWSE_PROMPT_TIMEOUT)
+            .map(|(path, _)| path.as_path())
+    }
+}
+
+impl Default for Removable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sends the removable drives that are mounted whenever udisks reports drives appearing,
+/// disappearing or getting mounted. They're looked up here, so the UI thread doesn't wait on
+/// D-Bus.
+fn watch(tx: Sender<Vec<RemovableMount>>) -> zbus::Result<()> {
+    let conn = Connection::system()?;
+    let added_removed = MatchRule::builder()
+        .msg_type(message::Type::Signal)
+        .sender(UDISKS)?
+        .interface("org.freedesktop.DBus.ObjectManager")?
+        .build();
+    let mounted = MatchRule::builder()
+        .msg_type(message::Type::Signal)
+        .sender(UDISKS)?
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .arg(0, "org.freedesktop.UDisks2.Filesystem")?
+        .build();
+
+    let (ping, pings) = mpsc::channel();
+    for rule in [added_removed, mounted] {
+        let messages = MessageIterator::for_match_rule(rule, &conn, None)?;
+        let ping = ping.clone();
+        std::thread::spawn(move || {
+            for msg in messages {
+                if msg.is_err() || ping.send(()).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(ping);
+
+    // what's there to begin with, now that changes after this are being watched
+    tx.send(removable_mounts()).ok();
+
+    while pings.recv().is_ok() {
+        // plugging in a drive sends a burst of signals, one look covers the ones already in
+        pings.try_iter().count();
+
+        if tx.send(removable_mounts()).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Device and mount point of every mounted block device.
+fn mounts() -> Vec<(String, PathBuf)> {
+    let Ok(mounts) = std::fs::read_to_string("/proc/self/mounts") else {
+        return vec![];
+    };
+
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let device = fields.next()?;
+            let mount_point = unescape_mount_path(fields.next()?);
+            Some((device.to_string(), PathBuf::from(mount_point)))
+        })
+        .filter(|(device, _)| device.starts_with("/dev/"))
+        .collect()
+}
+
+fn removable_mounts() -> Vec<RemovableMount> {
+    mounts()
+        .into_iter()
+        .filter(|(_, mount_point)| mount_point != Path::new("/"))
+        .filter_map(|(device, mount_point)| removable_mount(&device, mount_point))
+        .collect()
+}
+
+fn removable_mount(device: &str, mount_point: PathBuf) -> Option<RemovableMount> {
+    let name = Path::new(device).file_name()?.to_str()?;
+    let block =
+        OwnedObjectPath::try_from(format!("/org/freedesktop/UDisks2/block_devices/{name}")).ok()?;
+
+    let conn = Connection::system()
+        .map_err(|e| eprintln!("Failed to connect to system bus: {e}"))
+        .ok()?;
+
+    let block_proxy =
+        Proxy::new(&conn, UDISKS, block.clone(), "org.freedesktop.UDisks2.Block").ok()?;
+    let drive: OwnedObjectPath = block_proxy.get_property("Drive").ok()?;
+    if drive.as_str() == "/" {
+        return None;
+    }
+
+    let drive_proxy =
+        Proxy::new(&conn, UDISKS, drive.clone(), "org.freedesktop.UDisks2.Drive").ok()?;
+    let removable = drive_proxy
+        .get_property::<bool>("Removable")
+        .unwrap_or(false)
+        || drive_proxy
+            .get_property::<bool>("Ejectable")
+            .unwrap_or(false)
+        || drive_proxy
+            .get_property::<String>("ConnectionBus")
+            .is_ok_and(|bus| bus == "usb");
+    if !removable {
+        return None;
+    }
+
+    let label = block_proxy
+        .get_property::<String>("IdLabel")
+        .ok()
+        .filter(|label| !label.is_empty())
+        .or_else(|| Some(mount_point.file_name()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| name.to_string());
+
+    Some(RemovableMount { mount_point, label, block, drive })
+}
+
+/// Unmounts the filesystem and powers off its drive, after which it's safe to unplug.
+fn eject(mount: &RemovableMount) -> zbus::Result<()> {
+    let conn = Connection::system()?;
+    let options = HashMap::<&str, Value<'_>>::new();
+
+    Proxy::new(&conn, UDISKS, &mount.block, "org.freedesktop.UDisks2.Filesystem")?
+        .call_method("Unmount", &(&options,))?;
+
+    let drive = Proxy::new(&conn, UDISKS, &mount.drive, "org.freedesktop.UDisks2.Drive")?;
+
+    // not every drive can be powered off, but the filesystem is unmounted and safe either way
+    if drive.get_property::<bool>("CanPowerOff").unwrap_or(false) {
+        drive.call_method("PowerOff", &(&options,))?;
+    }
+
+    Ok(())
+}
+
+/// Undoes the octal escaping of spaces and such in `/proc/self/mounts`.
+fn unescape_mount_path(path: &str) -> String {
+    let mut out = Vec::with_capacity(path.len());
+    let mut bytes = path.bytes();
+
+    while let Some(b) = bytes.next() {
+        if b == b'\\' {
+            let digits: Vec<u8> = bytes.by_ref().take(3).collect();
+            match std::str::from_utf8(&digits)
+                .ok()
+                .and_then(|d| u8::from_str_radix(d, 8).ok())
+            {
+                Some(c) => out.push(c),
+                None => {
+                    out.push(b);
+                    out.extend(digits);
+                }
+            }
+        } else {
+            out.push(b);
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,144 @@
+//! Jumping through long lists with L1 and R1, by first letter in sorted lists and by page
+//! otherwise, with an indicator of where the jump landed. The indicator also follows focus while
+//! up or down is held to scroll through a list quickly.
+
+use std::time::{Duration, Instant};
+
+use egui::{Align2, Id, RichText, Vec2};
+use gilrs::Button;
+
+use super::overlay::OverlayWidget;
+use crate::{App, gamepad::Gamepad};
+
+/// How many entries a page jump skips.
+const PAGE: usize = 10;
+/// How long the indicator stays fully visible after a jump.
+const VISIBLE_FOR: Duration = Duration::from_millis(700);
+
+/// Which entry to jump to from the focused one in a list sorted by name: the start of its letter,
+/// or of the previous or next one.
+pub fn by_letter(
+    ctx: &egui::Context,
+    gamepad: &mut Gamepad,
+    letters: &[char],
+    focused: usize,
+) -> Option<usize> {
+    let start_of = |mut idx: usize| {
+        while idx > 0 && letters[idx - 1] == letters[idx] {
+            idx -= 1;
+        }
+        idx
+    };
+
+    let target = if gamepad.take_just_pressed(Button::LeftTrigger) {
+        match start_of(focused) {
+            start if start < focused => Some(start),
+            _ => focused.checked_sub(1).map(start_of),
+        }
+    } else if gamepad.take_just_pressed(Button::RightTrigger) {
+        (focused + 1..letters.len()).find(|&idx| letters[idx] != letters[focused])
+    } else {
+        None
+    }?;
+
+    show_indicator(ctx, letters[target].to_string(), None);
+    Some(target)
+}
+
+/// Which entry to jump to from the focused one in a list that isn't in any particular order.
+pub fn by_page(
+    ctx: &egui::Context,
+    gamepad: &mut Gamepad,
+    len: usize,
+    focused: usize,
+) -> Option<usize> {
+    let target = if gamepad.take_just_pressed(Button::LeftTrigger) {
+        focused.saturating_sub(PAGE)
+    } else if gamepad.take_just_pressed(Button::RightTrigger) {
+        (focused + PAGE).min(len.saturating_sub(1))
+    } else {
+        return None;
+    };
+
+    show_position(ctx, target, len, None);
+    Some(target)
+}
+
+/// Shows where the focused entry is in the list while up or down is repeating, with `detail` under
+/// it. Only for lists long enough to get lost in.
+pub fn while_repeating(
+    ctx: &egui::Context,
+    gamepad: &Gamepad,
+    len: usize,
+    focused: usize,
+    detail: impl FnOnce() -> Option<String>,
+) {
+    if len > PAGE
+        && (gamepad.is_repeating(Button::DPadUp) || gamepad.is_repeating(Button::DPadDown))
+    {
+        show_position(ctx, focused, len, detail());
+    }
+}
+
+/// What a name sorts under: its first letter or digit, or `#` for anything else.
+pub fn letter(name: &str) -> char {
+    match name.chars().find(|c| c.is_alphanumeric()) {
+        Some(c) if c.is_alphabetic() => c.to_uppercase().next().unwrap_or(c),
+        Some(c) => c,
+        None => '#',
+    }
+}
+
+fn indicator_id() -> Id {
+    Id::new("fast scroll indicator")
+}
+
+#[derive(Clone)]
+struct Shown {
+    text: String,
+    /// Smaller, under the text.
+    detail: Option<String>,
+    at: Instant,
+}
+
+fn show_indicator(ctx: &egui::Context, text: String, detail: Option<String>) {
+    let shown = Shown { text, detail, at: Instant::now() };
+    ctx.memory_mut(|mem| mem.data.insert_temp(indicator_id(), shown));
+}
+
+fn show_position(ctx: &egui::Context, idx: usize, len: usize, detail: Option<String>) {
+    show_indicator(ctx, format!("{} / {len}", idx + 1), detail);
+}
+
+/// Where the last jump landed, big in the middle of the screen.
+pub struct Indicator;
+
+impl OverlayWidget for Indicator {
+    fn name(&self) -> &'static str {
+        "fast-scroll"
+    }
+
+    fn visible(&self, ctx: &egui::Context, _app: &App) -> bool {
+        ctx.memory(|mem| mem.data.get_temp::<Shown>(indicator_id()))
+            .is_some_and(|shown| shown.at.elapsed() < VISIBLE_FOR)
+    }
+
+    fn anchor(&self, _ctx: &egui::Context) -> (Align2, Vec2) {
+        (Align2::CENTER_CENTER, Vec2::ZERO)
+    }
+
+    fn inner_margin(&self) -> f32 {
+        16.
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, _app: &mut App) {
+        let Some(shown) = ui.memory(|mem| mem.data.get_temp::<Shown>(indicator_id())) else {
+            return;
+        };
+
+        ui.label(RichText::new(shown.text).size(48.));
+        if let Some(detail) = shown.detail {
+            ui.label(RichText::new(detail).size(14.));
+        }
+    }
+}
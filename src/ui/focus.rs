@@ -0,0 +1,157 @@
+//! Explicit up/down focus order for views whose layout doesn't match the order widgets are
+//! added in, like the home menu, which lays its entries out bottom up under a row of cards.
+//!
+//! A view declares its groups top to bottom with [`FocusOrder`] before drawing, and adds its
+//! widgets to them with [`add`]. Moving up or down then goes through a group in order, and
+//! crossing into the next group lands on whatever was focused there last. Views that don't
+//! declare anything keep egui's own geometric focus movement.
+
+use std::collections::HashMap;
+
+use egui::{FocusDirection, Id, Response};
+
+/// How a group's widgets are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    #[expect(dead_code)]
+    TopDown,
+    /// Added bottom first, so the order is reversed.
+    BottomUp,
+    /// Side by side. Left and right stay up to egui, up and down leave the group.
+    Row,
+}
+
+#[derive(Debug, Clone)]
+struct Group {
+    name: &'static str,
+    flow: Flow,
+    ids: Vec<Id>,
+}
+
+/// The groups a view draws this frame, top to bottom.
+#[derive(Debug, Clone, Default)]
+pub struct FocusOrder {
+    groups: Vec<Group>,
+    /// Pass the order was declared in, so one left behind by a previous view doesn't apply.
+    pass: u64,
+    /// Last focused widget of each group, carried over from frame to frame.
+    remembered: HashMap<&'static str, Id>,
+}
+
+impl FocusOrder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a group below the ones added so far.
+    pub fn group(mut self, name: &'static str, flow: Flow) -> Self {
+        self.groups.push(Group { name, flow, ids: Vec::new() });
+        self
+    }
+
+    /// Replaces last frame's order, call before drawing the widgets.
+    pub fn begin(mut self, ctx: &egui::Context) {
+        self.pass = ctx.cumulative_pass_nr();
+        ctx.data_mut(|d| {
+            self.remembered = d
+                .get_temp::<FocusOrder>(id())
+                .map(|old| old.remembered)
+                .unwrap_or_default();
+            d.insert_temp(id(), self);
+        });
+    }
+}
+
+fn id() -> Id {
+    Id::new("focus order")
+}
+
+/// Puts a widget next in its group. Disabled widgets are skipped over.
+pub fn add(response: &Response, group: &'static str) {
+    if !response.enabled() {
+        return;
+    }
+
+    response.ctx.data_mut(|d| {
+        let order = d.get_temp_mut_or_default::<FocusOrder>(id());
+        let Some(g) = order.groups.iter_mut().find(|g| g.name == group) else {
+            return;
+        };
+
+        g.ids.push(response.id);
+        if response.has_focus() {
+            order.remembered.insert(group, response.id);
+        }
+    });
+}
+
+/// Moves focus up or down through the declared order. Returns `false` if there's no order for
+/// the focused widget, to leave it to egui.
+pub fn move_focus(ctx: &egui::Context, direction: FocusDirection) -> bool {
+    let down = match direction {
+        FocusDirection::Down => true,
+        FocusDirection::Up => false,
+        _ => return false,
+    };
+
+    let Some(focused) = ctx.memory(|m| m.focused()) else {
+        return false;
+    };
+    let Some(order) = ctx.data(|d| d.get_temp::<FocusOrder>(id())) else {
+        return false;
+    };
+    if order.pass + 1 < ctx.cumulative_pass_nr() {
+        return false;
+    }
+
+    let groups = order
+        .groups
+        .iter()
+        .map(|g| {
+            let mut ids = g.ids.clone();
+            if g.flow == Flow::BottomUp {
+                ids.reverse();
+            }
+            (g, ids)
+        })
+        .filter(|(_, ids)| !ids.is_empty())
+        .collect::<Vec<_>>();
+
+    let Some((group_idx, idx)) = groups
+        .iter()
+        .enumerate()
+        .find_map(|(gi, (_, ids))| Some((gi, ids.iter().position(|&i| i == focused)?)))
+    else {
+        return false;
+    };
+
+    let (group, ids) = &groups[group_idx];
+    let within = match (group.flow, down) {
+        (Flow::Row, _) => None,
+        (_, true) => ids.get(idx + 1),
+        (_, false) => idx.checked_sub(1).and_then(|i| ids.get(i)),
+    };
+
+    let target = within.copied().or_else(|| {
+        let next = if down {
+            groups.get(group_idx + 1)
+        } else {
+            groups.get(group_idx.checked_sub(1)?)
+        };
+        let (group, ids) = next?;
+
+        order
+            .remembered
+            .get(group.name)
+            .filter(|id| ids.contains(id))
+            .or(if down { ids.first() } else { ids.last() })
+            .copied()
+    });
+
+    // at the very top or bottom, focus stays put
+    if let Some(target) = target {
+        ctx.memory_mut(|m| m.request_focus(target));
+    }
+
+    true
+}
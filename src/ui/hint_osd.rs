@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use egui::{Align2, Vec2, vec2};
+use gilrs::Button;
+
+use super::overlay::OverlayWidget;
+use crate::{App, gamepad::button_prompt, store::hints::Hint};
+
+/// How long a hint stays up after its view opens.
+const VISIBLE_FOR: Duration = Duration::from_secs(6);
+
+/// Hint chip at the top of the screen, for the first few times a view opens.
+pub struct HintOsd;
+
+impl OverlayWidget for HintOsd {
+    fn name(&self) -> &'static str {
+        "hint"
+    }
+
+    fn visible(&self, _ctx: &egui::Context, app: &App) -> bool {
+        app.hint
+            .is_some_and(|(_, shown_at)| shown_at.elapsed() < VISIBLE_FOR)
+    }
+
+    fn anchor(&self, _ctx: &egui::Context) -> (Align2, Vec2) {
+        (Align2::CENTER_TOP, vec2(0., 32.))
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let Some((hint, _)) = app.hint else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            for (idx, &(button, label)) in prompts(hint).iter().enumerate() {
+                if idx > 0 {
+                    ui.add_space(12.);
+                }
+                ui.add(button_prompt(button, label));
+            }
+        });
+    }
+}
+
+fn prompts(hint: Hint) -> &'static [(Button, &'static str)] {
+    match hint {
+        Hint::SeekBar => &[
+            (Button::East, "Seek"),
+            (Button::North, "Play/pause"),
+            (Button::Start, "Media menu"),
+            (Button::Mode, "Home menu"),
+        ],
+        Hint::Seeking => &[
+            (Button::DPadLeft, "Step back"),
+            (Button::DPadRight, "Step forward"),
+            (Button::DPadUp, "Bigger steps"),
+            (Button::West, "Type a time"),
+            (Button::East, "Done"),
+        ],
+        Hint::HomeMenu | Hint::MediaMenu => {
+            &[(Button::East, "Open"), (Button::South, "Close"), (Button::North, "Play/pause")]
+        }
+    }
+}
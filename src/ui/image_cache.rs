@@ -0,0 +1,83 @@
+//! Keeps the images egui has loaded under a byte budget. The least recently shown ones are
+//! forgotten first, and whatever a view showed is forgotten once it's closed and the next view
+//! doesn't show it too.
+
+use std::collections::HashMap;
+
+use egui::{Id, Image};
+
+use crate::config::config;
+
+#[derive(Clone, Default)]
+struct Usage {
+    /// Pass each image URI was last shown in.
+    last_shown: HashMap<String, u64>,
+}
+
+fn id() -> Id {
+    Id::new("image cache usage")
+}
+
+/// An image that counts as shown for the budget. Use it instead of [`Image::new`] for anything
+/// that loads from a URI.
+pub fn image<'a>(ctx: &egui::Context, uri: String) -> Image<'a> {
+    let pass = ctx.cumulative_pass_nr();
+    ctx.data_mut(|data| {
+        data.get_temp_mut_or_default::<Usage>(id())
+            .last_shown
+            .insert(uri.clone(), pass)
+    });
+
+    Image::new(uri)
+}
+
+/// Bytes held by egui's loaders, both encoded and decoded.
+pub fn bytes(ctx: &egui::Context) -> usize {
+    let loaders = ctx.loaders();
+
+    let bytes: usize = loaders.bytes.lock().iter().map(|l| l.byte_size()).sum();
+    let images: usize = loaders.image.lock().iter().map(|l| l.byte_size()).sum();
+    let textures: usize = loaders.texture.lock().iter().map(|l| l.byte_size()).sum();
+
+    bytes + images + textures
+}
+
+pub fn budget() -> usize {
+    config().image_cache_mb * 1024 * 1024
+}
+
+/// Forgets images after everything's been drawn. With `view_changed`, that's everything the new
+/// view didn't show this frame.
+pub fn evict(ctx: &egui::Context, view_changed: bool) {
+    let pass = ctx.cumulative_pass_nr();
+    let mut usage = ctx
+        .data(|data| data.get_temp::<Usage>(id()))
+        .unwrap_or_default();
+
+    if view_changed {
+        usage.last_shown.retain(|uri, &mut shown| {
+            let keep = shown >= pass;
+            if !keep {
+                ctx.forget_image(uri);
+            }
+            keep
+        });
+    }
+
+    while bytes(ctx) > budget() {
+        let Some(oldest) = usage
+            .last_shown
+            .iter()
+            .filter(|&(_, &shown)| shown < pass)
+            .min_by_key(|&(_, &shown)| shown)
+            .map(|(uri, _)| uri.clone())
+        else {
+            break;
+        };
+
+        ctx.forget_image(&oldest);
+        usage.last_shown.remove(&oldest);
+    }
+
+    ctx.data_mut(|data| data.insert_temp(id(), usage));
+}
@@ -0,0 +1,92 @@
+//! On-screen keyboard for typing with a gamepad, a grid of keys moved between with the d-pad.
+
+use egui::{Button, Frame, RichText, vec2};
+
+use crate::utils::ResponseExt as _;
+
+const ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl'", "zxcvbnm,.-"];
+const KEY_SIZE: f32 = 24.;
+
+pub struct OnScreenKeyboard<'a> {
+    text: &'a mut String,
+    focus: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum KeyboardResponse {
+    Typing,
+    /// The Done key was pressed.
+    Done,
+}
+
+impl<'a> OnScreenKeyboard<'a> {
+    pub fn new(text: &'a mut String) -> Self {
+        Self { text, focus: false }
+    }
+
+    /// Moves focus to the first key, for when the keyboard was just opened.
+    pub fn focus(self, focus: bool) -> Self {
+        Self { focus, ..self }
+    }
+
+    /// Needs left and right to move focus, so views showing it should catch those.
+    pub fn show(self, ui: &mut egui::Ui) -> KeyboardResponse {
+        let mut response = KeyboardResponse::Typing;
+
+        Frame::new()
+            .fill(ui.visuals().extreme_bg_color)
+            .corner_radius(4.)
+            .inner_margin(4.)
+            .show(ui, |ui| {
+                ui.label(format!("{}▏", self.text));
+            });
+
+        ui.add_space(4.);
+
+        for (row_idx, row) in ROWS.iter().enumerate() {
+            ui.horizontal(|ui| {
+                for (key_idx, key) in row.chars().enumerate() {
+                    let button = ui.add_sized(
+                        vec2(KEY_SIZE, KEY_SIZE),
+                        Button::new(RichText::new(key.to_string()).monospace()),
+                    );
+
+                    if self.focus && row_idx == 0 && key_idx == 0 {
+                        button.request_focus();
+                    }
+
+                    if button.activated() {
+                        self.text.push(key);
+                    }
+                }
+            });
+        }
+
+        ui.horizontal(|ui| {
+            let width = KEY_SIZE * 3.;
+
+            if ui
+                .add_sized(vec2(width * 2., KEY_SIZE), Button::new("Space"))
+                .activated()
+            {
+                self.text.push(' ');
+            }
+
+            if ui
+                .add_sized(vec2(width, KEY_SIZE), Button::new("⌫"))
+                .activated()
+            {
+                self.text.pop();
+            }
+
+            if ui
+                .add_sized(vec2(width, KEY_SIZE), Button::new("Done"))
+                .activated()
+            {
+                response = KeyboardResponse::Done;
+            }
+        });
+
+        response
+    }
+}
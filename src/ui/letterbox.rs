@@ -0,0 +1,39 @@
+//! Room under the picture when the video is wider than the screen, which the seek bar and button
+//! prompts can sit in instead of covering the picture.
+
+use egui::{Frame, TopBottomPanel};
+
+use crate::mpv::Mpv;
+
+/// Height of the black bar under the picture, going by the video's aspect ratio.
+fn bottom_bar(ctx: &egui::Context, mpv: &Mpv) -> f32 {
+    let Some(aspect) = mpv.video_params().and_then(|p| p.aspect) else {
+        return 0.;
+    };
+
+    let screen = ctx.screen_rect();
+    let picture_height = screen.width() / aspect;
+    ((screen.height() - picture_height) / 2.).max(0.)
+}
+
+/// Pushes the panels along the bottom up into the middle of the bar under the picture, if they
+/// fit in it. `panels_height` is how tall they were last frame. Returns how much they were pushed
+/// up by.
+pub fn draw_margin(ctx: &egui::Context, mpv: &Mpv, panels_height: f32) -> f32 {
+    let bar = bottom_bar(ctx, mpv);
+
+    // full-frame video, nothing at the bottom, or not enough room to keep it off the picture
+    if panels_height <= 0. || panels_height > bar {
+        return 0.;
+    }
+
+    let margin = (bar - panels_height) / 2.;
+    TopBottomPanel::bottom("letterbox margin")
+        .show_separator_line(false)
+        .resizable(false)
+        .frame(Frame::NONE)
+        .exact_height(margin)
+        .show(ctx, |_ui| {});
+
+    margin
+}
@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use egui::{Align2, RichText, Vec2};
+
+use super::overlay::OverlayWidget;
+use crate::App;
+
+/// How long the icon stays fully visible after an ignored button press.
+const VISIBLE_FOR: Duration = Duration::from_millis(700);
+
+/// Lock icon that flashes when a button is pressed while the controls are locked, so it's clear
+/// why nothing happens.
+pub struct LockOsd;
+
+impl OverlayWidget for LockOsd {
+    fn name(&self) -> &'static str {
+        "lock"
+    }
+
+    fn visible(&self, _ctx: &egui::Context, app: &App) -> bool {
+        app.gamepad
+            .blocked_at()
+            .is_some_and(|blocked_at| blocked_at.elapsed() < VISIBLE_FOR)
+    }
+
+    fn anchor(&self, _ctx: &egui::Context) -> (Align2, Vec2) {
+        (Align2::CENTER_CENTER, Vec2::ZERO)
+    }
+
+    fn inner_margin(&self) -> f32 {
+        16.
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, _app: &mut App) {
+        ui.label(RichText::new("🔒").size(48.));
+    }
+}
@@ -3,16 +3,37 @@ use core::{any::Any, time::Duration};
 use gilrs::Button;
 
 use self::views::hidden::HiddenView;
-use crate::{App, BLUE, command::Actions, gamepad::button_prompt, utils::horizontal_left_right};
-
+use crate::{
+    App, BLUE, command::Actions, gamepad::button_prompt, store::hints::Hint,
+    utils::horizontal_left_right,
+};
+
+pub mod fast_scroll;
+pub mod focus;
+pub mod hint_osd;
+pub mod image_cache;
+pub mod keyboard;
+pub mod letterbox;
+pub mod lock_osd;
+pub mod overlay;
+pub mod skip_osd;
+pub mod theme;
 pub mod toast;
+pub mod up_next_osd;
+pub mod voice_osd;
+pub mod volume_osd;
 pub mod views {
+    pub mod confirm;
     pub mod hidden;
     pub mod home_menu;
+    pub mod idle;
     pub mod media_menu;
     pub mod miniseek;
+    pub mod resume;
     pub mod seekbar;
     pub mod seeking;
+    pub mod stats;
+    pub mod time_entry;
 }
 
 pub trait View: Any {
@@ -26,6 +47,16 @@ pub trait View: Any {
     fn hide_on_inactive(&self) -> Option<Duration> {
         None
     }
+
+    /// Tip on what the buttons do, for the first few times the view opens.
+    fn hint(&self) -> Option<Hint> {
+        None
+    }
+
+    /// Whether the view holds changes that would be lost by navigating away from it.
+    fn is_dirty(&self) -> bool {
+        false
+    }
 }
 
 impl dyn View {
@@ -47,6 +78,7 @@ impl View for ViewTaken {
     fn button_actions(&self) -> Actions { unreachable!() }
     fn show_prompts(&self) -> bool { unreachable!() }
     fn hide_on_inactive(&self) -> Option<Duration> { unreachable!() }
+    fn is_dirty(&self) -> bool { false }
 }
 
 pub fn button_prompts(ctx: &egui::Context, app: &App, actions: &Actions) {
@@ -57,7 +89,9 @@ pub fn button_prompts(ctx: &egui::Context, app: &App, actions: &Actions) {
 
             let (left, right) = actions
                 .iter()
-                .filter(|(_button, cmd)| cmd.show_prompt())
+                .filter(|(_button, cmd)| {
+                    cmd.show_prompt(app) && !(app.guest_mode && cmd.is_restricted())
+                })
                 .partition::<Vec<_>, _>(|(button, _action)| {
                     button_prompt_position(button) == PromptPosition::Left
                 });
@@ -0,0 +1,90 @@
+//! Widgets drawn over whatever view is active, like the volume bar and the lock icon. Each one
+//! says when it should be showing and what's in it, and this takes care of putting it in a box,
+//! fading it in and out and stacking it in the right order.
+
+use egui::{Align2, Area, Color32, Frame, Id, Order, Vec2};
+
+use crate::{
+    App,
+    config::config,
+    ui::{fast_scroll, hint_osd, lock_osd, skip_osd, up_next_osd, voice_osd, volume_osd},
+};
+
+/// Listed bottom to top, unless the config says otherwise.
+fn widgets() -> [Box<dyn OverlayWidget>; 7] {
+    [
+        Box::new(hint_osd::HintOsd),
+        Box::new(up_next_osd::UpNextOsd),
+        Box::new(skip_osd::SkipOsd),
+        Box::new(voice_osd::VoiceOsd),
+        Box::new(volume_osd::VolumeBar),
+        Box::new(fast_scroll::Indicator),
+        Box::new(lock_osd::LockOsd),
+    ]
+}
+
+pub trait OverlayWidget {
+    /// What it's called in the `overlays` config.
+    fn name(&self) -> &'static str;
+
+    /// Whether it should be showing, which it fades in and out of as this changes. It's still
+    /// drawn while fading out, so whatever it shows has to stick around for that long.
+    fn visible(&self, ctx: &egui::Context, app: &App) -> bool;
+
+    fn anchor(&self, ctx: &egui::Context) -> (Align2, Vec2);
+
+    fn inner_margin(&self) -> f32 {
+        8.
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App);
+}
+
+/// Draws every enabled widget that's showing or fading out, after the view.
+pub fn draw(ctx: &egui::Context, app: &mut App) {
+    let mut widgets = widgets()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, widget)| config().overlay(widget.name()).enabled)
+        .map(|(idx, widget)| {
+            let z = config().overlay(widget.name()).z.unwrap_or(idx as i32);
+            (z, widget)
+        })
+        .collect::<Vec<_>>();
+    widgets.sort_by_key(|&(z, _)| z);
+
+    for (_, widget) in widgets {
+        let widget_config = config().overlay(widget.name());
+        // everything steps aside while another program's OSD is up
+        let visible = !app.external_osd.is_showing() && widget.visible(ctx, app);
+
+        let id = Id::new(("overlay", widget.name()));
+        let fade = if visible {
+            widget_config.fade_in
+        } else {
+            widget_config.fade_out
+        };
+        let opacity = ctx.animate_bool_with_time(id.with("fade"), visible, fade);
+        if opacity == 0. {
+            continue;
+        }
+
+        let (align, offset) = widget.anchor(ctx);
+        let area = Area::new(id)
+            .anchor(align, offset)
+            .order(Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                ui.set_opacity(opacity);
+
+                Frame::new()
+                    .fill(Color32::from_black_alpha(192))
+                    .corner_radius(8.)
+                    .inner_margin(widget.inner_margin())
+                    .show(ui, |ui| widget.draw(ui, app));
+            });
+
+        // widgets drawn later go on top, whichever showed up first
+        ctx.move_to_top(area.response.layer_id);
+    }
+}
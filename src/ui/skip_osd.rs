@@ -0,0 +1,56 @@
+use egui::{Align2, Color32, Id, RichText, Vec2, vec2};
+use gilrs::Button;
+
+use super::overlay::OverlayWidget;
+use crate::{App, gamepad::button_prompt};
+
+/// Countdown to an automatic SponsorBlock skip, so it can be called off before it happens.
+pub struct SkipOsd;
+
+impl SkipOsd {
+    /// The last countdown shown, kept around so there's something to fade out once the segment
+    /// has been skipped or kept.
+    fn id() -> Id {
+        Id::new("skip osd")
+    }
+}
+
+impl OverlayWidget for SkipOsd {
+    fn name(&self) -> &'static str {
+        "skip"
+    }
+
+    fn visible(&self, ctx: &egui::Context, app: &App) -> bool {
+        let Some((segment, left)) = app.mpv.upcoming_skip() else {
+            return false;
+        };
+
+        let text = format!("{} skip in {}s", segment.category.label(), left.as_secs_f32().ceil());
+        let color = segment.category.color();
+        ctx.memory_mut(|mem| mem.data.insert_temp(Self::id(), (text, color)));
+
+        true
+    }
+
+    fn anchor(&self, _ctx: &egui::Context) -> (Align2, Vec2) {
+        (Align2::RIGHT_BOTTOM, vec2(-32., -64.))
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let Some((text, color)) =
+            ui.memory(|mem| mem.data.get_temp::<(String, Color32)>(Self::id()))
+        else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(text).color(color));
+
+            // menus keep B for going back
+            if app.passive {
+                ui.add_space(8.);
+                ui.add(button_prompt(Button::South, "Hold to keep"));
+            }
+        });
+    }
+}
@@ -0,0 +1,47 @@
+use egui::{Color32, Stroke, Style, Visuals, style::Selection};
+
+use crate::store::settings::Settings;
+
+/// How much larger text gets in the TV distance preset.
+const TV_DISTANCE_TEXT_SCALE: f32 = 1.25;
+/// Outline width of the focused widget in the TV distance preset.
+const TV_DISTANCE_FOCUS_STROKE: f32 = 3.;
+/// mpv's `sub-scale` in the TV distance preset.
+pub const TV_DISTANCE_SUB_SCALE: f32 = 1.3;
+
+pub fn apply(ctx: &egui::Context, settings: &Settings) {
+    let mut style = Style {
+        visuals: Visuals {
+            dark_mode: true,
+            override_text_color: Some(Color32::WHITE),
+            selection: Selection {
+                bg_fill: Color32::WHITE,
+                stroke: Stroke::new(1.0, Color32::RED),
+            },
+            extreme_bg_color: Color32::from_black_alpha(128),
+            panel_fill: Color32::from_black_alpha(192),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    if settings.tv_distance {
+        for font_id in style.text_styles.values_mut() {
+            font_id.size *= TV_DISTANCE_TEXT_SCALE;
+        }
+
+        style.visuals.widgets.active.bg_stroke.width = TV_DISTANCE_FOCUS_STROKE;
+    }
+
+    ctx.set_style(style);
+}
+
+pub fn sub_scale(settings: &Settings) -> f32 {
+    let scale = if settings.tv_distance {
+        TV_DISTANCE_SUB_SCALE
+    } else {
+        1.
+    };
+
+    scale * settings.subtitles.scale
+}
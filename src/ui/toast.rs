@@ -1,13 +1,50 @@
-use core::sync::atomic::{AtomicU32, Ordering};
-use std::time::Instant;
+use core::{
+    cmp::Reverse,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use egui::{Align, Align2, Area, Color32, Frame, Id, Layout, RichText, vec2};
+use chrono::{DateTime, Local};
+use egui::{
+    Align, Align2, Area, Color32, Frame, Id, Layout, ProgressBar, RichText, Vec2, Widget as _, vec2,
+};
+use gilrs::Button;
+
+use crate::{
+    cec::TvEvent,
+    command::Command,
+    config::{ToastPosition, config},
+    gamepad::{button_label, button_prompt},
+    mpv::sponsorblock::Category,
+};
+
+/// How many toasts can be up at once before the least important ones make room.
+const MAX_SHOWN: usize = 4;
+
+/// How long sliding out takes, at the end of a toast's duration.
+const SLIDE_OUT: f32 = 1.;
+
+/// How many toasts that have gone are kept for reviewing.
+const HISTORY_LEN: usize = 50;
+
+/// How long a progress toast stays up once its operation is done.
+const PROGRESS_LINGER: Duration = Duration::from_secs(3);
 
 #[derive(Debug)]
 pub struct SpawnedToast {
     id: Id,
     timestamp: Instant,
+    /// When it first came up, for the history, since `timestamp` moves on a restart.
+    shown_at: DateTime<Local>,
+    /// How long it stays up, which is cut short when it's dismissed.
+    duration: Duration,
     toast: Toast,
+    /// Size as of the last frame, so it knows how far to slide to get off screen.
+    size: Vec2,
 }
 
 impl SpawnedToast {
@@ -16,59 +53,345 @@ impl SpawnedToast {
         Self {
             id: Id::new("toast").with(COUNTER.fetch_add(1, Ordering::Relaxed)),
             timestamp: Instant::now(),
+            shown_at: Local::now(),
+            duration: toast.duration(),
             toast,
+            size: Vec2::ZERO,
         }
     }
+
+    /// A toast with a progress bar, which stays up for as long as the handle is kept.
+    pub fn new_progress(title: impl Into<String>) -> (Self, ProgressHandle) {
+        let progress = Arc::new(Mutex::new(Progress::default()));
+        let toast = Toast::Progress {
+            title: title.into(),
+            progress: progress.clone(),
+        };
+        (Self::new(toast), ProgressHandle(progress))
+    }
+
+    /// Shows the toast for its full duration again, from now.
+    pub fn restart(&mut self) {
+        self.timestamp = Instant::now();
+        self.duration = self.toast.duration();
+    }
+
+    /// What its button does, unless it's already on its way out.
+    pub fn action(&self) -> Option<ToastAction> {
+        self.toast.action().filter(|_| !self.is_leaving())
+    }
+
+    /// Slides it out right away.
+    pub fn dismiss(&mut self) {
+        let leave_at = self.timestamp.elapsed() + Duration::from_secs_f32(SLIDE_OUT);
+        self.duration = self.duration.min(leave_at);
+    }
+
+    fn is_leaving(&self) -> bool {
+        self.timestamp.elapsed().as_secs_f32() > self.duration.as_secs_f32() - SLIDE_OUT
+    }
+}
+
+/// Toasts that have gone, newest first, for catching up on ones missed from across the room.
+#[derive(Default)]
+pub struct ToastHistory {
+    toasts: VecDeque<(DateTime<Local>, Toast)>,
+}
+
+impl ToastHistory {
+    fn push(&mut self, toast: SpawnedToast) {
+        self.toasts.push_front((toast.shown_at, toast.toast));
+        self.toasts.truncate(HISTORY_LEN);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (DateTime<Local>, &Toast)> {
+        self.toasts.iter().map(|(at, toast)| (*at, toast))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.toasts.clear();
+    }
 }
 
-pub fn draw(toasts: &mut Vec<SpawnedToast>, ctx: &egui::Context) {
-    let margin = 6.;
+pub fn draw(toasts: &mut Vec<SpawnedToast>, history: &mut ToastHistory, ctx: &egui::Context) {
+    for toast in toasts.iter_mut() {
+        if toast.toast.is_ongoing() {
+            toast.duration = toast
+                .duration
+                .max(toast.timestamp.elapsed() + PROGRESS_LINGER);
+        }
+    }
+
+    let position = config().toasts.position;
+    let screen = ctx.screen_rect();
+    let available = ctx.available_rect();
+
+    // screen_rect is in points, so these already account for the zoom factor
+    let margin = (screen.height() * 0.01).round();
+    let max_width = screen.width() * 0.3;
+
+    // stay clear of side panels on the same side as the toasts
+    let side_margin = if position.is_left() {
+        available.left() - screen.left()
+    } else {
+        screen.right() - available.right()
+    };
+
+    let anchor = match position {
+        ToastPosition::TopRight => Align2::RIGHT_TOP,
+        ToastPosition::TopLeft => Align2::LEFT_TOP,
+        ToastPosition::BottomLeft => Align2::LEFT_BOTTOM,
+        ToastPosition::BottomRight => Align2::RIGHT_BOTTOM,
+    };
+
+    // the most important ones go nearest the edge, and otherwise they stay in the order they came
+    toasts.sort_by_key(|toast| Reverse(toast.toast.priority()));
+
+    // when too many are up, the oldest of the least important ones go first
+    let mut staying = toasts.iter().filter(|toast| !toast.is_leaving()).count();
+    while staying > MAX_SHOWN
+        && let Some(toast) = toasts
+            .iter_mut()
+            .filter(|toast| !toast.is_leaving() && !toast.toast.is_ongoing())
+            .min_by_key(|toast| (toast.toast.priority(), toast.timestamp))
+    {
+        toast.dismiss();
+        staying -= 1;
+    }
+
     let mut cursor = margin;
 
-    toasts.retain_mut(|toast| {
-        let slide_in = 1. - (toast.timestamp.elapsed().as_secs_f32() * 2.).clamp(0., 1.);
-        let slide_out = 1. - (toast.timestamp.elapsed().as_secs_f32() - 4.).clamp(0., 1.);
+    for toast in toasts.iter_mut() {
+        let elapsed = toast.timestamp.elapsed().as_secs_f32();
+        let slide_in = 1. - (elapsed * 2.).clamp(0., 1.);
+        let slide_out =
+            1. - ((elapsed - (toast.duration.as_secs_f32() - SLIDE_OUT)) / SLIDE_OUT).clamp(0., 1.);
 
         // easing
         let slide_in = slide_in * slide_in * slide_in;
         let slide_out = slide_out * slide_out * slide_out;
 
-        let right_margin = ctx.screen_rect().right() - ctx.available_rect().right();
+        // slide in from beyond the edge of the screen, and out past the stack's edge
+        let x = margin + side_margin - slide_in * (toast.size.x + margin + side_margin);
+        let y = cursor - (1. - slide_out) * (toast.size.y + margin);
 
-        let height = Area::new(toast.id)
-            .anchor(
-                Align2::RIGHT_TOP,
-                vec2(-margin + slide_in * 200. - right_margin, cursor - (1. - slide_out) * 100.),
-            )
+        let offset = vec2(
+            if position.is_left() { x } else { -x },
+            if position.is_bottom() { -y } else { y },
+        );
+
+        let align = if position.is_left() {
+            Align::Min
+        } else {
+            Align::Max
+        };
+
+        toast.size = Area::new(toast.id)
+            .anchor(anchor, offset)
             .constrain(false)
             .show(ctx, |ui| {
-                ui.with_layout(Layout::top_down(Align::Max), |ui| {
+                ui.with_layout(Layout::top_down(align), |ui| {
                     Frame::new()
                         .fill(Color32::from_black_alpha(192))
                         .corner_radius(8.)
-                        .inner_margin(6.)
+                        .inner_margin(margin)
                         .show(ui, |ui| {
+                            ui.set_max_width(max_width);
                             toast.toast.ui(ui);
+
+                            if let Some(action) = toast.toast.action() {
+                                ui.add(button_prompt(action.button, action.label));
+                            }
                         });
                 });
             })
             .response
             .rect
-            .height();
+            .size();
+
+        cursor += (margin + toast.size.y) * slide_out;
+    }
+
+    for toast in toasts.extract_if(.., |toast| toast.timestamp.elapsed() >= toast.duration) {
+        history.push(toast);
+    }
+}
 
-        cursor += (margin + height) * slide_out;
+/// How much a toast matters, which decides where it's stacked and which ones make room when too
+/// many are up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Feedback on something that was just done, which is visible anyway.
+    Low,
+    Normal,
+    /// Something went wrong, or needs doing.
+    High,
+}
 
-        toast.timestamp.elapsed().as_secs() < 5
-    });
+/// How far along an operation behind a progress toast is.
+#[derive(Debug, Default)]
+pub struct Progress {
+    /// From 0 to 1, or `None` when there's no telling.
+    fraction: Option<f32>,
+    detail: Option<String>,
+}
+
+/// Updates a progress toast in place, from any thread. The operation counts as done once this is
+/// dropped, and the toast goes away shortly after.
+#[derive(Debug)]
+pub struct ProgressHandle(Arc<Mutex<Progress>>);
+
+impl ProgressHandle {
+    pub fn set_fraction(&self, fraction: f32) {
+        if let Ok(mut progress) = self.0.lock() {
+            progress.fraction = Some(fraction.clamp(0., 1.));
+        }
+    }
+
+    /// A line under the bar, like how many of how many are done.
+    pub fn set_detail(&self, detail: impl Into<String>) {
+        if let Ok(mut progress) = self.0.lock() {
+            progress.detail = Some(detail.into());
+        }
+    }
+}
+
+/// A button that does something about a toast while it's up, whatever the button does otherwise.
+#[derive(Debug, Clone, Copy)]
+pub struct ToastAction {
+    pub button: Button,
+    pub label: &'static str,
+    pub command: Command,
 }
 
 #[derive(Debug)]
 pub enum Toast {
-    GamepadConnected { name: String },
-    GamepadLowBattery { name: String, level: u8 },
-    GamepadDisconnected { name: String },
+    GamepadConnected {
+        name: String,
+    },
+    GamepadLowBattery {
+        name: String,
+        level: u8,
+    },
+    GamepadDisconnected {
+        name: String,
+    },
     LastGamepadDisconnected,
-    DlnaDeviceDiscovered { name: String },
+    DlnaDeviceDiscovered {
+        name: String,
+        /// Whether A makes it the volume target.
+        offer_volume: bool,
+    },
+    DlnaDeviceLost {
+        name: String,
+    },
+    GuestMode {
+        enabled: bool,
+    },
+    WrongPin,
+    DisabledInGuestMode,
+    HdrToneMapped,
+    /// Text sent through the control socket.
+    Message {
+        text: String,
+    },
+    MpvDisconnected,
+    MpvNotResponding,
+    MpvReconnected,
+    SegmentSkipped {
+        category: Category,
+    },
+    DriveConnected {
+        name: String,
+    },
+    SafeToUnplug {
+        name: String,
+    },
+    EjectFailed {
+        name: String,
+    },
+    Queued {
+        name: String,
+    },
+    WatchPartyMember {
+        name: String,
+        joined: bool,
+    },
+    SyncplayUser {
+        name: String,
+        joined: bool,
+    },
+    /// Someone else in the Syncplay room paused, resumed or seeked.
+    SyncplayAction {
+        user: String,
+        action: String,
+    },
+    SyncplayChat {
+        user: String,
+        message: String,
+    },
+    ControlsLocked {
+        locked: bool,
+    },
+    NothingToSkip,
+    AudioOnly {
+        enabled: bool,
+    },
+    PausedForTv {
+        event: TvEvent,
+    },
+    /// Nothing was left to shuffle.
+    AllWatched,
+    /// Something failed to start, see the Diagnostics menu.
+    StartupProblems,
+    /// The chapter that was just jumped to.
+    Chapter {
+        number: usize,
+        count: usize,
+        title: Option<String>,
+    },
+    /// Subtitle size after nudging it, relative to the default.
+    SubtitleSize {
+        percent: u32,
+    },
+    BackedUp {
+        name: String,
+    },
+    BackupFailed {
+        reason: String,
+    },
+    Restored,
+    RestoreFailed {
+        reason: String,
+    },
+    FramesDropping,
+    CastFailed {
+        reason: String,
+    },
+    PlayingOn {
+        device: String,
+    },
+    /// Something a phone or other device cast to us.
+    CastReceived {
+        title: String,
+    },
+    PlayOnFailed {
+        reason: String,
+    },
+    /// Something taking a while, like looking up metadata, see [`SpawnedToast::new_progress`].
+    Progress {
+        title: String,
+        progress: Arc<Mutex<Progress>>,
+    },
+    /// A phrase the speech-to-text daemon heard while push-to-talk was held.
+    VoiceCommand {
+        phrase: String,
+        understood: bool,
+    },
 }
 
 impl Toast {
@@ -89,10 +412,267 @@ impl Toast {
             Toast::LastGamepadDisconnected => {
                 ui.label("Last gamepad disconnected");
             }
-            Toast::DlnaDeviceDiscovered { name } => {
+            Toast::DlnaDeviceDiscovered { name, .. } => {
                 ui.label("DLNA device discovered");
                 ui.label(RichText::new(name).size(10.));
             }
+            Toast::DlnaDeviceLost { name } => {
+                ui.label("DLNA device gone");
+                ui.label(RichText::new(name).size(10.));
+            }
+            Toast::GuestMode { enabled: true } => {
+                ui.label("Guest mode enabled");
+            }
+            Toast::GuestMode { enabled: false } => {
+                ui.label("Guest mode disabled");
+            }
+            Toast::WrongPin => {
+                ui.label("Wrong PIN");
+            }
+            Toast::DisabledInGuestMode => {
+                ui.label("Not available in guest mode");
+            }
+            Toast::Message { text } => {
+                ui.label(text);
+            }
+            Toast::MpvDisconnected => {
+                ui.label("Lost connection to mpv");
+            }
+            Toast::MpvNotResponding => {
+                ui.label("mpv stopped responding");
+                ui.label(RichText::new("Reconnecting").size(10.));
+            }
+            Toast::MpvReconnected => {
+                ui.label("Reconnected to mpv");
+            }
+            Toast::SegmentSkipped { category } => {
+                ui.label(
+                    RichText::new(format!("Skipped {}", category.label())).color(category.color()),
+                );
+            }
+            Toast::DriveConnected { name } => {
+                ui.label("USB drive connected");
+                ui.label(RichText::new(name).size(10.));
+            }
+            Toast::SafeToUnplug { name } => {
+                ui.label("Safe to unplug");
+                ui.label(RichText::new(name).size(10.));
+            }
+            Toast::EjectFailed { name } => {
+                ui.label("Failed to eject");
+                ui.label(RichText::new(name).size(10.));
+            }
+            Toast::Queued { name } => {
+                ui.label("Added to queue");
+                ui.label(RichText::new(name).size(10.));
+            }
+            Toast::WatchPartyMember { name, joined: true } => {
+                ui.label(format!("{name} joined the watch party"));
+            }
+            Toast::WatchPartyMember { name, joined: false } => {
+                ui.label(format!("{name} left the watch party"));
+            }
+            Toast::SyncplayUser { name, joined: true } => {
+                ui.label(format!("{name} joined the Syncplay room"));
+            }
+            Toast::SyncplayUser { name, joined: false } => {
+                ui.label(format!("{name} left the Syncplay room"));
+            }
+            Toast::SyncplayAction { user, action } => {
+                ui.label(format!("{user} {action}"));
+            }
+            Toast::SyncplayChat { user, message } => {
+                ui.label(message);
+                ui.label(RichText::new(user).size(10.));
+            }
+            Toast::ControlsLocked { locked: true } => {
+                ui.label("🔒 Controls locked");
+                ui.label(
+                    RichText::new(format!(
+                        "Hold {} + {} to unlock",
+                        button_label(Button::Select),
+                        button_label(Button::Start),
+                    ))
+                    .size(10.),
+                );
+            }
+            Toast::ControlsLocked { locked: false } => {
+                ui.label("🔓 Controls unlocked");
+            }
+            Toast::AudioOnly { enabled: true } => {
+                ui.label("Audio only");
+                ui.label(RichText::new("Video is off until you turn it back on").size(10.));
+            }
+            Toast::AudioOnly { enabled: false } => {
+                ui.label("Video back on");
+            }
+            Toast::PausedForTv { event } => {
+                ui.label("Paused");
+                ui.label(
+                    RichText::new(match event {
+                        TvEvent::Standby => "The TV went into standby",
+                        TvEvent::SwitchedAway => "The TV switched to another input",
+                    })
+                    .size(10.),
+                );
+            }
+            Toast::CastFailed { reason } => {
+                ui.label("Failed to cast audio");
+                ui.label(RichText::new(reason).size(10.));
+            }
+            Toast::PlayingOn { device } => {
+                ui.label("Playing on renderer");
+                ui.label(RichText::new(device).size(10.));
+            }
+            Toast::CastReceived { title } => {
+                ui.label("Playing from another device");
+                ui.label(RichText::new(title).size(10.));
+            }
+            Toast::PlayOnFailed { reason } => {
+                ui.label("Failed to play on renderer");
+                ui.label(RichText::new(reason).size(10.));
+            }
+            Toast::FramesDropping => {
+                ui.label("Playback is dropping frames");
+            }
+            Toast::NothingToSkip => {
+                ui.label("Nothing to skip here");
+            }
+            Toast::AllWatched => {
+                ui.label("Everything here has been watched");
+            }
+            Toast::StartupProblems => {
+                ui.label("Some things failed to start");
+                ui.label(RichText::new("See Diagnostics in the home menu").size(10.));
+            }
+            Toast::Chapter { number, count, title } => {
+                ui.label(format!("Chapter {number}/{count}"));
+                if let Some(title) = title {
+                    ui.label(RichText::new(title).size(10.));
+                }
+            }
+            Toast::BackedUp { name } => {
+                ui.label("Backup written");
+                ui.label(RichText::new(name).size(10.));
+            }
+            Toast::BackupFailed { reason } => {
+                ui.label("Backup failed");
+                ui.label(RichText::new(reason).size(10.));
+            }
+            Toast::Restored => {
+                ui.label("Backup restored");
+                ui.label(RichText::new("Restart to use the restored config").size(10.));
+            }
+            Toast::RestoreFailed { reason } => {
+                ui.label("Restore failed");
+                ui.label(RichText::new(reason).size(10.));
+            }
+            Toast::SubtitleSize { percent } => {
+                ui.label(format!("Subtitles at {percent}%"));
+            }
+            Toast::VoiceCommand { phrase, understood: true } => {
+                ui.label(format!("🎤 {phrase}"));
+            }
+            Toast::VoiceCommand { phrase, understood: false } => {
+                ui.label("🎤 Didn't catch that");
+                ui.label(RichText::new(phrase).size(10.));
+            }
+            Toast::HdrToneMapped => {
+                ui.label("HDR video on SDR output");
+            }
+            Toast::Progress { title, progress } => {
+                let Ok(progress) = progress.lock() else {
+                    return;
+                };
+
+                match progress.fraction {
+                    Some(fraction) => {
+                        ui.label(format!("{title}… {:.0}%", fraction * 100.));
+                        ProgressBar::new(fraction).desired_height(4.).ui(ui);
+                    }
+                    None => {
+                        ui.label(format!("{title}…"));
+                        ProgressBar::new(0.).desired_height(4.).animate(true).ui(ui);
+                    }
+                }
+
+                if let Some(detail) = &progress.detail {
+                    ui.label(RichText::new(detail).size(10.));
+                }
+            }
         }
     }
+
+    /// Whether it's a progress toast whose operation is still going.
+    fn is_ongoing(&self) -> bool {
+        matches!(self, Toast::Progress { progress, .. } if Arc::strong_count(progress) > 1)
+    }
+
+    pub fn priority(&self) -> Priority {
+        match self {
+            Toast::GamepadConnected { .. }
+            | Toast::DlnaDeviceLost { .. }
+            | Toast::MpvReconnected
+            | Toast::Queued { .. }
+            | Toast::SyncplayAction { .. }
+            | Toast::ControlsLocked { locked: false }
+            | Toast::AudioOnly { .. }
+            | Toast::Chapter { .. }
+            | Toast::SubtitleSize { .. }
+            | Toast::PlayingOn { .. }
+            | Toast::VoiceCommand { understood: true, .. } => Priority::Low,
+
+            Toast::GamepadLowBattery { .. }
+            | Toast::LastGamepadDisconnected
+            | Toast::WrongPin
+            | Toast::DisabledInGuestMode
+            | Toast::MpvDisconnected
+            | Toast::MpvNotResponding
+            | Toast::EjectFailed { .. }
+            | Toast::ControlsLocked { locked: true }
+            | Toast::PausedForTv { .. }
+            | Toast::StartupProblems
+            | Toast::BackupFailed { .. }
+            | Toast::RestoreFailed { .. }
+            | Toast::CastFailed { .. }
+            | Toast::PlayOnFailed { .. } => Priority::High,
+
+            _ => Priority::Normal,
+        }
+    }
+
+    /// How long it stays up, including sliding in and out.
+    pub fn duration(&self) -> Duration {
+        match self {
+            // long enough to read how to unlock, or what to look at
+            Toast::ControlsLocked { locked: true } | Toast::StartupProblems => {
+                Duration::from_secs(10)
+            }
+            Toast::SyncplayChat { message, .. } => {
+                Duration::from_secs(5 + message.len() as u64 / 20).min(Duration::from_secs(15))
+            }
+            // these can only be acted on for as long as the thing they're about allows
+            _ if self.action().is_some() => Duration::from_secs(5),
+            _ => match self.priority() {
+                Priority::Low => Duration::from_secs(3),
+                Priority::Normal => Duration::from_secs(5),
+                Priority::High => Duration::from_secs(8),
+            },
+        }
+    }
+
+    pub fn action(&self) -> Option<ToastAction> {
+        let (button, label, command) = match self {
+            Toast::SegmentSkipped { .. } => (Button::South, "Undo", Command::UndoSkip),
+            Toast::DriveConnected { .. } => (Button::West, "Browse", Command::BrowseDrive),
+            Toast::FramesDropping => (Button::West, "Fixes", Command::ShowPerformance),
+            Toast::HdrToneMapped => (Button::West, "Tone Mapping", Command::ShowToneMapping),
+            Toast::DlnaDeviceDiscovered { offer_volume: true, .. } => {
+                (Button::East, "Use for Volume", Command::UseFoundForVolume)
+            }
+            _ => return None,
+        };
+
+        Some(ToastAction { button, label, command })
+    }
 }
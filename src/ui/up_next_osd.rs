@@ -0,0 +1,64 @@
+use egui::{Align2, Id, RichText, Vec2, vec2};
+use gilrs::Button;
+
+use super::overlay::OverlayWidget;
+use crate::{App, gamepad::button_prompt};
+
+/// Card counting down to the next playlist entry near the end of a file, like streaming apps
+/// show before the next episode.
+pub struct UpNextOsd;
+
+impl UpNextOsd {
+    /// The last title and countdown shown, kept around to fade out with.
+    fn id() -> Id {
+        Id::new("up next osd")
+    }
+}
+
+impl OverlayWidget for UpNextOsd {
+    fn name(&self) -> &'static str {
+        "up-next"
+    }
+
+    fn visible(&self, ctx: &egui::Context, app: &App) -> bool {
+        // A and B only go to the card while no menu wants them
+        if !app.passive {
+            return false;
+        }
+
+        let Some((entry, left)) = app.mpv.up_next() else {
+            return false;
+        };
+
+        let card = (entry.display_name().to_string(), left.as_secs_f32().ceil());
+        ctx.memory_mut(|mem| mem.data.insert_temp(Self::id(), card));
+
+        true
+    }
+
+    fn anchor(&self, _ctx: &egui::Context) -> (Align2, Vec2) {
+        // above the skip countdown, which outros at the end of a file tend to bring up too
+        (Align2::RIGHT_BOTTOM, vec2(-32., -120.))
+    }
+
+    fn inner_margin(&self) -> f32 {
+        16.
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, _app: &mut App) {
+        let Some((title, left)) = ui.memory(|mem| mem.data.get_temp::<(String, f32)>(Self::id()))
+        else {
+            return;
+        };
+
+        ui.set_max_width(400.);
+        ui.label(RichText::new(format!("Up next in {left}s")).size(10.));
+        ui.label(RichText::new(title).strong());
+        ui.add_space(4.);
+        ui.horizontal(|ui| {
+            ui.add(button_prompt(Button::East, "Play now"));
+            ui.add_space(8.);
+            ui.add(button_prompt(Button::South, "Cancel"));
+        });
+    }
+}
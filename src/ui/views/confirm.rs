@@ -0,0 +1,63 @@
+use egui::{Align, Id, Layout, Modal, RichText};
+
+use crate::{
+    App,
+    command::{Actions, Command},
+    ui::View,
+};
+
+pub struct ConfirmView {
+    message: String,
+    previous: Box<dyn View>,
+    on_confirm: Box<dyn FnOnce(&mut App)>,
+}
+
+impl ConfirmView {
+    pub fn new(
+        message: impl Into<String>,
+        previous: Box<dyn View>,
+        on_confirm: impl FnOnce(&mut App) + 'static,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            previous,
+            on_confirm: Box::new(on_confirm),
+        }
+    }
+
+    /// Asks whether the unsaved changes in `previous` may be thrown away before moving on to
+    /// `next`.
+    pub fn discard_changes(previous: Box<dyn View>, next: impl View) -> Self {
+        Self::new("Discard unsaved changes?", previous, move |app| {
+            app.view = Box::new(next);
+        })
+    }
+
+    pub fn confirm(self, app: &mut App) {
+        app.view = self.previous;
+        (self.on_confirm)(app);
+    }
+
+    pub fn cancel(self, app: &mut App) {
+        app.view = self.previous;
+    }
+}
+
+impl View for ConfirmView {
+    fn draw(&self, ctx: &egui::Context, _app: &mut App) {
+        Modal::new(Id::new("confirm dialog")).show(ctx, |ui| {
+            ui.set_min_width(200.);
+            ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                ui.label(RichText::new(&self.message).heading());
+            });
+        });
+    }
+
+    fn button_actions(&self) -> Actions {
+        Actions {
+            a: Command::ConfirmDialog,
+            b: Command::CancelDialog,
+            ..Actions::default()
+        }
+    }
+}
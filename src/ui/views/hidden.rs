@@ -15,6 +15,10 @@ impl View for HiddenView {
             b: Command::ShowUi,
             x: Command::TogglePause,
             y: Command::ShowUi,
+            l1: Command::PrevChapter,
+            l2: Command::ToggleAudioOnly,
+            r1: Command::NextChapter,
+            r2: Command::ShowStats,
             left: Command::SeekBackwardStateless,
             right: Command::SeekForwardStateless,
             up: Command::VolumeUp,
@@ -22,7 +26,6 @@ impl View for HiddenView {
             select: Command::ShowMiniSeek,
             start: Command::ShowMediaMenu,
             home: Command::ShowHomeMenu,
-            ..Actions::default()
         }
     }
 
@@ -0,0 +1,135 @@
+//! Cards for recently played files that weren't finished, shown above the home menu buttons.
+
+use std::path::Path;
+
+use egui::{
+    Button, Label, ProgressBar, RichText, ScrollArea, Widget as _,
+    cache::{ComputerMut, FrameCache},
+    scroll_area::ScrollBarVisibility,
+    vec2,
+};
+
+use super::library;
+use crate::{
+    App,
+    command::Command,
+    store::history::HistoryEntry,
+    ui::{focus, image_cache},
+    utils::{ResponseExt as _, youtube_id_from_url},
+};
+
+const MAX_CARDS: usize = 10;
+const CARD_WIDTH: f32 = 140.;
+/// Files played further than this are done, and don't need continuing.
+const FINISHED: f32 = 0.9;
+
+fn unfinished(app: &App) -> impl Iterator<Item = &HistoryEntry> {
+    app.history
+        .entries()
+        .iter()
+        .filter(|entry| {
+            !app.watched.contains(Path::new(&entry.path))
+                && entry
+                    .duration
+                    .is_none_or(|duration| entry.position / duration < FINISHED)
+        })
+        .take(MAX_CARDS)
+}
+
+pub fn is_empty(app: &App) -> bool {
+    unfinished(app).next().is_none()
+}
+
+/// Draws the rail, returning whether one of its cards has focus so left and right can move
+/// between them.
+pub fn rail(ui: &mut egui::Ui, app: &mut App) -> bool {
+    let entries = unfinished(app).cloned().collect::<Vec<_>>();
+    let mut focused = false;
+
+    ScrollArea::horizontal()
+        .id_salt("continue watching")
+        .scroll_bar_visibility(ScrollBarVisibility::AlwaysHidden)
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for entry in &entries {
+                    let card = card(ui, app, entry);
+                    focus::add(&card, "continue watching");
+
+                    if card.has_focus() {
+                        focused = true;
+                        ui.scroll_to_rect(card.rect, None);
+                    }
+
+                    if card.activated() {
+                        app.mpv.load_file_at(&entry.path, entry.position).ok();
+                        app.mpv.unpause().ok();
+                        app.queue_command(Command::HideUi);
+                    }
+                }
+            });
+        });
+
+    ui.label(RichText::new("Continue watching").size(10.));
+
+    focused
+}
+
+fn card(ui: &mut egui::Ui, app: &App, entry: &HistoryEntry) -> egui::Response {
+    let artwork = app
+        .scrapers
+        .cached(Path::new(&entry.path))
+        .and_then(|m| m.poster_uri())
+        .or_else(|| {
+            ui.memory_mut(|mem| {
+                mem.caches
+                    .cache::<ArtworkCache<'_>>()
+                    .get(entry.path.as_str())
+            })
+        });
+
+    let title = entry.title.clone().unwrap_or_else(|| {
+        Path::new(&entry.path)
+            .file_stem()
+            .map_or_else(|| entry.path.clone(), |s| s.to_string_lossy().into_owned())
+    });
+
+    let size = vec2(CARD_WIDTH, CARD_WIDTH * 9. / 16.);
+
+    ui.vertical(|ui| {
+        ui.set_width(CARD_WIDTH);
+
+        let button = match artwork {
+            Some(uri) => ui.add(Button::image(
+                image_cache::image(ui.ctx(), uri)
+                    .fit_to_exact_size(size)
+                    .corner_radius(4.),
+            )),
+            None => ui.add_sized(size, Button::new(RichText::new("🎞").size(32.))),
+        };
+
+        ui.add(Label::new(RichText::new(title).size(10.)).truncate());
+
+        if let Some(duration) = entry.duration {
+            ProgressBar::new(entry.position / duration)
+                .desired_height(3.)
+                .ui(ui);
+        }
+
+        button
+    })
+    .inner
+}
+
+type ArtworkCache<'a> = FrameCache<Option<String>, ArtworkFetcher>;
+
+#[derive(Default)]
+struct ArtworkFetcher;
+impl ComputerMut<&str, Option<String>> for ArtworkFetcher {
+    fn compute(&mut self, path: &str) -> Option<String> {
+        if let Some(id) = youtube_id_from_url(path) {
+            return Some(format!("https://i.ytimg.com/vi/{id}/hqdefault.jpg"));
+        }
+
+        library::local_artwork(Path::new(path)).map(|a| format!("file://{}", a.display()))
+    }
+}
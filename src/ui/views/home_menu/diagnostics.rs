@@ -0,0 +1,45 @@
+use egui::RichText;
+
+use super::HomeMenu;
+use crate::{App, diagnostics::Subsystem, utils::ResponseExt as _};
+
+pub struct DiagnosticsMenu;
+
+impl HomeMenu for DiagnosticsMenu {
+    fn label(&self) -> &'static str {
+        "Diagnostics"
+    }
+
+    fn enabled(&self, _app: &App) -> bool {
+        true
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let mut retry = None;
+
+        for subsystem in Subsystem::ALL {
+            let status = subsystem.status(app);
+
+            ui.add_space(8.);
+            ui.horizontal(|ui| {
+                ui.label(subsystem.label());
+                ui.label(RichText::new(status.label()).color(status.color()));
+            });
+
+            if let Some(reason) = status.reason() {
+                ui.label(RichText::new(reason).size(10.).weak());
+
+                let button = ui.button(format!("Retry {}", subsystem.label()));
+                button.autofocus();
+
+                if button.activated() {
+                    retry = Some(subsystem);
+                }
+            }
+        }
+
+        if let Some(subsystem) = retry {
+            subsystem.retry(app);
+        }
+    }
+}
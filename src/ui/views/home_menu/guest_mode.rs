@@ -0,0 +1,101 @@
+use core::cell::Cell;
+
+use egui::{Color32, Frame, Margin, RichText};
+use gilrs::Button;
+
+use super::HomeMenu;
+use crate::{
+    App, BLUE,
+    command::{Command, Event},
+    config::config,
+    ui::toast::Toast,
+    utils::ResponseExt as _,
+};
+
+#[derive(Default)]
+pub struct GuestModeMenu {
+    digits: Cell<[u8; 4]>,
+    cursor: Cell<usize>,
+}
+
+impl HomeMenu for GuestModeMenu {
+    fn label(&self) -> &'static str {
+        "Guest Mode"
+    }
+
+    fn enabled(&self, _app: &App) -> bool {
+        true
+    }
+
+    fn width(&self) -> f32 {
+        200.
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let mut digits = self.digits.get();
+        let mut cursor = self.cursor.get();
+
+        if app.gamepad.claim(Button::DPadLeft, "Previous digit") {
+            cursor = cursor.saturating_sub(1);
+        }
+        if app.gamepad.claim(Button::DPadRight, "Next digit") {
+            cursor = (cursor + 1).min(digits.len() - 1);
+        }
+        if app.gamepad.take_just_pressed(Button::DPadUp) {
+            digits[cursor] = (digits[cursor] + 1) % 10;
+        }
+        if app.gamepad.take_just_pressed(Button::DPadDown) {
+            digits[cursor] = (digits[cursor] + 9) % 10;
+        }
+
+        ui.add_space(8.);
+        ui.label(if app.guest_mode {
+            "Enter PIN to leave guest mode"
+        } else {
+            "Enter PIN to enter guest mode"
+        });
+        ui.add_space(8.);
+
+        ui.horizontal(|ui| {
+            for (idx, digit) in digits.iter().enumerate() {
+                Frame::new()
+                    .fill(ui.visuals().extreme_bg_color)
+                    .corner_radius(4.)
+                    .inner_margin(Margin::symmetric(8, 4))
+                    .show(ui, |ui| {
+                        ui.label(
+                            RichText::new(digit.to_string())
+                                .size(24.)
+                                .color(if idx == cursor { BLUE } else { Color32::WHITE }),
+                        );
+                    });
+            }
+        });
+
+        ui.add_space(8.);
+
+        let button = ui.button("Submit");
+        button.autofocus();
+
+        if button.activated() {
+            if digits == config().guest_pin {
+                app.guest_mode = !app.guest_mode;
+                app.queued_events
+                    .push(Event::Toast(Toast::GuestMode { enabled: app.guest_mode }));
+                app.queue_command(Command::ShowHomeMenu);
+            } else {
+                app.queued_events.push(Event::Toast(Toast::WrongPin));
+            }
+
+            digits = [0; 4];
+            cursor = 0;
+        }
+
+        self.digits.set(digits);
+        self.cursor.set(cursor);
+    }
+
+    fn catch_up_down(&self) -> bool {
+        true
+    }
+}
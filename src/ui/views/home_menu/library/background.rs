@@ -0,0 +1,74 @@
+//! Per-file work that takes too long to do while drawing, like running ffprobe or ffmpeg, done on
+//! a pool of worker threads instead.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+};
+
+pub struct Background<T> {
+    queue: Sender<PathBuf>,
+    results: Receiver<(PathBuf, T)>,
+    /// Every file that was asked about, with its result once it's in.
+    known: HashMap<PathBuf, Option<T>>,
+}
+
+impl<T: Clone + Send + 'static> Background<T> {
+    /// Starts up to `max_workers` threads that run `job` for every file that's asked about.
+    pub fn new(max_workers: usize, job: fn(&Path) -> T) -> Self {
+        let (queue, jobs) = mpsc::channel::<PathBuf>();
+        let (done, results) = mpsc::channel();
+        let jobs = Arc::new(Mutex::new(jobs));
+
+        let workers = std::thread::available_parallelism().map_or(1, |n| n.get().min(max_workers));
+
+        for _ in 0..workers {
+            let jobs = jobs.clone();
+            let done = done.clone();
+
+            std::thread::spawn(move || {
+                loop {
+                    // only hold the lock while waiting, so the others can work in the meantime
+                    let Ok(path) = jobs.lock().unwrap().recv() else {
+                        break;
+                    };
+
+                    let result = job(&path);
+                    if done.send((path, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        Self {
+            queue,
+            results,
+            known: HashMap::new(),
+        }
+    }
+
+    /// The result for a file, or `None` while it's still being worked on.
+    pub fn get(&mut self, path: &Path) -> Option<T> {
+        for (path, result) in self.results.try_iter() {
+            self.known.insert(path, Some(result));
+        }
+
+        if let Some(result) = self.known.get(path) {
+            return result.clone();
+        }
+
+        self.known.insert(path.to_path_buf(), None);
+        self.queue.send(path.to_path_buf()).ok();
+        None
+    }
+
+    /// Forgets the result for a file, so it's worked out again the next time it's asked about.
+    pub fn forget(&mut self, path: &Path) {
+        self.known.remove(path);
+    }
+}
@@ -0,0 +1,1050 @@
+use core::cell::Cell;
+use std::{
+    io::BufRead as _,
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use egui::{
+    Button as ButtonWidget, Id, Label, RichText,
+    cache::{ComputerMut, FrameCache},
+    vec2,
+};
+use gilrs::Button;
+
+use self::{
+    background::Background,
+    probe::{DynamicRange, MediaInfo},
+};
+use super::HomeMenu;
+use crate::{
+    App, BLUE,
+    command::{Command, Event, LeftRight},
+    config::config,
+    gamepad::{button_prompt, button_prompt_raw},
+    shuffle,
+    store::settings::LibraryLayout,
+    ui::{
+        fast_scroll, image_cache,
+        keyboard::{KeyboardResponse, OnScreenKeyboard},
+        toast::Toast,
+    },
+    utils::ResponseExt as _,
+};
+
+mod background;
+mod probe;
+mod thumbnail;
+
+pub use self::thumbnail::local_artwork;
+
+const THUMBNAIL_WIDTH: f32 = 160.;
+const POSTER_WIDTH: f32 = 120.;
+
+/// How long Y has to be held to toggle whether a file was watched, since tapping it searches.
+const WATCHED_HOLD: Duration = Duration::from_millis(500);
+/// Search results beyond this many aren't shown.
+const MAX_RESULTS: usize = 200;
+
+#[derive(Default)]
+pub struct LibraryMenu {
+    /// Whether the last frame was drawn as a grid or with the keyboard open, which need left and
+    /// right to move focus.
+    sideways: Cell<bool>,
+}
+
+#[derive(Clone, Default)]
+struct Search {
+    query: String,
+    /// Whether the keyboard is open.
+    typing: bool,
+}
+
+impl HomeMenu for LibraryMenu {
+    fn label(&self) -> &'static str {
+        "Library"
+    }
+
+    fn enabled(&self, _app: &App) -> bool {
+        true
+    }
+
+    fn panel(&self, ctx: &egui::Context, app: &mut App) {
+        egui::CentralPanel::default()
+            .frame(self.frame(ctx))
+            .show(ctx, |ui| {
+                self.inner(ui, app);
+            });
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let mut cwd = ui
+            .memory(|mem| mem.data.get_temp::<PathBuf>(cwd_id()))
+            .unwrap_or_else(|| config().library_root.clone());
+
+        // drives are extra roots, go back to the main one when the one we're on disappears
+        let drive_root = app
+            .removable
+            .mounts()
+            .iter()
+            .find(|m| cwd.starts_with(&m.mount_point))
+            .map(|m| m.mount_point.clone());
+
+        if drive_root.is_none() && !cwd.starts_with(&config().library_root) {
+            cwd = config().library_root.clone();
+            set_cwd(ui.ctx(), &cwd);
+        }
+
+        if let Some(path) = ui.memory(|mem| mem.data.get_temp::<PathBuf>(play_on_id())) {
+            self.sideways.set(false);
+            play_on_menu(ui, app, &path);
+            return;
+        }
+
+        let mut contents = ui.memory_mut(|mem| {
+            let cache = mem.caches.cache::<DirContentsCache<'_>>();
+            cache.get(cwd.as_path())
+        });
+        contents.iter_mut().for_each(DirEntry::refresh);
+
+        let mut refocus = ui.memory_mut(|mem| mem.data.remove_temp::<PathBuf>(jump_id()));
+
+        // the buttons are different widgets in each layout, so focus has to be carried over
+        if app.gamepad.take_just_pressed(Button::Select) {
+            app.settings.cycle_library_layout(&cwd);
+            refocus = ui.memory(|mem| mem.data.get_temp::<PathBuf>(focused_id()));
+        }
+
+        let mut search = ui.memory(|mem| mem.data.get_temp::<Search>(search_id()));
+        let mut open_keyboard = false;
+
+        // claimed so the prompt says what a press does, holding it toggles watched on files
+        let label = if search.is_some() {
+            "Close search"
+        } else {
+            "Search"
+        };
+        app.gamepad.claim(Button::West, label);
+
+        if app.gamepad.take_short_press(Button::West) {
+            search = match search {
+                Some(_) => None,
+                None => {
+                    rescan(&cwd);
+                    open_keyboard = true;
+                    Some(Search { typing: true, ..Search::default() })
+                }
+            };
+        }
+
+        let searching = search.is_some();
+        let mut typing = false;
+        let mut done_typing = false;
+
+        if let Some(search) = &mut search {
+            ui.add(button_prompt(Button::West, "Close search"));
+
+            if !search.typing && ui.button(format!("🔍 {}", search.query)).activated() {
+                search.typing = true;
+                open_keyboard = true;
+            }
+
+            if search.typing {
+                let response = OnScreenKeyboard::new(&mut search.query)
+                    .focus(open_keyboard)
+                    .show(ui);
+
+                if response == KeyboardResponse::Done {
+                    search.typing = false;
+                    done_typing = true;
+                }
+            }
+
+            typing = search.typing;
+            contents = search_results(ui, &cwd, &search.query, app);
+
+            if done_typing {
+                refocus = contents.first().map(|e| e.path.clone());
+            }
+        }
+
+        ui.memory_mut(|mem| match &search {
+            Some(search) => mem.data.insert_temp(search_id(), search.clone()),
+            None => mem.data.remove::<Search>(search_id()),
+        });
+
+        let mut layout = app.settings.library_layout(&cwd);
+        if !searching {
+            ui.add(button_prompt(Button::Select, &format!("Layout: {}", layout.label())));
+        }
+        ui.add(button_prompt(Button::Start, "Add to queue"));
+        ui.add(button_prompt(Button::RightTrigger2, "Shuffle unwatched"));
+        if app.dlna.has_devices() {
+            ui.add(button_prompt(Button::LeftTrigger2, "Play on..."));
+        }
+
+        // results come from all over, so they don't make much of a grid
+        if searching {
+            layout = LibraryLayout::List;
+        }
+
+        // posters only make sense for folders the scrapers know about
+        if layout == LibraryLayout::Posters
+            && !contents.iter().any(|e| {
+                e.is_scrapable()
+                    && app
+                        .scrapers
+                        .get(&e.path)
+                        .is_some_and(|m| m.poster.is_some())
+            })
+        {
+            layout = LibraryLayout::List;
+        }
+
+        self.sideways.set(layout != LibraryLayout::List || typing);
+
+        let parent = if drive_root.as_ref() == Some(&cwd) {
+            Some(config().library_root.as_path())
+        } else if cwd != config().library_root {
+            cwd.parent()
+        } else {
+            None
+        };
+
+        if !searching && let Some(parent) = parent {
+            let button = ui.button(button_prompt_raw(Button::South, "Go up"));
+
+            if button.has_focus() {
+                ui.scroll_to_rect(button.rect, None);
+            }
+
+            if button.activated() {
+                set_cwd(ui.ctx(), parent);
+            }
+        }
+
+        // only offered at the root of the drive, where it was opened from
+        let removable = app.removable.mount_at(&cwd).cloned();
+
+        if !searching && let Some(mount) = removable {
+            let button = ui.button(format!("⏏ Eject {}", mount.label));
+
+            if button.has_focus() {
+                ui.scroll_to_rect(button.rect, None);
+            }
+
+            if button.activated() {
+                app.removable.eject(mount);
+                set_cwd(ui.ctx(), &config().library_root);
+            }
+        }
+
+        if cwd == config().library_root && !searching && !app.favorites.paths().is_empty() {
+            let mut favorites = ui.memory_mut(|mem| {
+                let cache = mem.caches.cache::<EntryCache<'_>>();
+                app.favorites
+                    .paths()
+                    .iter()
+                    .map(|path| cache.get(path.as_path()))
+                    .collect::<Vec<_>>()
+            });
+            favorites.iter_mut().for_each(DirEntry::refresh);
+
+            ui.label(RichText::new("Favorites").size(10.));
+
+            entries_layout(ui, layout, |ui| {
+                for (idx, entry) in favorites.iter().enumerate() {
+                    let label = format!("★ {}", entry_label(app, entry));
+                    let button = entry_button(ui, app, entry, label, layout);
+
+                    if idx == 0 {
+                        button.autofocus();
+                    }
+
+                    if refocus.as_ref() == Some(&entry.path) {
+                        button.request_focus();
+                    }
+
+                    if button.has_focus() {
+                        ui.scroll_to_rect(button.rect, None);
+                        set_focused(ui.ctx(), &entry.path);
+
+                        if app.gamepad.claim(Button::North, "Unpin") {
+                            app.favorites.toggle(&entry.path);
+                        }
+                        if app.gamepad.claim(Button::Start, "Queue") {
+                            queue_entry(app, entry);
+                        }
+                        if entry.is_media_file()
+                            && app.gamepad.take_long_press(Button::West, WATCHED_HOLD)
+                        {
+                            app.watched.toggle(&entry.path);
+                        }
+                        if app.gamepad.claim(Button::RightTrigger2, "Shuffle") {
+                            shuffle_entry(ui, app, entry);
+                        }
+                        if entry.is_media_file()
+                            && app.dlna.has_devices()
+                            && app.gamepad.claim(Button::LeftTrigger2, "Play on")
+                        {
+                            open_play_on(ui.ctx(), &entry.path);
+                        }
+                        if app.gamepad.claim(Button::LeftTrigger, "Move up") {
+                            app.favorites.move_up(&entry.path);
+                        }
+                        if app.gamepad.claim(Button::RightTrigger, "Move down") {
+                            app.favorites.move_down(&entry.path);
+                        }
+                    }
+
+                    if button.activated() {
+                        activate_entry(ui, app, entry);
+                    }
+                }
+            });
+
+            ui.add_space(8.);
+        }
+
+        if cwd == config().library_root && !searching && !app.removable.mounts().is_empty() {
+            ui.label(RichText::new("Drives").size(10.));
+
+            for mount in app.removable.mounts() {
+                let button = ui.button(format!("🖴 {}", mount.label));
+
+                if button.has_focus() {
+                    ui.scroll_to_rect(button.rect, None);
+                }
+
+                if button.activated() {
+                    set_cwd(ui.ctx(), &mount.mount_point);
+                }
+            }
+
+            ui.add_space(8.);
+        }
+
+        if cwd == config().library_root
+            && !searching
+            && (!app.favorites.paths().is_empty() || !app.removable.mounts().is_empty())
+        {
+            ui.label(RichText::new("All").size(10.));
+        }
+
+        let letters = contents
+            .iter()
+            .map(|e| fast_scroll::letter(&e.path.file_name().unwrap_or_default().to_string_lossy()))
+            .collect::<Vec<_>>();
+
+        entries_layout(ui, layout, |ui| {
+            for (idx, entry) in contents.iter().enumerate() {
+                let label = if app.favorites.contains(&entry.path) {
+                    format!("★ {}", entry_label(app, entry))
+                } else {
+                    entry_label(app, entry)
+                };
+
+                let button = ui
+                    .add_enabled_ui(!entry.is_other_file() || idx == 0, |ui| {
+                        entry_button(ui, app, entry, label, layout)
+                    })
+                    .inner;
+
+                if idx == 0 {
+                    button.autofocus();
+                }
+
+                if refocus.as_ref() == Some(&entry.path) {
+                    button.request_focus();
+                }
+
+                if button.has_focus() {
+                    ui.scroll_to_rect(button.rect, None);
+                    set_focused(ui.ctx(), &entry.path);
+
+                    if !entry.is_other_file() {
+                        let label = if app.favorites.contains(&entry.path) {
+                            "Unpin"
+                        } else {
+                            "Pin"
+                        };
+                        if app.gamepad.claim(Button::North, label) {
+                            app.favorites.toggle(&entry.path);
+                        }
+                    }
+                    if app.gamepad.claim(Button::Start, "Queue") {
+                        queue_entry(app, entry);
+                    }
+                    if entry.is_media_file()
+                        && app.gamepad.take_long_press(Button::West, WATCHED_HOLD)
+                    {
+                        app.watched.toggle(&entry.path);
+                    }
+                    if app.gamepad.claim(Button::RightTrigger2, "Shuffle") {
+                        shuffle_entry(ui, app, entry);
+                    }
+                    if entry.is_media_file()
+                        && app.dlna.has_devices()
+                        && app.gamepad.claim(Button::LeftTrigger2, "Play on")
+                    {
+                        open_play_on(ui.ctx(), &entry.path);
+                    }
+
+                    // search results are ordered by where they are, not by name
+                    let jump = if searching {
+                        fast_scroll::by_page(ui.ctx(), &mut app.gamepad, contents.len(), idx)
+                    } else {
+                        fast_scroll::by_letter(ui.ctx(), &mut app.gamepad, &letters, idx)
+                    };
+
+                    if let Some(target) = jump {
+                        let path = contents[target].path.clone();
+                        ui.memory_mut(|mem| mem.data.insert_temp(jump_id(), path));
+                    }
+                }
+
+                if button.activated() {
+                    activate_entry(ui, app, entry);
+                }
+            }
+        });
+    }
+
+    fn left_right(&self) -> LeftRight {
+        if self.sideways.get() {
+            LeftRight::MoveFocus
+        } else {
+            config().menu_left_right
+        }
+    }
+
+    fn catch_x(&self) -> bool {
+        true
+    }
+}
+
+/// Lays entries out in rows of tiles for the grid layouts, or one per line for the list.
+fn entries_layout(
+    ui: &mut egui::Ui,
+    layout: LibraryLayout,
+    add_contents: impl FnOnce(&mut egui::Ui),
+) {
+    if layout == LibraryLayout::List {
+        add_contents(ui);
+    } else {
+        ui.horizontal_wrapped(add_contents);
+    }
+}
+
+fn entry_button(
+    ui: &mut egui::Ui,
+    app: &App,
+    entry: &DirEntry,
+    label: String,
+    layout: LibraryLayout,
+) -> egui::Response {
+    let (width, size, thumbnail) = match layout {
+        LibraryLayout::List => {
+            let button = ui.button(entry_text(app, entry, label));
+            badges(ui, app, &button, entry);
+            return button;
+        }
+        LibraryLayout::Thumbnails => (
+            THUMBNAIL_WIDTH,
+            vec2(THUMBNAIL_WIDTH, THUMBNAIL_WIDTH * 9. / 16.),
+            entry
+                .has_thumbnail()
+                .then(|| thumbnail::thumbnail(&entry.path))
+                .flatten(),
+        ),
+        LibraryLayout::Posters => (
+            POSTER_WIDTH,
+            vec2(POSTER_WIDTH, POSTER_WIDTH * 3. / 2.),
+            app.scrapers
+                .cached(&entry.path)
+                .and_then(|m| m.poster_uri()),
+        ),
+    };
+
+    ui.vertical(|ui| {
+        ui.set_width(width);
+
+        let button = match thumbnail {
+            Some(uri) => ui.add(ButtonWidget::image(
+                image_cache::image(ui.ctx(), uri)
+                    .fit_to_exact_size(size)
+                    .corner_radius(4.),
+            )),
+            None => ui.add_sized(size, ButtonWidget::new(RichText::new(entry.icon()).size(32.))),
+        };
+
+        ui.add(Label::new(entry_text(app, entry, label).size(10.)).truncate());
+
+        button
+    })
+    .inner
+}
+
+/// Dims files that have been watched and ticks them off.
+fn entry_text(app: &App, entry: &DirEntry, label: String) -> RichText {
+    if app.watched.contains(&entry.path) {
+        RichText::new(format!("✔ {label}")).weak()
+    } else {
+        RichText::new(label)
+    }
+}
+
+/// The scraped title if there is one, otherwise what the file itself says.
+fn entry_label(app: &mut App, entry: &DirEntry) -> String {
+    if entry.is_scrapable()
+        && let Some(metadata) = app.scrapers.get(&entry.path)
+    {
+        return metadata.label();
+    }
+
+    entry.label()
+}
+
+fn badges(ui: &mut egui::Ui, app: &App, button: &egui::Response, entry: &DirEntry) {
+    let mut badges: Vec<String> = entry.badges().into_iter().map(String::from).collect();
+
+    if let Some(rating) = app.scrapers.cached(&entry.path).and_then(|m| m.rating) {
+        badges.push(format!("{rating:.1}"));
+    }
+
+    if badges.is_empty() {
+        return;
+    }
+
+    button.ralign_overlay(ui, |ui| {
+        ui.add_space(8.);
+        for badge in badges.into_iter().rev() {
+            ui.label(RichText::new(badge).size(10.).color(BLUE));
+        }
+    });
+}
+
+fn cwd_id() -> Id {
+    Id::new("library cwd")
+}
+
+pub fn set_cwd(ctx: &egui::Context, path: &Path) {
+    ctx.memory_mut(|mem| {
+        mem.data.insert_temp(cwd_id(), path.to_path_buf());
+        mem.data.remove::<Search>(search_id());
+    });
+}
+
+fn search_id() -> Id {
+    Id::new("library search")
+}
+
+/// Everything under `dir` whose name or scraped title contains all words of the query.
+fn search_results(ui: &egui::Ui, dir: &Path, query: &str, app: &App) -> Vec<DirEntry> {
+    let matches = matching_paths(dir, query, app);
+
+    ui.memory_mut(|mem| {
+        let cache = mem.caches.cache::<EntryCache<'_>>();
+        matches
+            .iter()
+            .map(|path| {
+                let mut entry = cache.get(path.as_path());
+                entry.refresh();
+                entry
+            })
+            .collect()
+    })
+}
+
+fn matching_paths(dir: &Path, query: &str, app: &App) -> Vec<PathBuf> {
+    let words = query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+
+    if words.is_empty() {
+        return vec![];
+    }
+
+    // nothing matches until the tree is walked
+    let tree = TREES.lock().unwrap().get(dir).unwrap_or_default();
+    tree.into_iter()
+        .filter(|path| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            let title = app
+                .scrapers
+                .cached(path)
+                .map(|m| m.label().to_lowercase())
+                .unwrap_or_default();
+
+            words
+                .iter()
+                .all(|word| name.contains(word) || title.contains(word))
+        })
+        .take(MAX_RESULTS)
+        .collect()
+}
+
+/// Searches the whole Library, for the search menu. Returns paths with what to call them.
+pub fn search(ui: &egui::Ui, app: &mut App, query: &str) -> Vec<(PathBuf, String)> {
+    search_results(ui, &config().library_root, query, app)
+        .into_iter()
+        .map(|entry| (entry.path.clone(), entry_label(app, &entry)))
+        .collect()
+}
+
+/// Plays a file or media folder like the Library would, or browses to a plain folder.
+pub fn open(ui: &mut egui::Ui, app: &mut App, path: &Path) {
+    let mut entry = ui.memory_mut(|mem| mem.caches.cache::<EntryCache<'_>>().get(path));
+    entry.refresh();
+
+    if let EntryInfo::RawFolder = entry.info {
+        set_cwd(ui.ctx(), path);
+        app.change_view(super::HomeMenuView::library());
+    } else {
+        activate_entry(ui, app, &entry);
+    }
+}
+
+/// The entry that had focus last, remembered by path.
+fn focused_id() -> Id {
+    Id::new("library focused entry")
+}
+
+fn set_focused(ctx: &egui::Context, path: &Path) {
+    ctx.memory_mut(|mem| mem.data.insert_temp(focused_id(), path.to_path_buf()));
+}
+
+/// The entry L1 or R1 jumped to, to focus next frame.
+fn jump_id() -> Id {
+    Id::new("library jump")
+}
+
+/// The file the "Play on..." list is open for.
+fn play_on_id() -> Id {
+    Id::new("library play on")
+}
+
+fn open_play_on(ctx: &egui::Context, path: &Path) {
+    ctx.memory_mut(|mem| mem.data.insert_temp(play_on_id(), path.to_path_buf()));
+}
+
+/// Goes back to the entry the list was opened for.
+fn close_play_on(ctx: &egui::Context, path: &Path) {
+    ctx.memory_mut(|mem| {
+        mem.data.remove::<PathBuf>(play_on_id());
+        mem.data.insert_temp(jump_id(), path.to_path_buf());
+    });
+}
+
+/// Lists the renderers a file can be played on instead of in mpv, and controls for whatever is
+/// already playing on one.
+fn play_on_menu(ui: &mut egui::Ui, app: &mut App, path: &Path) {
+    if !app.dlna.has_devices() || app.gamepad.take_just_pressed(Button::LeftTrigger2) {
+        close_play_on(ui.ctx(), path);
+        return;
+    }
+
+    ui.add(button_prompt(Button::LeftTrigger2, "Back"));
+
+    if let Some((device, playing)) = app.dlna.playing_on() {
+        let name = playing
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let device = app.dlna.devices()[device].friendly_name().to_string();
+
+        ui.label(RichText::new(format!("Playing on {device}")).size(10.));
+        ui.label(name);
+
+        let label = if app.dlna.remote_paused() {
+            "Resume"
+        } else {
+            "Pause"
+        };
+        if ui.button(label).activated() {
+            app.dlna.toggle_remote_pause();
+        }
+        if ui.button("Stop").activated() {
+            app.dlna.stop_remote();
+        }
+
+        ui.add_space(8.);
+    }
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    ui.label(RichText::new(format!("Play {name} on")).size(10.));
+
+    for idx in 0..app.dlna.devices().len() {
+        let device = app.dlna.devices()[idx].friendly_name().to_string();
+        let button = ui.button(&device);
+
+        if idx == 0 {
+            button.autofocus();
+        }
+
+        if button.activated() {
+            match app.dlna.play_on(idx, path, &mut app.mpv) {
+                Ok(()) => {
+                    app.queued_events
+                        .push(Event::Toast(Toast::PlayingOn { device }));
+                    close_play_on(ui.ctx(), path);
+                }
+                Err(reason) => {
+                    eprintln!("Failed to play {} on {device}: {reason}", path.display());
+                    app.queued_events
+                        .push(Event::Toast(Toast::PlayOnFailed { reason }));
+                }
+            }
+        }
+    }
+}
+
+fn activate_entry(ui: &mut egui::Ui, app: &mut App, entry: &DirEntry) {
+    match &entry.info {
+        EntryInfo::MediaFile(_media_info) => {
+            let path = entry.path.to_string_lossy();
+            if let Some(position) = app.resume.get(&path) {
+                app.queued_events
+                    .push(Event::AskResume { path: path.into_owned(), position });
+                return;
+            }
+
+            app.mpv.load_file(&path).ok();
+            app.mpv.unpause().ok();
+
+            app.queue_command(Command::HideUi);
+        }
+        EntryInfo::MediaFolder(playlist) => {
+            app.mpv
+                .load_file(&playlist.index_path.to_string_lossy())
+                .ok();
+            app.mpv.unpause().ok();
+
+            app.queue_command(Command::HideUi);
+        }
+        EntryInfo::OtherFile | EntryInfo::Probing => {}
+        EntryInfo::RawFolder => {
+            set_cwd(ui.ctx(), &entry.path);
+        }
+    }
+}
+
+/// Adds a media file or folder to the end of mpv's playlist, leaving whatever's playing alone.
+fn queue_entry(app: &mut App, entry: &DirEntry) {
+    let path = match &entry.info {
+        EntryInfo::MediaFile(_) => entry.path.clone(),
+        EntryInfo::MediaFolder(playlist) => playlist.index_path.clone(),
+        EntryInfo::OtherFile | EntryInfo::Probing | EntryInfo::RawFolder => return,
+    };
+
+    if let Err(e) = app.mpv.append_file(&path.to_string_lossy()) {
+        eprintln!("Failed to queue {}: {e}", path.display());
+        return;
+    }
+
+    let name = entry_label(app, entry);
+    app.queued_events.push(Event::Toast(Toast::Queued { name }));
+}
+
+/// Plays the unwatched files in a folder in a random order, newer ones first more often.
+fn shuffle_entry(ui: &mut egui::Ui, app: &mut App, entry: &DirEntry) {
+    let paths = match &entry.info {
+        EntryInfo::MediaFolder(playlist) => playlist.paths(),
+        EntryInfo::RawFolder => {
+            let mut contents = ui.memory_mut(|mem| {
+                mem.caches
+                    .cache::<DirContentsCache<'_>>()
+                    .get(entry.path.as_path())
+            });
+            contents.iter_mut().for_each(DirEntry::refresh);
+
+            // files ffprobe hasn't gotten to yet go by their extension, rather than being left
+            // out and making the folder look all watched
+            contents
+                .into_iter()
+                .filter(|e| e.is_media_file() || e.is_probing_media())
+                .map(|e| e.path)
+                .collect()
+        }
+        EntryInfo::MediaFile(_) | EntryInfo::OtherFile | EntryInfo::Probing => return,
+    };
+
+    if shuffle::play(&mut app.mpv, &shuffle::unwatched(paths, &app.watched)) {
+        app.queue_command(Command::HideUi);
+    } else {
+        app.queued_events.push(Event::Toast(Toast::AllWatched));
+    }
+}
+
+/// Extensions of files that are probably media, for when they haven't been probed yet.
+const MEDIA_EXTENSIONS: [&str; 16] = [
+    "mkv", "mp4", "m4v", "avi", "mov", "webm", "wmv", "ts", "m2ts", "mpg", "mpeg", "flv", "mp3",
+    "flac", "m4a", "ogg",
+];
+
+type DirContentsCache<'a> = FrameCache<Vec<DirEntry>, DirFetcher>;
+
+#[derive(Default)]
+struct DirFetcher;
+impl ComputerMut<&Path, Vec<DirEntry>> for DirFetcher {
+    fn compute(&mut self, key: &Path) -> Vec<DirEntry> {
+        let Ok(read_dir) = std::fs::read_dir(key) else {
+            return vec![];
+        };
+
+        let mut entries = vec![];
+        for entry in read_dir.flatten() {
+            entries.push(DirEntry::from_path(entry.path()));
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        entries
+    }
+}
+
+/// How deep search looks into subdirectories.
+const MAX_SEARCH_DEPTH: usize = 8;
+
+/// Everything below the directories being searched, walked on a worker thread since big trees
+/// take a while.
+static TREES: LazyLock<Mutex<Background<Vec<PathBuf>>>> =
+    LazyLock::new(|| Mutex::new(Background::new(1, walk_tree)));
+
+/// Walks `dir` again the next time it's searched, to pick up what changed since.
+pub fn rescan(dir: &Path) {
+    TREES.lock().unwrap().forget(dir);
+}
+
+/// Every file and directory below a directory, for searching.
+fn walk_tree(dir: &Path) -> Vec<PathBuf> {
+    fn walk(dir: &Path, depth: usize, paths: &mut Vec<PathBuf>) {
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            return;
+        };
+
+        let mut entries = read_dir
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| {
+                !p.file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+            })
+            .collect::<Vec<_>>();
+        entries.sort();
+
+        for path in entries {
+            paths.push(path.clone());
+            if path.is_dir() && depth < MAX_SEARCH_DEPTH {
+                walk(&path, depth + 1, paths);
+            }
+        }
+    }
+
+    let mut paths = vec![];
+    walk(dir, 0, &mut paths);
+    paths
+}
+
+type EntryCache<'a> = FrameCache<DirEntry, EntryFetcher>;
+
+#[derive(Default)]
+struct EntryFetcher;
+impl ComputerMut<&Path, DirEntry> for EntryFetcher {
+    fn compute(&mut self, key: &Path) -> DirEntry {
+        DirEntry::from_path(key.to_path_buf())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DirEntry {
+    path: PathBuf,
+    info: EntryInfo,
+}
+
+impl DirEntry {
+    fn from_path(path: PathBuf) -> Self {
+        DirEntry {
+            info: EntryInfo::from_path(&path),
+            path,
+        }
+    }
+
+    /// Picks up metadata that came in since the entry was cached.
+    fn refresh(&mut self) {
+        if let EntryInfo::Probing = self.info {
+            self.info = EntryInfo::from_probe(&self.path);
+        }
+    }
+
+    fn label(&self) -> String {
+        let filename = || self.path.file_name().unwrap().to_string_lossy().to_string();
+
+        match &self.info {
+            EntryInfo::MediaFile(media_info) => media_info.title.clone().unwrap_or_else(filename),
+            EntryInfo::MediaFolder(playlist) => playlist.title.clone().unwrap_or_else(filename),
+            EntryInfo::OtherFile | EntryInfo::RawFolder | EntryInfo::Probing => filename(),
+        }
+    }
+
+    fn badges(&self) -> Vec<&'static str> {
+        let EntryInfo::MediaFile(info) = &self.info else {
+            return vec![];
+        };
+
+        let mut badges = vec![];
+
+        match info.dynamic_range {
+            DynamicRange::Sdr => {}
+            DynamicRange::Hdr10 => badges.push("HDR"),
+            DynamicRange::Hlg => badges.push("HLG"),
+            DynamicRange::DolbyVision => badges.push("DoVi"),
+        }
+
+        if info.ten_bit {
+            badges.push("10-bit");
+        }
+
+        badges
+    }
+
+    fn is_other_file(&self) -> bool {
+        matches!(self.info, EntryInfo::OtherFile)
+    }
+
+    fn has_thumbnail(&self) -> bool {
+        !matches!(self.info, EntryInfo::OtherFile | EntryInfo::Probing)
+    }
+
+    /// Stands in for the thumbnail in grid mode when there isn't one.
+    fn icon(&self) -> &'static str {
+        match self.info {
+            EntryInfo::MediaFile(_) => "🎞",
+            EntryInfo::MediaFolder(_) | EntryInfo::RawFolder => "🗀",
+            EntryInfo::OtherFile => "🗋",
+            EntryInfo::Probing => "…",
+        }
+    }
+
+    fn is_media_file(&self) -> bool {
+        matches!(self.info, EntryInfo::MediaFile(_))
+    }
+
+    /// Whether the entry is still being probed, but its extension says it's likely media.
+    fn is_probing_media(&self) -> bool {
+        matches!(self.info, EntryInfo::Probing)
+            && self
+                .path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| MEDIA_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+    }
+
+    /// Whether it makes sense to look the entry up online. Plain folders are often just
+    /// categories like "Movies", which would get bogus matches.
+    fn is_scrapable(&self) -> bool {
+        matches!(self.info, EntryInfo::MediaFile(_) | EntryInfo::MediaFolder(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum EntryInfo {
+    MediaFile(MediaInfo),
+    MediaFolder(Playlist),
+    OtherFile,
+    RawFolder,
+    /// A file that ffprobe hasn't gotten to yet.
+    Probing,
+}
+
+impl EntryInfo {
+    fn from_path(path: &Path) -> Self {
+        if path.is_dir() {
+            if let Some(playlist) = Playlist::from_path(path) {
+                EntryInfo::MediaFolder(playlist)
+            } else {
+                EntryInfo::RawFolder
+            }
+        } else {
+            Self::from_probe(path)
+        }
+    }
+
+    fn from_probe(path: &Path) -> Self {
+        match probe::media_info(path) {
+            Some(Some(info)) => EntryInfo::MediaFile(info),
+            Some(None) => EntryInfo::OtherFile,
+            None => EntryInfo::Probing,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Playlist {
+    index_path: PathBuf,
+    title: Option<String>,
+    num_entries: usize,
+}
+
+impl Playlist {
+    fn from_path(path: &Path) -> Option<Self> {
+        let index_path = if path.is_dir() {
+            [path.join("index.m3u8"), path.join("index.m3u")]
+                .into_iter()
+                .find(|p| p.is_file())?
+        } else {
+            path.to_path_buf()
+        };
+
+        let file = std::fs::File::open(&index_path).ok()?;
+        let reader = std::io::BufReader::new(file);
+
+        let mut playlist = Playlist { index_path, ..Default::default() };
+
+        for line in reader.lines().map_while(Result::ok) {
+            if line == "#EXTM3U" {
+                continue;
+            }
+
+            match line.chars().next() {
+                Some('#') => {
+                    if let Some(title) = line.strip_prefix("#PLAYLIST:") {
+                        playlist.title = Some(title.trim().to_string());
+                    }
+                }
+                Some(_) => {
+                    playlist.num_entries += 1;
+                }
+                None => {}
+            }
+        }
+
+        Some(playlist)
+    }
+
+    /// Every entry in the index, with relative paths made relative to the index.
+    fn paths(&self) -> Vec<PathBuf> {
+        let Ok(file) = std::fs::File::open(&self.index_path) else {
+            return vec![];
+        };
+        let dir = self.index_path.parent().unwrap_or(Path::new(""));
+
+        std::io::BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                if line.contains("://") {
+                    PathBuf::from(line)
+                } else {
+                    dir.join(line)
+                }
+            })
+            .collect()
+    }
+}
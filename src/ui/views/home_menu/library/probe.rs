@@ -0,0 +1,133 @@
+//! Reading metadata out of media files with ffprobe.
+
+use std::{
+    path::Path,
+    process,
+    sync::{LazyLock, Mutex},
+};
+
+use serde::Deserialize;
+
+use super::background::Background;
+use crate::utils::youtube_id_from_url;
+
+/// Upper limit on how many ffprobes run at once.
+const MAX_WORKERS: usize = 4;
+
+static PROBES: LazyLock<Mutex<Background<Option<MediaInfo>>>> =
+    LazyLock::new(|| Mutex::new(Background::new(MAX_WORKERS, MediaInfo::from_path)));
+
+/// The metadata of a file, or `None` while it's still being probed.
+///
+/// Files that aren't media come back as `Some(None)`.
+pub fn media_info(path: &Path) -> Option<Option<MediaInfo>> {
+    PROBES.lock().unwrap().get(path)
+}
+
+#[derive(Debug, Clone)]
+pub struct MediaInfo {
+    pub title: Option<String>,
+    pub youtube_id: Option<String>,
+    pub dynamic_range: DynamicRange,
+    pub ten_bit: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DynamicRange {
+    Sdr,
+    Hdr10,
+    Hlg,
+    DolbyVision,
+}
+
+impl MediaInfo {
+    fn from_path(path: &Path) -> Option<Self> {
+        let output = process::Command::new("ffprobe")
+            .arg("-i")
+            .arg(path)
+            .args(["-select_streams", "v:0"])
+            .args([
+                "-show_entries",
+                "format_tags:stream=pix_fmt,color_transfer:stream_side_data=side_data_type",
+            ])
+            .args(["-of", "json"])
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        #[derive(Deserialize)]
+        struct Root {
+            #[serde(default)]
+            format: Format,
+            #[serde(default)]
+            streams: Vec<Stream>,
+        }
+
+        #[derive(Default, Deserialize)]
+        struct Stream {
+            pix_fmt: Option<String>,
+            color_transfer: Option<String>,
+            #[serde(default)]
+            side_data_list: Vec<SideData>,
+        }
+
+        #[derive(Deserialize)]
+        struct SideData {
+            side_data_type: String,
+        }
+
+        #[derive(Default, Deserialize)]
+        struct Format {
+            #[serde(default)]
+            tags: Tags,
+        }
+
+        #[derive(Default, Deserialize)]
+        struct Tags {
+            title: Option<String>,
+            purl: Option<String>,
+        }
+
+        let root: Root = serde_json::from_slice(&output.stdout).ok()?;
+
+        let video = root.streams.first();
+
+        let dynamic_range = match video {
+            Some(stream)
+                if stream
+                    .side_data_list
+                    .iter()
+                    .any(|s| s.side_data_type.starts_with("DOVI")) =>
+            {
+                DynamicRange::DolbyVision
+            }
+            Some(Stream { color_transfer: Some(trc), .. }) if trc == "smpte2084" => {
+                DynamicRange::Hdr10
+            }
+            Some(Stream { color_transfer: Some(trc), .. }) if trc == "arib-std-b67" => {
+                DynamicRange::Hlg
+            }
+            _ => DynamicRange::Sdr,
+        };
+
+        let ten_bit = video
+            .and_then(|s| s.pix_fmt.as_deref())
+            .is_some_and(|fmt| fmt.contains("10") || fmt.contains("12"));
+
+        Some(Self {
+            dynamic_range,
+            ten_bit,
+            title: root.format.tags.title,
+            youtube_id: root
+                .format
+                .tags
+                .purl
+                .as_deref()
+                .and_then(youtube_id_from_url)
+                .map(|s| s.to_string()),
+        })
+    }
+}
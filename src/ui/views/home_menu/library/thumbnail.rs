@@ -0,0 +1,95 @@
+//! Thumbnails for the Library's grid mode: artwork that sits next to the media, or otherwise a
+//! frame grabbed with ffmpeg and cached under the state directory.
+
+use std::{
+    hash::{DefaultHasher, Hash as _, Hasher as _},
+    path::{Path, PathBuf},
+    process,
+    sync::{LazyLock, Mutex},
+};
+
+use super::background::Background;
+use crate::store;
+
+/// Image file names that are used as artwork for every file in their directory.
+const DIRECTORY_ARTWORK: [&str; 4] = ["cover", "folder", "poster", "thumb"];
+const ARTWORK_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "webp"];
+
+/// ffmpeg decodes a lot of video per thumbnail, so keep it from hogging the machine.
+const MAX_WORKERS: usize = 2;
+/// How far into a video to grab the frame from, to skip past black frames and cold opens.
+const GRAB_AT: &str = "30";
+const WIDTH: u32 = 320;
+
+static THUMBNAILS: LazyLock<Mutex<Background<Option<PathBuf>>>> =
+    LazyLock::new(|| Mutex::new(Background::new(MAX_WORKERS, find_or_generate)));
+
+/// Image URI of the thumbnail for a file or folder, once there is one.
+pub fn thumbnail(path: &Path) -> Option<String> {
+    let thumbnail = THUMBNAILS.lock().unwrap().get(path)??;
+    Some(format!("file://{}", thumbnail.display()))
+}
+
+/// Artwork that comes with a file, either named after it or for its whole directory. For a
+/// directory, artwork inside it.
+pub fn local_artwork(path: &Path) -> Option<PathBuf> {
+    let (dir, stem) = if path.is_dir() {
+        (path, None)
+    } else {
+        (path.parent()?, Some(path.file_stem()?.to_str()?))
+    };
+
+    // artwork for this specific file first, then for the whole directory
+    stem.into_iter()
+        .chain(DIRECTORY_ARTWORK)
+        .flat_map(|name| ARTWORK_EXTENSIONS.map(|ext| dir.join(format!("{name}.{ext}"))))
+        .find(|candidate| candidate.is_file())
+}
+
+fn find_or_generate(path: &Path) -> Option<PathBuf> {
+    if let Some(artwork) = local_artwork(path) {
+        return Some(artwork);
+    }
+
+    if path.is_dir() {
+        return None;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+
+    let dir = store::state_dir().join("thumbnails");
+    let thumbnail = dir.join(format!("{:016x}.jpg", hasher.finish()));
+
+    if thumbnail.is_file() {
+        return Some(thumbnail);
+    }
+
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| eprintln!("Failed to create {}: {e}", dir.display()))
+        .ok()?;
+
+    // videos shorter than GRAB_AT don't get a frame at all, so fall back to the first one
+    [Some(GRAB_AT), None]
+        .into_iter()
+        .any(|seek| grab_frame(path, seek, &thumbnail))
+        .then_some(thumbnail)
+}
+
+fn grab_frame(path: &Path, seek: Option<&str>, out: &Path) -> bool {
+    let mut cmd = process::Command::new("ffmpeg");
+    cmd.args(["-loglevel", "error", "-y"]);
+
+    if let Some(seek) = seek {
+        cmd.args(["-ss", seek]);
+    }
+
+    cmd.arg("-i")
+        .arg(path)
+        .args(["-frames:v", "1", "-vf"])
+        .arg(format!("scale={WIDTH}:-2"))
+        .arg(out);
+
+    cmd.output()
+        .is_ok_and(|output| output.status.success() && out.is_file())
+}
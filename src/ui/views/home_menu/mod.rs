@@ -1,32 +1,71 @@
-use core::fmt::Debug;
+use core::{cell::Cell, fmt::Debug};
+use std::path::Path;
 
 use egui::{Align, Color32, FocusDirection, Frame, Id, Layout, Margin, ScrollArea};
 use gilrs::PowerInfo;
 
 use crate::{
-    command::{Actions, Command},
-    ui::View,
+    command::{Actions, Command, LeftRight},
+    config::config,
+    store::hints::Hint,
+    ui::{
+        View,
+        focus::{self, Flow, FocusOrder},
+    },
     utils::ResponseExt as _,
 };
 
+mod continue_watching;
+mod diagnostics;
+mod guest_mode;
 mod library;
-
-fn entries() -> [Box<dyn HomeMenu>; 1] {
-    [Box::new(library::LibraryMenu)]
+mod notifications;
+mod now_playing;
+mod search;
+mod settings;
+mod tv;
+
+/// Listed bottom to top.
+fn entries() -> [Box<dyn HomeMenu>; 8] {
+    [
+        Box::new(library::LibraryMenu::default()),
+        Box::new(search::SearchMenu::default()),
+        Box::new(tv::TvMenu),
+        Box::new(settings::SettingsMenu),
+        Box::new(guest_mode::GuestModeMenu::default()),
+        Box::new(notifications::NotificationsMenu),
+        Box::new(diagnostics::DiagnosticsMenu),
+        Box::new(now_playing::NowPlayingMenu::default()),
+    ]
 }
 
 #[derive(Debug, Default)]
 pub struct HomeMenuView {
     pub submenu: Option<Box<dyn HomeMenu>>,
+    /// Whether a continue watching card had focus last frame, so left and right move between
+    /// them instead of seeking.
+    rail_focused: Cell<bool>,
 }
 
 impl HomeMenuView {
     pub fn main() -> Self {
-        Self { submenu: None }
+        Self::default()
     }
 
     pub fn sub(menu: Box<dyn HomeMenu>) -> Self {
-        Self { submenu: Some(menu) }
+        Self {
+            submenu: Some(menu),
+            ..Self::default()
+        }
+    }
+
+    pub fn library() -> Self {
+        Self::sub(Box::new(library::LibraryMenu::default()))
+    }
+
+    /// Makes the library show `path` next time it's opened.
+    pub fn browse(ctx: &egui::Context, path: &Path) {
+        library::set_cwd(ctx, path);
     }
 }
 
@@ -43,8 +82,17 @@ impl View for HomeMenuView {
                         .inner_margin(Margin::symmetric(2, 2))
                         .fill(ctx.style().visuals.panel_fill)
                 })
-                .exact_width(150.)
+                .exact_width(if continue_watching::is_empty(app) {
+                    150.
+                } else {
+                    300.
+                })
                 .show(ctx, |ui| {
+                    FocusOrder::new()
+                        .group("continue watching", Flow::Row)
+                        .group("entries", Flow::BottomUp)
+                        .begin(ctx);
+
                     ui.with_layout(Layout::bottom_up(Align::Min).with_cross_justify(true), |ui| {
                         ui.spacing_mut().interact_size.y = 24.;
                         ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::TRANSPARENT;
@@ -57,6 +105,7 @@ impl View for HomeMenuView {
                         for entry in entries() {
                             let resp = ui
                                 .add_enabled(entry.enabled(app), egui::Button::new(entry.label()));
+                            focus::add(&resp, "entries");
 
                             if entry.label() == autofocus {
                                 resp.autofocus();
@@ -68,20 +117,28 @@ impl View for HomeMenuView {
                             }
                         }
 
+                        if continue_watching::is_empty(app) {
+                            self.rail_focused.set(false);
+                        } else {
+                            ui.add_space(8.);
+                            self.rail_focused.set(continue_watching::rail(ui, app));
+                        }
+
                         ui.with_layout(
                             Layout::top_down(Align::Min).with_cross_justify(true),
                             |ui| {
                                 ui.add_space(8.);
 
                                 for &id in app.gamepad.gamepads() {
-                                    let gamepad = app.gamepad.get(id);
-
                                     match app.gamepad.power_info(id) {
                                         PowerInfo::Charging(level)
                                         | PowerInfo::Discharging(level) => {
-                                            ui.label(gamepad.name()).ralign_overlay(ui, |ui| {
-                                                ui.label(format!("{}%", level));
-                                            });
+                                            ui.label(app.gamepad.name(id)).ralign_overlay(
+                                                ui,
+                                                |ui| {
+                                                    ui.label(format!("{}%", level));
+                                                },
+                                            );
                                         }
                                         _ => {}
                                     }
@@ -94,12 +151,19 @@ impl View for HomeMenuView {
     }
 
     fn button_actions(&self) -> Actions {
-        let left_right = if self.submenu.as_ref().is_some_and(|m| m.catch_left_right()) {
+        let left_right = match &self.submenu {
+            Some(submenu) => submenu.left_right(),
+            None if self.rail_focused.get() => LeftRight::MoveFocus,
+            None => config().menu_left_right,
+        }
+        .actions();
+
+        let up_down = if self.submenu.as_ref().is_some_and(|m| m.catch_up_down()) {
             Actions::default()
         } else {
             Actions {
-                left: Command::SeekBackwardStateless,
-                right: Command::SeekForwardStateless,
+                up: Command::MoveFocus(FocusDirection::Up),
+                down: Command::MoveFocus(FocusDirection::Down),
                 ..Actions::default()
             }
         };
@@ -111,15 +175,21 @@ impl View for HomeMenuView {
             } else {
                 Command::HideUi
             },
-            x: Command::TogglePause,
-            up: Command::MoveFocus(FocusDirection::Up),
-            down: Command::MoveFocus(FocusDirection::Down),
-            // left: Command::MoveFocus(FocusDirection::Left),
-            // right: Command::MoveFocus(FocusDirection::Right),
+            x: if self.submenu.as_ref().is_some_and(|m| m.catch_x()) {
+                Command::None
+            } else {
+                Command::TogglePause
+            },
+            up: up_down.up,
+            down: up_down.down,
             home: Command::HideUi,
             ..left_right
         }
     }
+
+    fn hint(&self) -> Option<Hint> {
+        self.submenu.is_none().then_some(Hint::HomeMenu)
+    }
 }
 
 pub trait HomeMenu: 'static {
@@ -160,7 +230,17 @@ pub trait HomeMenu: 'static {
 
     fn draw(&self, ui: &mut egui::Ui, app: &mut crate::App);
 
-    fn catch_left_right(&self) -> bool {
+    /// What left and right do while the submenu is open.
+    fn left_right(&self) -> LeftRight {
+        config().menu_left_right
+    }
+
+    fn catch_up_down(&self) -> bool {
+        false
+    }
+
+    /// Whether the submenu uses X itself, instead of it pausing.
+    fn catch_x(&self) -> bool {
         false
     }
 }
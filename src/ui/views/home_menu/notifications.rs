@@ -0,0 +1,55 @@
+use egui::{Frame, Id, RichText, Sense};
+
+use super::HomeMenu;
+use crate::{App, utils::ResponseExt as _};
+
+/// Toasts that have already gone, with when they came up.
+pub struct NotificationsMenu;
+
+impl HomeMenu for NotificationsMenu {
+    fn label(&self) -> &'static str {
+        "Notifications"
+    }
+
+    fn enabled(&self, app: &App) -> bool {
+        !app.toast_history.is_empty()
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let clear = ui.button("Clear");
+        clear.autofocus();
+
+        if app.toast_history.is_empty() {
+            ui.label(RichText::new("Nothing yet").weak());
+        }
+
+        for (i, (at, toast)) in app.toast_history.iter().enumerate() {
+            ui.add_space(8.);
+
+            let entry = Frame::new()
+                .show(ui, |ui| {
+                    ui.label(
+                        RichText::new(at.format("%H:%M").to_string())
+                            .size(10.)
+                            .weak(),
+                    );
+                    toast.ui(ui);
+                })
+                .response;
+
+            // focusable so the list can be scrolled through with the d-pad
+            let entry = ui.interact(
+                entry.rect,
+                Id::new("notification").with(i),
+                Sense::focusable_noninteractive(),
+            );
+            if entry.gained_focus() {
+                entry.scroll_to_me(None);
+            }
+        }
+
+        if clear.activated() {
+            app.toast_history.clear();
+        }
+    }
+}
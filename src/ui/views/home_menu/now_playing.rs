@@ -0,0 +1,100 @@
+use core::cell::RefCell;
+use std::path::Path;
+
+use egui::{ProgressBar, RichText, Widget as _};
+
+use super::{HomeMenu, library};
+use crate::{
+    App,
+    command::Command,
+    ui::{image_cache, views::media_menu::MediaMenuView},
+    utils::ResponseExt as _,
+};
+
+#[derive(Default)]
+pub struct NowPlayingMenu {
+    /// Artwork URI for the path it was looked up for, so we don't hit the filesystem every frame.
+    artwork: RefCell<Option<(String, Option<String>)>>,
+}
+
+impl HomeMenu for NowPlayingMenu {
+    fn label(&self) -> &'static str {
+        "Now Playing"
+    }
+
+    fn enabled(&self, app: &App) -> bool {
+        app.mpv.get_property_cached::<String>("path").is_some()
+    }
+
+    fn width(&self) -> f32 {
+        400.
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let Some(path) = app.mpv.get_property_cached::<String>("path") else {
+            ui.label("Nothing is playing");
+            return;
+        };
+
+        let artwork = {
+            let mut cache = self.artwork.borrow_mut();
+            match &*cache {
+                Some((cached, artwork)) if *cached == path => artwork.clone(),
+                _ => {
+                    let artwork = find_artwork(app, &path);
+                    *cache = Some((path.clone(), artwork.clone()));
+                    artwork
+                }
+            }
+        };
+
+        if let Some(uri) = artwork {
+            ui.add(
+                image_cache::image(ui.ctx(), uri)
+                    .max_height(200.)
+                    .corner_radius(4.),
+            );
+        }
+
+        ui.add_space(4.);
+        ui.label(RichText::new(app.mpv.get_property::<String>("media-title")).size(18.));
+        if let Some(artist) = &app.mpv.metadata().artist {
+            ui.label(RichText::new(artist).size(12.));
+        }
+
+        ui.add_space(4.);
+        let pos = app.mpv.time_pos_fallback();
+        let duration = app.mpv.duration_fallback();
+        ProgressBar::new(pos / duration).desired_height(4.).ui(ui);
+        ui.label(RichText::new(format!("{} / {}", pos.mmss(), duration.mmss())).size(10.));
+        ui.add_space(8.);
+
+        let paused = app.mpv.get_property_cached("pause") == Some(true);
+        let button = ui.button(if paused { "Play" } else { "Pause" });
+        button.autofocus();
+        if button.activated() {
+            app.queue_command(Command::TogglePause);
+        }
+
+        if ui.button("Media Menu").activated() {
+            app.change_view(MediaMenuView::main());
+        }
+
+        if ui.button("Stop").activated() {
+            app.queue_command(Command::Stop);
+        }
+    }
+}
+
+fn find_artwork(app: &App, path: &str) -> Option<String> {
+    if let Some(id) = app.mpv.youtube_id() {
+        return Some(format!("https://i.ytimg.com/vi/{id}/hqdefault.jpg"));
+    }
+
+    let path = Path::new(path);
+
+    library::local_artwork(path)
+        .map(|artwork| format!("file://{}", artwork.display()))
+        // artwork that comes with the file beats whatever the scrapers found
+        .or_else(|| app.scrapers.cached(path)?.poster_uri())
+}
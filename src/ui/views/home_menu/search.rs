@@ -0,0 +1,273 @@
+//! Searching the Library, the playlist and watch history at once, with the results grouped by
+//! where they came from.
+
+use core::cell::RefCell;
+use std::{collections::HashSet, path::PathBuf};
+
+use egui::RichText;
+
+use super::{HomeMenu, library};
+use crate::{
+    App,
+    command::{Command, LeftRight},
+    config::config,
+    mpv::time::Time,
+    ui::keyboard::{KeyboardResponse, OnScreenKeyboard},
+    utils::ResponseExt as _,
+};
+
+/// Results shown per group until it's expanded.
+const COLLAPSED_RESULTS: usize = 5;
+
+pub struct SearchMenu {
+    state: RefCell<State>,
+}
+
+struct State {
+    query: String,
+    /// Whether the keyboard is open.
+    typing: bool,
+    /// Whether the keyboard should take focus, for when it was just opened.
+    focus_keyboard: bool,
+    /// Groups that show all their results.
+    expanded: HashSet<Source>,
+}
+
+impl Default for SearchMenu {
+    fn default() -> Self {
+        Self {
+            state: RefCell::new(State {
+                query: String::new(),
+                typing: true,
+                focus_keyboard: true,
+                expanded: HashSet::new(),
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Source {
+    Library,
+    Playlist,
+    History,
+}
+
+impl Source {
+    fn label(self) -> &'static str {
+        match self {
+            Source::Library => "Library",
+            Source::Playlist => "Playlist",
+            Source::History => "History",
+        }
+    }
+}
+
+struct Hit {
+    label: String,
+    target: Target,
+}
+
+#[derive(Clone)]
+enum Target {
+    Library(PathBuf),
+    /// Index into mpv's playlist.
+    Playlist(usize),
+    History {
+        path: String,
+        position: Time,
+    },
+}
+
+impl HomeMenu for SearchMenu {
+    fn label(&self) -> &'static str {
+        "Search"
+    }
+
+    fn enabled(&self, _app: &App) -> bool {
+        true
+    }
+
+    fn width(&self) -> f32 {
+        500.
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+        let mut done_typing = false;
+
+        if !state.typing && ui.button(format!("🔍 {}", state.query)).activated() {
+            state.typing = true;
+            state.focus_keyboard = true;
+        }
+
+        if state.focus_keyboard {
+            library::rescan(&config().library_root);
+        }
+
+        if state.typing {
+            let response = OnScreenKeyboard::new(&mut state.query)
+                .focus(state.focus_keyboard)
+                .show(ui);
+            state.focus_keyboard = false;
+
+            if response == KeyboardResponse::Done {
+                state.typing = false;
+                done_typing = true;
+            }
+        }
+
+        let words = state
+            .query
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+
+        if words.is_empty() {
+            return;
+        }
+
+        let groups = [
+            (Source::Library, library_hits(ui, app, &state.query)),
+            (Source::Playlist, playlist_hits(app, &words)),
+            (Source::History, history_hits(app, &words)),
+        ];
+
+        if groups.iter().all(|(_, hits)| hits.is_empty()) {
+            ui.add_space(8.);
+            ui.label(RichText::new("Nothing found").weak());
+            return;
+        }
+
+        let mut focus_first = done_typing;
+        let mut open = None;
+
+        for (source, hits) in groups {
+            if hits.is_empty() {
+                continue;
+            }
+
+            ui.add_space(8.);
+            ui.label(RichText::new(format!("{} ({})", source.label(), hits.len())).size(10.));
+
+            let expanded = state.expanded.contains(&source);
+            let shown = if expanded {
+                hits.len()
+            } else {
+                COLLAPSED_RESULTS
+            };
+
+            for hit in hits.iter().take(shown) {
+                let button = ui.button(&hit.label);
+
+                if focus_first {
+                    button.request_focus();
+                    focus_first = false;
+                }
+
+                if button.has_focus() {
+                    ui.scroll_to_rect(button.rect, None);
+                }
+
+                if button.activated() {
+                    open = Some(hit.target.clone());
+                }
+            }
+
+            if hits.len() > COLLAPSED_RESULTS {
+                let label = if expanded {
+                    "Show fewer".to_string()
+                } else {
+                    format!("Show all {}", hits.len())
+                };
+
+                let button = ui.button(RichText::new(label).weak());
+
+                if button.has_focus() {
+                    ui.scroll_to_rect(button.rect, None);
+                }
+
+                if button.activated() && !state.expanded.remove(&source) {
+                    state.expanded.insert(source);
+                }
+            }
+
+            if let Some(target) = open.take() {
+                open_target(ui, app, &target);
+                return;
+            }
+        }
+    }
+
+    fn left_right(&self) -> LeftRight {
+        if self.state.borrow().typing {
+            // between the keys
+            LeftRight::MoveFocus
+        } else {
+            config().menu_left_right
+        }
+    }
+}
+
+fn library_hits(ui: &egui::Ui, app: &mut App, query: &str) -> Vec<Hit> {
+    library::search(ui, app, query)
+        .into_iter()
+        .map(|(path, label)| Hit { label, target: Target::Library(path) })
+        .collect()
+}
+
+fn playlist_hits(app: &App, words: &[String]) -> Vec<Hit> {
+    app.mpv
+        .playlist()
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| {
+            matches(words, &entry.filename)
+                || entry.title.as_ref().is_some_and(|t| matches(words, t))
+        })
+        .map(|(index, entry)| Hit {
+            label: entry.display_name().to_string(),
+            target: Target::Playlist(index),
+        })
+        .collect()
+}
+
+fn history_hits(app: &App, words: &[String]) -> Vec<Hit> {
+    app.history
+        .entries()
+        .iter()
+        .filter(|entry| {
+            matches(words, &entry.path) || entry.title.as_ref().is_some_and(|t| matches(words, t))
+        })
+        .map(|entry| Hit {
+            label: entry.title.clone().unwrap_or_else(|| entry.path.clone()),
+            target: Target::History {
+                path: entry.path.clone(),
+                position: entry.position,
+            },
+        })
+        .collect()
+}
+
+/// Whether the text contains all of the (lowercase) words.
+fn matches(words: &[String], text: &str) -> bool {
+    let text = text.to_lowercase();
+    words.iter().all(|word| text.contains(word.as_str()))
+}
+
+fn open_target(ui: &mut egui::Ui, app: &mut App, target: &Target) {
+    match target {
+        Target::Library(path) => library::open(ui, app, path),
+        Target::Playlist(index) => {
+            app.mpv.set_property("playlist-pos", *index as i64).ok();
+            app.mpv.unpause().ok();
+            app.queue_command(Command::HideUi);
+        }
+        Target::History { path, position } => {
+            app.mpv.load_file_at(path, *position).ok();
+            app.mpv.unpause().ok();
+            app.queue_command(Command::HideUi);
+        }
+    }
+}
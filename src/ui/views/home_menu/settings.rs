@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use egui::RichText;
+use gilrs::Button;
+
+use super::HomeMenu;
+use crate::{
+    App,
+    store::settings::CachePreset,
+    ui::{
+        image_cache, theme,
+        views::{confirm::ConfirmView, home_menu::HomeMenuView},
+    },
+    utils::ResponseExt as _,
+};
+
+const AUDIO_FADE_STEPS: [f32; 5] = [0., 0.2, 0.3, 0.5, 1.];
+
+pub struct SettingsMenu;
+
+impl HomeMenu for SettingsMenu {
+    fn label(&self) -> &'static str {
+        "Settings"
+    }
+
+    fn enabled(&self, app: &App) -> bool {
+        !app.guest_mode
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let button = ui.button("TV distance");
+        button.ralign_overlay(ui, |ui| {
+            ui.add_space(8.);
+            ui.label(if app.settings.tv_distance {
+                "On"
+            } else {
+                "Off"
+            });
+        });
+        button.autofocus();
+
+        if button.activated() {
+            app.settings.tv_distance = !app.settings.tv_distance;
+            app.settings.save();
+
+            theme::apply(ui.ctx(), &app.settings);
+            app.mpv
+                .set_property("sub-scale", theme::sub_scale(&app.settings))
+                .ok();
+        }
+
+        let button = ui.button("Audio fade-in");
+        button.ralign_overlay(ui, |ui| {
+            ui.add_space(8.);
+            ui.label(if app.settings.audio_fade > 0. {
+                format!("{:.1}s", app.settings.audio_fade)
+            } else {
+                "Off".to_string()
+            });
+        });
+
+        if button.has_focus() {
+            let fade = app.settings.audio_fade;
+
+            let shorter = app.gamepad.claim(Button::DPadLeft, "Shorter");
+            let longer = app.gamepad.claim(Button::DPadRight, "Longer");
+
+            // stepping from whatever it is, which might not be one of the steps if it was edited
+            // by hand
+            let new_fade = if shorter {
+                AUDIO_FADE_STEPS.iter().rev().find(|&&s| s < fade)
+            } else if longer {
+                AUDIO_FADE_STEPS.iter().find(|&&s| s > fade)
+            } else {
+                None
+            };
+
+            if let Some(&new_fade) = new_fade {
+                app.settings.audio_fade = new_fade;
+                app.settings.save();
+                app.mpv.set_audio_fade(app.settings.audio_fade());
+            }
+        }
+
+        ui.add_space(8.);
+        ui.label(RichText::new("Network playback").size(10.));
+
+        let preset = app.settings.cache_preset;
+        let button = ui.button("Cache");
+        button.ralign_overlay(ui, |ui| {
+            ui.add_space(8.);
+            ui.label(preset.label());
+        });
+
+        if button.has_focus() {
+            let idx = CachePreset::ALL
+                .iter()
+                .position(|&p| p == preset)
+                .unwrap_or(0);
+
+            let less = app.gamepad.claim(Button::DPadLeft, "Less");
+            let more = app.gamepad.claim(Button::DPadRight, "More");
+
+            let new_idx = if less {
+                idx.saturating_sub(1)
+            } else if more {
+                (idx + 1).min(CachePreset::ALL.len() - 1)
+            } else {
+                idx
+            };
+
+            if CachePreset::ALL[new_idx] != preset {
+                app.settings.cache_preset = CachePreset::ALL[new_idx];
+                app.settings.save();
+                app.mpv.set_cache_preset(app.settings.cache_preset);
+            }
+
+            let hint = match app.settings.cache_preset.properties() {
+                Some([_, (_, demuxer), (_, stream)]) => {
+                    format!("Buffers up to {demuxer}, reading {stream} at a time")
+                }
+                None => "Uses mpv's own cache settings".to_string(),
+            };
+            ui.label(RichText::new(hint).size(10.).weak());
+        }
+
+        ui.add_space(8.);
+        if ui.button("Show hints again").activated() {
+            app.hints.reset();
+        }
+
+        ui.add_space(8.);
+        ui.label(RichText::new("Backup").size(10.));
+        backup_rows(ui, app);
+
+        ui.add_space(8.);
+        ui.label(
+            RichText::new(format!(
+                "Image cache: {:.0} / {} MB",
+                image_cache::bytes(ui.ctx()) as f32 / 1024. / 1024.,
+                image_cache::budget() / 1024 / 1024,
+            ))
+            .size(10.)
+            .weak(),
+        );
+    }
+}
+
+/// Backing up to and restoring from each plugged in drive.
+fn backup_rows(ui: &mut egui::Ui, app: &mut App) {
+    let mounts = app.removable.mounts().to_vec();
+    if mounts.is_empty() {
+        ui.label(
+            RichText::new("Plug in a USB drive to back up to or restore from")
+                .size(10.)
+                .weak(),
+        );
+    }
+
+    for mount in mounts {
+        let label = if app.backups.is_writing(&mount.mount_point) {
+            format!("Backing up to {}…", mount.label)
+        } else {
+            format!("Back up to {}", mount.label)
+        };
+        if ui.button(label).activated() {
+            app.backups.create(&mount.mount_point);
+        }
+
+        let Some(latest) = app
+            .backups
+            .latest(&mount.mount_point)
+            .map(Path::to_path_buf)
+        else {
+            continue;
+        };
+        let name = latest
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+
+        let label = if app.backups.is_restoring() {
+            format!("Restoring {name}…")
+        } else {
+            format!("Restore {name}")
+        };
+        if ui.button(label).activated() {
+            app.change_view(ConfirmView::new(
+                "Replace settings, history and favorites with the backup?",
+                Box::new(HomeMenuView::sub(Box::new(SettingsMenu))),
+                move |app| app.backups.restore(&latest),
+            ));
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use super::HomeMenu;
+use crate::{App, command::Command, utils::ResponseExt as _};
+
+/// Controls the TV over HDMI-CEC, for when its own remote isn't around.
+pub struct TvMenu;
+
+impl HomeMenu for TvMenu {
+    fn label(&self) -> &'static str {
+        "TV"
+    }
+
+    fn enabled(&self, app: &App) -> bool {
+        app.cec.is_open()
+    }
+
+    fn width(&self) -> f32 {
+        200.
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        for (idx, command) in [Command::TakeCecFocus, Command::TvOn, Command::TvStandby]
+            .into_iter()
+            .enumerate()
+        {
+            let button = ui.button(command.label(app));
+
+            if idx == 0 {
+                button.autofocus();
+            }
+
+            if button.activated() {
+                app.queue_command(command);
+            }
+        }
+    }
+}
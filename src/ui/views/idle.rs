@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use egui::{Align, Align2, Area, Color32, Frame, Id, Layout, RichText, vec2};
+
+use crate::{
+    App,
+    command::{Actions, Command},
+    ui::{
+        View,
+        views::{hidden::HiddenView, home_menu::HomeMenuView},
+    },
+    utils::ResponseExt as _,
+};
+
+/// Shown when mpv goes idle with nothing left to play, so the TV isn't just a black screen.
+pub struct IdleView;
+
+impl View for IdleView {
+    fn draw(&self, ctx: &egui::Context, app: &mut App) {
+        Area::new(Id::new("idle"))
+            .anchor(Align2::CENTER_CENTER, vec2(0., 0.))
+            .show(ctx, |ui| {
+                Frame::new()
+                    .fill(Color32::from_black_alpha(192))
+                    .corner_radius(8.)
+                    .inner_margin(16.)
+                    .show(ui, |ui| {
+                        ui.set_width(300.);
+                        ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
+                            draw_buttons(ui, app);
+                        });
+                    });
+            });
+    }
+
+    fn button_actions(&self) -> Actions {
+        Actions {
+            a: Command::Activate,
+            b: Command::HideUi,
+            home: Command::ShowHomeMenu,
+            ..Actions::default()
+        }
+    }
+}
+
+fn draw_buttons(ui: &mut egui::Ui, app: &mut App) {
+    ui.label(RichText::new("Nothing playing").heading());
+    ui.add_space(8.);
+
+    let library = ui.button("Open Library");
+    library.autofocus();
+    if library.activated() {
+        app.change_view(HomeMenuView::library());
+    }
+
+    if let Some(last) = app.history.entries().first() {
+        let name = last.title.clone().unwrap_or_else(|| {
+            let path = Path::new(&last.path);
+            path.file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned()
+        });
+        let path = last.path.clone();
+
+        let replay = ui.button(format!("Replay {name}"));
+        if replay.activated() {
+            app.mpv.load_file(&path).ok();
+            app.mpv.unpause().ok();
+            app.change_view(HiddenView);
+        }
+    }
+
+    if !app.guest_mode && ui.button("Quit").activated() {
+        app.queue_command(Command::Quit);
+    }
+}
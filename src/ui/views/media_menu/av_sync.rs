@@ -0,0 +1,84 @@
+use gilrs::Button;
+
+use super::MediaMenu;
+use crate::{App, mpv::AudioDevice, utils::ResponseExt as _};
+
+/// Step size for both offsets, in seconds.
+const STEP: f32 = 0.05;
+
+pub struct AvSyncMenu;
+
+impl MediaMenu for AvSyncMenu {
+    fn label(&self) -> &'static str {
+        "A/V Sync"
+    }
+
+    fn enabled(&self, _app: &App) -> bool {
+        true
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let device = app.mpv.get_property::<String>("audio-device");
+        let devices = app
+            .mpv
+            .get_property::<Vec<AudioDevice>>("audio-device-list");
+        let label = devices
+            .iter()
+            .find(|d| d.name == device)
+            .map_or(device.as_str(), |d| d.description.as_str());
+
+        let delay = app.settings.audio_delay(&device);
+        if let Some(new_delay) = offset_row(ui, app, &format!("Audio ({label})"), delay) {
+            // remembered per device, see App::update
+            app.settings.set_audio_delay(&device, new_delay);
+            app.mpv.set_property("audio-delay", new_delay).ok();
+        }
+
+        // the speaker seeks itself to match, see Dlna::update_cast
+        if let Some(idx) = app.dlna.casting() {
+            let device = &app.dlna.devices()[idx];
+            let (key, name) = (device.delay_key(), device.friendly_name().to_string());
+
+            let delay = app.settings.audio_delay(&key);
+            if let Some(new_delay) = offset_row(ui, app, &format!("Speaker ({name})"), delay) {
+                app.settings.set_audio_delay(&key, new_delay);
+            }
+        }
+
+        let sub_delay = app.mpv.get_property::<f32>("sub-delay");
+        if let Some(new_sub_delay) = offset_row(ui, app, "Subtitles", sub_delay) {
+            app.mpv.set_property("sub-delay", new_sub_delay).ok();
+        }
+    }
+}
+
+/// A row that shows an offset in seconds, adjusted with left/right and reset by activating it.
+/// Returns the new offset if it was changed.
+fn offset_row(ui: &mut egui::Ui, app: &mut App, label: &str, offset: f32) -> Option<f32> {
+    let button = ui.button(label);
+    button.ralign_overlay(ui, |ui| {
+        ui.add_space(8.);
+        ui.label(format!("{:+.0} ms", offset * 1000.));
+    });
+    button.autofocus();
+
+    let mut new_offset = offset;
+
+    if button.has_focus() && app.gamepad.claim(Button::DPadLeft, "Earlier") {
+        new_offset -= STEP;
+    }
+
+    if button.has_focus() && app.gamepad.claim(Button::DPadRight, "Later") {
+        new_offset += STEP;
+    }
+
+    if button.activated() {
+        new_offset = 0.;
+    }
+
+    // keep it on the step grid instead of accumulating float error, and don't count what mpv
+    // reports back with its own float error as a change
+    let round = |offset: f32| (offset * 1000.).round() / 1000.;
+    let new_offset = round(new_offset);
+    (new_offset != round(offset)).then_some(new_offset)
+}
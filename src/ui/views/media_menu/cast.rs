@@ -0,0 +1,63 @@
+use egui::{Color32, RichText};
+
+use super::MediaMenu;
+use crate::{App, BLUE, command::Event, ui::toast::Toast, utils::ResponseExt as _};
+
+/// Plays the audio on a DLNA speaker instead, with the video staying here muted.
+pub struct CastMenu;
+
+impl MediaMenu for CastMenu {
+    fn label(&self) -> &'static str {
+        "Cast Audio"
+    }
+
+    fn enabled(&self, app: &App) -> bool {
+        app.dlna.has_devices()
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let casting = app.dlna.casting();
+
+        let button = ui.button(RichText::new("This device").color(if casting.is_none() {
+            BLUE
+        } else {
+            Color32::WHITE
+        }));
+
+        if casting.is_none() {
+            button.autofocus();
+        }
+
+        if button.activated() {
+            app.dlna.stop_cast(&mut app.mpv);
+        }
+
+        for idx in 0..app.dlna.devices().len() {
+            let name = app.dlna.devices()[idx].friendly_name().to_string();
+            let is_current = casting == Some(idx);
+
+            let button = ui.button(RichText::new(name).color(if is_current {
+                BLUE
+            } else {
+                Color32::WHITE
+            }));
+
+            if is_current {
+                button.autofocus();
+            }
+
+            if button.activated()
+                && !is_current
+                && let Err(reason) = app.dlna.cast(idx, &mut app.mpv, &app.settings)
+            {
+                app.queued_events
+                    .push(Event::Toast(Toast::CastFailed { reason }));
+            }
+        }
+
+        if casting.is_some() {
+            ui.add_space(8.);
+            ui.label(RichText::new("Adjust the speaker's delay under A/V Sync").size(10.));
+        }
+    }
+}
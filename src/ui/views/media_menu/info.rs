@@ -1,6 +1,14 @@
+use std::path::Path;
+
 use egui::{Margin, RichText};
 
 use super::MediaMenu;
+use crate::{
+    mpv::VideoParams,
+    scraper::Metadata,
+    ui::image_cache,
+    utils::{decoder_label, detail_row},
+};
 
 pub struct InfoMenu;
 
@@ -11,6 +19,8 @@ impl MediaMenu for InfoMenu {
 
     fn enabled(&self, app: &crate::App) -> bool {
         app.mpv.metadata().has_anything_interesting()
+            || scraped(app).is_some()
+            || app.mpv.video_params().is_some()
     }
 
     fn width(&self) -> f32 {
@@ -24,23 +34,159 @@ impl MediaMenu for InfoMenu {
     }
 
     fn draw(&self, ui: &mut egui::Ui, app: &mut crate::App) {
-        if let Some(ref title) = app.mpv.metadata().title {
-            ui.label(RichText::new(title).heading());
+        if let Some(metadata) = scraped(app) {
+            draw_scraped(ui, metadata);
+            ui.add_space(16.);
+        } else if app.mpv.metadata().has_anything_interesting() {
+            draw_metadata(ui, app);
+            ui.add_space(16.);
         }
 
-        ui.horizontal(|ui| {
-            if let Some(ref artist) = app.mpv.metadata().artist {
-                ui.label(artist);
-            }
+        if let Some(params) = app.mpv.video_params() {
+            draw_technical(ui, app, &params);
+        }
+    }
+}
+
+fn draw_metadata(ui: &mut egui::Ui, app: &crate::App) {
+    if let Some(ref title) = app.mpv.metadata().title {
+        ui.label(RichText::new(title).heading());
+    }
+
+    ui.horizontal(|ui| {
+        if let Some(ref artist) = app.mpv.metadata().artist {
+            ui.label(artist);
+        }
+
+        if let Some(ref date) = app.mpv.metadata().date {
+            ui.label(date.format("%Y-%m-%d").to_string());
+        }
+    });
 
-            if let Some(ref date) = app.mpv.metadata().date {
-                ui.label(date.format("%Y-%m-%d").to_string());
+    if let Some(ref description) = app.mpv.metadata().description {
+        ui.add_space(16.);
+        ui.label(description);
+    }
+}
+
+/// What the scrapers found for the playing file, if it came from the Library.
+fn scraped(app: &crate::App) -> Option<&Metadata> {
+    let path = app.mpv.get_property_cached::<String>("path")?;
+    app.scrapers.cached(Path::new(&path))
+}
+
+fn draw_scraped(ui: &mut egui::Ui, metadata: &Metadata) {
+    ui.horizontal_top(|ui| {
+        if let Some(uri) = metadata.poster_uri() {
+            ui.add(
+                image_cache::image(ui.ctx(), uri)
+                    .max_width(120.)
+                    .corner_radius(4.),
+            );
+        }
+
+        ui.vertical(|ui| {
+            ui.label(RichText::new(&metadata.title).heading());
+
+            if let Some((season, episode, title)) = &metadata.episode {
+                ui.label(format!("S{season:02}E{episode:02} · {title}"));
             }
+
+            ui.horizontal(|ui| {
+                if let Some(year) = metadata.year {
+                    ui.label(year.to_string());
+                }
+
+                if let Some(rating) = metadata.rating {
+                    ui.label(format!("{rating:.1}/10"));
+                }
+            });
+
+            ui.label(RichText::new(&metadata.source).size(10.));
         });
+    });
 
-        if let Some(ref description) = app.mpv.metadata().description {
-            ui.add_space(16.);
-            ui.label(description);
+    if let Some(overview) = &metadata.overview {
+        ui.add_space(16.);
+        ui.label(overview);
+    }
+}
+
+/// What's actually being decoded and output, for checking HDR passthrough and hardware decoding.
+fn draw_technical(ui: &mut egui::Ui, app: &mut crate::App, params: &VideoParams) {
+    ui.label(RichText::new("Technical").size(10.).weak());
+
+    if let (Some(w), Some(h)) = (params.w, params.h) {
+        let format = app.mpv.get_property::<String>("video-format");
+        detail_row(ui, "Video", format!("{w}×{h} {format}"));
+    }
+
+    if let Some(pixelformat) = &params.pixelformat {
+        detail_row(ui, "Pixel format", pixelformat.clone());
+    }
+
+    let color = [&params.colormatrix, &params.primaries, &params.gamma]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if !color.is_empty() {
+        detail_row(ui, "Color", color.join(" · "));
+    }
+
+    if params.is_hdr() {
+        if let (Some(min), Some(max)) = (params.min_luma, params.max_luma) {
+            detail_row(ui, "Mastering display", format!("{min:.4}–{max:.0} nits"));
         }
+
+        if let (Some(cll), Some(fall)) = (params.max_cll, params.max_fall) {
+            detail_row(ui, "MaxCLL / MaxFALL", format!("{cll:.0} / {fall:.0} nits"));
+        }
+
+        let output = if app.mpv.is_tone_mapping_hdr() {
+            "Tone mapped to SDR"
+        } else {
+            "HDR passthrough"
+        };
+        detail_row(ui, "Output", output.to_string());
+    }
+
+    let hwdec = app.mpv.get_property::<String>("hwdec-current");
+    detail_row(ui, "Decoder", decoder_label(&hwdec));
+
+    let estimated = app.mpv.get_property::<Option<f64>>("estimated-vf-fps");
+    let container = app.mpv.get_property::<Option<f64>>("container-fps");
+    match (estimated, container) {
+        (Some(estimated), Some(container)) => {
+            detail_row(ui, "Frame rate", format!("{estimated:.3} fps (file says {container:.3})"));
+        }
+        (Some(fps), None) | (None, Some(fps)) => {
+            detail_row(ui, "Frame rate", format!("{fps:.3} fps"))
+        }
+        (None, None) => {}
+    }
+
+    let container = app.mpv.get_property::<String>("file-format");
+    if !container.is_empty() {
+        detail_row(ui, "Container", container);
+    }
+
+    let video = app.mpv.get_property::<Option<f64>>("video-bitrate");
+    let audio = app.mpv.get_property::<Option<f64>>("audio-bitrate");
+    if video.is_some() || audio.is_some() {
+        let bitrates = [("video", video), ("audio", audio)]
+            .into_iter()
+            .filter_map(|(kind, rate)| Some(format!("{} {kind}", bitrate(rate?))))
+            .collect::<Vec<_>>();
+        detail_row(ui, "Bitrate", bitrates.join(" · "));
+    }
+}
+
+/// Bits per second, in whichever unit keeps it short.
+fn bitrate(bps: f64) -> String {
+    if bps >= 1_000_000. {
+        format!("{:.1} Mbps", bps / 1_000_000.)
+    } else {
+        format!("{:.0} kbps", bps / 1_000.)
     }
 }
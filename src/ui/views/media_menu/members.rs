@@ -0,0 +1,41 @@
+use egui::RichText;
+
+use crate::{BLUE, mpv::time::Time};
+
+/// Someone else watching along, as the Watch Party and Syncplay menus list them.
+pub struct MemberRow<'a> {
+    pub name: &'a str,
+    /// File name of what they're playing.
+    pub file: Option<&'a str>,
+    pub position: Time,
+}
+
+/// Lists the members with what they're playing, highlighting the ones playing the same file as
+/// us.
+pub fn member_list<'a>(
+    ui: &mut egui::Ui,
+    playing: Option<&str>,
+    members: impl ExactSizeIterator<Item = MemberRow<'a>>,
+) {
+    if members.len() == 0 {
+        ui.label(RichText::new("Nobody else here yet").size(10.).weak());
+    }
+
+    for member in members {
+        let in_sync = member.file.is_some() && member.file == playing;
+
+        ui.label(RichText::new(member.name).color(if in_sync {
+            BLUE
+        } else {
+            ui.visuals().text_color()
+        }));
+        ui.label(
+            RichText::new(match member.file {
+                Some(file) if in_sync => format!("{} · {}", member.position.mmss(), file),
+                Some(file) => format!("Playing something else: {file}"),
+                None => "Not playing anything".to_string(),
+            })
+            .size(10.),
+        );
+    }
+}
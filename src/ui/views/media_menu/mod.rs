@@ -3,26 +3,49 @@ use core::fmt::Debug;
 use egui::{Align, Color32, FocusDirection, Frame, Id, Layout, Margin, ScrollArea};
 
 use crate::{
-    command::{Actions, Command},
+    command::{Actions, Command, LeftRight},
+    config::config,
     mpv::TrackType,
+    store::hints::Hint,
     ui::View,
     utils::ResponseExt as _,
 };
 
+mod av_sync;
+mod cast;
 mod chapters;
 mod info;
+mod members;
+mod performance;
+mod picture;
 mod playlist;
+mod rendering;
+mod speed;
+mod sub_style;
+mod syncplay;
+mod tone_mapping;
 mod tracks;
 mod volume;
+mod watch_party;
 
-fn entries() -> [Box<dyn MediaMenu>; 7] {
+fn entries() -> [Box<dyn MediaMenu>; 17] {
     [
         Box::new(volume::VolumeMenu),
-        Box::new(playlist::PlaylistMenu),
+        Box::new(av_sync::AvSyncMenu),
+        Box::new(cast::CastMenu),
+        Box::new(watch_party::WatchPartyMenu),
+        Box::new(syncplay::SyncplayMenu),
+        Box::new(speed::SpeedMenu),
+        Box::new(playlist::PlaylistMenu::default()),
         Box::new(chapters::ChaptersMenu),
         Box::new(tracks::TrackMenu(TrackType::Video)),
         Box::new(tracks::TrackMenu(TrackType::Audio)),
         Box::new(tracks::TrackMenu(TrackType::Sub)),
+        Box::new(sub_style::SubStyleMenu),
+        Box::new(picture::PictureMenu),
+        Box::new(tone_mapping::ToneMappingMenu),
+        Box::new(rendering::RenderingMenu),
+        Box::new(performance::PerformanceMenu),
         Box::new(info::InfoMenu),
     ]
 }
@@ -40,6 +63,14 @@ impl MediaMenuView {
     pub fn sub(menu: Box<dyn MediaMenu>) -> Self {
         Self { submenu: Some(menu) }
     }
+
+    pub fn tone_mapping() -> Self {
+        Self::sub(Box::new(tone_mapping::ToneMappingMenu))
+    }
+
+    pub fn performance() -> Self {
+        Self::sub(Box::new(performance::PerformanceMenu))
+    }
 }
 
 impl View for MediaMenuView {
@@ -107,12 +138,18 @@ impl View for MediaMenuView {
     }
 
     fn button_actions(&self) -> Actions {
-        let left_right = if self.submenu.as_ref().is_some_and(|m| m.catch_left_right()) {
+        let left_right = self
+            .submenu
+            .as_ref()
+            .map_or(config().menu_left_right, |m| m.left_right())
+            .actions();
+
+        let up_down = if self.submenu.as_ref().is_some_and(|m| m.catch_up_down()) {
             Actions::default()
         } else {
             Actions {
-                left: Command::SeekBackwardStateless,
-                right: Command::SeekForwardStateless,
+                up: Command::MoveFocus(FocusDirection::Up),
+                down: Command::MoveFocus(FocusDirection::Down),
                 ..Actions::default()
             }
         };
@@ -124,15 +161,30 @@ impl View for MediaMenuView {
             } else {
                 Command::HideUi
             },
-            x: Command::TogglePause,
-            up: Command::MoveFocus(FocusDirection::Up),
-            down: Command::MoveFocus(FocusDirection::Down),
-            // left: Command::MoveFocus(FocusDirection::Left),
-            // right: Command::MoveFocus(FocusDirection::Right),
+            x: if self.submenu.as_ref().is_some_and(|m| m.catch_x()) {
+                Command::None
+            } else {
+                Command::TogglePause
+            },
+            y: if self.submenu.is_none() {
+                Command::ToggleAudioOnly
+            } else {
+                Command::None
+            },
+            up: up_down.up,
+            down: up_down.down,
             start: Command::HideUi,
             ..left_right
         }
     }
+
+    fn is_dirty(&self) -> bool {
+        self.submenu.as_ref().is_some_and(|m| m.is_dirty())
+    }
+
+    fn hint(&self) -> Option<Hint> {
+        self.submenu.is_none().then_some(Hint::MediaMenu)
+    }
 }
 
 pub trait MediaMenu: 'static {
@@ -149,7 +201,22 @@ pub trait MediaMenu: 'static {
 
     fn draw(&self, ui: &mut egui::Ui, app: &mut crate::App);
 
-    fn catch_left_right(&self) -> bool {
+    /// What left and right do while the submenu is open.
+    fn left_right(&self) -> LeftRight {
+        config().menu_left_right
+    }
+
+    fn catch_up_down(&self) -> bool {
+        false
+    }
+
+    /// Whether the submenu uses X itself, instead of it pausing.
+    fn catch_x(&self) -> bool {
+        false
+    }
+
+    /// Whether the submenu has staged changes that haven't been applied yet.
+    fn is_dirty(&self) -> bool {
         false
     }
 }
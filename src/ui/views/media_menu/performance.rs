@@ -0,0 +1,89 @@
+use super::MediaMenu;
+use crate::{App, command::Command, utils::ResponseExt as _};
+
+/// Heights to step down through when streams are too much for the machine.
+const STREAM_HEIGHTS: [u32; 3] = [1080, 720, 480];
+
+/// Counters for dropped frames, with fixes that each take one press.
+pub struct PerformanceMenu;
+
+impl MediaMenu for PerformanceMenu {
+    fn label(&self) -> &'static str {
+        "Performance"
+    }
+
+    fn enabled(&self, app: &App) -> bool {
+        app.mpv.get_property_cached::<String>("path").is_some()
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let dropped = app.mpv.get_property::<u64>("frame-drop-count");
+        let delayed = app.mpv.get_property::<u64>("vo-delayed-frame-count");
+
+        ui.label(format!("Dropped frames: {dropped}"));
+        ui.label(format!("Delayed frames: {delayed}"));
+        ui.add_space(8.);
+
+        let button = ui.button("Show live statistics");
+        button.autofocus();
+
+        if button.activated() {
+            app.queue_command(Command::ShowStats);
+        }
+
+        // the fixes change how mpv is set up
+        if app.guest_mode {
+            return;
+        }
+
+        let shaders = app.mpv.get_property::<Vec<String>>("glsl-shaders");
+        if !shaders.is_empty() {
+            let button = ui.button(format!("Disable shaders ({})", shaders.len()));
+            button.autofocus();
+
+            if button.activated() {
+                app.mpv
+                    .set_property("glsl-shaders", Vec::<String>::new())
+                    .ok();
+            }
+        }
+
+        // software decoding is the usual culprit, but broken hardware decoding happens too
+        let hwdec = app.mpv.get_property::<String>("hwdec-current");
+        let (label, new_hwdec) = if hwdec.is_empty() || hwdec == "no" {
+            ("Use hardware decoding".to_string(), "auto-safe")
+        } else {
+            (format!("Use software decoding (now {hwdec})"), "no")
+        };
+        let button = ui.button(label);
+        button.autofocus();
+
+        if button.activated() {
+            app.mpv.set_property("hwdec", new_hwdec).ok();
+        }
+
+        if let Some(path) = app.mpv.get_property_cached::<String>("path")
+            && (path.starts_with("http://") || path.starts_with("https://"))
+        {
+            let height = app.mpv.video_params().and_then(|p| p.h);
+
+            if let Some(lower) = STREAM_HEIGHTS
+                .into_iter()
+                .find(|&h| height.is_none_or(|current| h < current))
+            {
+                let button = ui.button(format!("Lower stream to {lower}p"));
+                button.autofocus();
+
+                if button.activated() {
+                    let format =
+                        format!("bestvideo[height<=?{lower}]+bestaudio/best[height<=?{lower}]");
+                    let position = app.mpv.time_pos_fallback();
+
+                    app.mpv.set_property("ytdl-format", format).ok();
+                    // the format only applies when the stream is opened
+                    app.mpv.load_file_at(&path, position).ok();
+                }
+            }
+        }
+    }
+}
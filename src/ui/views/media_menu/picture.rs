@@ -0,0 +1,67 @@
+use gilrs::Button;
+
+use super::MediaMenu;
+use crate::{App, utils::ResponseExt as _};
+
+const PROPERTIES: [(&str, &str); 5] = [
+    ("Brightness", "brightness"),
+    ("Contrast", "contrast"),
+    ("Saturation", "saturation"),
+    ("Gamma", "gamma"),
+    ("Hue", "hue"),
+];
+const STEP: i64 = 5;
+
+/// mpv's video equalizer, for TVs that can't be adjusted per input.
+pub struct PictureMenu;
+
+impl MediaMenu for PictureMenu {
+    fn label(&self) -> &'static str {
+        "Picture"
+    }
+
+    fn enabled(&self, app: &App) -> bool {
+        !app.guest_mode
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        for (label, property) in PROPERTIES {
+            let value = app.mpv.get_property::<i64>(property);
+
+            let button = ui.button(label);
+            button.ralign_overlay(ui, |ui| {
+                ui.add_space(8.);
+                ui.label(format!("{value:+}"));
+            });
+            button.autofocus();
+            button.bg_progress_indicator((value + 100) as f32 / 200.);
+
+            let mut new_value = value;
+
+            if button.has_focus() && app.gamepad.claim(Button::DPadLeft, "Less") {
+                new_value -= STEP;
+            }
+
+            if button.has_focus() && app.gamepad.claim(Button::DPadRight, "More") {
+                new_value += STEP;
+            }
+
+            if button.activated() {
+                new_value = 0;
+            }
+
+            let new_value = new_value.clamp(-100, 100);
+            if new_value != value {
+                app.mpv.set_property(property, new_value).ok();
+            }
+        }
+
+        ui.add_space(8.);
+
+        if ui.button("Reset all").activated() {
+            for (_, property) in PROPERTIES {
+                app.mpv.set_property(property, 0).ok();
+            }
+        }
+    }
+}
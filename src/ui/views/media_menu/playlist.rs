@@ -1,9 +1,128 @@
+use core::{
+    cell::{Cell, RefCell},
+    ops::Range,
+    time::Duration,
+};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
 use egui::{Color32, RichText};
+use gilrs::Button;
 
 use super::MediaMenu;
-use crate::{BLUE, utils::ResponseExt as _};
+use crate::{
+    BLUE,
+    command::{Command, Event},
+    gamepad::button_prompt,
+    mpv::PlaylistEntry,
+    shuffle,
+    ui::{fast_scroll, toast::Toast},
+    utils::ResponseExt as _,
+};
+
+/// How long X has to be held on an entry to open its actions, since tapping it pauses.
+const ACTIONS_HOLD: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+pub struct PlaylistMenu {
+    reorder: RefCell<Option<Reorder>>,
+    /// Position L1 or R1 jumped to, to focus next frame.
+    jump: Cell<Option<usize>>,
+    /// Entry whose actions are open.
+    actions: Cell<Option<ActionsFor>>,
+    /// Folders whose entries are hidden under their header. `None` until the playlist is first
+    /// shown, when everything but the playing folder starts out collapsed.
+    collapsed: RefCell<Option<HashSet<PathBuf>>>,
+    /// Folder header L2 or R2 jumped to, to focus next frame.
+    jump_group: Cell<Option<usize>>,
+}
+
+/// Consecutive entries from the same folder, like an episode list in a queue of whole series.
+struct Group {
+    folder: PathBuf,
+    entries: Range<usize>,
+}
+
+/// The folders the playlist goes through in order, or nothing if it's all the same folder and
+/// headers wouldn't help.
+fn groups(playlist: &[PlaylistEntry]) -> Vec<Group> {
+    let mut groups = Vec::<Group>::new();
+
+    for (idx, entry) in playlist.iter().enumerate() {
+        let folder = Path::new(&entry.filename).parent().unwrap_or(Path::new(""));
+
+        match groups.last_mut() {
+            Some(group) if group.folder == folder => group.entries.end = idx + 1,
+            _ => groups.push(Group {
+                folder: folder.to_path_buf(),
+                entries: idx..idx + 1,
+            }),
+        }
+    }
+
+    if groups.len() > 1 { groups } else { Vec::new() }
+}
+
+#[derive(Clone, Copy)]
+struct ActionsFor {
+    /// Index into mpv's playlist.
+    index: usize,
+    /// Whether the actions were just opened and should take focus.
+    focus: bool,
+}
+
+#[derive(Clone, Copy)]
+enum EntryAction {
+    Remove,
+    MoveUp,
+    MoveDown,
+    Clear,
+}
+
+impl EntryAction {
+    fn label(self) -> &'static str {
+        match self {
+            EntryAction::Remove => "Remove",
+            EntryAction::MoveUp => "Move up",
+            EntryAction::MoveDown => "Move down",
+            EntryAction::Clear => "Clear playlist",
+        }
+    }
+}
+
+/// A playlist order that is being edited but hasn't been sent to mpv yet.
+struct Reorder {
+    /// Indices into mpv's playlist, in their new order.
+    order: Vec<usize>,
+    /// Position in `order` of the entry being moved.
+    grabbed: usize,
+}
+
+impl Reorder {
+    fn new(len: usize, grabbed: usize) -> Self {
+        Self { order: (0..len).collect(), grabbed }
+    }
 
-pub struct PlaylistMenu;
+    fn is_changed(&self) -> bool {
+        self.order.iter().enumerate().any(|(pos, &idx)| pos != idx)
+    }
+
+    fn move_up(&mut self) {
+        if self.grabbed > 0 {
+            self.order.swap(self.grabbed, self.grabbed - 1);
+            self.grabbed -= 1;
+        }
+    }
+
+    fn move_down(&mut self) {
+        if self.grabbed + 1 < self.order.len() {
+            self.order.swap(self.grabbed, self.grabbed + 1);
+            self.grabbed += 1;
+        }
+    }
+}
 
 impl MediaMenu for PlaylistMenu {
     fn label(&self) -> &'static str {
@@ -19,30 +138,269 @@ impl MediaMenu for PlaylistMenu {
     }
 
     fn draw(&self, ui: &mut egui::Ui, app: &mut crate::App) {
+        let mut reorder = self.reorder.borrow_mut();
+
+        if reorder
+            .as_ref()
+            .is_some_and(|r| r.order.len() != app.mpv.playlist().len())
+        {
+            *reorder = None;
+        }
+
+        if let Some(ref mut r) = *reorder {
+            if app.gamepad.take_just_pressed(Button::DPadUp) {
+                r.move_up();
+            }
+            if app.gamepad.take_just_pressed(Button::DPadDown) {
+                r.move_down();
+            }
+        }
+
+        // a short tap still pauses, like X does everywhere else
+        if app.gamepad.take_short_press(Button::North) {
+            app.queue_command(Command::TogglePause);
+        }
+
+        let editable = reorder.is_none() && !app.guest_mode;
+        if editable {
+            ui.add(button_prompt(Button::North, "Hold for actions"));
+
+            if ui.button("🔀 Shuffle unwatched").activated() {
+                let paths = app
+                    .mpv
+                    .playlist()
+                    .iter()
+                    .map(|entry| PathBuf::from(&entry.filename))
+                    .collect::<Vec<_>>();
+
+                if !shuffle::play(&mut app.mpv, &shuffle::unwatched(paths, &app.watched)) {
+                    app.queued_events.push(Event::Toast(Toast::AllWatched));
+                }
+            }
+        }
+
+        let mut actions = self
+            .actions
+            .get()
+            .filter(|a| editable && a.index < app.mpv.playlist().len());
+        let mut action = None;
+        let mut cancel = false;
+
         let playlist = app.mpv.playlist();
+        let order = match *reorder {
+            Some(ref r) => r.order.clone(),
+            None => (0..playlist.len()).collect(),
+        };
 
+        let jump = self.jump.take();
         let mut goto = None;
+        let mut grab = None;
+        let mut apply = false;
+
+        // entries move around while reordering, so the folders only show outside of it
+        let groups = if reorder.is_none() {
+            groups(playlist)
+        } else {
+            Vec::new()
+        };
+        let mut collapsed = self.collapsed.borrow_mut();
+        let collapsed = collapsed.get_or_insert_with(|| {
+            groups
+                .iter()
+                .filter(|g| !playlist[g.entries.clone()].iter().any(|e| e.current))
+                .map(|g| g.folder.clone())
+                .collect()
+        });
+        if let Some(group) = groups
+            .iter()
+            .find(|g| jump.is_some_and(|j| g.entries.contains(&j)))
+        {
+            collapsed.remove(&group.folder);
+        }
 
-        for (index, entry) in playlist.iter().enumerate() {
-            let button = ui.button(RichText::new(entry.display_name()).color(if entry.current {
+        let jump_group = self.jump_group.take();
+        let mut focused_group = None;
+
+        for (pos, &index) in order.iter().enumerate() {
+            let entry = &playlist[index];
+            let group = groups.iter().position(|g| g.entries.contains(&pos));
+
+            if let Some(g) = group
+                && groups[g].entries.start == pos
+            {
+                let folder = &groups[g].folder;
+                let is_collapsed = collapsed.contains(folder);
+                let name = folder
+                    .file_name()
+                    .map_or_else(|| folder.to_string_lossy(), |n| n.to_string_lossy());
+
+                let header = ui.button(
+                    RichText::new(format!(
+                        "{} {name} ({})",
+                        if is_collapsed { "▸" } else { "▾" },
+                        groups[g].entries.len(),
+                    ))
+                    .strong(),
+                );
+
+                if jump_group == Some(g) {
+                    header.request_focus();
+                }
+
+                if header.has_focus() {
+                    focused_group = Some(g);
+                    ui.scroll_to_rect(header.rect, None);
+                }
+
+                if header.activated() && !collapsed.remove(folder) {
+                    collapsed.insert(folder.clone());
+                }
+            }
+
+            if group.is_some_and(|g| collapsed.contains(&groups[g].folder)) {
+                continue;
+            }
+
+            let grabbed = reorder.as_ref().is_some_and(|r| r.grabbed == pos);
+
+            let label = if grabbed {
+                format!("⏶⏷ {}", entry.display_name())
+            } else {
+                entry.display_name().to_string()
+            };
+
+            let button = ui.button(RichText::new(label).color(if entry.current {
                 BLUE
             } else {
                 Color32::WHITE
             }));
 
-            if entry.current {
+            if grabbed || jump == Some(pos) {
+                button.request_focus();
+            } else if entry.current {
                 button.autofocus();
+            }
+
+            if entry.current {
                 button.bg_progress_indicator(
                     app.mpv.time_pos_fallback() / app.mpv.duration_fallback(),
                 );
             }
 
             if button.activated() {
-                goto = Some(index);
+                if reorder.is_some() {
+                    apply = true;
+                } else {
+                    goto = Some(index);
+                }
+            }
+
+            if reorder.is_none()
+                && !app.guest_mode
+                && button.has_focus()
+                && app.gamepad.take_just_pressed(Button::West)
+            {
+                grab = Some(pos);
             }
 
             if button.has_focus() {
+                focused_group = group;
                 ui.scroll_to_rect(button.rect, None);
+
+                if reorder.is_none()
+                    && let Some(target) =
+                        fast_scroll::by_page(ui.ctx(), &mut app.gamepad, order.len(), pos)
+                {
+                    self.jump.set(Some(target));
+                }
+
+                if reorder.is_none() {
+                    fast_scroll::while_repeating(ui.ctx(), &app.gamepad, order.len(), pos, || {
+                        folder_name(&entry.filename)
+                    });
+                }
+
+                if editable && app.gamepad.take_long_press(Button::North, ACTIONS_HOLD) {
+                    actions = Some(ActionsFor { index, focus: true });
+                }
+            }
+
+            if let Some(a) = &mut actions
+                && a.index == index
+            {
+                let available = [
+                    Some(EntryAction::Remove),
+                    (index > 0).then_some(EntryAction::MoveUp),
+                    (index + 1 < playlist.len()).then_some(EntryAction::MoveDown),
+                    Some(EntryAction::Clear),
+                ];
+
+                ui.indent("playlist entry actions", |ui| {
+                    for (idx, entry_action) in available.into_iter().flatten().enumerate() {
+                        let button = ui.button(entry_action.label());
+
+                        if idx == 0 && a.focus {
+                            button.request_focus();
+                        }
+
+                        if button.activated() {
+                            action = Some(entry_action);
+                        }
+                    }
+
+                    if ui.button("Cancel").activated() {
+                        cancel = true;
+                        // give focus back to the entry the actions were for
+                        self.jump.set(Some(pos));
+                    }
+                });
+
+                a.focus = false;
+            }
+        }
+
+        if let Some(g) = focused_group {
+            if app.gamepad.take_just_pressed(Button::LeftTrigger2) {
+                self.jump_group.set(Some(g.saturating_sub(1)));
+            } else if app.gamepad.take_just_pressed(Button::RightTrigger2) {
+                self.jump_group.set(Some((g + 1).min(groups.len() - 1)));
+            }
+        }
+
+        self.actions.set(if action.is_some() || cancel {
+            None
+        } else {
+            actions
+        });
+
+        if let (Some(action), Some(ActionsFor { index, .. })) = (action, actions) {
+            match action {
+                EntryAction::Remove => app.mpv.playlist_remove(index).ok(),
+                EntryAction::MoveUp => app.mpv.playlist_move(index, index - 1).ok(),
+                EntryAction::MoveDown => app.mpv.playlist_move(index, index + 1).ok(),
+                EntryAction::Clear => {
+                    app.queue_command(Command::ClearPlaylist);
+                    None
+                }
+            };
+        }
+
+        if let Some(pos) = grab {
+            *reorder = Some(Reorder::new(order.len(), pos));
+        }
+
+        if apply && let Some(r) = reorder.take() {
+            let mut current: Vec<usize> = (0..r.order.len()).collect();
+            for (target, idx) in r.order.into_iter().enumerate() {
+                let Some(pos) = current.iter().position(|&i| i == idx) else {
+                    continue;
+                };
+
+                if pos != target {
+                    app.mpv.playlist_move(pos, target).ok();
+                    current.remove(pos);
+                    current.insert(target, idx);
+                }
             }
         }
 
@@ -50,4 +408,25 @@ impl MediaMenu for PlaylistMenu {
             app.mpv.set_property("playlist-pos", entry as i64).ok();
         }
     }
+
+    fn catch_up_down(&self) -> bool {
+        self.reorder.borrow().is_some()
+    }
+
+    fn catch_x(&self) -> bool {
+        true
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.reorder
+            .borrow()
+            .as_ref()
+            .is_some_and(|r| r.is_changed())
+    }
+}
+
+/// Name of the folder an entry is in, to tell where in a long queue it is.
+fn folder_name(filename: &str) -> Option<String> {
+    let name = Path::new(filename).parent()?.file_name()?;
+    Some(name.to_string_lossy().into_owned())
 }
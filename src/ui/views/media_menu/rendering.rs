@@ -0,0 +1,88 @@
+use egui::{Color32, RichText};
+
+use super::MediaMenu;
+use crate::{App, BLUE, config::config, mpv::Profile, utils::ResponseExt as _};
+
+/// Profiles mpv always has that don't make sense to apply mid-playback.
+const HIDDEN_PROFILES: [&str; 4] = ["default", "encoding", "libmpv", "pseudo-gui"];
+
+/// mpv profiles and the shader presets from the config, for switching between e.g. a quality and
+/// a power-saving setup without a keyboard.
+pub struct RenderingMenu;
+
+impl MediaMenu for RenderingMenu {
+    fn label(&self) -> &'static str {
+        "Rendering"
+    }
+
+    fn enabled(&self, app: &App) -> bool {
+        !app.guest_mode
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let presets = &config().shader_presets;
+        let shaders = app.mpv.get_property::<Vec<String>>("glsl-shaders");
+
+        if !presets.is_empty() {
+            ui.label(RichText::new("Shaders").size(10.));
+
+            let mut selected = None;
+
+            let none = [("None", &[][..])];
+            let presets = presets
+                .iter()
+                .map(|p| (p.name.as_str(), p.shaders.as_slice()));
+            for (name, preset) in none.into_iter().chain(presets) {
+                let is_current = shaders == preset;
+
+                let button = ui.button(RichText::new(name).color(if is_current {
+                    BLUE
+                } else {
+                    Color32::WHITE
+                }));
+                button.autofocus();
+
+                if button.activated() {
+                    selected = Some(preset);
+                }
+            }
+
+            if let Some(preset) = selected {
+                app.mpv.set_property("glsl-shaders", preset).ok();
+            }
+
+            ui.add_space(8.);
+        }
+
+        let profiles = app.mpv.get_property::<Vec<Profile>>("profile-list");
+        let profiles = profiles
+            .iter()
+            .filter(|p| {
+                p.profile_cond.is_none()
+                    && !p.name.starts_with("builtin-")
+                    && !HIDDEN_PROFILES.contains(&p.name.as_str())
+            })
+            .collect::<Vec<_>>();
+
+        ui.label(RichText::new("Profiles").size(10.));
+
+        if profiles.is_empty() {
+            ui.label(RichText::new("No profiles").weak());
+        }
+
+        for profile in profiles {
+            let button = ui.button(&profile.name);
+            if let Some(desc) = &profile.profile_desc {
+                ui.label(RichText::new(desc).size(10.));
+            }
+            button.autofocus();
+
+            // mpv doesn't say which profiles have been applied, so there's nothing to highlight
+            if button.activated()
+                && let Err(e) = app.mpv.apply_profile(&profile.name)
+            {
+                eprintln!("Failed to apply profile {}: {e}", profile.name);
+            }
+        }
+    }
+}
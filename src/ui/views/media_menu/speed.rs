@@ -0,0 +1,48 @@
+use egui::{Color32, RichText};
+
+use super::MediaMenu;
+use crate::{BLUE, mpv::SPEED_PRESETS, utils::ResponseExt as _};
+
+pub struct SpeedMenu;
+
+impl MediaMenu for SpeedMenu {
+    fn label(&self) -> &'static str {
+        "Speed"
+    }
+
+    fn enabled(&self, _app: &crate::App) -> bool {
+        true
+    }
+
+    fn width(&self) -> f32 {
+        150.
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut crate::App) {
+        let current = app.mpv.speed();
+
+        let mut selected = None;
+
+        for speed in SPEED_PRESETS {
+            let is_current = (speed - current).abs() < 0.01;
+
+            let button = ui.button(RichText::new(format!("{speed}x")).color(if is_current {
+                BLUE
+            } else {
+                Color32::WHITE
+            }));
+
+            if is_current {
+                button.autofocus();
+            }
+
+            if button.activated() {
+                selected = Some(speed);
+            }
+        }
+
+        if let Some(speed) = selected {
+            app.mpv.set_speed(speed).ok();
+        }
+    }
+}
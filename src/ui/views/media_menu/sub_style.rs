@@ -0,0 +1,106 @@
+use gilrs::Button;
+
+use super::MediaMenu;
+use crate::{App, store::settings::SubtitleStyle, ui::theme, utils::ResponseExt as _};
+
+struct Adjustable {
+    label: &'static str,
+    step: f32,
+    min: f32,
+    max: f32,
+    text: fn(f32) -> String,
+}
+
+const SIZE: Adjustable = Adjustable {
+    label: "Size",
+    step: SubtitleStyle::SCALE_STEP,
+    min: SubtitleStyle::MIN_SCALE,
+    max: SubtitleStyle::MAX_SCALE,
+    text: |v| format!("{:.0}%", v * 100.),
+};
+
+const POSITION: Adjustable = Adjustable {
+    label: "Position",
+    step: 1.,
+    min: -50.,
+    max: 50.,
+    // sub-pos counts down from the top
+    text: |v| match v {
+        0. => "Default".to_string(),
+        v if v < 0. => format!("{:.0}% higher", -v),
+        v => format!("{v:.0}% lower"),
+    },
+};
+
+const OUTLINE: Adjustable = Adjustable {
+    label: "Outline",
+    step: 0.5,
+    min: 0.,
+    max: 10.,
+    text: |v| format!("{v:.1}"),
+};
+
+/// Size, position and outline of subtitles, since built-in ones are often too small or end up
+/// behind the overlay's panels.
+pub struct SubStyleMenu;
+
+impl MediaMenu for SubStyleMenu {
+    fn label(&self) -> &'static str {
+        "Subtitle Style"
+    }
+
+    fn enabled(&self, app: &App) -> bool {
+        !app.guest_mode
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let style = app.settings.subtitles;
+        let default = SubtitleStyle::default();
+        let mut new_style = style;
+
+        new_style.scale = row(ui, app, &SIZE, style.scale, default.scale);
+        new_style.pos_offset = row(ui, app, &POSITION, style.pos_offset, default.pos_offset);
+        new_style.border_size = row(ui, app, &OUTLINE, style.border_size, default.border_size);
+
+        if new_style != style {
+            app.settings.subtitles = new_style;
+            app.settings.save();
+
+            // sub-pos follows along on its own, see App::update
+            app.mpv
+                .set_property("sub-scale", theme::sub_scale(&app.settings))
+                .ok();
+            app.mpv
+                .set_property("sub-border-size", new_style.border_size)
+                .ok();
+        }
+    }
+}
+
+/// A row that's adjusted by `adjustable.step` with left/right and reset to `default` by
+/// activating it.
+fn row(ui: &mut egui::Ui, app: &mut App, adjustable: &Adjustable, value: f32, default: f32) -> f32 {
+    let button = ui.button(adjustable.label);
+    button.ralign_overlay(ui, |ui| {
+        ui.add_space(8.);
+        ui.label((adjustable.text)(value));
+    });
+    button.autofocus();
+
+    let mut new_value = value;
+
+    if button.has_focus() && app.gamepad.claim(Button::DPadLeft, "Less") {
+        new_value -= adjustable.step;
+    }
+
+    if button.has_focus() && app.gamepad.claim(Button::DPadRight, "More") {
+        new_value += adjustable.step;
+    }
+
+    if button.activated() {
+        new_value = default;
+    }
+
+    // keep it on the step grid instead of accumulating float error
+    ((new_value / adjustable.step).round() * adjustable.step).clamp(adjustable.min, adjustable.max)
+}
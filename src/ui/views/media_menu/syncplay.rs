@@ -0,0 +1,65 @@
+use egui::RichText;
+
+use super::{
+    MediaMenu,
+    members::{MemberRow, member_list},
+};
+use crate::{App, config::config, syncplay::Connection};
+
+/// Who else is in the Syncplay room, and what they're watching.
+pub struct SyncplayMenu;
+
+impl MediaMenu for SyncplayMenu {
+    fn label(&self) -> &'static str {
+        "Syncplay"
+    }
+
+    fn enabled(&self, _app: &App) -> bool {
+        config().syncplay.server.is_some()
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let config = &config().syncplay;
+
+        ui.label(format!("Room {}", config.room));
+        ui.label(
+            RichText::new(format!(
+                "{} on {}",
+                config.username,
+                config.server.as_deref().unwrap_or_default()
+            ))
+            .size(10.),
+        );
+
+        ui.add_space(8.);
+
+        match app.syncplay.connection() {
+            Connection::Disabled => return,
+            Connection::Connecting => {
+                ui.label(RichText::new("Connecting…").weak());
+                return;
+            }
+            Connection::Joining => {
+                ui.label(RichText::new("Joining room…").weak());
+                return;
+            }
+            Connection::Disconnected => {
+                ui.label(RichText::new("Not connected, retrying soon").weak());
+                return;
+            }
+            Connection::Joined => {}
+        }
+
+        let playing = app.mpv.file_name();
+
+        member_list(
+            ui,
+            playing.as_deref(),
+            app.syncplay.users().iter().map(|user| MemberRow {
+                name: &user.name,
+                file: user.file.as_deref(),
+                position: user.position,
+            }),
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use gilrs::Button;
+use serde_json::Value;
+
+use super::MediaMenu;
+use crate::{App, utils::ResponseExt as _};
+
+pub struct ToneMappingMenu;
+
+impl MediaMenu for ToneMappingMenu {
+    fn label(&self) -> &'static str {
+        "Tone Mapping"
+    }
+
+    fn enabled(&self, app: &App) -> bool {
+        app.mpv.video_params().is_some_and(|p| p.is_hdr())
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        option_row(ui, app, "Output", "target-trc", &["auto", "pq", "hlg", "bt.1886", "srgb"]);
+        option_row(ui, app, "Algorithm", "tone-mapping", &[
+            "auto", "bt.2390", "spline", "hable", "mobius", "reinhard", "clip",
+        ]);
+        option_row(ui, app, "Peak detection", "hdr-compute-peak", &["auto", "yes", "no"]);
+    }
+}
+
+/// A row that cycles a choice property through `options` with left/right.
+fn option_row(ui: &mut egui::Ui, app: &mut App, label: &str, property: &str, options: &[&str]) {
+    let current = match app.mpv.get_property::<Value>(property) {
+        Value::String(s) => s,
+        Value::Bool(true) => "yes".to_string(),
+        Value::Bool(false) => "no".to_string(),
+        other => other.to_string(),
+    };
+
+    let button = ui.button(label);
+    button.ralign_overlay(ui, |ui| {
+        ui.add_space(8.);
+        ui.label(&current);
+    });
+    button.autofocus();
+
+    if !button.has_focus() {
+        return;
+    }
+
+    let idx = options.iter().position(|&o| o == current);
+    // claim both every frame, or the other one would seek for a frame after either is pressed
+    let left = app.gamepad.claim(Button::DPadLeft, "Previous");
+    let right = app.gamepad.claim(Button::DPadRight, "Next");
+
+    let new_idx = if left {
+        Some(idx.map_or(0, |i| (i + options.len() - 1) % options.len()))
+    } else if right {
+        Some(idx.map_or(0, |i| (i + 1) % options.len()))
+    } else {
+        None
+    };
+
+    if let Some(new_idx) = new_idx {
+        app.mpv.set_property(property, options[new_idx]).ok();
+    }
+}
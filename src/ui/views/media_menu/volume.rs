@@ -1,7 +1,7 @@
 use gilrs::Button;
 
 use super::MediaMenu;
-use crate::{App, utils::ResponseExt};
+use crate::{App, store::settings::VolumeTarget, utils::ResponseExt};
 
 pub struct VolumeMenu;
 
@@ -15,39 +15,100 @@ impl MediaMenu for VolumeMenu {
     }
 
     fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        if app.dlna.has_devices() {
+            target_picker(ui, app);
+            ui.add_space(8.);
+        }
+
         self.draw_impl(ui, app, Mpv);
         for idx in 0..app.dlna.devices().len() {
             self.draw_impl(ui, app, Dlna(idx));
         }
     }
-
-    fn catch_left_right(&self) -> bool {
-        true
-    }
 }
 
 impl VolumeMenu {
     fn draw_impl<V: VolumeImpl>(&self, ui: &mut egui::Ui, app: &mut App, mut v: V) {
         let volume = v.current_volume(app);
+        let muted = v.is_muted(app);
 
         let button = ui.button(v.label(app));
 
         button.ralign_overlay(ui, |ui| {
             ui.add_space(8.);
-            ui.label(format!("{volume:.0}%"));
+            if muted {
+                ui.label("Muted");
+            } else {
+                ui.label(format!("{volume:.0}%"));
+            }
         });
 
         button.autofocus();
 
         button.bg_progress_indicator(volume / 100.0);
 
-        if button.has_focus() && app.gamepad.take_just_pressed(Button::DPadLeft) {
+        if button.has_focus() && app.gamepad.claim(Button::DPadLeft, "Quieter") {
             v.change_volume(app, -5.0);
         }
 
-        if button.has_focus() && app.gamepad.take_just_pressed(Button::DPadRight) {
+        if button.has_focus() && app.gamepad.claim(Button::DPadRight, "Louder") {
             v.change_volume(app, 5.0);
         }
+
+        if button.activated() {
+            v.set_muted(app, !muted);
+        }
+    }
+}
+
+/// Which of mpv and the DLNA renderers the volume buttons control, since only one of them is
+/// usually the amplifier that matters.
+fn target_picker(ui: &mut egui::Ui, app: &mut App) {
+    let mut targets = vec![VolumeTarget::FirstRenderer, VolumeTarget::Mpv];
+    targets.extend(
+        app.dlna
+            .devices()
+            .iter()
+            .map(|d| VolumeTarget::Renderer(d.udn().to_string())),
+    );
+
+    let current = app.dlna.volume_target().clone();
+    let label = match &current {
+        VolumeTarget::FirstRenderer => "First found".to_string(),
+        VolumeTarget::Mpv => "mpv".to_string(),
+        VolumeTarget::Renderer(udn) => app
+            .dlna
+            .devices()
+            .iter()
+            .find(|d| d.udn() == udn)
+            .map_or_else(|| "(not found)".to_string(), |d| d.friendly_name().to_string()),
+    };
+
+    let button = ui.button("Volume buttons");
+    button.ralign_overlay(ui, |ui| {
+        ui.add_space(8.);
+        ui.label(label);
+    });
+
+    if !button.has_focus() || app.guest_mode {
+        return;
+    }
+
+    // a renderer that's gone isn't in the list, so stepping away from it starts at the top
+    let idx = targets.iter().position(|t| *t == current).unwrap_or(0);
+
+    let new_idx = if app.gamepad.claim(Button::DPadLeft, "Previous") {
+        idx.saturating_sub(1)
+    } else if app.gamepad.claim(Button::DPadRight, "Next") {
+        (idx + 1).min(targets.len() - 1)
+    } else {
+        idx
+    };
+
+    if targets[new_idx] != current {
+        app.settings.volume_target = targets[new_idx].clone();
+        app.settings.save();
+        app.dlna.set_volume_target(targets[new_idx].clone());
     }
 }
 
@@ -55,6 +116,8 @@ trait VolumeImpl {
     fn label(&self, app: &mut App) -> String;
     fn current_volume(&mut self, app: &mut App) -> f32;
     fn change_volume(&mut self, app: &mut App, delta: f32);
+    fn is_muted(&mut self, app: &mut App) -> bool;
+    fn set_muted(&mut self, app: &mut App, muted: bool);
 }
 
 struct Mpv;
@@ -70,6 +133,14 @@ impl VolumeImpl for Mpv {
     fn change_volume(&mut self, app: &mut App, delta: f32) {
         app.mpv.change_volume(delta).ok();
     }
+
+    fn is_muted(&mut self, app: &mut App) -> bool {
+        app.mpv.get_property::<bool>("mute")
+    }
+
+    fn set_muted(&mut self, app: &mut App, muted: bool) {
+        app.mpv.set_property("mute", muted).ok();
+    }
 }
 
 struct Dlna(usize);
@@ -94,4 +165,14 @@ impl VolumeImpl for Dlna {
             device.set_volume((device.volume() as f32 + delta) as u8);
         }
     }
+
+    fn is_muted(&mut self, app: &mut App) -> bool {
+        app.dlna.devices().get(self.0).is_some_and(|d| d.muted())
+    }
+
+    fn set_muted(&mut self, app: &mut App, muted: bool) {
+        if let Some(device) = app.dlna.devices().get_mut(self.0) {
+            device.set_muted(muted);
+        }
+    }
 }
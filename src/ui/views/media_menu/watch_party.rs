@@ -0,0 +1,63 @@
+use egui::RichText;
+
+use super::{
+    MediaMenu,
+    members::{MemberRow, member_list},
+};
+use crate::{App, utils::ResponseExt as _};
+
+/// Keeps playback in step with other overlays on the network.
+pub struct WatchPartyMenu;
+
+impl MediaMenu for WatchPartyMenu {
+    fn label(&self) -> &'static str {
+        "Watch Party"
+    }
+
+    fn enabled(&self, _app: &App) -> bool {
+        true
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let joined = app.watch_party.is_joined();
+
+        let button = ui.button(if joined {
+            "Leave watch party"
+        } else {
+            "Join watch party"
+        });
+        button.autofocus();
+
+        if button.activated() {
+            if joined {
+                app.watch_party.leave();
+            } else if let Err(e) = app.watch_party.join() {
+                eprintln!("[Watch party] Failed to join: {e}");
+            }
+        }
+
+        if !app.watch_party.is_joined() {
+            ui.add_space(8.);
+            ui.label(
+                RichText::new("Pausing, playing and seeking is shared with everyone in the party")
+                    .size(10.),
+            );
+            return;
+        }
+
+        let playing = app.mpv.file_name();
+
+        ui.add_space(8.);
+
+        let members = app.watch_party.members();
+        member_list(
+            ui,
+            playing.as_deref(),
+            members.iter().map(|member| MemberRow {
+                name: &member.name,
+                file: member.file.as_deref(),
+                position: member.position,
+            }),
+        );
+    }
+}
@@ -1,10 +1,9 @@
-use core::time::Duration;
-
 use egui::{Color32, FontFamily, Frame, ProgressBar, RichText, Widget as _};
 use gilrs::Button;
 
 use crate::{
     command::{Actions, Command},
+    config::config,
     ui::{HiddenView, View},
     utils::available_characters,
 };
@@ -54,6 +53,6 @@ impl View for MiniSeekView {
     }
 
     fn hide_on_inactive(&self) -> Option<std::time::Duration> {
-        Some(Duration::from_secs(2))
+        Some(config().timeouts.miniseek())
     }
 }
@@ -0,0 +1,58 @@
+use egui::{Align, Id, Layout, Modal, RichText};
+
+use crate::{
+    App,
+    command::{Actions, Command},
+    mpv::time::Time,
+    ui::{View, views::hidden::HiddenView},
+};
+
+/// Asks whether a file that was played before should continue where it was left off.
+pub struct ResumeView {
+    path: String,
+    position: Time,
+    /// Where X goes back to, without playing anything.
+    previous: Box<dyn View>,
+}
+
+impl ResumeView {
+    pub fn new(path: String, position: Time, previous: Box<dyn View>) -> Self {
+        Self { path, position, previous }
+    }
+
+    pub fn resume(self, app: &mut App) {
+        app.mpv.load_file_at(&self.path, self.position).ok();
+        app.mpv.unpause().ok();
+        app.change_view(HiddenView);
+    }
+
+    pub fn start_over(self, app: &mut App) {
+        app.mpv.load_file(&self.path).ok();
+        app.mpv.unpause().ok();
+        app.change_view(HiddenView);
+    }
+
+    pub fn cancel(self, app: &mut App) {
+        app.view = self.previous;
+    }
+}
+
+impl View for ResumeView {
+    fn draw(&self, ctx: &egui::Context, _app: &mut App) {
+        Modal::new(Id::new("resume dialog")).show(ctx, |ui| {
+            ui.set_min_width(200.);
+            ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                ui.label(RichText::new(format!("Resume from {}?", self.position.mmss())).heading());
+            });
+        });
+    }
+
+    fn button_actions(&self) -> Actions {
+        Actions {
+            a: Command::Resume,
+            b: Command::StartOver,
+            x: Command::CancelDialog,
+            ..Actions::default()
+        }
+    }
+}
@@ -1,9 +1,12 @@
-use core::time::Duration;
-
-use egui::{ProgressBar, RichText, Widget as _};
+use chrono::{Local, TimeDelta};
+use egui::{ProgressBar, Rect, RichText, Widget as _, vec2};
 
 use crate::{
+    BLUE,
     command::{Actions, Command},
+    config::config,
+    mpv::time::Time,
+    store::hints::Hint,
     ui::View,
     utils::horizontal_left_right,
 };
@@ -37,7 +40,7 @@ impl View for SeekBarView {
 
                         if let Some(segment) = app
                             .mpv
-                            .sponsorblock_segments()
+                            .skip_segments()
                             .iter()
                             .find(|s| s.contains(app.mpv.time_pos_fallback()))
                         {
@@ -52,6 +55,27 @@ impl View for SeekBarView {
                         if let Some(duration) = app.mpv.duration() {
                             ui.label(RichText::new(duration.mmss()).size(10.));
                         }
+
+                        if let Some(remaining) = app.mpv.remaining() {
+                            let speed = app.mpv.speed();
+                            if speed != 1. {
+                                ui.label(
+                                    RichText::new(format!("−{} @{speed}x", remaining.mmss()))
+                                        .size(10.)
+                                        .color(BLUE),
+                                );
+                            } else {
+                                ui.label(RichText::new(format!("−{}", remaining.mmss())).size(10.));
+                            }
+
+                            let ends_at = Local::now()
+                                + TimeDelta::milliseconds((remaining.as_secs() * 1000.) as i64);
+                            ui.label(
+                                RichText::new(format!("ends {}", ends_at.format("%H:%M")))
+                                    .size(10.)
+                                    .weak(),
+                            );
+                        }
                     },
                 );
 
@@ -62,7 +86,7 @@ impl View for SeekBarView {
 
                 let duration = app.mpv.duration_fallback();
 
-                for segment in app.mpv.sponsorblock_segments() {
+                for segment in app.mpv.skip_segments() {
                     let start = rect.left() + rect.width() * (segment.start() / duration);
                     let end = rect.left() + rect.width() * (segment.end() / duration);
 
@@ -75,6 +99,8 @@ impl View for SeekBarView {
                         segment.category.color(),
                     );
                 }
+
+                paint_ab_loop(ui, rect, app);
             });
     }
 
@@ -83,15 +109,49 @@ impl View for SeekBarView {
             a: Command::StartSeeking,
             b: Command::HideUi,
             x: Command::TogglePause,
+            y: Command::ShowToneMapping,
+            l1: Command::PrevChapter,
+            l2: Command::SubScaleDown,
+            r1: Command::NextChapter,
+            r2: Command::SubScaleUp,
             left: Command::SeekBackwardStateless,
             right: Command::SeekForwardStateless,
+            up: Command::SpeedUp,
+            down: Command::SpeedDown,
+            select: Command::ResetSpeed,
             start: Command::ShowMediaMenu,
             home: Command::ShowHomeMenu,
-            ..Actions::default()
         }
     }
 
     fn hide_on_inactive(&self) -> Option<std::time::Duration> {
-        Some(Duration::from_secs(5))
+        Some(config().timeouts.seekbar())
+    }
+
+    fn hint(&self) -> Option<Hint> {
+        Some(Hint::SeekBar)
+    }
+}
+
+/// Highlights the A-B loop on a progress bar, or marks where it starts if it has no end yet.
+pub fn paint_ab_loop(ui: &egui::Ui, rect: Rect, app: &crate::App) {
+    let duration = app.mpv.duration_fallback();
+    let x = |time: Time| rect.left() + rect.width() * (time / duration);
+
+    match app.mpv.ab_loop() {
+        (Some(a), Some(b)) => {
+            // B can be set to before A
+            let (start, end) = (x(a).min(x(b)), x(a).max(x(b)));
+            ui.painter().rect_filled(
+                Rect::from_x_y_ranges(start..=end, rect.y_range()).expand2(vec2(0., 2.)),
+                1.,
+                BLUE.gamma_multiply(0.5),
+            );
+        }
+        (Some(a), None) => {
+            ui.painter()
+                .vline(x(a), rect.y_range().expand(4.), (2., BLUE));
+        }
+        _ => {}
     }
 }
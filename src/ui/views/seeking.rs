@@ -1,9 +1,10 @@
-use egui::{Align2, Color32, FontId, ProgressBar, RichText, Widget as _};
+use egui::{Align2, Color32, FontId, ProgressBar, Rect, RichText, Widget as _};
 
 use crate::{
     BLUE,
     command::{Actions, Command},
-    ui::View,
+    store::hints::Hint,
+    ui::{View, views::seekbar},
     utils::horizontal_left_right,
 };
 
@@ -55,7 +56,9 @@ impl View for SeekingView {
                     },
                 );
 
-                ProgressBar::new(pos).desired_height(4.).ui(ui);
+                let rect = ProgressBar::new(pos).desired_height(4.).ui(ui).rect;
+                seekbar::paint_ab_loop(ui, rect, app);
+                paint_keyframe_snaps(ui, rect, app);
             });
     }
 
@@ -63,7 +66,11 @@ impl View for SeekingView {
         Actions {
             a: Command::DoneSeeking,
             b: Command::CancelSeeking,
-            y: Command::SeekExact,
+            x: Command::SeekExact,
+            y: Command::EnterTime,
+            l1: Command::SetLoopA,
+            r1: Command::SetLoopB,
+            select: Command::ClearLoop,
             up: Command::SeekFaster,
             down: Command::SeekSlower,
             left: Command::SeekBackward,
@@ -71,4 +78,23 @@ impl View for SeekingView {
             ..Actions::default()
         }
     }
+
+    fn hint(&self) -> Option<Hint> {
+        Some(Hint::Seeking)
+    }
+}
+
+/// Ghost markers where the next step each way will likely land, since keyframe seeks snap to the
+/// nearest keyframe and can go quite a bit further or shorter than the step says.
+fn paint_keyframe_snaps(ui: &egui::Ui, rect: Rect, app: &crate::App) {
+    let Some((back, forward)) = app.mpv.keyframe_snaps() else {
+        return;
+    };
+
+    let duration = app.mpv.duration_fallback();
+    for snap in [back, forward] {
+        let x = rect.left() + rect.width() * (snap / duration).clamp(0., 1.);
+        ui.painter()
+            .vline(x, rect.y_range().expand(3.), (2., Color32::from_white_alpha(96)));
+    }
 }
@@ -0,0 +1,83 @@
+use egui::{Align2, Area, Color32, Frame, Id, vec2};
+
+use crate::{
+    App,
+    command::{Actions, Command},
+    ui::View,
+    utils::{decoder_label, detail_row},
+};
+
+/// Live playback statistics in the corner, like mpv's stats script but without a keyboard.
+pub struct StatsView;
+
+impl View for StatsView {
+    fn draw(&self, ctx: &egui::Context, app: &mut App) {
+        Area::new(Id::new("stats"))
+            .anchor(Align2::LEFT_TOP, vec2(32., 32.))
+            .interactable(false)
+            .show(ctx, |ui| {
+                Frame::new()
+                    .fill(Color32::from_black_alpha(192))
+                    .corner_radius(8.)
+                    .inner_margin(8.)
+                    .show(ui, |ui| {
+                        ui.set_width(300.);
+                        draw_stats(ui, app);
+                    });
+            });
+    }
+
+    fn button_actions(&self) -> Actions {
+        Actions {
+            b: Command::HideUi,
+            x: Command::TogglePause,
+            r2: Command::HideUi,
+            left: Command::SeekBackwardStateless,
+            right: Command::SeekForwardStateless,
+            start: Command::ShowMediaMenu,
+            home: Command::ShowHomeMenu,
+            ..Actions::default()
+        }
+    }
+}
+
+fn draw_stats(ui: &mut egui::Ui, app: &mut App) {
+    let dropped = app.mpv.get_property::<u64>("frame-drop-count");
+    let decoder_dropped = app.mpv.get_property::<u64>("decoder-frame-drop-count");
+    let delayed = app.mpv.get_property::<u64>("vo-delayed-frame-count");
+    detail_row(ui, "Dropped frames", format!("{dropped} (decoder {decoder_dropped})"));
+    detail_row(ui, "Delayed frames", delayed.to_string());
+
+    let estimated = app.mpv.get_property::<Option<f64>>("estimated-vf-fps");
+    let display = app.mpv.get_property::<Option<f64>>("display-fps");
+    if let Some(estimated) = estimated {
+        detail_row(ui, "Frame rate", match display {
+            Some(display) => format!("{estimated:.3} fps on a {display:.2} Hz display"),
+            None => format!("{estimated:.3} fps"),
+        });
+    }
+
+    let avsync = app.mpv.get_property::<Option<f64>>("avsync");
+    if let Some(avsync) = avsync {
+        detail_row(ui, "A/V sync", format!("{:+.0} ms", avsync * 1000.));
+    }
+
+    let hwdec = app.mpv.get_property::<String>("hwdec-current");
+    detail_row(ui, "Decoder", decoder_label(&hwdec));
+
+    let cache = app
+        .mpv
+        .get_property::<Option<f64>>("demuxer-cache-duration");
+    if let Some(cache) = cache {
+        detail_row(ui, "Cache", format!("{cache:.1} s"));
+    }
+
+    let is_stream = app
+        .mpv
+        .get_property_cached::<String>("path")
+        .is_some_and(|path| path.contains("://"));
+    if is_stream {
+        let speed = app.mpv.get_property::<f64>("cache-speed");
+        detail_row(ui, "Network", format!("{:.1} Mbps", speed * 8. / 1_000_000.));
+    }
+}
@@ -0,0 +1,125 @@
+use core::cell::Cell;
+
+use egui::RichText;
+use gilrs::Button;
+
+use crate::{
+    App, BLUE,
+    command::{Actions, Command},
+    mpv::time::Time,
+    ui::{View, views::seekbar::SeekBarView},
+};
+
+/// Highest value of each digit in `H:MM:SS`.
+const MAX_DIGITS: [u8; 5] = [9, 5, 9, 5, 9];
+
+/// Picks an exact time to seek to, one digit at a time, while seeking.
+pub struct TimeEntryView {
+    /// `H:MM:SS`, without the colons.
+    digits: Cell<[u8; 5]>,
+    cursor: Cell<usize>,
+}
+
+impl TimeEntryView {
+    /// Starts out at `time`, with the cursor on the minutes.
+    pub fn new(time: Time) -> Self {
+        let secs = time.as_secs().max(0.) as u32;
+        let (h, m, s) = ((secs / 3600).min(9), secs / 60 % 60, secs % 60);
+
+        Self {
+            digits: Cell::new([h, m / 10, m % 10, s / 10, s % 10].map(|d| d as u8)),
+            cursor: Cell::new(2),
+        }
+    }
+
+    fn time(&self) -> Time {
+        let [h, m1, m2, s1, s2] = self.digits.get().map(u32::from);
+        Time::seconds(h * 3600 + (m1 * 10 + m2) * 60 + s1 * 10 + s2)
+    }
+
+    /// Seeks to the entered time, or the end if it's past that, and finishes seeking.
+    pub fn seek(self, app: &mut App) {
+        let time = match app.mpv.duration() {
+            Some(duration) if self.time() > duration => duration,
+            _ => self.time(),
+        };
+
+        app.mpv.seek_to(time).ok();
+        app.mpv.finish_seek().ok();
+        app.change_view(SeekBarView);
+    }
+}
+
+impl View for TimeEntryView {
+    fn draw(&self, ctx: &egui::Context, app: &mut App) {
+        let mut digits = self.digits.get();
+        let mut cursor = self.cursor.get();
+
+        if app.gamepad.take_just_pressed(Button::DPadLeft) {
+            cursor = cursor.saturating_sub(1);
+        }
+        if app.gamepad.take_just_pressed(Button::DPadRight) {
+            cursor = (cursor + 1).min(digits.len() - 1);
+        }
+        if app.gamepad.take_just_pressed(Button::DPadUp) {
+            digits[cursor] = if digits[cursor] >= MAX_DIGITS[cursor] {
+                0
+            } else {
+                digits[cursor] + 1
+            };
+        }
+        if app.gamepad.take_just_pressed(Button::DPadDown) {
+            digits[cursor] = if digits[cursor] == 0 {
+                MAX_DIGITS[cursor]
+            } else {
+                digits[cursor] - 1
+            };
+        }
+
+        self.digits.set(digits);
+        self.cursor.set(cursor);
+
+        egui::TopBottomPanel::bottom("time entry")
+            .show_separator_line(false)
+            .show(ctx, |ui| {
+                ui.add_space(8.);
+
+                ui.label(RichText::new("Go to time").size(10.));
+
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 2.;
+
+                    for (idx, digit) in digits.iter().enumerate() {
+                        if idx == 1 || idx == 3 {
+                            ui.label(RichText::new(":").size(32.));
+                        }
+
+                        let text = RichText::new(digit.to_string()).size(32.);
+                        ui.label(if idx == cursor {
+                            text.color(BLUE).underline()
+                        } else {
+                            text
+                        });
+                    }
+                });
+
+                if let Some(duration) = app.mpv.duration() {
+                    ui.label(
+                        RichText::new(format!("of {}", duration.mmss()))
+                            .size(10.)
+                            .weak(),
+                    );
+                }
+
+                ui.add_space(8.);
+            });
+    }
+
+    fn button_actions(&self) -> Actions {
+        Actions {
+            a: Command::SeekToEntered,
+            b: Command::StartSeeking,
+            ..Actions::default()
+        }
+    }
+}
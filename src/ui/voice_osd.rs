@@ -0,0 +1,29 @@
+use egui::{Align2, RichText, Vec2, vec2};
+
+use super::overlay::OverlayWidget;
+use crate::App;
+
+/// Microphone at the bottom of the screen while push-to-talk is held.
+pub struct VoiceOsd;
+
+impl OverlayWidget for VoiceOsd {
+    fn name(&self) -> &'static str {
+        "voice"
+    }
+
+    fn visible(&self, _ctx: &egui::Context, app: &App) -> bool {
+        app.voice.is_listening()
+    }
+
+    fn anchor(&self, _ctx: &egui::Context) -> (Align2, Vec2) {
+        (Align2::CENTER_BOTTOM, vec2(0., -32.))
+    }
+
+    fn inner_margin(&self) -> f32 {
+        12.
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, _app: &mut App) {
+        ui.label(RichText::new("🎤 Listening…").size(24.));
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+use egui::{Align2, Color32, ProgressBar, Vec2, Widget as _, vec2};
+
+use super::overlay::OverlayWidget;
+use crate::{App, BLUE, utils::horizontal_left_right};
+
+/// How long the bar stays fully visible after the last change.
+const VISIBLE_FOR: Duration = Duration::from_secs(1);
+
+/// Volume bar that pops up over whatever view is active when the volume is changed without the
+/// volume menu open.
+#[derive(Default)]
+pub struct VolumeOsd {
+    shown: Option<(VolumeSource, Instant)>,
+}
+
+#[derive(Clone, Copy)]
+pub enum VolumeSource {
+    Mpv,
+    /// Index into `Dlna::devices`.
+    Dlna(usize),
+}
+
+impl VolumeOsd {
+    pub fn show(&mut self, source: VolumeSource) {
+        self.shown = Some((source, Instant::now()));
+    }
+}
+
+pub struct VolumeBar;
+
+impl OverlayWidget for VolumeBar {
+    fn name(&self) -> &'static str {
+        "volume"
+    }
+
+    fn visible(&self, _ctx: &egui::Context, app: &App) -> bool {
+        app.volume_osd
+            .shown
+            .is_some_and(|(_, shown)| shown.elapsed() < VISIBLE_FOR)
+    }
+
+    fn anchor(&self, ctx: &egui::Context) -> (Align2, Vec2) {
+        (Align2::CENTER_BOTTOM, vec2(0., -ctx.screen_rect().height() * 0.1))
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, app: &mut App) {
+        let Some((source, _)) = app.volume_osd.shown else {
+            return;
+        };
+
+        let (label, volume, muted) = match source {
+            VolumeSource::Mpv => (
+                "Volume".to_string(),
+                app.mpv.get_property::<f32>("volume"),
+                app.mpv.get_property::<bool>("mute"),
+            ),
+            VolumeSource::Dlna(idx) => match app.dlna.devices().get(idx) {
+                Some(device) => {
+                    (device.friendly_name().to_string(), device.volume() as f32, device.muted())
+                }
+                None => return,
+            },
+        };
+
+        ui.set_width(ui.ctx().screen_rect().width() * 0.25);
+
+        horizontal_left_right(
+            ui,
+            |ui| ui.label(label),
+            |ui| {
+                if muted {
+                    ui.label("Muted")
+                } else {
+                    ui.label(format!("{volume:.0}%"))
+                }
+            },
+        );
+
+        ProgressBar::new(volume / 100.)
+            .desired_height(6.)
+            .fill(if muted { Color32::GRAY } else { BLUE })
+            .ui(ui);
+    }
+}
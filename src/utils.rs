@@ -1,4 +1,6 @@
-use egui::{Align, Align2, Id, InnerResponse, Layout, Response, UiBuilder};
+use std::io::{self, BufRead, ErrorKind};
+
+use egui::{Align, Align2, Id, InnerResponse, Layout, Response, RichText, UiBuilder};
 use egui_flex::Flex;
 
 pub trait ResponseExt: Sized {
@@ -20,11 +22,14 @@ impl ResponseExt for Response {
     }
 
     fn activated(&self) -> bool {
+        // taken by the first widget to check, so drawing more than one pass in a frame can't
+        // activate anything twice
         self.has_focus()
-            && self
-                .ctx
-                .memory(|m| m.data.get_temp::<Activated>(Id::NULL).unwrap_or_default())
-                .0
+            && self.ctx.memory_mut(|m| {
+                m.data
+                    .remove_temp::<PendingActivation>(activation_id())
+                    .is_some()
+            })
     }
 
     fn bg_progress_indicator(&self, progress: f32) {
@@ -76,8 +81,42 @@ pub fn horizontal_left_right<A, B>(
     })
 }
 
+/// A small label on the left with its value on the right, for lists of details about playback.
+pub fn detail_row(ui: &mut egui::Ui, label: &str, value: String) {
+    horizontal_left_right(
+        ui,
+        |ui| ui.label(RichText::new(label).size(10.)),
+        |ui| ui.label(RichText::new(value).size(10.)),
+    );
+}
+
+/// How video is being decoded, from mpv's `hwdec-current`.
+pub fn decoder_label(hwdec: &str) -> String {
+    match hwdec {
+        "" | "no" => "Software".to_string(),
+        hwdec => format!("Hardware ({hwdec})"),
+    }
+}
+
+/// An activation of whichever widget has focus, from A, a stick click or the remote's OK button.
+/// Queued after a frame is drawn and picked up by [`ResponseExt::activated`] during the next.
 #[derive(Clone, Copy, Default)]
-pub struct Activated(pub bool);
+struct PendingActivation;
+
+fn activation_id() -> Id {
+    Id::new("pending activation")
+}
+
+/// Activates the focused widget during the next frame.
+pub fn queue_activation(ctx: &egui::Context) {
+    ctx.memory_mut(|m| m.data.insert_temp(activation_id(), PendingActivation));
+}
+
+/// Drops an activation no widget picked up, e.g. because nothing had focus, so it doesn't
+/// linger until something does.
+pub fn clear_activation(ctx: &egui::Context) {
+    ctx.memory_mut(|m| m.data.remove::<PendingActivation>(activation_id()));
+}
 
 pub fn available_characters(ui: &egui::Ui, family: egui::FontFamily) -> Vec<char> {
     ui.fonts(|f| {
@@ -97,10 +136,51 @@ pub fn available_characters(ui: &egui::Ui, family: egui::FontFamily) -> Vec<char
 }
 
 pub fn youtube_id_from_url(url: &str) -> Option<&str> {
-    let (_, id) = url.split_once("youtube.com/watch?v=")?;
-    if id.len() >= 11 && id.is_char_boundary(11) {
-        Some(&id[..11])
-    } else {
-        None
+    let (_, id) = ["youtube.com/watch?v=", "youtube.com/shorts/", "youtu.be/", "ytdl://"]
+        .iter()
+        .find_map(|prefix| url.split_once(prefix))
+        .or_else(|| {
+            url.split_once("youtube.com/watch?")
+                .and_then(|(_, q)| q.split_once("&v="))
+        })?;
+
+    id.get(..11).filter(|id| is_youtube_id(id))
+}
+
+/// The id yt-dlp puts at the end of file names by default, like `Some Video [dQw4w9WgXcQ]`,
+/// which mpv's `media-title` falls back to when the file has no title.
+pub fn youtube_id_from_title(title: &str) -> Option<&str> {
+    let title = title.rsplit_once('.').map_or(
+        title,
+        |(stem, ext)| {
+            if ext.len() <= 4 { stem } else { title }
+        },
+    );
+
+    let (_, id) = title.strip_suffix(']')?.rsplit_once('[')?;
+    Some(id).filter(|id| is_youtube_id(id))
+}
+
+fn is_youtube_id(id: &str) -> bool {
+    id.len() == 11
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Reads from a nonblocking socket until the end of a line, returning the line once it's
+/// complete. Until then what's been read so far is kept in `buf`, so it should be the same buffer
+/// every time.
+pub fn read_line_nonblocking(
+    reader: &mut impl BufRead,
+    buf: &mut String,
+) -> io::Result<Option<String>> {
+    match reader.read_line(buf) {
+        Ok(0) => Err(io::Error::new(ErrorKind::UnexpectedEof, "socket closed")),
+        Ok(_) if buf.ends_with('\n') => Ok(Some(std::mem::take(buf))),
+        // read_line keeps whatever it got before running out of data
+        Ok(_) => Ok(None),
+        Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+        Err(e) => Err(e),
     }
 }
@@ -0,0 +1,53 @@
+use crate::command::Command;
+
+/// Phrases for each command, as they are after [`normalize`]. Only whole phrases match, so "skip"
+/// on its own doesn't catch "skip forward".
+const PHRASES: &[(&[&str], Command)] = &[
+    (&["pause", "hold on", "wait"], Command::Pause),
+    (&["play", "resume", "unpause", "continue"], Command::Play),
+    (&["skip intro", "skip the intro", "skip opening", "skip this", "skip"], Command::SkipSegment),
+    (&["play next episode", "next episode", "play next", "next"], Command::NextEntry),
+    (
+        &["play previous episode", "previous episode", "previous", "go back an episode"],
+        Command::PreviousEntry,
+    ),
+    (&["next chapter", "skip chapter"], Command::NextChapter),
+    (&["previous chapter", "last chapter"], Command::PrevChapter),
+    (&["undo", "undo skip", "go back"], Command::UndoSkip),
+    (&["stop", "stop playback"], Command::Stop),
+    (&["skip forward", "forward"], Command::SeekForwardStateless),
+    (&["skip back", "skip backward", "rewind", "back"], Command::SeekBackwardStateless),
+    (&["volume up", "louder", "turn it up"], Command::VolumeUp),
+    (&["volume down", "quieter", "softer", "turn it down"], Command::VolumeDown),
+    (&["mute", "unmute"], Command::ToggleMute),
+    (&["faster", "speed up"], Command::SpeedUp),
+    (&["slower", "slow down"], Command::SpeedDown),
+    (&["normal speed", "reset speed"], Command::ResetSpeed),
+    (&["bigger subtitles", "bigger subs"], Command::SubScaleUp),
+    (&["smaller subtitles", "smaller subs"], Command::SubScaleDown),
+    (&["show menu", "menu", "home"], Command::ShowHomeMenu),
+    (&["hide", "hide menu", "close"], Command::HideUi),
+];
+
+/// Words that are fine to say around a command but don't change it.
+const FILLER: &[&str] = &["please", "ok", "okay", "hey", "now"];
+
+/// The command a recognized phrase asks for, if it's one we know.
+pub fn parse(phrase: &str) -> Option<Command> {
+    let phrase = normalize(phrase);
+
+    PHRASES
+        .iter()
+        .find(|(phrases, _)| phrases.contains(&phrase.as_str()))
+        .map(|&(_, command)| command)
+}
+
+/// Lowercases and drops punctuation and filler words, so "Pause, please." is just "pause".
+fn normalize(phrase: &str) -> String {
+    phrase
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty() && !FILLER.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,178 @@
+//! Voice commands from an external speech-to-text daemon, which we talk to over a Unix socket.
+//! Phrases only count while push-to-talk is held, or shortly after for the last words to come in.
+
+use std::{
+    io::{self, BufReader, ErrorKind, Write as _},
+    os::unix::net::UnixStream,
+    process::{Child, Stdio},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    command::{Command, Event},
+    config::config,
+    gamepad::Gamepad,
+    ui::toast::Toast,
+    utils::read_line_nonblocking,
+};
+
+mod grammar;
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+/// How long after push-to-talk is released recognized phrases are still accepted, since the
+/// daemon needs a moment to finish.
+const LATE_RESULTS: Duration = Duration::from_secs(3);
+
+pub struct Voice {
+    socket: Option<BufReader<UnixStream>>,
+    line_buf: String,
+    last_connect_attempt: Option<Instant>,
+    daemon: Option<Child>,
+    listening: bool,
+    released_at: Option<Instant>,
+}
+
+impl Voice {
+    pub fn new() -> Self {
+        let mut this = Self {
+            socket: None,
+            line_buf: String::new(),
+            last_connect_attempt: None,
+            daemon: None,
+            listening: false,
+            released_at: None,
+        };
+        let config = &config().voice;
+
+        if config.socket.is_some()
+            && let Some((program, args)) = config.command.split_first()
+        {
+            this.daemon = std::process::Command::new(program)
+                .args(args)
+                .stdin(Stdio::null())
+                .spawn()
+                .map_err(|e| eprintln!("Failed to start speech-to-text daemon {program}: {e}"))
+                .ok();
+        }
+
+        this
+    }
+
+    /// Whether push-to-talk is held, for showing that we're listening.
+    pub fn is_listening(&self) -> bool {
+        self.listening
+    }
+
+    pub fn update(
+        &mut self,
+        gamepad: &mut Gamepad,
+        commands: &mut Vec<Command>,
+        events: &mut Vec<Event>,
+    ) {
+        let Some(socket) = &config().voice.socket else {
+            return;
+        };
+
+        if self.socket.is_none()
+            && self
+                .last_connect_attempt
+                .is_none_or(|at| at.elapsed() > RECONNECT_INTERVAL)
+        {
+            self.last_connect_attempt = Some(Instant::now());
+
+            match UnixStream::connect(socket).and_then(|stream| {
+                stream.set_nonblocking(true)?;
+                Ok(stream)
+            }) {
+                Ok(stream) => self.socket = Some(BufReader::new(stream)),
+                Err(e)
+                    if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {}
+                Err(e) => eprintln!("Failed to connect to speech-to-text daemon: {e}"),
+            }
+        }
+
+        let push_to_talk = config().voice.push_to_talk;
+
+        // keep the button from doing whatever it normally does
+        gamepad.take_just_pressed(push_to_talk);
+
+        let held = gamepad.is_down(push_to_talk) && !gamepad.is_locked();
+        if held != self.listening {
+            self.listening = held;
+            if !held {
+                self.released_at = Some(Instant::now());
+            }
+
+            let msg = if held { "start\n" } else { "stop\n" };
+            if let Err(e) = self.send(msg) {
+                eprintln!("Failed to send to speech-to-text daemon: {e}");
+                self.socket = None;
+            }
+        }
+
+        loop {
+            match self.read_line() {
+                Ok(Some(phrase)) => self.heard(&phrase, commands, events),
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("Lost connection to speech-to-text daemon: {e}");
+                    self.socket = None;
+                    self.line_buf.clear();
+                    break;
+                }
+            }
+        }
+    }
+
+    fn heard(&self, phrase: &str, commands: &mut Vec<Command>, events: &mut Vec<Event>) {
+        let phrase = phrase.trim();
+
+        let accepting = self.listening
+            || self
+                .released_at
+                .is_some_and(|at| at.elapsed() < LATE_RESULTS);
+
+        if phrase.is_empty() || !accepting {
+            return;
+        }
+
+        let command = grammar::parse(phrase);
+        if let Some(command) = command {
+            commands.push(command);
+        }
+
+        events.push(Event::Toast(Toast::VoiceCommand {
+            phrase: phrase.to_string(),
+            understood: command.is_some(),
+        }));
+    }
+
+    fn send(&mut self, msg: &str) -> io::Result<()> {
+        match &mut self.socket {
+            Some(socket) => socket.get_mut().write_all(msg.as_bytes()),
+            None => Ok(()),
+        }
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let Some(socket) = &mut self.socket else {
+            return Ok(None);
+        };
+
+        read_line_nonblocking(socket, &mut self.line_buf)
+    }
+}
+
+impl Default for Voice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Voice {
+    fn drop(&mut self) {
+        if let Some(daemon) = &mut self.daemon {
+            daemon.kill().ok();
+        }
+    }
+}
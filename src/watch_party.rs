@@ -0,0 +1,240 @@
+//! Watch parties: overlays on the same LAN keeping their mpv in lockstep, for watching the same
+//! thing in two rooms. Every member broadcasts what it's playing over UDP, and whoever last
+//! paused, played or seeked is the one everyone else follows.
+
+use core::net::{Ipv4Addr, SocketAddrV4};
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind},
+    net::UdpSocket,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command::Event,
+    config::config,
+    mpv::{Mpv, local_changes::LocalChanges, time::Time},
+    ui::toast::Toast,
+};
+
+const HEARTBEAT: Duration = Duration::from_secs(1);
+/// How long a member can go quiet before it counts as having left.
+const PEER_TIMEOUT: Duration = Duration::from_secs(5);
+/// How far behind or ahead of the leader a member can get before it seeks to catch up.
+const MAX_DRIFT: f32 = 0.5;
+
+#[derive(Serialize, Deserialize)]
+struct Message {
+    id: u64,
+    name: String,
+    /// File name of what's playing, since paths differ between machines.
+    file: Option<String>,
+    position: f32,
+    paused: bool,
+    /// Bumped on every pause, play or seek, so the latest one wins.
+    version: u64,
+    /// Member who made the change `version` is about.
+    leader: u64,
+}
+
+pub struct Member {
+    pub name: String,
+    pub file: Option<String>,
+    pub position: Time,
+    seen: Instant,
+}
+
+#[derive(Default)]
+pub struct WatchParty {
+    id: u64,
+    /// What other members call us.
+    name: String,
+    /// `Some` while we're in the party.
+    socket: Option<UdpSocket>,
+    members: HashMap<u64, Member>,
+    version: u64,
+    leader: u64,
+    changes: LocalChanges,
+    last_heartbeat: Option<Instant>,
+}
+
+impl WatchParty {
+    pub fn is_joined(&self) -> bool {
+        self.socket.is_some()
+    }
+
+    pub fn join(&mut self) -> io::Result<()> {
+        let socket =
+            UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, config().watch_party_port))?;
+        socket.set_nonblocking(true)?;
+        socket.set_broadcast(true)?;
+
+        // unique enough to tell members apart, without pulling in a crate for randomness
+        self.id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64
+            ^ u64::from(std::process::id());
+        self.name = hostname();
+        self.socket = Some(socket);
+        self.members.clear();
+        // nobody's leading until someone pauses, plays or seeks, so joining doesn't yank anyone
+        // else's playback around
+        self.version = 0;
+        self.leader = 0;
+        self.changes.reset();
+        self.last_heartbeat = None;
+
+        Ok(())
+    }
+
+    pub fn leave(&mut self) {
+        self.socket = None;
+        self.members.clear();
+    }
+
+    /// Everyone else in the party, sorted by name.
+    pub fn members(&self) -> Vec<&Member> {
+        let mut members = self.members.values().collect::<Vec<_>>();
+        members.sort_by(|a, b| a.name.cmp(&b.name));
+        members
+    }
+
+    pub fn update(&mut self, mpv: &mut Mpv, events: &mut Vec<Event>) {
+        if self.socket.is_none() {
+            return;
+        }
+
+        let file = mpv.file_name();
+        let paused = mpv.get_property_cached::<bool>("pause").unwrap_or(true);
+        let position = mpv.time_pos_fallback().as_secs();
+
+        if self.changes.check(mpv).is_some() {
+            self.version += 1;
+            self.leader = self.id;
+            // let everyone know right away
+            self.last_heartbeat = None;
+        }
+
+        while let Some(msg) = self.receive() {
+            if msg.id == self.id {
+                continue;
+            }
+
+            let member = Member {
+                name: msg.name.clone(),
+                file: msg.file.clone(),
+                position: Time::seconds(msg.position),
+                seen: Instant::now(),
+            };
+            if self.members.insert(msg.id, member).is_none() {
+                events.push(Event::Toast(Toast::WatchPartyMember {
+                    name: msg.name.clone(),
+                    joined: true,
+                }));
+            }
+
+            if file.is_none() || msg.file != file {
+                continue;
+            }
+
+            let newer = (msg.version, msg.leader) > (self.version, self.leader);
+            let from_leader = (msg.version, msg.leader) == (self.version, self.leader)
+                && msg.id == msg.leader
+                && msg.leader != self.id;
+
+            if newer || from_leader {
+                self.version = msg.version;
+                self.leader = msg.leader;
+                self.follow(mpv, &msg, paused, position);
+            }
+        }
+
+        self.members.retain(|_, member| {
+            let alive = member.seen.elapsed() < PEER_TIMEOUT;
+            if !alive {
+                events.push(Event::Toast(Toast::WatchPartyMember {
+                    name: member.name.clone(),
+                    joined: false,
+                }));
+            }
+            alive
+        });
+
+        if self
+            .last_heartbeat
+            .is_none_or(|at| at.elapsed() > HEARTBEAT)
+        {
+            self.last_heartbeat = Some(Instant::now());
+
+            let msg = Message {
+                id: self.id,
+                name: self.name.clone(),
+                file,
+                position,
+                paused,
+                version: self.version,
+                leader: self.leader,
+            };
+            if let Err(e) = self.send(&msg) {
+                eprintln!("[Watch party] Failed to broadcast: {e}");
+            }
+        }
+    }
+
+    /// Matches mpv up with what another member reported.
+    fn follow(&mut self, mpv: &mut Mpv, msg: &Message, paused: bool, position: f32) {
+        let mut changed = false;
+
+        if paused != msg.paused {
+            mpv.set_property("pause", msg.paused).ok();
+            changed = true;
+        }
+
+        if (position - msg.position).abs() > MAX_DRIFT {
+            mpv.seek_to(Time::seconds(msg.position)).ok();
+            changed = true;
+        }
+
+        if changed {
+            self.changes.settle();
+        }
+    }
+
+    fn receive(&self) -> Option<Message> {
+        let socket = self.socket.as_ref()?;
+        let mut buf = [0; 2048];
+
+        loop {
+            match socket.recv_from(&mut buf) {
+                Ok((size, _)) => match serde_json::from_slice(&buf[..size]) {
+                    Ok(msg) => return Some(msg),
+                    Err(e) => eprintln!("[Watch party] Ignoring malformed message: {e}"),
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return None,
+                Err(e) => {
+                    eprintln!("[Watch party] Failed to receive: {e}");
+                    return None;
+                }
+            }
+        }
+    }
+
+    fn send(&self, msg: &Message) -> io::Result<()> {
+        let Some(socket) = &self.socket else {
+            return Ok(());
+        };
+
+        let data = serde_json::to_vec(msg).map_err(io::Error::other)?;
+        socket.send_to(&data, (Ipv4Addr::BROADCAST, config().watch_party_port))?;
+        Ok(())
+    }
+}
+
+fn hostname() -> String {
+    std::fs::read_to_string("/etc/hostname")
+        .map(|name| name.trim().to_string())
+        .unwrap_or_else(|_| "htpc".to_string())
+}
@@ -0,0 +1,302 @@
+//! A WebSocket that streams playback state to remote UIs, so they can show what's playing live
+//! without polling. Every message is a JSON object of the properties that changed, by their mpv
+//! names, and the first one a client gets has all of them.
+//!
+//! Only the server's side of the protocol is spoken: what clients send is never read, and they're
+//! dropped once writing to them fails.
+
+use std::{
+    io::{self, BufRead as _, BufReader, Write as _},
+    net::{Ipv4Addr, SocketAddrV4, TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use serde_json::{Map, Value};
+
+use crate::{config::config, mpv::Mpv};
+
+/// What's sent, as mpv reports them.
+const PROPERTIES: [&str; 6] = ["path", "media-title", "pause", "duration", "time-pos", "playlist"];
+
+/// `time-pos` changes every frame, clients can count along between updates.
+const POSITION_EVERY: Duration = Duration::from_millis(500);
+
+/// From the WebSocket spec, for proving the handshake was understood.
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+pub struct WebSocket {
+    /// `None` unless a port is configured and could be listened on.
+    updates: Option<Sender<Message>>,
+    sent: Map<String, Value>,
+    position_sent_at: Instant,
+}
+
+enum Message {
+    Joined(TcpStream),
+    Changed(Map<String, Value>),
+}
+
+impl WebSocket {
+    pub fn new() -> Self {
+        let updates = config().websocket_port.and_then(|port| {
+            listen(port)
+                .inspect_err(|e| eprintln!("Failed to open WebSocket on port {port}: {e}"))
+                .ok()
+        });
+
+        Self {
+            updates,
+            sent: Map::new(),
+            position_sent_at: Instant::now(),
+        }
+    }
+
+    pub fn update(&mut self, mpv: &Mpv) {
+        let Some(updates) = &self.updates else {
+            return;
+        };
+
+        let mut changed = Map::new();
+        for name in PROPERTIES {
+            let value = mpv
+                .get_property_cached::<Value>(name)
+                .unwrap_or(Value::Null);
+
+            if name == "time-pos"
+                && self.sent.contains_key(name)
+                && self.position_sent_at.elapsed() < POSITION_EVERY
+            {
+                continue;
+            }
+
+            if self.sent.get(name) != Some(&value) {
+                if name == "time-pos" {
+                    self.position_sent_at = Instant::now();
+                }
+                self.sent.insert(name.to_string(), value.clone());
+                changed.insert(name.to_string(), value);
+            }
+        }
+
+        if !changed.is_empty() {
+            updates.send(Message::Changed(changed)).ok();
+        }
+    }
+}
+
+impl Default for WebSocket {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Accepts clients on one thread and writes to them on another, so neither a slow handshake nor
+/// a slow client holds up the overlay.
+fn listen(port: u16) -> io::Result<Sender<Message>> {
+    let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port))?;
+    let (tx, rx) = mpsc::channel();
+
+    let joined = tx.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let joined = joined.clone();
+            std::thread::spawn(move || match handshake(stream) {
+                Ok(stream) => {
+                    joined.send(Message::Joined(stream)).ok();
+                }
+                Err(e) => eprintln!("WebSocket handshake failed: {e}"),
+            });
+        }
+    });
+
+    std::thread::spawn(move || broadcast(rx));
+
+    Ok(tx)
+}
+
+fn broadcast(messages: Receiver<Message>) {
+    let mut clients = Vec::<TcpStream>::new();
+    // everything sent so far, for clients that join later
+    let mut state = Map::new();
+
+    for message in messages {
+        match message {
+            Message::Joined(mut stream) => {
+                if send(&mut stream, &state).is_ok() {
+                    clients.push(stream);
+                }
+            }
+            Message::Changed(changed) => {
+                clients.retain_mut(|stream| send(stream, &changed).is_ok());
+                state.extend(changed);
+            }
+        }
+    }
+}
+
+/// Reads the HTTP upgrade request and agrees to it.
+fn handshake(stream: TcpStream) -> io::Result<TcpStream> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream);
+
+    let mut key = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("sec-websocket-key")
+        {
+            key = Some(value.trim().to_string());
+        }
+    }
+
+    let mut stream = reader.into_inner();
+    let Some(key) = key else {
+        stream.write_all(
+            b"HTTP/1.1 426 Upgrade Required\r\nUpgrade: websocket\r\nContent-Length: 0\r\n\r\n",
+        )?;
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a WebSocket request"));
+    };
+
+    let accept = base64(&sha1(format!("{key}{ACCEPT_GUID}").as_bytes()));
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n\r\n",
+    )?;
+
+    stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+    Ok(stream)
+}
+
+/// Writes a JSON object as a single text frame.
+fn send(stream: &mut TcpStream, object: &Map<String, Value>) -> io::Result<()> {
+    let payload = serde_json::to_vec(object)?;
+
+    // FIN and the text opcode, and servers don't mask
+    let mut frame = vec![0x81];
+    match payload.len() {
+        len @ ..126 => frame.push(len as u8),
+        len @ ..=0xffff => {
+            frame.push(126);
+            frame.extend((len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend((len as u64).to_be_bytes());
+        }
+    }
+    frame.extend(payload);
+
+    stream.write_all(&frame)
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend(((data.len() as u64) * 8).to_be_bytes());
+
+    for chunk in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &w) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..20 => ((b & c) | (!b & d), 0x5A827999),
+                20..40 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..60 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(w);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (h, v) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(v);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, h) in digest.chunks_mut(4).zip(h) {
+        bytes.copy_from_slice(&h.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - i * 8));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - i * 6)) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handshake_accept() {
+        // the example from RFC 6455, section 1.3
+        let key = "dGhlIHNhbXBsZSBub25jZQ==";
+        let accept = base64(&sha1(format!("{key}{ACCEPT_GUID}").as_bytes()));
+        assert_eq!(accept, "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn sha1_vectors() {
+        let hex = |digest: [u8; 20]| digest.map(|b| format!("{b:02x}")).concat();
+        assert_eq!(hex(sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hex(sha1(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        // two blocks, since the length doesn't fit in the first
+        assert_eq!(
+            hex(sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+        );
+    }
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+}